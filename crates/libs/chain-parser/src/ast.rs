@@ -14,10 +14,21 @@ pub struct ParsedChain {
     pub triggers: Vec<String>,
     /// Default machine/worker group for fragments.
     pub default_machine: String,
+    /// Workspace checkout options (None = full checkout).
+    pub checkout: Option<ParsedCheckout>,
     /// Flattened list of fragments (imports resolved).
     pub fragments: Vec<ParsedFragment>,
 }
 
+/// Options controlling how the triggering commit is checked out for each fragment.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedCheckout {
+    /// Number of commits of history to fetch (None = full history).
+    pub depth: Option<i32>,
+    /// Paths to materialize with a sparse checkout (empty = whole tree).
+    pub sparse_paths: Vec<String>,
+}
+
 /// A parsed fragment (either inline script or group container).
 #[derive(Debug, Clone)]
 pub struct ParsedFragment {
@@ -33,6 +44,8 @@ pub struct ParsedFragment {
     pub run_script: Option<String>,
     /// Worker group/machine override.
     pub machine: Option<String>,
+    /// Labels a worker must have to execute this fragment.
+    pub runs_on: Vec<String>,
     /// Whether children execute in parallel (for group fragments).
     pub is_parallel: bool,
     /// Condition expression for conditional execution.
@@ -61,6 +74,7 @@ impl ParsedFragment {
             fragment_type: ParsedFragmentType::Inline,
             run_script: Some(run_script),
             machine: None,
+            runs_on: Vec::new(),
             is_parallel: false,
             condition: None,
             source_url: None,
@@ -77,6 +91,7 @@ impl ParsedFragment {
             fragment_type: ParsedFragmentType::Group,
             run_script: None,
             machine: None,
+            runs_on: Vec::new(),
             is_parallel: true,
             condition: None,
             source_url: None,
@@ -97,6 +112,13 @@ impl ParsedFragment {
         self
     }
 
+    /// Set the labels a worker must have to execute this fragment.
+    #[must_use]
+    pub fn with_runs_on(mut self, runs_on: Vec<String>) -> Self {
+        self.runs_on = runs_on;
+        self
+    }
+
     /// Set a condition for execution.
     #[must_use]
     pub fn with_condition(mut self, condition: String) -> Self {
@@ -0,0 +1,236 @@
+//! Located diagnostics for problems in workflow files.
+//!
+//! A [`Diagnostic`] pairs a [`ParseError`] with the file it was found in and the
+//! span of the offending KDL, so tools can point at the line and column of every
+//! problem. Diagnostics implement [`miette::Diagnostic`] for rendering.
+
+use std::fmt;
+use std::sync::Arc;
+
+use miette::{LabeledSpan, SourceCode, SourceSpan};
+
+pub use miette::Severity;
+
+use crate::error::ParseError;
+
+/// A workflow or imported file, as diagnostics point into it.
+#[derive(Debug)]
+pub struct SourceFile {
+    /// Path or URL of the file, if known.
+    pub name: Option<String>,
+    /// Content of the file.
+    pub content: String,
+}
+
+impl SourceFile {
+    /// Create a new source file.
+    #[must_use]
+    pub fn new(name: Option<&str>, content: &str) -> Arc<Self> {
+        Arc::new(Self {
+            name: name.map(String::from),
+            content: content.to_string(),
+        })
+    }
+}
+
+/// Line and column of a position in a file, both from 1.
+///
+/// Columns count characters, not bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    /// Line number.
+    pub line: usize,
+    /// Column number.
+    pub column: usize,
+}
+
+/// A problem found while parsing a workflow, with where it is.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// What the problem is.
+    pub error: ParseError,
+    /// Whether the problem fails the workflow.
+    pub severity: Severity,
+    /// Span of the problem in its file, in bytes.
+    pub span: Option<SourceSpan>,
+    /// Short description of the span, e.g. "unknown node".
+    pub label: Option<String>,
+    /// Suggestion for fixing the problem.
+    pub help: Option<String>,
+    /// File the problem is in.
+    source: Option<Arc<SourceFile>>,
+}
+
+impl Diagnostic {
+    /// Create an error diagnostic in a file, without a span.
+    #[must_use]
+    pub fn new(error: ParseError, source: &Arc<SourceFile>) -> Self {
+        Self {
+            source: Some(Arc::clone(source)),
+            ..Self::from(error)
+        }
+    }
+
+    /// Create a warning diagnostic in a file, without a span.
+    #[must_use]
+    pub fn warning(error: ParseError, source: &Arc<SourceFile>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::new(error, source)
+        }
+    }
+
+    /// Point the diagnostic at a span of its file.
+    #[must_use]
+    pub fn with_span(mut self, span: SourceSpan, label: impl Into<String>) -> Self {
+        self.span = Some(span);
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Add a suggestion for fixing the problem.
+    #[must_use]
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Name of the diagnostic's kind, e.g. `vulcan::unknown_node`.
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        self.error.code()
+    }
+
+    /// Whether the diagnostic is an error, as opposed to a warning.
+    #[must_use]
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// The file the problem is in.
+    #[must_use]
+    pub fn source(&self) -> Option<&SourceFile> {
+        self.source.as_deref()
+    }
+
+    /// Path or URL of the file the problem is in, if known.
+    #[must_use]
+    pub fn file(&self) -> Option<&str> {
+        self.source()?.name.as_deref()
+    }
+
+    /// Line and column of the start of the span.
+    #[must_use]
+    pub fn location(&self) -> Option<Location> {
+        let content = &self.source()?.content;
+        let offset = self.span?.offset().min(content.len());
+        let before = content.get(..offset)?;
+
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        Some(Location {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        })
+    }
+
+    /// The line of the file the span starts on.
+    #[must_use]
+    pub fn snippet(&self) -> Option<&str> {
+        let location = self.location()?;
+        self.source()?.content.lines().nth(location.line - 1)
+    }
+}
+
+impl From<ParseError> for Diagnostic {
+    fn from(error: ParseError) -> Self {
+        Self {
+            error,
+            severity: Severity::Error,
+            span: None,
+            label: None,
+            help: None,
+            source: None,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.file(), self.location()) {
+            (Some(file), Some(location)) => {
+                write!(f, "{file}:{}:{}: ", location.line, location.column)?;
+            },
+            (Some(file), None) => write!(f, "{file}: ")?,
+            (None, Some(location)) => write!(f, "{}:{}: ", location.line, location.column)?,
+            (None, None) => {},
+        }
+        write!(f, "{}", self.error)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+impl miette::Diagnostic for Diagnostic {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        Some(self.severity)
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.help
+            .as_ref()
+            .map(|help| Box::new(help) as Box<dyn fmt::Display>)
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.source()
+            .map(|source| &source.content as &dyn SourceCode)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let span = self.span?;
+        let label = LabeledSpan::new_primary_with_span(self.label.clone(), span);
+        Some(Box::new(std::iter::once(label)))
+    }
+}
+
+/// Outcome of checking a workflow: what it parsed to if it has no errors, along
+/// with every diagnostic found.
+#[derive(Debug)]
+pub struct Checked<T> {
+    /// The parsed value, if there are no errors.
+    pub value: Option<T>,
+    /// Problems found, in the order they were found.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl<T> Checked<T> {
+    /// Whether any diagnostic is an error.
+    #[must_use]
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(Diagnostic::is_error)
+    }
+
+    /// Treat warnings as errors, dropping the value if there are any.
+    #[must_use]
+    pub fn promote_warnings(mut self) -> Self {
+        for diagnostic in &mut self.diagnostics {
+            diagnostic.severity = Severity::Error;
+        }
+        if self.has_errors() {
+            self.value = None;
+        }
+        self
+    }
+
+    /// Convert the value, keeping the diagnostics.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Checked<U> {
+        Checked {
+            value: self.value.map(f),
+            diagnostics: self.diagnostics,
+        }
+    }
+}
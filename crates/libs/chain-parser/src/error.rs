@@ -3,7 +3,7 @@
 use thiserror::Error;
 
 /// Errors that can occur during chain parsing.
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum ParseError {
     /// KDL syntax error.
     #[error("invalid KDL syntax: {0}")]
@@ -51,6 +51,23 @@ pub enum ParseError {
     #[error("unknown node type: {0}")]
     UnknownNode(String),
 
+    /// Node given more than once where only one is allowed.
+    #[error("duplicate node: {0}")]
+    DuplicateNode(String),
+
+    /// Property on a node, which takes none.
+    #[error("unknown property: {0}")]
+    UnknownProperty(String),
+
+    /// Node with values or children of the wrong kind.
+    #[error("invalid {node}: expected {expected}")]
+    InvalidValue {
+        /// The node name.
+        node: String,
+        /// What the node takes.
+        expected: &'static str,
+    },
+
     /// Invalid node in imported file.
     #[error("imported files can only contain fragment/parallel nodes, found: {0}")]
     InvalidImportNode(String),
@@ -59,9 +76,58 @@ pub enum ParseError {
     #[error("unsupported version: {0}")]
     UnsupportedVersion(String),
 
+    /// Invalid checkout options.
+    #[error("invalid checkout options: {0}")]
+    InvalidCheckout(String),
+
+    /// Invalid `runs-on` label.
+    #[error("invalid runs-on label: {0}")]
+    InvalidLabel(String),
+
     /// Invalid trigger type.
     #[error("invalid trigger type: {0}")]
     InvalidTrigger(String),
+
+    /// Fragment whose script is empty (a warning).
+    #[error("fragment has an empty script")]
+    EmptyScript,
+
+    /// Machine override of a fragment that has no effect (a warning).
+    #[error("machine override has no effect: {0}")]
+    UnusedMachine(String),
+
+    /// Version of the format to migrate off (a warning).
+    #[error("deprecated version: {0}")]
+    DeprecatedVersion(String),
+}
+
+impl ParseError {
+    /// Name of the kind of error, for tools handling diagnostics.
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidSyntax(_) => "vulcan::invalid_syntax",
+            Self::MissingRequired { .. } => "vulcan::missing_required",
+            Self::InvalidUrl(_) => "vulcan::invalid_url",
+            Self::FetchFailed { .. } => "vulcan::fetch_failed",
+            Self::CircularImport(_) => "vulcan::circular_import",
+            Self::MutualExclusion => "vulcan::mutual_exclusion",
+            Self::NoContent => "vulcan::no_content",
+            Self::NoMachine => "vulcan::no_machine",
+            Self::UnknownNode(_) => "vulcan::unknown_node",
+            Self::DuplicateNode(_) => "vulcan::duplicate_node",
+            Self::UnknownProperty(_) => "vulcan::unknown_property",
+            Self::InvalidValue { .. } => "vulcan::invalid_value",
+            Self::InvalidImportNode(_) => "vulcan::invalid_import_node",
+            Self::UnsupportedVersion(_) => "vulcan::unsupported_version",
+            Self::InvalidCheckout(_) => "vulcan::invalid_checkout",
+            Self::InvalidLabel(_) => "vulcan::invalid_label",
+            Self::InvalidTrigger(_) => "vulcan::invalid_trigger",
+            Self::EmptyScript => "vulcan::empty_script",
+            Self::UnusedMachine(_) => "vulcan::unused_machine",
+            Self::DeprecatedVersion(_) => "vulcan::deprecated_version",
+        }
+    }
 }
 
 /// Result type for parser operations.
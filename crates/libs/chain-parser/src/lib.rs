@@ -9,7 +9,11 @@
 //! - Parses KDL workflow files with version, triggers, and chain definitions
 //! - Recursively resolves import fragments from external URLs
 //! - Detects circular imports
-//! - Validates workflow structure and required fields
+//! - Validates workflow structure and required fields against the schema of the
+//!   workflow's version, warning about suspicious patterns
+//! - Reads every supported version of the format, and migrates workflows to the
+//!   newest one
+//! - Reports problems as [`Diagnostic`]s located by file, line and column
 //! - Converts parsed AST to database models ready for insertion
 //!
 //! # Example
@@ -56,19 +60,31 @@
 
 /// Abstract syntax tree types for parsed workflows.
 pub mod ast;
+/// Located diagnostics for problems in workflows.
+pub mod diagnostic;
 /// Error types for parsing operations.
 pub mod error;
+/// Migration of workflows to the newest version of the format.
+pub mod migrate;
 /// KDL parser implementation.
 pub mod parser;
+/// Declared schema of workflows, per version.
+pub mod schema;
 /// High-level parsing service.
 pub mod service;
+/// Supported versions of the workflow format.
+pub mod versions;
 
+#[cfg(test)]
+mod migrate_tests;
 #[cfg(test)]
 mod parser_tests;
 #[cfg(test)]
 mod service_tests;
 
 // Re-export main types for convenience
+pub use diagnostic::{Checked, Diagnostic, Severity, SourceFile};
 pub use error::{ParseError, Result};
 pub use parser::{ChainParser, ImportFetcher};
 pub use service::{ChainParserService, ParsedWorkflow, WorkflowContext};
+pub use versions::Version;
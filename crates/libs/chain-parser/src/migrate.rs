@@ -0,0 +1,110 @@
+//! Migration of workflow files to the newest version of the format.
+//!
+//! Files are rewritten by the front-ends of their [`Version`] and its successors,
+//! editing the KDL document in place, so comments and formatting are kept except
+//! inside the nodes a version replaces.
+
+use std::sync::Arc;
+
+use kdl::KdlDocument;
+
+use crate::diagnostic::{Checked, Diagnostic, SourceFile};
+use crate::error::ParseError;
+use crate::parser::{read_document, unsupported_version};
+use crate::versions::Version;
+
+/// Rewrite a workflow file to the newest version of the format.
+///
+/// The version is read from the file's `version` node. The value is the content of
+/// the migrated file, unchanged if it is of the newest version, or `None` if the
+/// file has errors. Warnings are the nodes dropped because they had no effect.
+/// Imported files are not migrated: see [`migrate_fragments`].
+#[must_use]
+pub fn migrate_workflow(content: &str, source_name: Option<&str>) -> Checked<String> {
+    let source = SourceFile::new(source_name, content);
+    let doc = match read_document(&source) {
+        Ok(doc) => doc,
+        Err(diagnostics) => return failed(diagnostics),
+    };
+
+    let version = doc.get("version").and_then(|node| {
+        let entry = node.entries().first()?;
+        Some((entry.value().as_string()?.to_string(), entry.span()))
+    });
+    let Some((name, span)) = version else {
+        let missing = ParseError::MissingRequired {
+            field: "version",
+            context: "workflow root".to_string(),
+        };
+        return failed(vec![Diagnostic::new(missing, &source)]);
+    };
+    let Some(version) = Version::find(&name) else {
+        return failed(vec![unsupported_version(name, span, &source)]);
+    };
+
+    let diagnostics = version.schema.validate_workflow(&doc, &source);
+    upgrade(doc, version, &source, diagnostics)
+}
+
+/// Rewrite a fragment file imported by workflows of a version to the newest version
+/// of the format.
+///
+/// Fragment files have no `version` node, so the version they are written for is
+/// given. Otherwise like [`migrate_workflow`].
+#[must_use]
+pub fn migrate_fragments(
+    content: &str,
+    source_name: Option<&str>,
+    version: &str,
+) -> Checked<String> {
+    let source = SourceFile::new(source_name, content);
+    let Some(version) = Version::find(version) else {
+        let unsupported = ParseError::UnsupportedVersion(version.to_string());
+        let diagnostic = Diagnostic::new(unsupported, &source)
+            .with_help(format!("use version {}", Version::supported()));
+        return failed(vec![diagnostic]);
+    };
+    let doc = match read_document(&source) {
+        Ok(doc) => doc,
+        Err(diagnostics) => return failed(diagnostics),
+    };
+
+    let diagnostics = version.schema.validate_fragments(&doc, &source);
+    upgrade(doc, version, &source, diagnostics)
+}
+
+/// Rewrite a validated document to the newest version, unless it has errors.
+fn upgrade(
+    mut doc: KdlDocument,
+    version: &Version,
+    source: &Arc<SourceFile>,
+    mut diagnostics: Vec<Diagnostic>,
+) -> Checked<String> {
+    if diagnostics.iter().any(Diagnostic::is_error) {
+        return failed(diagnostics);
+    }
+    if version.is_latest() {
+        return Checked {
+            value: Some(source.content.clone()),
+            diagnostics,
+        };
+    }
+
+    diagnostics.extend(version.upgrade(&mut doc, source));
+    let mut checked = Checked {
+        value: Some(doc.to_string()),
+        diagnostics,
+    };
+    if checked.has_errors() {
+        checked.value = None;
+    }
+    checked
+}
+
+/// Outcome of a migration that failed.
+const fn failed(diagnostics: Vec<Diagnostic>) -> Checked<String> {
+    Checked {
+        value: None,
+        diagnostics,
+    }
+}
@@ -0,0 +1,220 @@
+//! Tests for migrating workflows to the newest version.
+
+use crate::ast::ParsedChain;
+use crate::diagnostic::Diagnostic;
+use crate::error::{ParseError, Result};
+use crate::migrate::{migrate_fragments, migrate_workflow};
+use crate::parser::{ChainParser, ImportFetcher};
+use crate::versions::Version;
+
+/// Fetcher that returns the same fragments for every URL.
+struct StaticFetcher(&'static str);
+
+impl ImportFetcher for StaticFetcher {
+    fn fetch(&self, _url: &str) -> Result<String> {
+        Ok(self.0.to_string())
+    }
+}
+
+/// A workflow of the deprecated version tests register before 0.1.
+const WORKFLOW_0_0: &str = r#"// CI for the web app
+version "0.0"
+triggers "push" "pull_request"
+
+chain {
+    machine   "default-worker"   // odd spacing is kept
+
+    // install
+    fragment { script "npm ci" }
+
+    parallel {
+        fragment {
+            // Lint first
+            script   "npm run lint"
+            machine "linter"
+        }
+        fragment { from "https://example.com/test.kdl" }
+    }
+}
+"#;
+
+const WORKFLOW_0_1: &str = r#"// CI for the web app
+version "0.1"
+triggers "push" "pull_request"
+
+chain {
+    machine   "default-worker"   // odd spacing is kept
+
+    fragment { run "npm ci" }
+
+    // Shared test steps
+    fragment {
+        from "https://example.com/test.kdl" // pinned
+    }
+
+    parallel {
+        fragment { from "https://example.com/lint.kdl" }
+        fragment { run "npm run build" }
+    }
+}
+"#;
+
+#[test]
+fn test_newest_version_is_registered() {
+    let latest = Version::latest();
+
+    assert_eq!(latest.name, "0.1");
+    assert!(latest.is_latest());
+    assert!(!latest.deprecated);
+    assert!(std::ptr::eq(Version::find("0.1").unwrap(), latest));
+    assert!(Version::find("9.9").is_none());
+
+    let deprecated = Version::find("0.0").unwrap();
+    assert!(deprecated.deprecated);
+    assert!(!deprecated.is_latest());
+    assert_eq!(Version::supported(), "\"0.0\" or \"0.1\"");
+}
+
+#[test]
+fn test_migrate_workflow_keeps_comments_and_formatting() {
+    let checked = migrate_workflow(WORKFLOW_0_0, Some("ci.kdl"));
+
+    assert!(checked.diagnostics.is_empty(), "{:?}", checked.diagnostics);
+    assert_eq!(
+        checked.value.as_deref(),
+        Some(
+            r#"// CI for the web app
+version "0.1"
+triggers "push" "pull_request"
+
+chain {
+    machine   "default-worker"   // odd spacing is kept
+
+    // install
+    fragment { run "npm ci" }
+
+    parallel {
+        fragment {
+            // Lint first
+            run   "npm run lint"
+            machine "linter"
+        }
+        fragment { from "https://example.com/test.kdl" }
+    }
+}
+"#
+        )
+    );
+}
+
+#[test]
+fn test_migrated_workflow_parses_the_same() {
+    let migrated = migrate_workflow(WORKFLOW_0_0, None).value.unwrap();
+
+    // Imports are read as the workflow's version, so they are migrated too.
+    let before = ChainParser::new(StaticFetcher(r#"fragment { script "npm test" }"#))
+        .parse_workflow(WORKFLOW_0_0, None)
+        .unwrap();
+    let checked = ChainParser::new(StaticFetcher(r#"fragment { run "npm test" }"#))
+        .check_workflow(&migrated, None);
+
+    assert!(checked.diagnostics.is_empty());
+    let after = checked.value.unwrap();
+    let shape = |chain: &ParsedChain| -> Vec<_> {
+        chain
+            .fragments
+            .iter()
+            .map(|f| {
+                (
+                    f.run_script.clone(),
+                    f.machine.clone(),
+                    f.source_url.clone(),
+                )
+            })
+            .collect()
+    };
+    assert_eq!(shape(&before), shape(&after));
+}
+
+#[test]
+fn test_migrate_newest_version_is_unchanged() {
+    let checked = migrate_workflow(WORKFLOW_0_1, Some("ci.kdl"));
+
+    assert!(checked.diagnostics.is_empty());
+    assert_eq!(checked.value.as_deref(), Some(WORKFLOW_0_1));
+}
+
+#[test]
+fn test_migrate_workflow_errors() {
+    // (content, code)
+    let cases = [
+        (
+            r#"version "0.1"
+triggers "push"
+chain {
+    machine "default-worker"
+    fragment { run "npm build"; import "https://example.com/build.kdl" }
+}
+"#,
+            "vulcan::unknown_node",
+        ),
+        (
+            r#"version "0.0"
+triggers "push"
+chain {
+    machine "default-worker"
+    fragment { run "npm build" }
+}
+"#,
+            "vulcan::unknown_node",
+        ),
+        (
+            "version \"9.9\"\ntriggers \"push\"\n",
+            "vulcan::unsupported_version",
+        ),
+        ("triggers \"push\"\n", "vulcan::missing_required"),
+        ("version \"0.1\"\nchain {", "vulcan::invalid_syntax"),
+    ];
+
+    for (content, code) in cases {
+        let checked = migrate_workflow(content, None);
+        assert!(checked.value.is_none(), "{content}");
+        let codes: Vec<_> = checked.diagnostics.iter().map(Diagnostic::code).collect();
+        assert!(codes.contains(&code), "{content}: {codes:?}");
+    }
+}
+
+#[test]
+fn test_migrate_fragments() {
+    let content = r#"fragment { run "npm build" }
+fragment { from "https://example.com/test.kdl" }
+"#;
+
+    let checked = migrate_fragments(content, Some("build.kdl"), "0.1");
+
+    assert!(checked.diagnostics.is_empty());
+    assert_eq!(checked.value.as_deref(), Some(content));
+
+    let deprecated = r#"// build
+fragment { script "npm build" }
+parallel { fragment { script "npm test" } }
+"#;
+    let checked = migrate_fragments(deprecated, Some("build.kdl"), "0.0");
+    assert!(checked.diagnostics.is_empty());
+    assert_eq!(
+        checked.value.as_deref(),
+        Some(
+            r#"// build
+fragment { run "npm build" }
+parallel { fragment { run "npm test" } }
+"#
+        )
+    );
+
+    let unsupported = migrate_fragments(content, None, "9.9");
+    assert!(unsupported.value.is_none());
+    assert!(matches!(
+        &unsupported.diagnostics[0].error,
+        ParseError::UnsupportedVersion(version) if version == "9.9"
+    ));
+}
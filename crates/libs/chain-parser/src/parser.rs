@@ -1,15 +1,25 @@
 //! KDL parser for workflow and fragment files.
 //!
 //! This module parses KDL files into the intermediate AST representation.
-//! Import resolution is handled separately by the resolver module.
+//! Imports are resolved recursively while parsing, through an [`ImportFetcher`].
+//!
+//! Every file is validated against the schema of the workflow's [`Version`], then
+//! rewritten into the newest version by the version's front-end, before it is
+//! lowered to the AST. Problems are reported as [`Diagnostic`]s located in the file
+//! they are found in. Parsing stops at the first error, except when checking a
+//! workflow, which carries on past errors to report every problem of the workflow at
+//! once.
 
 use std::collections::HashSet;
+use std::sync::Arc;
 
-use kdl::{KdlDocument, KdlNode};
+use kdl::{KdlDocument, KdlError, KdlNode};
 use uuid::Uuid;
 
-use crate::ast::{ParsedChain, ParsedFragment};
+use crate::ast::{ParsedChain, ParsedCheckout, ParsedFragment};
+use crate::diagnostic::{Checked, Diagnostic, SourceFile};
 use crate::error::{ParseError, Result};
+use crate::versions::Version;
 
 /// Fetcher trait for resolving import URLs.
 ///
@@ -22,6 +32,84 @@ pub trait ImportFetcher {
     fn fetch(&self, url: &str) -> Result<String>;
 }
 
+/// Parsing stopped at an error, recorded in the [`Walk`].
+struct Stop;
+
+/// Result of parsing part of a workflow.
+type Walked<T> = std::result::Result<T, Stop>;
+
+/// State of parsing a workflow and its imports.
+struct Walk {
+    /// Whether to carry on past errors.
+    recover: bool,
+    /// URLs of the files being parsed, to detect circular imports.
+    visited: HashSet<String>,
+    /// Version of the workflow, which its imports are read as.
+    version: &'static Version,
+    /// Machine of fragments without one.
+    default_machine: String,
+    /// Problems found so far.
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Walk {
+    fn new(recover: bool, visited: HashSet<String>, default_machine: &str) -> Self {
+        Self {
+            recover,
+            visited,
+            version: Version::latest(),
+            default_machine: default_machine.to_string(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Record an error that parsing can carry on past, when recovering.
+    fn error(&mut self, diagnostic: Diagnostic) -> Walked<()> {
+        self.diagnostics.push(diagnostic);
+        self.carry_on()
+    }
+
+    /// Record errors that parsing can carry on past, when recovering, and warnings.
+    fn record(&mut self, diagnostics: Vec<Diagnostic>) -> Walked<()> {
+        for diagnostic in diagnostics {
+            if diagnostic.is_error() {
+                self.error(diagnostic)?;
+            } else {
+                self.warn(diagnostic);
+            }
+        }
+        Ok(())
+    }
+
+    /// Record a warning, which never stops parsing.
+    fn warn(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Record an error the current node cannot be parsed past.
+    fn fail<T>(&mut self, diagnostic: Diagnostic) -> Walked<T> {
+        self.diagnostics.push(diagnostic);
+        Err(Stop)
+    }
+
+    /// Carry on past a node that failed to parse, when recovering.
+    const fn carry_on(&self) -> Walked<()> {
+        if self.recover { Ok(()) } else { Err(Stop) }
+    }
+
+    /// Collect the outcome of the walk.
+    fn finish<T>(self, result: Walked<T>) -> Checked<T> {
+        let mut checked = Checked {
+            value: result.ok(),
+            diagnostics: self.diagnostics,
+        };
+        if checked.has_errors() {
+            checked.value = None;
+        }
+        checked
+    }
+}
+
 /// Parser for KDL workflow files.
 pub struct ChainParser<F: ImportFetcher> {
     fetcher: F,
@@ -38,53 +126,106 @@ impl<F: ImportFetcher> ChainParser<F> {
     /// # Errors
     /// Returns an error if the content is not valid KDL or doesn't match the workflow schema.
     pub fn parse_workflow(&self, content: &str, source_url: Option<&str>) -> Result<ParsedChain> {
-        let doc: KdlDocument = content
-            .parse()
-            .map_err(|e: kdl::KdlError| ParseError::InvalidSyntax(e.to_string()))?;
-
-        // Parse version
-        let version = get_string_value(&doc, "version").ok_or_else(|| ParseError::MissingRequired {
-            field: "version",
-            context: "workflow root".to_string(),
-        })?;
-
-        if version != "0.1" {
-            return Err(ParseError::UnsupportedVersion(version));
-        }
+        first_error(self.walk_workflow(content, source_url, false))
+    }
 
-        // Parse triggers
-        let triggers = get_string_args(&doc, "triggers").ok_or_else(|| ParseError::MissingRequired {
-            field: "triggers",
-            context: "workflow root".to_string(),
-        })?;
-
-        // Parse chain node
-        let chain_node = doc
-            .nodes()
-            .iter()
-            .find(|n| n.name().value() == "chain")
-            .ok_or_else(|| ParseError::MissingRequired {
-                field: "chain",
-                context: "workflow root".to_string(),
-            })?;
-
-        let chain_doc = chain_node.children().ok_or_else(|| ParseError::MissingRequired {
-            field: "chain children",
-            context: "chain node".to_string(),
-        })?;
-
-        // Get default machine
-        let default_machine =
-            get_string_value(chain_doc, "machine").ok_or_else(|| ParseError::MissingRequired {
-                field: "machine",
-                context: "chain node".to_string(),
-            })?;
+    /// Check a workflow file, collecting every problem in it and its imports.
+    ///
+    /// Unlike [`parse_workflow`](Self::parse_workflow), parsing carries on past
+    /// errors in a node with the next node, so all of them are reported at once.
+    pub fn check_workflow(&self, content: &str, source_url: Option<&str>) -> Checked<ParsedChain> {
+        self.walk_workflow(content, source_url, true)
+    }
+
+    /// Parse a fragment file (no chain wrapper, just fragments).
+    ///
+    /// Used for resolving imports.
+    ///
+    /// # Errors
+    /// Returns an error if the content is not valid KDL or contains invalid nodes.
+    pub fn parse_fragment_file(
+        &self,
+        content: &str,
+        source_url: &str,
+        default_machine: &str,
+        visited: &mut HashSet<String>,
+    ) -> Result<Vec<ParsedFragment>> {
+        let mut walk = Walk::new(false, std::mem::take(visited), default_machine);
+        let result = self.parse_fragments(content, source_url, &mut walk);
+        *visited = std::mem::take(&mut walk.visited);
+        first_error(walk.finish(result))
+    }
 
+    /// Parse a workflow file, stopping at the first error unless recovering.
+    fn walk_workflow(
+        &self,
+        content: &str,
+        source_url: Option<&str>,
+        recover: bool,
+    ) -> Checked<ParsedChain> {
         // Track visited URLs for circular import detection
-        let mut visited = HashSet::new();
-        if let Some(url) = source_url {
-            visited.insert(url.to_string());
+        let visited = source_url.map(String::from).into_iter().collect();
+        let mut walk = Walk::new(recover, visited, "");
+
+        let source = SourceFile::new(source_url, content);
+        let result = self.parse_chain(&source, &mut walk);
+        walk.finish(result)
+    }
+
+    /// Parse the root of a workflow file.
+    fn parse_chain(&self, source: &Arc<SourceFile>, walk: &mut Walk) -> Walked<ParsedChain> {
+        let mut doc = parse_document(source, walk)?;
+
+        // Read the workflow as its version, or as the newest if the version is
+        // unsupported. A missing or mistyped version fails validation.
+        if let Some((name, span)) = find_node(&doc, "version").and_then(string_entry) {
+            match Version::find(&name) {
+                Some(version) => {
+                    walk.version = version;
+                    if version.deprecated {
+                        walk.warn(deprecated_version(version, span, source));
+                    }
+                },
+                None => walk.error(unsupported_version(name, span, source))?,
+            }
         }
+        let problems = walk.version.schema.validate_workflow(&doc, source);
+        walk.record(problems)?;
+        let problems = walk.version.upgrade(&mut doc, source);
+        walk.record(problems)?;
+
+        // Missing nodes and values were reported by validation
+        let triggers = get_string_args(&doc, "triggers").unwrap_or_default();
+        let Some(chain_node) = find_node(&doc, "chain") else {
+            return Err(Stop);
+        };
+
+        let Some(chain_doc) = chain_node.children() else {
+            return walk.fail(
+                Diagnostic::new(
+                    ParseError::MissingRequired {
+                        field: "chain children",
+                        context: "chain node".to_string(),
+                    },
+                    source,
+                )
+                .with_span(chain_node.name().span(), "chain without fragments"),
+            );
+        };
+
+        walk.default_machine = get_string_value(chain_doc, "machine").unwrap_or_default();
+
+        // Get optional checkout options
+        let checkout = match find_node(chain_doc, "checkout") {
+            Some(node) => {
+                let checkout = parse_checkout(node, source, walk).ok();
+                if checkout.is_none() {
+                    walk.carry_on()?;
+                }
+                checkout
+            },
+            None => None,
+        };
 
         // Parse fragments
         let mut fragments = Vec::new();
@@ -92,11 +233,14 @@ impl<F: ImportFetcher> ChainParser<F> {
 
         for node in chain_doc.nodes() {
             let name = node.name().value();
-            if name == "machine" {
+            if name == "machine" || name == "checkout" {
                 continue; // Already processed
             }
 
-            let parsed = self.parse_node(node, &default_machine, &mut visited, None)?;
+            let Ok(parsed) = self.parse_node(node, source, walk, None) else {
+                walk.carry_on()?;
+                continue;
+            };
             for mut frag in parsed {
                 if frag.parent_id.is_none() {
                     frag.sequence = sequence;
@@ -109,37 +253,33 @@ impl<F: ImportFetcher> ChainParser<F> {
         Ok(ParsedChain {
             id: Uuid::new_v4(),
             triggers,
-            default_machine,
+            default_machine: walk.default_machine.clone(),
+            checkout,
             fragments,
         })
     }
 
-    /// Parse a fragment file (no chain wrapper, just fragments).
-    ///
-    /// Used for resolving imports.
-    ///
-    /// # Errors
-    /// Returns an error if the content is not valid KDL or contains invalid nodes.
-    pub fn parse_fragment_file(
+    /// Parse the nodes of a fragment file.
+    fn parse_fragments(
         &self,
         content: &str,
         source_url: &str,
-        default_machine: &str,
-        visited: &mut HashSet<String>,
-    ) -> Result<Vec<ParsedFragment>> {
-        let doc: KdlDocument = content
-            .parse()
-            .map_err(|e: kdl::KdlError| ParseError::InvalidSyntax(e.to_string()))?;
+        walk: &mut Walk,
+    ) -> Walked<Vec<ParsedFragment>> {
+        let source = SourceFile::new(Some(source_url), content);
+        let mut doc = parse_document(&source, walk)?;
+        let problems = walk.version.schema.validate_fragments(&doc, &source);
+        walk.record(problems)?;
+        let problems = walk.version.upgrade(&mut doc, &source);
+        walk.record(problems)?;
 
         let mut fragments = Vec::new();
 
         for node in doc.nodes() {
-            let name = node.name().value();
-            if name != "fragment" && name != "parallel" {
-                return Err(ParseError::InvalidImportNode(name.to_string()));
-            }
-
-            let parsed = self.parse_node(node, default_machine, visited, None)?;
+            let Ok(parsed) = self.parse_node(node, &source, walk, None) else {
+                walk.carry_on()?;
+                continue;
+            };
             for mut frag in parsed {
                 // Mark all fragments as coming from this import
                 if frag.source_url.is_none() {
@@ -156,14 +296,15 @@ impl<F: ImportFetcher> ChainParser<F> {
     fn parse_node(
         &self,
         node: &KdlNode,
-        default_machine: &str,
-        visited: &mut HashSet<String>,
+        source: &Arc<SourceFile>,
+        walk: &mut Walk,
         parent_id: Option<Uuid>,
-    ) -> Result<Vec<ParsedFragment>> {
+    ) -> Walked<Vec<ParsedFragment>> {
         match node.name().value() {
-            "fragment" => self.parse_fragment(node, default_machine, visited, parent_id),
-            "parallel" => self.parse_parallel(node, default_machine, visited, parent_id),
-            other => Err(ParseError::UnknownNode(other.to_string())),
+            "fragment" => self.parse_fragment(node, source, walk, parent_id),
+            "parallel" => self.parse_parallel(node, source, walk, parent_id),
+            // Unknown nodes were reported by validation
+            _ => Ok(Vec::new()),
         }
     }
 
@@ -171,70 +312,129 @@ impl<F: ImportFetcher> ChainParser<F> {
     fn parse_fragment(
         &self,
         node: &KdlNode,
-        default_machine: &str,
-        visited: &mut HashSet<String>,
+        source: &Arc<SourceFile>,
+        walk: &mut Walk,
         parent_id: Option<Uuid>,
-    ) -> Result<Vec<ParsedFragment>> {
+    ) -> Walked<Vec<ParsedFragment>> {
         let children = node.children();
+        let span = node.name().span();
 
-        let from_url = children.and_then(|c| get_string_value(c, "from"));
-        let run_script = children.and_then(|c| get_string_value(c, "run"));
+        let from_node = children.and_then(|c| find_node(c, "from"));
+        let run_node = children.and_then(|c| find_node(c, "run"));
+        let machine_node = children.and_then(|c| find_node(c, "machine"));
 
         // Check mutual exclusion
-        if from_url.is_some() && run_script.is_some() {
-            return Err(ParseError::MutualExclusion);
+        if from_node.is_some() && run_node.is_some() {
+            return walk.fail(
+                Diagnostic::new(ParseError::MutualExclusion, source)
+                    .with_span(span, "fragment with both `run` and `from`")
+                    .with_help("split it into two fragments"),
+            );
         }
 
-        if from_url.is_none() && run_script.is_none() {
-            return Err(ParseError::NoContent);
-        }
+        // Values of the wrong type were reported by validation
+        if let Some(from_node) = from_node {
+            let Some((url, url_span)) = string_entry(from_node) else {
+                return Err(Stop);
+            };
+            if let Some(node) = machine_node {
+                walk.warn(
+                    unused_machine(node, source)
+                        .with_span(node.name().span(), "ignored for imported fragments")
+                        .with_help("set the machine in the imported file's fragments instead"),
+                );
+            }
 
-        if let Some(url) = from_url {
             // Import: recursively resolve
-            self.resolve_import(&url, default_machine, visited, parent_id)
-        } else {
-            // Inline fragment
-            let machine = children
-                .and_then(|c| get_string_value(c, "machine"))
-                .unwrap_or_else(|| default_machine.to_string());
-
-            let condition = children.and_then(|c| get_string_value(c, "condition"));
+            return self.resolve_import(&url, url_span, source, walk, parent_id);
+        }
 
-            let mut fragment = ParsedFragment::inline(0, run_script.expect("run_script checked above"))
-                .with_machine(machine);
+        let Some(run_node) = run_node else {
+            return walk.fail(
+                Diagnostic::new(ParseError::NoContent, source)
+                    .with_span(span, "fragment without `run` or `from`")
+                    .with_help("add a script with `run \"...\"` or import one with `from \"...\"`"),
+            );
+        };
+        let Some((run_script, script_span)) = string_entry(run_node) else {
+            return Err(Stop);
+        };
+        if run_script.trim().is_empty() {
+            walk.warn(
+                Diagnostic::warning(ParseError::EmptyScript, source)
+                    .with_span(script_span, "empty script")
+                    .with_help("give the fragment a script, or remove it"),
+            );
+        }
 
-            if let Some(cond) = condition {
-                fragment = fragment.with_condition(cond);
+        // Inline fragment
+        let mut machine = walk.default_machine.clone();
+        if let Some(node) = machine_node
+            && let Some((name, _)) = string_entry(node)
+        {
+            if name == machine {
+                walk.warn(
+                    unused_machine(node, source)
+                        .with_span(node.name().span(), "same as the chain's machine")
+                        .with_help("remove it, fragments run on the chain's machine by default"),
+                );
             }
+            machine = name;
+        }
 
-            if let Some(pid) = parent_id {
-                fragment = fragment.with_parent(pid);
-            }
+        let condition = children.and_then(|c| get_string_value(c, "condition"));
+
+        let runs_on = match children.and_then(|c| find_node(c, "runs-on")) {
+            Some(node) => parse_labels(node, source, walk)?,
+            None => Vec::new(),
+        };
+
+        let mut fragment = ParsedFragment::inline(0, run_script)
+            .with_machine(machine)
+            .with_runs_on(runs_on);
 
-            Ok(vec![fragment])
+        if let Some(cond) = condition {
+            fragment = fragment.with_condition(cond);
+        }
+
+        if let Some(pid) = parent_id {
+            fragment = fragment.with_parent(pid);
         }
+
+        Ok(vec![fragment])
     }
 
     /// Resolve an import URL recursively.
+    ///
+    /// `span` is the span of the URL in the importing file.
     fn resolve_import(
         &self,
         url: &str,
-        default_machine: &str,
-        visited: &mut HashSet<String>,
+        span: miette::SourceSpan,
+        source: &Arc<SourceFile>,
+        walk: &mut Walk,
         parent_id: Option<Uuid>,
-    ) -> Result<Vec<ParsedFragment>> {
+    ) -> Walked<Vec<ParsedFragment>> {
         // Check for circular imports
-        if visited.contains(url) {
-            return Err(ParseError::CircularImport(url.to_string()));
+        if walk.visited.contains(url) {
+            return walk.fail(
+                Diagnostic::new(ParseError::CircularImport(url.to_string()), source)
+                    .with_span(span, "imported again here"),
+            );
         }
 
-        visited.insert(url.to_string());
+        walk.visited.insert(url.to_string());
 
         // Fetch the content
-        let content = self.fetcher.fetch(url)?;
+        let content = match self.fetcher.fetch(url) {
+            Ok(content) => content,
+            Err(e) => {
+                return walk.fail(Diagnostic::new(e, source).with_span(span, "imported here"));
+            },
+        };
 
         // Parse as fragment file
-        let mut fragments = self.parse_fragment_file(&content, url, default_machine, visited)?;
+        let mut fragments = self.parse_fragments(&content, url, walk)?;
 
         // Set parent_id on top-level fragments from this import
         if let Some(pid) = parent_id {
@@ -252,10 +452,10 @@ impl<F: ImportFetcher> ChainParser<F> {
     fn parse_parallel(
         &self,
         node: &KdlNode,
-        default_machine: &str,
-        visited: &mut HashSet<String>,
+        source: &Arc<SourceFile>,
+        walk: &mut Walk,
         parent_id: Option<Uuid>,
-    ) -> Result<Vec<ParsedFragment>> {
+    ) -> Walked<Vec<ParsedFragment>> {
         let mut group = ParsedFragment::parallel_group(0);
 
         if let Some(pid) = parent_id {
@@ -268,7 +468,10 @@ impl<F: ImportFetcher> ChainParser<F> {
         if let Some(children) = node.children() {
             let mut child_sequence = 0;
             for child_node in children.nodes() {
-                let parsed = self.parse_node(child_node, default_machine, visited, Some(group_id))?;
+                let Ok(parsed) = self.parse_node(child_node, source, walk, Some(group_id)) else {
+                    walk.carry_on()?;
+                    continue;
+                };
                 for mut frag in parsed {
                     // Only set sequence for direct children (not nested)
                     if frag.parent_id == Some(group_id) {
@@ -284,11 +487,170 @@ impl<F: ImportFetcher> ChainParser<F> {
     }
 }
 
+/// The first error of a parse that stops at it.
+fn first_error<T>(checked: Checked<T>) -> Result<T> {
+    match checked.diagnostics.into_iter().find(Diagnostic::is_error) {
+        Some(diagnostic) => Err(diagnostic.error),
+        None => Ok(checked.value.expect("a parse without errors has a value")),
+    }
+}
+
+/// Parse a KDL document, recording its syntax errors.
+fn parse_document(source: &Arc<SourceFile>, walk: &mut Walk) -> Walked<KdlDocument> {
+    read_document(source).map_err(|diagnostics| {
+        walk.diagnostics.extend(diagnostics);
+        Stop
+    })
+}
+
+/// Parse a KDL document, locating its syntax errors.
+pub(crate) fn read_document(
+    source: &Arc<SourceFile>,
+) -> std::result::Result<KdlDocument, Vec<Diagnostic>> {
+    source.content.parse().map_err(|error: KdlError| {
+        error
+            .diagnostics
+            .into_iter()
+            .map(|diagnostic| {
+                let message = diagnostic
+                    .message
+                    .unwrap_or_else(|| "unexpected input".to_string());
+                let located = Diagnostic::new(ParseError::InvalidSyntax(message), source)
+                    .with_span(
+                        diagnostic.span,
+                        diagnostic.label.unwrap_or_else(|| "here".to_string()),
+                    );
+                match diagnostic.help {
+                    Some(help) => located.with_help(help),
+                    None => located,
+                }
+            })
+            .collect()
+    })
+}
+
+/// Warning for the `machine` node of a fragment that has no effect.
+fn unused_machine(node: &KdlNode, source: &Arc<SourceFile>) -> Diagnostic {
+    let machine = string_entry(node)
+        .map(|(machine, _)| machine)
+        .unwrap_or_default();
+    Diagnostic::warning(ParseError::UnusedMachine(machine), source)
+}
+
+/// Warning for a workflow of a deprecated version.
+fn deprecated_version(
+    version: &Version,
+    span: miette::SourceSpan,
+    source: &Arc<SourceFile>,
+) -> Diagnostic {
+    Diagnostic::warning(
+        ParseError::DeprecatedVersion(version.name.to_string()),
+        source,
+    )
+    .with_span(span, "deprecated version")
+    .with_help(format!(
+        "run `vulcan-parse migrate` to upgrade the workflow to version \"{}\"",
+        Version::latest().name
+    ))
+}
+
+/// Error for a workflow of an unsupported version.
+pub(crate) fn unsupported_version(
+    name: String,
+    span: miette::SourceSpan,
+    source: &Arc<SourceFile>,
+) -> Diagnostic {
+    Diagnostic::new(ParseError::UnsupportedVersion(name), source)
+        .with_span(span, "unsupported version")
+        .with_help(format!("use version {}", Version::supported()))
+}
+
+/// Parse a `checkout` node into checkout options.
+fn parse_checkout(
+    node: &KdlNode,
+    source: &Arc<SourceFile>,
+    walk: &mut Walk,
+) -> Walked<ParsedCheckout> {
+    let Some(children) = node.children() else {
+        return Ok(ParsedCheckout::default());
+    };
+
+    let invalid = |reason: String, span| {
+        Diagnostic::new(ParseError::InvalidCheckout(reason), source)
+            .with_span(span, "invalid depth")
+    };
+    let depth = match find_node(children, "depth").and_then(|node| node.entries().first()) {
+        Some(entry) => match entry.value().as_integer() {
+            Some(depth) if depth > 0 => match i32::try_from(depth) {
+                Ok(depth) => Some(depth),
+                Err(_) => {
+                    return walk.fail(invalid(format!("depth {depth} is too large"), entry.span()));
+                },
+            },
+            Some(depth) => {
+                return walk.fail(invalid(
+                    format!("depth must be positive, got {depth}"),
+                    entry.span(),
+                ));
+            },
+            None => None,
+        },
+        None => None,
+    };
+
+    let sparse_paths = get_string_args(children, "sparse").unwrap_or_default();
+
+    Ok(ParsedCheckout {
+        depth,
+        sparse_paths,
+    })
+}
+
+/// Validate the labels of a `runs-on` node, dropping duplicates.
+///
+/// A label is either a bare tag (`docker`) or a `key=value` pair (`arch=x86_64`).
+fn parse_labels(node: &KdlNode, source: &Arc<SourceFile>, walk: &mut Walk) -> Walked<Vec<String>> {
+    let mut result: Vec<String> = Vec::with_capacity(node.entries().len());
+
+    for entry in node.entries() {
+        let Some(label) = entry.value().as_string() else {
+            continue;
+        };
+        let valid = !label.is_empty()
+            && !label.starts_with('=')
+            && !label.chars().any(|c| c.is_whitespace() || c == ',');
+        if !valid {
+            return walk.fail(
+                Diagnostic::new(ParseError::InvalidLabel(format!("{label:?}")), source)
+                    .with_span(entry.span(), "invalid label")
+                    .with_help(
+                        "labels are tags like `docker` or `key=value` pairs like `arch=x86_64`",
+                    ),
+            );
+        }
+        if !result.iter().any(|existing| existing == label) {
+            result.push(label.to_string());
+        }
+    }
+
+    Ok(result)
+}
+
+/// Find the first node with the given name.
+fn find_node<'a>(doc: &'a KdlDocument, node_name: &str) -> Option<&'a KdlNode> {
+    doc.nodes().iter().find(|n| n.name().value() == node_name)
+}
+
+/// Get a node's first argument as a string, with its span.
+fn string_entry(node: &KdlNode) -> Option<(String, miette::SourceSpan)> {
+    let entry = node.entries().first()?;
+    let value = entry.value().as_string()?;
+    Some((value.to_string(), entry.span()))
+}
+
 /// Get a string value from a node's first argument.
 fn get_string_value(doc: &KdlDocument, node_name: &str) -> Option<String> {
-    doc.nodes()
-        .iter()
-        .find(|n| n.name().value() == node_name)
+    find_node(doc, node_name)
         .and_then(|node| node.entries().first())
         .and_then(|entry| entry.value().as_string())
         .map(String::from)
@@ -296,9 +658,7 @@ fn get_string_value(doc: &KdlDocument, node_name: &str) -> Option<String> {
 
 /// Get all string arguments from a node.
 fn get_string_args(doc: &KdlDocument, node_name: &str) -> Option<Vec<String>> {
-    doc.nodes()
-        .iter()
-        .find(|n| n.name().value() == node_name)
+    find_node(doc, node_name)
         .map(|node| {
             node.entries()
                 .iter()
@@ -3,6 +3,7 @@
 use std::collections::HashMap;
 
 use crate::ast::ParsedFragmentType;
+use crate::diagnostic::{Diagnostic, Severity};
 use crate::error::{ParseError, Result};
 use crate::parser::{ChainParser, ImportFetcher};
 
@@ -248,3 +249,409 @@ chain {
 
     assert_eq!(chain.triggers, vec!["push", "pull_request", "tag"]);
 }
+
+#[test]
+fn test_parse_checkout_options() {
+    let content = r#"
+version "0.1"
+triggers "push"
+
+chain {
+    machine "default-worker"
+
+    checkout {
+        depth 1
+        sparse "src" "Cargo.toml"
+    }
+
+    fragment { run "cargo build" }
+}
+"#;
+
+    let parser = ChainParser::new(MockFetcher::new());
+    let chain = parser.parse_workflow(content, None).unwrap();
+
+    let checkout = chain.checkout.expect("checkout options should be parsed");
+    assert_eq!(checkout.depth, Some(1));
+    assert_eq!(checkout.sparse_paths, vec!["src", "Cargo.toml"]);
+    assert_eq!(chain.fragments.len(), 1);
+    assert_eq!(chain.fragments[0].sequence, 0);
+}
+
+#[test]
+fn test_invalid_checkout_depth_error() {
+    let content = r#"
+version "0.1"
+triggers "push"
+
+chain {
+    machine "default-worker"
+    checkout { depth 0 }
+
+    fragment { run "cargo build" }
+}
+"#;
+
+    let parser = ChainParser::new(MockFetcher::new());
+    let result = parser.parse_workflow(content, None);
+
+    assert!(matches!(result, Err(ParseError::InvalidCheckout(_))));
+}
+
+#[test]
+fn test_parse_runs_on_labels() {
+    let content = r#"
+version "0.1"
+triggers "push"
+
+chain {
+    machine "default-worker"
+
+    fragment {
+        run "nvidia-smi"
+        runs-on "os=linux" "gpu=nvidia" "docker" "docker"
+    }
+    fragment { run "cargo build" }
+}
+"#;
+
+    let parser = ChainParser::new(MockFetcher::new());
+    let chain = parser.parse_workflow(content, None).unwrap();
+
+    assert_eq!(chain.fragments.len(), 2);
+    assert_eq!(chain.fragments[0].runs_on, vec!["os=linux", "gpu=nvidia", "docker"]);
+    assert!(chain.fragments[1].runs_on.is_empty());
+}
+
+#[test]
+fn test_invalid_runs_on_label_error() {
+    let content = r#"
+version "0.1"
+triggers "push"
+
+chain {
+    machine "default-worker"
+
+    fragment {
+        run "cargo build"
+        runs-on "os=linux,arch=arm64"
+    }
+}
+"#;
+
+    let parser = ChainParser::new(MockFetcher::new());
+    let result = parser.parse_workflow(content, None);
+
+    assert!(matches!(result, Err(ParseError::InvalidLabel(_))));
+}
+
+#[test]
+fn test_check_reports_every_problem() {
+    let content = r#"version "0.1"
+triggers "push"
+
+chain {
+    machine "default-worker"
+
+    fragmnet { run "npm build" }
+    fragment { machine "gpu" }
+    parallel {
+        fragment { run "npm test"; runs-on "arch =x86" }
+        fragment { run "npm lint" }
+    }
+}
+"#;
+
+    let parser = ChainParser::new(MockFetcher::new());
+    let checked = parser.check_workflow(content, Some(".vulcan/ci.kdl"));
+
+    assert!(checked.value.is_none());
+    // (code, line, column, label)
+    let found: Vec<_> = checked
+        .diagnostics
+        .iter()
+        .map(|d| {
+            let location = d.location().unwrap();
+            (d.code(), location.line, location.column, d.label.as_deref())
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("vulcan::unknown_node", 7, 5, Some("unknown node")),
+            (
+                "vulcan::no_content",
+                8,
+                5,
+                Some("fragment without `run` or `from`")
+            ),
+            ("vulcan::invalid_label", 10, 44, Some("invalid label")),
+        ]
+    );
+
+    let unknown = &checked.diagnostics[0];
+    assert_eq!(unknown.file(), Some(".vulcan/ci.kdl"));
+    assert_eq!(
+        unknown.snippet(),
+        Some("    fragmnet { run \"npm build\" }")
+    );
+    assert_eq!(
+        unknown.to_string(),
+        ".vulcan/ci.kdl:7:5: unknown node type: fragmnet"
+    );
+
+    // Parsing stops at the first of them
+    let result = parser.parse_workflow(content, Some(".vulcan/ci.kdl"));
+    assert!(matches!(result, Err(ParseError::UnknownNode(_))));
+}
+
+#[test]
+fn test_check_locates_syntax_errors() {
+    let content = "version \"0.1\"\ntriggers \"push\"\nchain {\n    fragment { run \"é\" }}\n}\n";
+
+    let parser = ChainParser::new(MockFetcher::new());
+    let checked = parser.check_workflow(content, None);
+
+    let first = &checked.diagnostics[0];
+    assert_eq!(first.code(), "vulcan::invalid_syntax");
+    assert!(first.span.is_some());
+    assert!(first.location().unwrap().line >= 4);
+    assert!(checked.value.is_none());
+}
+
+#[test]
+fn test_check_locates_problems_in_imports() {
+    let imported = r#"
+fragment { run "npm install" }
+deploy { run "npm deploy" }
+"#;
+
+    let workflow = r#"version "0.1"
+triggers "push"
+
+chain {
+    machine "default-worker"
+
+    fragment { from "https://example.com/build.kdl" }
+    fragment { from "https://example.com/missing.kdl" }
+}
+"#;
+
+    let fetcher = MockFetcher::new().with_response("https://example.com/build.kdl", imported);
+    let parser = ChainParser::new(fetcher);
+    let checked = parser.check_workflow(workflow, Some(".vulcan/ci.kdl"));
+
+    // (file, code, line, column)
+    let found: Vec<_> = checked
+        .diagnostics
+        .iter()
+        .map(|d| {
+            let location = d.location().unwrap();
+            (d.file(), d.code(), location.line, location.column)
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (
+                Some("https://example.com/build.kdl"),
+                "vulcan::invalid_import_node",
+                3,
+                1
+            ),
+            (Some(".vulcan/ci.kdl"), "vulcan::fetch_failed", 8, 21),
+        ]
+    );
+}
+
+#[test]
+fn test_check_valid_workflow() {
+    let content = r#"
+version "0.1"
+triggers "push"
+
+chain {
+    machine "default-worker"
+    fragment { run "npm build" }
+}
+"#;
+
+    let parser = ChainParser::new(MockFetcher::new());
+    let checked = parser.check_workflow(content, None);
+
+    assert!(checked.diagnostics.is_empty());
+    assert_eq!(checked.value.unwrap().fragments.len(), 1);
+}
+
+#[test]
+fn test_validation_rejects_unknown_duplicate_and_mistyped_entries() {
+    let content = r#"version "0.1"
+triggers "push"
+
+chain {
+    machine "default-worker"
+    checkout { depth "1" }
+    fragment { run "npm build"; mahcine "gpu" }
+    fragment { run "npm test"; run "npm lint" }
+    fragment { run 42 }
+    fragment { run "npm audit" retry=3 }
+    fragment { run "npm lint" { retry 3 } }
+}
+"#;
+
+    let parser = ChainParser::new(MockFetcher::new());
+    let checked = parser.check_workflow(content, None);
+
+    assert!(checked.value.is_none());
+    // (code, line, column, label)
+    let found: Vec<_> = checked
+        .diagnostics
+        .iter()
+        .map(|d| {
+            let location = d.location().unwrap();
+            (d.code(), location.line, location.column, d.label.as_deref())
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("vulcan::invalid_value", 6, 22, Some("a string")),
+            ("vulcan::unknown_node", 7, 33, Some("unknown node")),
+            ("vulcan::duplicate_node", 8, 32, Some("given again here")),
+            ("vulcan::invalid_value", 9, 20, Some("an integer")),
+            ("vulcan::unknown_property", 10, 32, Some("unknown property")),
+            ("vulcan::invalid_value", 11, 33, Some("unexpected children")),
+        ]
+    );
+
+    let typo = &checked.diagnostics[1];
+    assert_eq!(typo.help.as_deref(), Some("did you mean `machine`?"));
+    assert_eq!(typo.error.to_string(), "unknown node type: mahcine");
+    assert_eq!(
+        checked.diagnostics[0].error.to_string(),
+        "invalid depth: expected a single integer"
+    );
+}
+
+#[test]
+fn test_validation_warns_about_suspicious_fragments() {
+    let content = r#"version "0.1"
+triggers "push"
+
+chain {
+    machine "default-worker"
+    fragment { run "  " }
+    fragment { run "npm build"; machine "default-worker" }
+    fragment { from "https://example.com/test.kdl"; machine "gpu" }
+}
+"#;
+
+    let fetcher = MockFetcher::new()
+        .with_response("https://example.com/test.kdl", r#"fragment { run "npm test" }"#);
+    let parser = ChainParser::new(fetcher);
+    let checked = parser.check_workflow(content, None);
+
+    // Warnings keep the workflow
+    assert!(!checked.has_errors());
+    assert_eq!(checked.value.as_ref().unwrap().fragments.len(), 3);
+    let found: Vec<_> = checked
+        .diagnostics
+        .iter()
+        .map(|d| (d.code(), d.severity, d.location().unwrap().line))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("vulcan::empty_script", Severity::Warning, 6),
+            ("vulcan::unused_machine", Severity::Warning, 7),
+            ("vulcan::unused_machine", Severity::Warning, 8),
+        ]
+    );
+    assert!(parser.parse_workflow(content, None).is_ok());
+
+    // Strict checks fail on them
+    let strict = checked.promote_warnings();
+    assert!(strict.value.is_none());
+    assert!(strict.diagnostics.iter().all(Diagnostic::is_error));
+}
+
+#[test]
+fn test_unsupported_version_error() {
+    let content = r#"version "9.9"
+triggers "push"
+
+chain {
+    machine "default-worker"
+    fragment { run "npm build" }
+}
+"#;
+
+    let parser = ChainParser::new(MockFetcher::new());
+    let checked = parser.check_workflow(content, None);
+
+    assert!(checked.value.is_none());
+    assert_eq!(checked.diagnostics.len(), 1);
+    let diagnostic = &checked.diagnostics[0];
+    assert_eq!(diagnostic.code(), "vulcan::unsupported_version");
+    assert_eq!(
+        diagnostic.help.as_deref(),
+        Some("use version \"0.0\" or \"0.1\"")
+    );
+    assert!(matches!(
+        parser.parse_workflow(content, None),
+        Err(ParseError::UnsupportedVersion(version)) if version == "9.9"
+    ));
+}
+
+#[test]
+fn test_deprecated_version_is_read_with_a_warning() {
+    let workflow = r#"version "0.0"
+triggers "push"
+
+chain {
+    machine "default-worker"
+    fragment { script "npm ci" }
+    fragment { from "https://example.com/test.kdl" }
+}
+"#;
+
+    // Imported files are read as the workflow's version
+    let fetcher = MockFetcher::new()
+        .with_response("https://example.com/test.kdl", r#"fragment { script "npm test" }"#);
+    let parser = ChainParser::new(fetcher);
+    let checked = parser.check_workflow(workflow, Some("ci.kdl"));
+
+    assert!(!checked.has_errors());
+    let scripts: Vec<_> = checked
+        .value
+        .as_ref()
+        .unwrap()
+        .fragments
+        .iter()
+        .map(|fragment| fragment.run_script.as_deref())
+        .collect();
+    assert_eq!(scripts, vec![Some("npm ci"), Some("npm test")]);
+
+    // (code, severity, line, column)
+    let found: Vec<_> = checked
+        .diagnostics
+        .iter()
+        .map(|d| {
+            let location = d.location().unwrap();
+            (d.code(), d.severity, location.line, location.column)
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![("vulcan::deprecated_version", Severity::Warning, 1, 9)]
+    );
+    assert_eq!(
+        checked.diagnostics[0].help.as_deref(),
+        Some("run `vulcan-parse migrate` to upgrade the workflow to version \"0.1\"")
+    );
+
+    // A warning keeps the workflow, unless warnings are errors
+    assert!(parser.parse_workflow(workflow, None).is_ok());
+    let strict = parser.check_workflow(workflow, None).promote_warnings();
+    assert!(strict.value.is_none());
+}
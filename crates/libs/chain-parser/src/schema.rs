@@ -0,0 +1,334 @@
+//! Declared schema of workflow files, per version of the format.
+//!
+//! A [`Schema`] lists the nodes a workflow may hold, with the values and children
+//! each of them takes. Every [`Version`](crate::versions::Version) of the format
+//! declares its own. Documents are checked against it before they are parsed:
+//! unknown, repeated and mistyped nodes, properties, and missing required nodes are
+//! errors.
+
+use std::sync::Arc;
+
+use kdl::{KdlDocument, KdlEntry, KdlNode, KdlValue};
+
+use crate::diagnostic::{Diagnostic, SourceFile};
+use crate::error::ParseError;
+
+/// Values a node takes as arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Args {
+    /// No arguments.
+    None,
+    /// A single string.
+    String,
+    /// One or more strings.
+    Strings,
+    /// A single integer.
+    Integer,
+}
+
+impl Args {
+    /// Description of the arguments, for diagnostics.
+    #[must_use]
+    pub const fn expected(self) -> &'static str {
+        match self {
+            Self::None => "no arguments",
+            Self::String => "a single string",
+            Self::Strings => "one or more strings",
+            Self::Integer => "a single integer",
+        }
+    }
+
+    /// Whether a value is of the type of the arguments.
+    fn accepts(self, value: &KdlValue) -> bool {
+        match self {
+            Self::None => false,
+            Self::String | Self::Strings => value.is_string(),
+            Self::Integer => value.is_integer(),
+        }
+    }
+}
+
+/// A node of a workflow file.
+#[derive(Debug)]
+pub struct NodeSchema {
+    /// Name of the node.
+    pub name: &'static str,
+    /// Arguments of the node.
+    pub args: Args,
+    /// Nodes of its children block, or `None` if it takes no children.
+    pub children: Option<&'static [&'static Self]>,
+    /// Whether the node must be given.
+    pub required: bool,
+    /// Whether the node may be given more than once.
+    pub repeatable: bool,
+    /// How to add the node, when a required node is missing.
+    pub hint: &'static str,
+}
+
+impl NodeSchema {
+    /// A node taking arguments and no children.
+    pub(crate) const fn leaf(name: &'static str, args: Args) -> Self {
+        Self {
+            name,
+            args,
+            children: None,
+            required: false,
+            repeatable: false,
+            hint: "",
+        }
+    }
+
+    /// A node taking a children block and no arguments.
+    pub(crate) const fn block(name: &'static str, children: &'static [&'static Self]) -> Self {
+        Self {
+            children: Some(children),
+            ..Self::leaf(name, Args::None)
+        }
+    }
+
+    /// Make the node required, with a hint on how to add it.
+    pub(crate) const fn required(mut self, hint: &'static str) -> Self {
+        self.required = true;
+        self.hint = hint;
+        self
+    }
+
+    /// Allow the node more than once.
+    pub(crate) const fn repeatable(mut self) -> Self {
+        self.repeatable = true;
+        self
+    }
+}
+
+/// Schema of a version of the workflow format.
+#[derive(Debug)]
+pub struct Schema {
+    /// Nodes at the root of a workflow file.
+    pub workflow: &'static [&'static NodeSchema],
+    /// Nodes at the root of an imported fragment file.
+    pub fragments: &'static [&'static NodeSchema],
+}
+
+impl Schema {
+    /// Check a workflow file against the schema.
+    #[must_use]
+    pub fn validate_workflow(
+        &self,
+        doc: &KdlDocument,
+        source: &Arc<SourceFile>,
+    ) -> Vec<Diagnostic> {
+        validate(doc, self.workflow, ParseError::UnknownNode, source)
+    }
+
+    /// Check an imported fragment file against the schema.
+    #[must_use]
+    pub fn validate_fragments(
+        &self,
+        doc: &KdlDocument,
+        source: &Arc<SourceFile>,
+    ) -> Vec<Diagnostic> {
+        validate(doc, self.fragments, ParseError::InvalidImportNode, source)
+    }
+}
+
+/// Check the nodes of a document against the nodes allowed at its root.
+///
+/// `unknown` makes the error for a root node the schema does not allow. Problems
+/// are returned in the order of the document, with missing nodes after the
+/// nodes of the block they are missing from.
+fn validate(
+    doc: &KdlDocument,
+    allowed: &[&NodeSchema],
+    unknown: fn(String) -> ParseError,
+    source: &Arc<SourceFile>,
+) -> Vec<Diagnostic> {
+    let mut validator = Validator {
+        source,
+        diagnostics: Vec::new(),
+    };
+    validator.block(doc, allowed, None, unknown);
+    validator.diagnostics
+}
+
+/// State of validating a document.
+struct Validator<'a> {
+    source: &'a Arc<SourceFile>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator<'_> {
+    /// Validate the nodes of a block, the root when `parent` is `None`.
+    fn block(
+        &mut self,
+        doc: &KdlDocument,
+        allowed: &[&NodeSchema],
+        parent: Option<&KdlNode>,
+        unknown: fn(String) -> ParseError,
+    ) {
+        let mut seen: Vec<&str> = Vec::new();
+
+        for node in doc.nodes() {
+            let name = node.name().value();
+            let Some(schema) = allowed.iter().find(|schema| schema.name == name) else {
+                let help = similar(name, allowed).map_or_else(
+                    || format!("expected {}", list(allowed)),
+                    |similar| format!("did you mean `{similar}`?"),
+                );
+                self.report(
+                    Diagnostic::new(unknown(name.to_string()), self.source)
+                        .with_span(node.name().span(), "unknown node")
+                        .with_help(help),
+                );
+                continue;
+            };
+
+            if !schema.repeatable && seen.contains(&name) {
+                self.report(
+                    Diagnostic::new(ParseError::DuplicateNode(name.to_string()), self.source)
+                        .with_span(node.name().span(), "given again here")
+                        .with_help(format!("remove all but one `{name}`")),
+                );
+            }
+            seen.push(name);
+
+            self.node(node, schema);
+        }
+
+        let context = parent.map_or_else(
+            || "workflow root".to_string(),
+            |parent| format!("{} node", parent.name().value()),
+        );
+        for schema in allowed {
+            if !schema.required || seen.contains(&schema.name) {
+                continue;
+            }
+            let mut diagnostic = Diagnostic::new(
+                ParseError::MissingRequired {
+                    field: schema.name,
+                    context: context.clone(),
+                },
+                self.source,
+            )
+            .with_help(schema.hint);
+            if let Some(parent) = parent {
+                let label = format!("{} without `{}`", parent.name().value(), schema.name);
+                diagnostic = diagnostic.with_span(parent.name().span(), label);
+            }
+            self.report(diagnostic);
+        }
+    }
+
+    /// Validate the values and children of a node.
+    fn node(&mut self, node: &KdlNode, schema: &NodeSchema) {
+        let name = schema.name;
+        let invalid = |source| {
+            Diagnostic::new(
+                ParseError::InvalidValue {
+                    node: name.to_string(),
+                    expected: schema.args.expected(),
+                },
+                source,
+            )
+        };
+
+        let mut args = 0;
+        for entry in node.entries() {
+            if let Some(key) = entry.name() {
+                self.report(
+                    Diagnostic::new(
+                        ParseError::UnknownProperty(key.value().to_string()),
+                        self.source,
+                    )
+                    .with_span(entry.span(), "unknown property")
+                    .with_help(format!("`{name}` takes {}", schema.args.expected())),
+                );
+                continue;
+            }
+
+            args += 1;
+            let single = matches!(schema.args, Args::String | Args::Integer);
+            if schema.args == Args::None || (single && args > 1) {
+                self.report(invalid(self.source).with_span(entry.span(), "unexpected argument"));
+            } else if !schema.args.accepts(entry.value()) {
+                self.report(invalid(self.source).with_span(entry.span(), describe(entry)));
+            }
+        }
+        if args == 0 && schema.args != Args::None {
+            self.report(invalid(self.source).with_span(node.name().span(), "no value"));
+        }
+
+        match (node.children(), schema.children) {
+            (Some(children), Some(allowed)) => {
+                self.block(children, allowed, Some(node), ParseError::UnknownNode);
+            },
+            (Some(children), None) => self.report(
+                Diagnostic::new(
+                    ParseError::InvalidValue {
+                        node: name.to_string(),
+                        expected: "no children",
+                    },
+                    self.source,
+                )
+                .with_span(children.span(), "unexpected children")
+                .with_help(format!("`{name}` takes {}", schema.args.expected())),
+            ),
+            (None, _) => {},
+        }
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+}
+
+/// Label for a value of the wrong type.
+fn describe(entry: &KdlEntry) -> &'static str {
+    match entry.value() {
+        KdlValue::String(_) => "a string",
+        KdlValue::Integer(_) => "an integer",
+        KdlValue::Float(_) => "a number",
+        KdlValue::Bool(_) => "a boolean",
+        KdlValue::Null => "null",
+    }
+}
+
+/// The allowed node a misspelled name is closest to, if it is close enough.
+fn similar<'a>(name: &str, allowed: &[&'a NodeSchema]) -> Option<&'a str> {
+    let threshold = (name.chars().count() / 3).max(1);
+    allowed
+        .iter()
+        .map(|schema| (edit_distance(name, schema.name), schema.name))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name)
+}
+
+/// Levenshtein distance between two strings, in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// The names of nodes, as "`a`, `b` or `c`".
+fn list(allowed: &[&NodeSchema]) -> String {
+    let names: Vec<String> = allowed
+        .iter()
+        .map(|schema| format!("`{}`", schema.name))
+        .collect();
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} or {last}", rest.join(", ")),
+        Some((last, _)) => last.clone(),
+        None => "no nodes".to_string(),
+    }
+}
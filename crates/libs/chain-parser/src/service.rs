@@ -8,6 +8,7 @@ use vulcan_core::models::chain::{NewChain, TriggerType};
 use vulcan_core::models::fragment::{FragmentType, NewFragment};
 
 use crate::ast::{ParsedChain, ParsedFragment, ParsedFragmentType};
+use crate::diagnostic::{Checked, Diagnostic, SourceFile};
 use crate::error::{ParseError, Result};
 use crate::parser::{ChainParser, ImportFetcher};
 
@@ -118,13 +119,7 @@ impl<F: ImportFetcher> ChainParserService<F> {
 
         // Validate trigger matches if provided
         if let Some(trigger) = context.trigger {
-            let trigger_str = trigger_type_to_str(trigger);
-            if !parsed.triggers.iter().any(|t| t == trigger_str) {
-                return Err(ParseError::InvalidTrigger(format!(
-                    "workflow does not support trigger '{trigger_str}', only: {:?}",
-                    parsed.triggers
-                )));
-            }
+            check_trigger(&parsed, trigger)?;
         }
 
         let chain = self.create_new_chain(&parsed, context);
@@ -153,12 +148,43 @@ impl<F: ImportFetcher> ChainParserService<F> {
         Ok(ParsedWorkflow { chain, fragments })
     }
 
+    /// Check a workflow file, collecting every problem in it.
+    ///
+    /// Like [`parse`](Self::parse), this validates that the workflow's triggers match
+    /// the context trigger, if any. The workflow is ready for database storage if
+    /// there are no errors.
+    pub fn check(&self, content: &str, context: &WorkflowContext) -> Checked<ParsedWorkflow> {
+        let source_url = context.source_file_path.as_deref();
+        let mut checked = self.parser.check_workflow(content, source_url);
+
+        if let (Some(parsed), Some(trigger)) = (&checked.value, context.trigger)
+            && let Err(e) = check_trigger(parsed, trigger)
+        {
+            let source = SourceFile::new(source_url, content);
+            checked.diagnostics.push(Diagnostic::new(e, &source));
+            checked.value = None;
+        }
+
+        checked.map(|parsed| {
+            let chain = self.create_new_chain(&parsed, context);
+            let fragments = self.create_new_fragments(&parsed, chain.id);
+            ParsedWorkflow { chain, fragments }
+        })
+    }
+
     /// Create a `NewChain` from the parsed chain and context.
     fn create_new_chain(&self, parsed: &ParsedChain, context: &WorkflowContext) -> NewChain {
         let mut chain = NewChain::new(context.tenant_id);
         chain.id = parsed.id;
         chain.default_machine = Some(parsed.default_machine.clone());
 
+        if let Some(ref checkout) = parsed.checkout {
+            chain.checkout_depth = checkout.depth;
+            if !checkout.sparse_paths.is_empty() {
+                chain.checkout_sparse_paths = Some(checkout.sparse_paths.clone());
+            }
+        }
+
         if let Some(ref path) = context.source_file_path {
             chain.source_file_path = Some(path.clone());
         }
@@ -210,6 +236,7 @@ impl<F: ImportFetcher> ChainParserService<F> {
         fragment.id = parsed.id;
         fragment.parent_fragment_id = parsed.parent_id;
         fragment.is_parallel = parsed.is_parallel;
+        fragment.runs_on.clone_from(&parsed.runs_on);
 
         if let Some(ref machine) = parsed.machine {
             fragment.machine = Some(machine.clone());
@@ -225,6 +252,19 @@ impl<F: ImportFetcher> ChainParserService<F> {
     }
 }
 
+/// Validate that a workflow supports a trigger.
+fn check_trigger(parsed: &ParsedChain, trigger: TriggerType) -> Result<()> {
+    let trigger_str = trigger_type_to_str(trigger);
+    if parsed.triggers.iter().any(|t| t == trigger_str) {
+        Ok(())
+    } else {
+        Err(ParseError::InvalidTrigger(format!(
+            "workflow does not support trigger '{trigger_str}', only: {:?}",
+            parsed.triggers
+        )))
+    }
+}
+
 /// Convert a `TriggerType` to its string representation for matching.
 const fn trigger_type_to_str(trigger: TriggerType) -> &'static str {
     match trigger {
@@ -97,3 +97,35 @@ chain {
 
     assert!(result.is_ok());
 }
+
+#[test]
+fn test_check_collects_trigger_mismatch() {
+    let content = r#"
+version "0.1"
+triggers "push"
+
+chain {
+    machine "default-worker"
+
+    fragment { run "npm build" }
+}
+"#;
+
+    let service = ChainParserService::new(MockFetcher);
+    let context = WorkflowContext::new(Uuid::new_v4())
+        .with_source(".vulcan/ci.kdl".to_string())
+        .with_trigger(TriggerType::PullRequest, Some("123".to_string()));
+
+    let checked = service.check(content, &context);
+
+    assert!(checked.value.is_none());
+    assert_eq!(checked.diagnostics.len(), 1);
+    assert!(matches!(
+        checked.diagnostics[0].error,
+        ParseError::InvalidTrigger(_)
+    ));
+    assert_eq!(checked.diagnostics[0].file(), Some(".vulcan/ci.kdl"));
+
+    let checked = service.check(content, &WorkflowContext::new(Uuid::new_v4()));
+    assert_eq!(checked.value.unwrap().fragments.len(), 1);
+}
@@ -0,0 +1,123 @@
+//! Versions of the workflow format.
+//!
+//! Every supported version has a [`Version`] in [`VERSIONS`], with the schema its
+//! files are validated against and a front-end reading them. The parser lowers
+//! documents of the newest version to the [`ParsedChain`](crate::ast::ParsedChain)
+//! AST; the front-end of an older version rewrites its documents into the next
+//! version first, which is also how [`migrate`](crate::migrate) upgrades files.
+//!
+//! Version 0.1 is the only one so far, so it has no front-end and files are
+//! never rewritten. Tests register a deprecated version 0.0 before it.
+
+#[cfg(test)]
+mod v0_0;
+mod v0_1;
+
+use std::sync::Arc;
+
+use kdl::KdlDocument;
+
+use crate::diagnostic::{Diagnostic, SourceFile};
+use crate::schema::Schema;
+
+/// Rewrites a document of a version into the next version, returning the problems
+/// found in it.
+type Upgrade = fn(&mut KdlDocument, &Arc<SourceFile>) -> Vec<Diagnostic>;
+
+/// A supported version of the workflow format.
+#[derive(Debug)]
+pub struct Version {
+    /// The version, as given by the `version` node.
+    pub name: &'static str,
+    /// Nodes files of the version may hold.
+    pub schema: &'static Schema,
+    /// Whether workflows should be migrated off the version.
+    pub deprecated: bool,
+    /// Front-end rewriting documents into the next version, `None` for the newest.
+    upgrade: Option<Upgrade>,
+}
+
+/// Supported versions, oldest first.
+#[cfg(not(test))]
+pub static VERSIONS: &[&Version] = &[&v0_1::VERSION];
+
+/// Supported versions, oldest first.
+#[cfg(test)]
+pub static VERSIONS: &[&Version] = &[&v0_0::VERSION, &v0_1::VERSION];
+
+impl Version {
+    /// A supported version, by name.
+    #[must_use]
+    pub fn find(name: &str) -> Option<&'static Self> {
+        VERSIONS
+            .iter()
+            .copied()
+            .find(|version| version.name == name)
+    }
+
+    /// The newest version.
+    #[must_use]
+    pub fn latest() -> &'static Self {
+        VERSIONS[VERSIONS.len() - 1]
+    }
+
+    /// Whether this is the newest version.
+    #[must_use]
+    pub fn is_latest(&self) -> bool {
+        self.upgrade.is_none()
+    }
+
+    /// The names of the supported versions, quoted and joined with "or".
+    #[must_use]
+    pub fn supported() -> String {
+        let names: Vec<String> = VERSIONS
+            .iter()
+            .map(|version| format!("\"{}\"", version.name))
+            .collect();
+        match names.split_last() {
+            Some((last, rest)) if !rest.is_empty() => format!("{} or {last}", rest.join(", ")),
+            _ => names.concat(),
+        }
+    }
+
+    /// Rewrite a document of this version into the newest version, editing it in
+    /// place so its comments and formatting are kept.
+    ///
+    /// Nodes keep the spans they have in the original file, for diagnostics.
+    pub(crate) fn upgrade(
+        &self,
+        doc: &mut KdlDocument,
+        source: &Arc<SourceFile>,
+    ) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut version = self;
+        while let Some(upgrade) = version.upgrade {
+            diagnostics.extend(upgrade(doc, source));
+            version = version.next();
+        }
+
+        if version.name != self.name
+            && let Some(entry) = doc
+                .get_mut("version")
+                .and_then(|node| node.entries_mut().first_mut())
+        {
+            entry.set_value(version.name);
+            if let Some(format) = entry.format_mut() {
+                format.value_repr = format!("\"{}\"", version.name);
+            }
+        }
+        diagnostics
+    }
+
+    /// The version after this one.
+    fn next(&self) -> &'static Self {
+        let index = VERSIONS
+            .iter()
+            .position(|version| version.name == self.name)
+            .expect("versions are registered");
+        VERSIONS
+            .get(index + 1)
+            .copied()
+            .unwrap_or_else(Self::latest)
+    }
+}
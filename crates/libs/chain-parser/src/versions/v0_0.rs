@@ -0,0 +1,70 @@
+//! A version before 0.1, registered in tests only, where fragments give their
+//! script with `script` instead of `run`.
+//!
+//! It exercises what real versions will go through once 0.1 is superseded:
+//! deprecation warnings, and rewriting documents into the next version.
+
+use std::sync::Arc;
+
+use kdl::{KdlDocument, KdlIdentifier};
+
+use super::Version;
+use super::v0_1::{CHECKOUT, CONDITION, FROM, MACHINE, MACHINE_DEFAULT, RUNS_ON, TRIGGERS};
+use crate::diagnostic::{Diagnostic, SourceFile};
+use crate::schema::{Args, NodeSchema, Schema};
+
+/// Version 0.0.
+pub(super) static VERSION: Version = Version {
+    name: "0.0",
+    schema: &SCHEMA,
+    deprecated: true,
+    upgrade: Some(upgrade),
+};
+
+static SCHEMA: Schema = Schema {
+    workflow: &[&VERSION_NODE, &TRIGGERS, &CHAIN],
+    fragments: &[&FRAGMENT, &PARALLEL],
+};
+
+static VERSION_NODE: NodeSchema = NodeSchema::leaf("version", Args::String)
+    .required("add `version \"0.0\"` at the top of the workflow");
+static CHAIN: NodeSchema = NodeSchema::block(
+    "chain",
+    &[&MACHINE_DEFAULT, &CHECKOUT, &FRAGMENT, &PARALLEL],
+)
+.required("add a `chain { ... }` node");
+static FRAGMENT: NodeSchema = NodeSchema::block(
+    "fragment",
+    &[&SCRIPT, &FROM, &MACHINE, &RUNS_ON, &CONDITION],
+)
+.repeatable();
+static SCRIPT: NodeSchema = NodeSchema::leaf("script", Args::String);
+static PARALLEL: NodeSchema = NodeSchema::block("parallel", &[&FRAGMENT, &PARALLEL]).repeatable();
+
+/// Rewrite a 0.0 document into 0.1.
+fn upgrade(doc: &mut KdlDocument, _source: &Arc<SourceFile>) -> Vec<Diagnostic> {
+    upgrade_block(doc);
+    Vec::new()
+}
+
+/// Rename the `script` of the fragments of a block, and of the blocks in it, to `run`.
+fn upgrade_block(doc: &mut KdlDocument) {
+    for node in doc.nodes_mut() {
+        let is_fragment = node.name().value() == "fragment";
+        let Some(children) = node.children_mut() else {
+            continue;
+        };
+        if !is_fragment {
+            upgrade_block(children);
+            continue;
+        }
+
+        for child in children.nodes_mut() {
+            if child.name().value() == "script" {
+                let mut name = KdlIdentifier::from("run");
+                name.set_span(child.name().span());
+                child.set_name(name);
+            }
+        }
+    }
+}
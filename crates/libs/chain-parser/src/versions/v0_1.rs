@@ -0,0 +1,43 @@
+//! Version 0.1 of the workflow format, the newest.
+//!
+//! A fragment either runs a script with `run`, or imports the fragments of
+//! another file with `from`.
+
+use super::Version;
+use crate::schema::{Args, NodeSchema, Schema};
+
+/// Version 0.1.
+pub(super) static VERSION: Version = Version {
+    name: "0.1",
+    schema: &SCHEMA,
+    deprecated: false,
+    upgrade: None,
+};
+
+static SCHEMA: Schema = Schema {
+    workflow: &[&VERSION_NODE, &TRIGGERS, &CHAIN],
+    fragments: &[&FRAGMENT, &PARALLEL],
+};
+
+static VERSION_NODE: NodeSchema = NodeSchema::leaf("version", Args::String)
+    .required("add `version \"0.1\"` at the top of the workflow");
+pub(super) static TRIGGERS: NodeSchema = NodeSchema::leaf("triggers", Args::Strings)
+    .required("list the events, e.g. `triggers \"push\"`");
+static CHAIN: NodeSchema = NodeSchema::block(
+    "chain",
+    &[&MACHINE_DEFAULT, &CHECKOUT, &FRAGMENT, &PARALLEL],
+)
+.required("add a `chain { ... }` node");
+pub(super) static MACHINE_DEFAULT: NodeSchema = NodeSchema::leaf("machine", Args::String)
+    .required("add e.g. `machine \"default\"` to the chain");
+pub(super) static CHECKOUT: NodeSchema = NodeSchema::block("checkout", &[&DEPTH, &SPARSE]);
+static DEPTH: NodeSchema = NodeSchema::leaf("depth", Args::Integer);
+static SPARSE: NodeSchema = NodeSchema::leaf("sparse", Args::Strings);
+static FRAGMENT: NodeSchema =
+    NodeSchema::block("fragment", &[&RUN, &FROM, &MACHINE, &RUNS_ON, &CONDITION]).repeatable();
+static RUN: NodeSchema = NodeSchema::leaf("run", Args::String);
+pub(super) static FROM: NodeSchema = NodeSchema::leaf("from", Args::String);
+pub(super) static MACHINE: NodeSchema = NodeSchema::leaf("machine", Args::String);
+pub(super) static RUNS_ON: NodeSchema = NodeSchema::leaf("runs-on", Args::Strings);
+pub(super) static CONDITION: NodeSchema = NodeSchema::leaf("condition", Args::String);
+static PARALLEL: NodeSchema = NodeSchema::block("parallel", &[&FRAGMENT, &PARALLEL]).repeatable();
@@ -16,7 +16,7 @@ pub mod schema;
 pub use db::{establish_connection, run_migrations};
 pub use models::{
     chain::{Chain, ChainStatus, NewChain},
-    fragment::{Fragment, FragmentStatus, NewFragment},
+    fragment::{Fragment, FragmentStatus, NewFragment, QueueDepth},
     worker::{NewWorker, Worker, WorkerStatus},
 };
 pub use repositories::{
@@ -93,6 +93,12 @@ pub struct Chain {
     pub started_at: Option<NaiveDateTime>,
     /// When execution completed.
     pub completed_at: Option<NaiveDateTime>,
+    /// Clone depth for the workspace checkout (None = full history).
+    pub checkout_depth: Option<i32>,
+    /// Paths to materialize with a sparse checkout (None = whole tree).
+    pub checkout_sparse_paths: Option<Vec<String>>,
+    /// W3C `traceparent` of the span that stored the chain (None = not traced).
+    pub trace_context: Option<String>,
 }
 
 /// Data for creating a new chain.
@@ -121,6 +127,12 @@ pub struct NewChain {
     pub trigger_ref: Option<String>,
     /// Default machine/worker group.
     pub default_machine: Option<String>,
+    /// Clone depth for the workspace checkout.
+    pub checkout_depth: Option<i32>,
+    /// Paths for a sparse workspace checkout.
+    pub checkout_sparse_paths: Option<Vec<String>>,
+    /// W3C `traceparent` of the span storing the chain.
+    pub trace_context: Option<String>,
 }
 
 impl NewChain {
@@ -138,6 +150,9 @@ impl NewChain {
             trigger: None,
             trigger_ref: None,
             default_machine: None,
+            checkout_depth: None,
+            checkout_sparse_paths: None,
+            trace_context: None,
         }
     }
 
@@ -177,4 +192,57 @@ impl NewChain {
         self.default_machine = Some(machine);
         self
     }
+
+    /// Set the workspace checkout options.
+    #[must_use]
+    pub fn with_checkout(mut self, depth: Option<i32>, sparse_paths: Option<Vec<String>>) -> Self {
+        self.checkout_depth = depth;
+        self.checkout_sparse_paths = sparse_paths;
+        self
+    }
+
+    /// Set the trace context executions of the chain continue.
+    #[must_use]
+    pub fn with_trace_context(mut self, traceparent: String) -> Self {
+        self.trace_context = Some(traceparent);
+        self
+    }
+}
+
+/// Whether a string is a commit SHA: 7 to 64 lowercase hexadecimal digits.
+#[must_use]
+pub fn is_commit_sha(sha: &str) -> bool {
+    (7..=64).contains(&sha.len()) && sha.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Whether a string is a repository URL workers may fetch from: `https://`,
+/// `ssh://` or scp-like `user@host:path`.
+///
+/// Local repositories and other transports are refused, as are URLs and hosts that
+/// git or ssh could read as options.
+#[must_use]
+pub fn is_remote_repository_url(url: &str) -> bool {
+    if url.starts_with('-') || url.chars().any(char::is_control) {
+        return false;
+    }
+
+    let host = if let Some(rest) = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("ssh://"))
+    {
+        rest.split('/').next().unwrap_or_default()
+    } else if !url.contains("://")
+        && let Some((host, path)) = url.split_once(':')
+        // A colon before any slash, and not `<transport>::<address>`
+        && !host.contains('/')
+        && !path.is_empty()
+        && !path.starts_with(':')
+    {
+        host
+    } else {
+        return false;
+    };
+
+    let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+    !host.is_empty() && !host.starts_with('-')
 }
@@ -14,7 +14,9 @@ pub enum FragmentStatus {
     Suspended,
     /// Fragment encountered an error.
     Error,
-    /// Fragment is waiting to be executed.
+    /// Fragment is waiting for its dependencies to complete.
+    Blocked,
+    /// Fragment is ready and waiting to be claimed by a worker.
     Pending,
     /// Fragment is currently being executed.
     Running,
@@ -62,7 +64,7 @@ pub enum FragmentType {
 ///
 /// Note: Import fragments are resolved at parse time and not stored.
 /// The `source_url` field tracks where a fragment was imported from.
-#[derive(Debug, Queryable, Selectable, Identifiable)]
+#[derive(Debug, Queryable, QueryableByName, Selectable, Identifiable)]
 #[diesel(table_name = fragments)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Fragment {
@@ -105,6 +107,21 @@ pub struct Fragment {
     pub exit_code: Option<i32>,
     /// Error message if execution failed.
     pub error_message: Option<String>,
+    /// Labels a worker must have to execute this fragment.
+    pub runs_on: Vec<String>,
+}
+
+/// Number of queued fragments of a tenant, machine group and status.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueDepth {
+    /// Tenant owning the fragments' chains.
+    pub tenant_id: Uuid,
+    /// Machine group the fragments run on, or `None` for any.
+    pub machine_group: Option<String>,
+    /// Status of the fragments, `Pending` or `Running`.
+    pub status: FragmentStatus,
+    /// Number of fragments.
+    pub count: i64,
 }
 
 /// Data for creating a new fragment.
@@ -134,8 +151,10 @@ pub struct NewFragment {
     pub source_url: Option<String>,
     /// Initial attempt count.
     pub attempt: i32,
-    /// Initial status of the fragment.
+    /// Initial status of the fragment (`Blocked` until its dependencies are satisfied).
     pub status: FragmentStatus,
+    /// Labels a worker must have to execute this fragment.
+    pub runs_on: Vec<String>,
 }
 
 impl NewFragment {
@@ -153,7 +172,8 @@ impl NewFragment {
             condition: None,
             source_url: None,
             attempt: 1,
-            status: FragmentStatus::Active,
+            status: FragmentStatus::Blocked,
+            runs_on: Vec::new(),
         }
     }
 
@@ -171,7 +191,8 @@ impl NewFragment {
             condition: None,
             source_url: None,
             attempt: 1,
-            status: FragmentStatus::Active,
+            status: FragmentStatus::Blocked,
+            runs_on: Vec::new(),
         }
     }
 
@@ -187,6 +208,13 @@ impl NewFragment {
         self
     }
 
+    /// Set the labels a worker must have to execute this fragment.
+    #[must_use]
+    pub fn with_runs_on(mut self, runs_on: Vec<String>) -> Self {
+        self.runs_on = runs_on;
+        self
+    }
+
     /// Set a condition for execution.
     pub fn with_condition(mut self, condition: String) -> Self {
         self.condition = Some(condition);
@@ -14,6 +14,10 @@ pub enum WorkerStatus {
     Suspended,
     /// Worker encountered an error.
     Error,
+    /// Worker finishes its in-flight fragments but accepts no new work.
+    Draining,
+    /// Worker shut down and deregistered itself.
+    Deregistered,
 }
 
 impl WorkerStatus {
@@ -48,8 +52,15 @@ pub struct Worker {
     pub last_heartbeat_at: Option<NaiveDateTime>,
     /// Machine group this worker belongs to.
     pub machine_group: Option<String>,
-    /// Currently assigned fragment, if any.
-    pub current_fragment_id: Option<Uuid>,
+    /// Maximum number of fragments the worker executes at once.
+    pub max_concurrency: i32,
+    /// Capability labels (`key=value` or bare tags) used to route fragments.
+    pub labels: Vec<String>,
+    /// Name of the worker's instance, e.g. its pod.
+    pub name: Option<String>,
+    /// When the worker errored or deregistered; retired workers are deleted after
+    /// a retention period.
+    pub retired_at: Option<NaiveDateTime>,
 }
 
 /// Data for creating a new worker.
@@ -72,8 +83,12 @@ pub struct NewWorker {
     pub last_heartbeat_at: Option<NaiveDateTime>,
     /// Machine group this worker belongs to.
     pub machine_group: Option<String>,
-    /// Initially assigned fragment, if any.
-    pub current_fragment_id: Option<Uuid>,
+    /// Maximum number of fragments the worker executes at once.
+    pub max_concurrency: i32,
+    /// Capability labels (`key=value` or bare tags) used to route fragments.
+    pub labels: Vec<String>,
+    /// Name of the worker's instance, e.g. its pod.
+    pub name: Option<String>,
 }
 
 impl NewWorker {
@@ -88,7 +103,9 @@ impl NewWorker {
             next_chain_id: None,
             last_heartbeat_at: None,
             machine_group: None,
-            current_fragment_id: None,
+            max_concurrency: 1,
+            labels: Vec::new(),
+            name: None,
         }
     }
 
@@ -98,6 +115,27 @@ impl NewWorker {
         self
     }
 
+    /// Set the maximum number of fragments the worker executes at once.
+    #[must_use]
+    pub const fn with_max_concurrency(mut self, max_concurrency: i32) -> Self {
+        self.max_concurrency = max_concurrency;
+        self
+    }
+
+    /// Set the capability labels for this worker.
+    #[must_use]
+    pub fn with_labels(mut self, labels: Vec<String>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Set the name of the worker's instance.
+    #[must_use]
+    pub fn with_name(mut self, name: String) -> Self {
+        self.name = Some(name);
+        self
+    }
+
     /// Set the initial heartbeat timestamp.
     pub fn with_heartbeat(mut self, heartbeat_at: NaiveDateTime) -> Self {
         self.last_heartbeat_at = Some(heartbeat_at);
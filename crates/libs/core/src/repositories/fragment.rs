@@ -1,18 +1,54 @@
 use chrono::Utc;
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
+use diesel::sql_types;
 use uuid::Uuid;
 
-use crate::models::fragment::{Fragment, FragmentStatus, NewFragment};
-use crate::schema::fragments;
+use crate::models::fragment::{Fragment, FragmentStatus, FragmentType, NewFragment, QueueDepth};
+use crate::schema::{chains, fragments};
 
 use super::error::Result;
+use super::readiness::ChainTree;
+
+/// Claims the first ready fragment, see [`FragmentRepository::claim_next_ready`].
+///
+/// Readiness is maintained by [`FragmentRepository::activate_chain`] and
+/// [`FragmentRepository::release_successors`], so a pending fragment is runnable.
+/// Nothing is claimed for a worker that is not active, e.g. one that started draining
+/// after it asked for work.
+///
+/// Binds: `$1` worker ID, `$2` start time, `$3` tenant ID, `$4` machine group (or
+/// NULL for any), `$5` worker labels, `$6` the only fragment to claim (or NULL for
+/// any). `type` is also returned as `type_`, the name `QueryableByName` expects for
+/// that column.
+const CLAIM_NEXT_READY_SQL: &str = "
+UPDATE fragments
+SET status = 'running', assigned_worker_id = $1, started_at = $2
+WHERE id = (
+    SELECT f.id
+    FROM fragments f
+    JOIN chains c ON c.id = f.chain_id
+    WHERE f.status = 'pending'
+      AND c.tenant_id = $3
+      AND ($4::text IS NULL OR f.machine = $4)
+      AND f.runs_on <@ $5
+      AND ($6::uuid IS NULL OR f.id = $6)
+      AND EXISTS (SELECT 1 FROM workers w WHERE w.id = $1 AND w.status = 'active')
+    ORDER BY f.sequence
+    LIMIT 1
+    FOR UPDATE OF f SKIP LOCKED
+)
+RETURNING *, type AS type_
+";
 
 /// Repository trait for Fragment entities.
 pub trait FragmentRepository {
     /// Find a fragment by its ID.
     fn find_by_id(&mut self, id: Uuid) -> Result<Option<Fragment>>;
 
+    /// Find a fragment by its ID, locking its row until the end of the current transaction.
+    fn find_by_id_for_update(&mut self, id: Uuid) -> Result<Option<Fragment>>;
+
     /// Find all fragments.
     fn find_all(&mut self) -> Result<Vec<Fragment>>;
 
@@ -43,8 +79,19 @@ pub trait FragmentRepository {
     /// Count fragments for a specific chain.
     fn count_by_chain(&mut self, chain_id: Uuid) -> Result<i64>;
 
-    /// Find pending fragments optionally filtered by machine group.
-    fn find_pending_by_machine(&mut self, machine: Option<&str>) -> Result<Vec<Fragment>>;
+    /// Find pending fragments a worker can execute.
+    ///
+    /// A fragment matches if its chain belongs to `tenant_id`, its `runs_on`
+    /// requirements are a subset of `labels` (when given) and its machine group
+    /// equals `machine` (when given). Fragments are ordered as they are claimed,
+    /// and at most `limit` of them are returned.
+    fn find_pending_matching(
+        &mut self,
+        tenant_id: Uuid,
+        machine: Option<&str>,
+        labels: Option<&[String]>,
+        limit: i64,
+    ) -> Result<Vec<Fragment>>;
 
     /// Find all child fragments of a given parent.
     fn find_children(&mut self, parent_id: Uuid) -> Result<Vec<Fragment>>;
@@ -67,14 +114,78 @@ pub trait FragmentRepository {
     /// Atomically try to claim a fragment for a worker.
     ///
     /// Uses optimistic locking: only succeeds if fragment is still pending.
+    /// Never claims a fragment whose chain belongs to a tenant other than `tenant_id`.
     /// Returns `Some(fragment)` if claimed, `None` if already taken by another worker.
-    fn try_claim(&mut self, fragment_id: Uuid, worker_id: Uuid) -> Result<Option<Fragment>>;
-
-    /// Count pending fragments for a specific machine group (or all if None).
-    fn count_pending_by_machine(&mut self, machine: Option<&str>) -> Result<i64>;
+    fn try_claim(
+        &mut self,
+        fragment_id: Uuid,
+        worker_id: Uuid,
+        tenant_id: Uuid,
+    ) -> Result<Option<Fragment>>;
+
+    /// Atomically claim the next ready fragment for a worker in a single statement.
+    ///
+    /// A fragment is eligible when it is pending (its dependencies are satisfied),
+    /// its chain belongs to `tenant_id`, its `runs_on` requirements are a subset of
+    /// `labels`, and its machine group equals `machine` (when given). Nothing is
+    /// claimed unless the worker is active.
+    ///
+    /// Candidates locked by a concurrent claim are skipped (`FOR UPDATE SKIP LOCKED`),
+    /// so polling workers never block on each other.
+    /// Returns `None` if no ready fragment is available.
+    fn claim_next_ready(
+        &mut self,
+        worker_id: Uuid,
+        tenant_id: Uuid,
+        machine: Option<&str>,
+        labels: &[String],
+    ) -> Result<Option<Fragment>>;
+
+    /// Atomically claim a specific fragment for a worker launched to execute it.
+    ///
+    /// The fragment must be eligible for the worker exactly as for
+    /// [`claim_next_ready`](Self::claim_next_ready). Returns `None` if it is not, e.g.
+    /// because it was claimed by another worker already.
+    fn claim_ready_fragment(
+        &mut self,
+        fragment_id: Uuid,
+        worker_id: Uuid,
+        tenant_id: Uuid,
+        machine: Option<&str>,
+        labels: &[String],
+    ) -> Result<Option<Fragment>>;
+
+    /// Start a newly created chain by making its entry fragments ready.
+    ///
+    /// Returns the IDs of the fragments that became `Pending`.
+    fn activate_chain(&mut self, chain_id: Uuid) -> Result<Vec<Uuid>>;
 
-    /// Count running fragments for a specific machine group (or all if None).
-    fn count_running_by_machine(&mut self, machine: Option<&str>) -> Result<i64>;
+    /// Make the successors of a fragment that just reached a terminal state ready.
+    ///
+    /// Depending on the fragment's position this promotes its next sequential
+    /// sibling, or finishes its group once all the group's children are terminal
+    /// (which releases the group's own successor, and so on). Should run in the same
+    /// transaction as the status change.
+    ///
+    /// Returns the IDs of the fragments that became `Pending`.
+    fn release_successors(&mut self, fragment_id: Uuid) -> Result<Vec<Uuid>>;
+
+    /// Count pending (ready) fragments for a tenant and machine group (or all if None).
+    fn count_pending_by_machine(
+        &mut self,
+        tenant_id: Option<Uuid>,
+        machine: Option<&str>,
+    ) -> Result<i64>;
+
+    /// Count running fragments for a tenant and machine group (or all if None).
+    fn count_running_by_machine(
+        &mut self,
+        tenant_id: Option<Uuid>,
+        machine: Option<&str>,
+    ) -> Result<i64>;
+
+    /// Count pending and running (inline) fragments per tenant and machine group.
+    fn queue_depth(&mut self) -> Result<Vec<QueueDepth>>;
 }
 
 /// `PostgreSQL` implementation of `FragmentRepository`.
@@ -105,6 +216,15 @@ impl FragmentRepository for PgFragmentRepository<'_> {
         Ok(fragment)
     }
 
+    fn find_by_id_for_update(&mut self, id: Uuid) -> Result<Option<Fragment>> {
+        let fragment = fragments::table
+            .find(id)
+            .for_update()
+            .first::<Fragment>(self.conn)
+            .optional()?;
+        Ok(fragment)
+    }
+
     fn find_all(&mut self) -> Result<Vec<Fragment>> {
         let results = fragments::table.load::<Fragment>(self.conn)?;
         Ok(results)
@@ -176,16 +296,30 @@ impl FragmentRepository for PgFragmentRepository<'_> {
         Ok(count)
     }
 
-    fn find_pending_by_machine(&mut self, machine: Option<&str>) -> Result<Vec<Fragment>> {
+    fn find_pending_matching(
+        &mut self,
+        tenant_id: Uuid,
+        machine: Option<&str>,
+        labels: Option<&[String]>,
+        limit: i64,
+    ) -> Result<Vec<Fragment>> {
         let mut query = fragments::table
+            .inner_join(chains::table)
+            .filter(chains::tenant_id.eq(tenant_id))
             .filter(fragments::status.eq(FragmentStatus::Pending))
             .order(fragments::sequence.asc())
+            .limit(limit)
+            .select(Fragment::as_select())
             .into_boxed();
 
         if let Some(m) = machine {
             query = query.filter(fragments::machine.eq(m));
         }
 
+        if let Some(labels) = labels {
+            query = query.filter(fragments::runs_on.is_contained_by(labels.to_vec()));
+        }
+
         let results = query.load::<Fragment>(self.conn)?;
         Ok(results)
     }
@@ -277,7 +411,12 @@ impl FragmentRepository for PgFragmentRepository<'_> {
         Ok(updated)
     }
 
-    fn try_claim(&mut self, fragment_id: Uuid, worker_id: Uuid) -> Result<Option<Fragment>> {
+    fn try_claim(
+        &mut self,
+        fragment_id: Uuid,
+        worker_id: Uuid,
+        tenant_id: Uuid,
+    ) -> Result<Option<Fragment>> {
         let now = Utc::now().naive_utc();
 
         // Atomic update: only succeeds if fragment is still pending
@@ -285,7 +424,8 @@ impl FragmentRepository for PgFragmentRepository<'_> {
         let result = diesel::update(
             fragments::table
                 .filter(fragments::id.eq(fragment_id))
-                .filter(fragments::status.eq(FragmentStatus::Pending)),
+                .filter(fragments::status.eq(FragmentStatus::Pending))
+                .filter(fragments::chain_id.eq_any(tenant_chains(tenant_id))),
         )
         .set((
             fragments::status.eq(FragmentStatus::Running),
@@ -299,11 +439,68 @@ impl FragmentRepository for PgFragmentRepository<'_> {
         Ok(result)
     }
 
-    fn count_pending_by_machine(&mut self, machine: Option<&str>) -> Result<i64> {
+    fn claim_next_ready(
+        &mut self,
+        worker_id: Uuid,
+        tenant_id: Uuid,
+        machine: Option<&str>,
+        labels: &[String],
+    ) -> Result<Option<Fragment>> {
+        claim_ready(self.conn, None, worker_id, tenant_id, machine, labels)
+    }
+
+    fn claim_ready_fragment(
+        &mut self,
+        fragment_id: Uuid,
+        worker_id: Uuid,
+        tenant_id: Uuid,
+        machine: Option<&str>,
+        labels: &[String],
+    ) -> Result<Option<Fragment>> {
+        claim_ready(
+            self.conn,
+            Some(fragment_id),
+            worker_id,
+            tenant_id,
+            machine,
+            labels,
+        )
+    }
+
+    fn activate_chain(&mut self, chain_id: Uuid) -> Result<Vec<Uuid>> {
+        self.conn.transaction(|conn| {
+            let mut tree = ChainTree::new(load_chain_for_update(conn, chain_id)?);
+            tree.activate_entry();
+            apply_changes(conn, tree.into_changes())
+        })
+    }
+
+    fn release_successors(&mut self, fragment_id: Uuid) -> Result<Vec<Uuid>> {
+        self.conn.transaction(|conn| {
+            let chain_id = fragments::table
+                .find(fragment_id)
+                .select(fragments::chain_id)
+                .first::<Uuid>(conn)?;
+
+            let mut tree = ChainTree::new(load_chain_for_update(conn, chain_id)?);
+            tree.release_successors(fragment_id);
+            apply_changes(conn, tree.into_changes())
+        })
+    }
+
+    fn count_pending_by_machine(
+        &mut self,
+        tenant_id: Option<Uuid>,
+        machine: Option<&str>,
+    ) -> Result<i64> {
         let mut query = fragments::table
             .filter(fragments::status.eq(FragmentStatus::Pending))
             .into_boxed();
 
+        if let Some(t) = tenant_id {
+            query = query.filter(fragments::chain_id.eq_any(tenant_chains(t)));
+        }
+
         if let Some(m) = machine {
             query = query.filter(
                 fragments::machine
@@ -316,11 +513,21 @@ impl FragmentRepository for PgFragmentRepository<'_> {
         Ok(count)
     }
 
-    fn count_running_by_machine(&mut self, machine: Option<&str>) -> Result<i64> {
+    fn count_running_by_machine(
+        &mut self,
+        tenant_id: Option<Uuid>,
+        machine: Option<&str>,
+    ) -> Result<i64> {
+        // Running groups only wait for their children
         let mut query = fragments::table
             .filter(fragments::status.eq(FragmentStatus::Running))
+            .filter(fragments::type_.eq(FragmentType::Inline))
             .into_boxed();
 
+        if let Some(t) = tenant_id {
+            query = query.filter(fragments::chain_id.eq_any(tenant_chains(t)));
+        }
+
         if let Some(m) = machine {
             query = query.filter(
                 fragments::machine
@@ -332,4 +539,129 @@ impl FragmentRepository for PgFragmentRepository<'_> {
         let count = query.count().get_result(self.conn)?;
         Ok(count)
     }
+
+    fn queue_depth(&mut self) -> Result<Vec<QueueDepth>> {
+        // Running groups only wait for their children
+        let rows = fragments::table
+            .inner_join(chains::table)
+            .filter(
+                fragments::status.eq(FragmentStatus::Pending).or(fragments::status
+                    .eq(FragmentStatus::Running)
+                    .and(fragments::type_.eq(FragmentType::Inline))),
+            )
+            .group_by((chains::tenant_id, fragments::machine, fragments::status))
+            .select((
+                chains::tenant_id,
+                fragments::machine,
+                fragments::status,
+                diesel::dsl::count_star(),
+            ))
+            .load::<(Uuid, Option<String>, FragmentStatus, i64)>(self.conn)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(tenant_id, machine_group, status, count)| QueueDepth {
+                tenant_id,
+                machine_group,
+                status,
+                count,
+            })
+            .collect())
+    }
+}
+
+diesel::allow_columns_to_appear_in_same_group_by_clause!(
+    chains::tenant_id,
+    fragments::machine,
+    fragments::status,
+);
+
+/// Subquery selecting the IDs of all chains owned by a tenant.
+fn tenant_chains(
+    tenant_id: Uuid,
+) -> chains::BoxedQuery<'static, diesel::pg::Pg, diesel::sql_types::Uuid> {
+    chains::table
+        .filter(chains::tenant_id.eq(tenant_id))
+        .select(chains::id)
+        .into_boxed()
+}
+
+/// Claim the first ready fragment for a worker with [`CLAIM_NEXT_READY_SQL`], only
+/// `fragment_id` if given.
+fn claim_ready(
+    conn: &mut PgConnection,
+    fragment_id: Option<Uuid>,
+    worker_id: Uuid,
+    tenant_id: Uuid,
+    machine: Option<&str>,
+    labels: &[String],
+) -> Result<Option<Fragment>> {
+    let now = Utc::now().naive_utc();
+
+    let claimed = diesel::sql_query(CLAIM_NEXT_READY_SQL)
+        .bind::<sql_types::Uuid, _>(worker_id)
+        .bind::<sql_types::Timestamp, _>(now)
+        .bind::<sql_types::Uuid, _>(tenant_id)
+        .bind::<sql_types::Nullable<sql_types::Text>, _>(machine)
+        .bind::<sql_types::Array<sql_types::Text>, _>(labels)
+        .bind::<sql_types::Nullable<sql_types::Uuid>, _>(fragment_id)
+        .get_result::<Fragment>(conn)
+        .optional()?;
+
+    Ok(claimed)
+}
+
+/// Load all fragments of a chain, locking the chain so concurrent completions
+/// within it compute readiness one after another.
+fn load_chain_for_update(conn: &mut PgConnection, chain_id: Uuid) -> Result<Vec<Fragment>> {
+    chains::table
+        .find(chain_id)
+        .select(chains::id)
+        .for_update()
+        .first::<Uuid>(conn)?;
+
+    let results = fragments::table
+        .filter(fragments::chain_id.eq(chain_id))
+        .select(Fragment::as_select())
+        .load(conn)?;
+    Ok(results)
+}
+
+/// Persist readiness status changes, returning the IDs of fragments made `Pending`.
+fn apply_changes(
+    conn: &mut PgConnection,
+    changes: Vec<(Uuid, FragmentStatus)>,
+) -> Result<Vec<Uuid>> {
+    let now = Utc::now().naive_utc();
+    let mut ready = Vec::new();
+
+    for (id, status) in changes {
+        let target = fragments::table.find(id);
+        match status {
+            FragmentStatus::Pending => {
+                diesel::update(target)
+                    .set(fragments::status.eq(status))
+                    .execute(conn)?;
+                ready.push(id);
+            }
+            FragmentStatus::Running => {
+                diesel::update(target)
+                    .set((
+                        fragments::status.eq(status),
+                        fragments::started_at.eq(Some(now)),
+                    ))
+                    .execute(conn)?;
+            }
+            _ => {
+                diesel::update(target)
+                    .set((
+                        fragments::status.eq(status),
+                        fragments::completed_at.eq(Some(now)),
+                    ))
+                    .execute(conn)?;
+            }
+        }
+    }
+
+    Ok(ready)
 }
@@ -9,6 +9,7 @@
 mod chain;
 mod error;
 mod fragment;
+mod readiness;
 mod worker;
 
 pub use chain::{ChainRepository, PgChainRepository};
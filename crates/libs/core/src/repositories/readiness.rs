@@ -0,0 +1,160 @@
+//! Fragment readiness transitions within a chain.
+//!
+//! Fragments start out `Blocked` and are promoted to `Pending` (ready to be
+//! claimed) once their dependencies are satisfied:
+//!
+//! - Top-level fragments and children of a sequential group run one after another:
+//!   a fragment becomes ready when its previous sibling reaches a terminal state.
+//! - Children of a parallel group all become ready when the group starts.
+//!
+//! Groups are never executed themselves. A group is `Running` while its children
+//! run, and becomes `Completed` (or `Failed`, if any child failed) once all of
+//! them are terminal, which in turn releases the group's successor.
+
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::models::fragment::{Fragment, FragmentStatus, FragmentType};
+
+/// Node of a chain's fragment tree.
+struct Node {
+    parent: Option<Uuid>,
+    fragment_type: FragmentType,
+    is_parallel: bool,
+    status: FragmentStatus,
+}
+
+/// In-memory fragment tree of one chain, recording the status changes it makes.
+pub(super) struct ChainTree {
+    nodes: HashMap<Uuid, Node>,
+    /// Children of each parent (`None` = top level), ordered by sequence.
+    children: HashMap<Option<Uuid>, Vec<Uuid>>,
+    /// Status changes made so far, in order.
+    changes: Vec<(Uuid, FragmentStatus)>,
+}
+
+impl ChainTree {
+    /// Build the tree from all fragments of a chain.
+    pub(super) fn new(fragments: Vec<Fragment>) -> Self {
+        let mut ordered: Vec<(Option<Uuid>, i32, Uuid)> = fragments
+            .iter()
+            .map(|f| (f.parent_fragment_id, f.sequence, f.id))
+            .collect();
+        ordered.sort_by_key(|(_, sequence, _)| *sequence);
+
+        let mut children: HashMap<Option<Uuid>, Vec<Uuid>> = HashMap::new();
+        for (parent, _, id) in ordered {
+            children.entry(parent).or_default().push(id);
+        }
+
+        let nodes = fragments
+            .into_iter()
+            .map(|f| {
+                let node = Node {
+                    parent: f.parent_fragment_id,
+                    fragment_type: f.fragment_type,
+                    is_parallel: f.is_parallel,
+                    status: f.status,
+                };
+                (f.id, node)
+            })
+            .collect();
+
+        Self {
+            nodes,
+            children,
+            changes: Vec::new(),
+        }
+    }
+
+    /// The status changes made so far.
+    pub(super) fn into_changes(self) -> Vec<(Uuid, FragmentStatus)> {
+        self.changes
+    }
+
+    /// Start the chain by activating its first top-level fragment.
+    pub(super) fn activate_entry(&mut self) {
+        if let Some(&first) = self.children_of(None).first() {
+            self.activate(first);
+        }
+    }
+
+    /// Release the successors of a fragment that has just reached a terminal state.
+    pub(super) fn release_successors(&mut self, id: Uuid) {
+        let Some(parent) = self.nodes.get(&id).map(|n| n.parent) else {
+            return;
+        };
+        let siblings = self.children_of(parent);
+
+        let parallel_parent = parent.is_some_and(|p| self.nodes[&p].is_parallel);
+        if !parallel_parent {
+            let next = siblings.iter().skip_while(|&&s| s != id).nth(1).copied();
+            if let Some(next) = next {
+                self.activate(next);
+                return;
+            }
+        }
+
+        // Last sibling of a sequential group, or any sibling of a parallel one
+        if let Some(group) = parent
+            && siblings.iter().all(|s| self.nodes[s].status.is_terminal())
+        {
+            self.finish_group(group);
+        }
+    }
+
+    /// Make a blocked fragment ready. Groups start running and activate their children.
+    fn activate(&mut self, id: Uuid) {
+        let node = &self.nodes[&id];
+        if node.status != FragmentStatus::Blocked {
+            return;
+        }
+
+        if node.fragment_type != FragmentType::Group {
+            self.set_status(id, FragmentStatus::Pending);
+            return;
+        }
+
+        let is_parallel = node.is_parallel;
+        self.set_status(id, FragmentStatus::Running);
+
+        let children = self.children_of(Some(id));
+        if children.is_empty() {
+            self.finish_group(id);
+        } else if is_parallel {
+            for child in children {
+                self.activate(child);
+            }
+        } else {
+            self.activate(children[0]);
+        }
+    }
+
+    /// Mark a group whose children are all terminal as finished.
+    fn finish_group(&mut self, id: Uuid) {
+        let failed = self
+            .children_of(Some(id))
+            .iter()
+            .any(|c| self.nodes[c].status == FragmentStatus::Failed);
+        let status = if failed {
+            FragmentStatus::Failed
+        } else {
+            FragmentStatus::Completed
+        };
+
+        self.set_status(id, status);
+        self.release_successors(id);
+    }
+
+    fn children_of(&self, parent: Option<Uuid>) -> Vec<Uuid> {
+        self.children.get(&parent).cloned().unwrap_or_default()
+    }
+
+    fn set_status(&mut self, id: Uuid, status: FragmentStatus) {
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.status = status;
+            self.changes.push((id, status));
+        }
+    }
+}
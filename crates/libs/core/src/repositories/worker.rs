@@ -3,8 +3,9 @@ use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use uuid::Uuid;
 
+use crate::models::fragment::FragmentStatus;
 use crate::models::worker::{NewWorker, Worker, WorkerStatus};
-use crate::schema::workers;
+use crate::schema::{fragments, worker_fragments, workers};
 
 use super::error::Result;
 
@@ -34,26 +35,99 @@ pub trait WorkerRepository {
     /// Count all workers.
     fn count(&mut self) -> Result<i64>;
 
-    /// Find workers whose heartbeat is older than the given threshold (dead workers).
+    /// Count workers per status.
+    fn count_by_status(&mut self) -> Result<Vec<(WorkerStatus, i64)>>;
+
+    /// Find a tenant's worker by the name of its instance.
+    fn find_by_name(&mut self, tenant_id: Uuid, name: &str) -> Result<Option<Worker>>;
+
+    /// Find a tenant's worker by the name of its instance, locking its row until the
+    /// end of the current transaction.
+    fn find_by_name_for_update(&mut self, tenant_id: Uuid, name: &str) -> Result<Option<Worker>>;
+
+    /// Bring a worker back into service for a new registration: make it active with
+    /// a heartbeat of now, and the registration's machine group, concurrency and labels.
+    fn reactivate(
+        &mut self,
+        worker_id: Uuid,
+        machine_group: Option<&str>,
+        max_concurrency: i32,
+        labels: &[String],
+    ) -> Result<Worker>;
+
+    /// Take a worker out of service with the given status (error or deregistered),
+    /// recording when it was retired.
+    ///
+    /// Returns the worker, or `None` if it does not exist.
+    fn retire(&mut self, worker_id: Uuid, status: WorkerStatus) -> Result<Option<Worker>>;
+
+    /// Delete workers retired before the given time, returning how many were deleted.
+    fn delete_retired(&mut self, retired_before: NaiveDateTime) -> Result<usize>;
+
+    /// Find live (active or draining) workers whose heartbeat is older than the given
+    /// threshold (dead workers).
     fn find_dead_workers(&mut self, threshold: NaiveDateTime) -> Result<Vec<Worker>>;
 
-    /// Find idle workers (active status, no current fragment) optionally filtered by machine group.
+    /// Find idle workers (active status, no in-flight fragments) optionally filtered by machine group.
     fn find_idle_by_machine_group(&mut self, machine_group: Option<&str>) -> Result<Vec<Worker>>;
 
-    /// Update a worker's heartbeat timestamp to now.
-    fn update_heartbeat(&mut self, worker_id: Uuid) -> Result<Worker>;
+    /// Put an active worker into the draining state, in which it is assigned no new work.
+    ///
+    /// Returns the worker if it is draining now, or `None` if it does not exist or is
+    /// neither active nor already draining.
+    fn mark_draining(&mut self, worker_id: Uuid) -> Result<Option<Worker>>;
 
-    /// Assign a fragment to a worker.
-    fn assign_fragment(&mut self, worker_id: Uuid, fragment_id: Uuid) -> Result<Worker>;
+    /// Find a tenant's draining workers, optionally filtered by machine group.
+    fn find_draining(
+        &mut self,
+        tenant_id: Uuid,
+        machine_group: Option<&str>,
+    ) -> Result<Vec<Worker>>;
 
-    /// Clear a worker's current fragment assignment.
-    fn clear_assignment(&mut self, worker_id: Uuid) -> Result<Worker>;
+    /// Find a tenant's live (active or draining) workers, optionally filtered by
+    /// machine group.
+    fn find_live(&mut self, tenant_id: Uuid, machine_group: Option<&str>) -> Result<Vec<Worker>>;
 
-    /// Count active workers for a specific machine group (or all if None).
-    fn count_active_by_machine_group(&mut self, machine_group: Option<&str>) -> Result<i64>;
+    /// Update a worker's heartbeat timestamp to now.
+    fn update_heartbeat(&mut self, worker_id: Uuid) -> Result<Worker>;
 
-    /// Check if a worker is currently executing a fragment.
-    fn is_busy(&mut self, worker_id: Uuid) -> Result<Option<Uuid>>;
+    /// Record a fragment as in flight on a worker.
+    fn assign_fragment(&mut self, worker_id: Uuid, fragment_id: Uuid) -> Result<()>;
+
+    /// Remove a single in-flight fragment from a worker.
+    fn release_fragment(&mut self, worker_id: Uuid, fragment_id: Uuid) -> Result<bool>;
+
+    /// Remove all in-flight fragments from a worker.
+    fn clear_assignments(&mut self, worker_id: Uuid) -> Result<usize>;
+
+    /// Find the fragments currently in flight on a worker.
+    fn find_assigned_fragments(&mut self, worker_id: Uuid) -> Result<Vec<Uuid>>;
+
+    /// Count the fragments currently in flight on a worker.
+    fn count_assigned_fragments(&mut self, worker_id: Uuid) -> Result<i64>;
+
+    /// Reconcile a worker's in-flight fragments with the ones it reports.
+    ///
+    /// Reported fragments that are running on this worker but missing from its
+    /// assignments are added back. Assignments the worker did not report and that
+    /// were made before `assigned_before` are removed; their fragment IDs are returned
+    /// so the caller can recover them.
+    fn sync_assignments(
+        &mut self,
+        worker_id: Uuid,
+        reported: &[Uuid],
+        assigned_before: NaiveDateTime,
+    ) -> Result<Vec<Uuid>>;
+
+    /// Count active workers for a tenant and machine group (or all if None).
+    fn count_active_by_machine_group(
+        &mut self,
+        tenant_id: Option<Uuid>,
+        machine_group: Option<&str>,
+    ) -> Result<i64>;
+
+    /// Check if a worker is currently executing any fragment.
+    fn is_busy(&mut self, worker_id: Uuid) -> Result<bool>;
 }
 
 /// `PostgreSQL` implementation of `WorkerRepository`.
@@ -121,7 +195,10 @@ impl WorkerRepository for PgWorkerRepository<'_> {
                 workers::next_chain_id.eq(&worker.next_chain_id),
                 workers::last_heartbeat_at.eq(&worker.last_heartbeat_at),
                 workers::machine_group.eq(&worker.machine_group),
-                workers::current_fragment_id.eq(&worker.current_fragment_id),
+                workers::max_concurrency.eq(&worker.max_concurrency),
+                workers::labels.eq(&worker.labels),
+                workers::name.eq(&worker.name),
+                workers::retired_at.eq(&worker.retired_at),
             ))
             .returning(Worker::as_returning())
             .get_result(self.conn)?;
@@ -138,9 +215,78 @@ impl WorkerRepository for PgWorkerRepository<'_> {
         Ok(count)
     }
 
+    fn count_by_status(&mut self) -> Result<Vec<(WorkerStatus, i64)>> {
+        let counts = workers::table
+            .group_by(workers::status)
+            .select((workers::status, diesel::dsl::count_star()))
+            .load(self.conn)?;
+        Ok(counts)
+    }
+
+    fn find_by_name(&mut self, tenant_id: Uuid, name: &str) -> Result<Option<Worker>> {
+        let worker = workers::table
+            .filter(workers::tenant_id.eq(tenant_id))
+            .filter(workers::name.eq(name))
+            .first::<Worker>(self.conn)
+            .optional()?;
+        Ok(worker)
+    }
+
+    fn find_by_name_for_update(&mut self, tenant_id: Uuid, name: &str) -> Result<Option<Worker>> {
+        let worker = workers::table
+            .filter(workers::tenant_id.eq(tenant_id))
+            .filter(workers::name.eq(name))
+            .for_update()
+            .first::<Worker>(self.conn)
+            .optional()?;
+        Ok(worker)
+    }
+
+    fn reactivate(
+        &mut self,
+        worker_id: Uuid,
+        machine_group: Option<&str>,
+        max_concurrency: i32,
+        labels: &[String],
+    ) -> Result<Worker> {
+        let now = chrono::Utc::now().naive_utc();
+        let updated = diesel::update(workers::table.find(worker_id))
+            .set((
+                workers::status.eq(WorkerStatus::Active),
+                workers::retired_at.eq(None::<NaiveDateTime>),
+                workers::last_heartbeat_at.eq(now),
+                workers::machine_group.eq(machine_group),
+                workers::max_concurrency.eq(max_concurrency),
+                workers::labels.eq(labels),
+            ))
+            .returning(Worker::as_returning())
+            .get_result(self.conn)?;
+        Ok(updated)
+    }
+
+    fn retire(&mut self, worker_id: Uuid, status: WorkerStatus) -> Result<Option<Worker>> {
+        let now = chrono::Utc::now().naive_utc();
+        let updated = diesel::update(workers::table.find(worker_id))
+            .set((workers::status.eq(status), workers::retired_at.eq(now)))
+            .returning(Worker::as_returning())
+            .get_result(self.conn)
+            .optional()?;
+        Ok(updated)
+    }
+
+    fn delete_retired(&mut self, retired_before: NaiveDateTime) -> Result<usize> {
+        let deleted = diesel::delete(
+            workers::table
+                .filter(workers::status.eq_any([WorkerStatus::Error, WorkerStatus::Deregistered]))
+                .filter(workers::retired_at.lt(retired_before)),
+        )
+        .execute(self.conn)?;
+        Ok(deleted)
+    }
+
     fn find_dead_workers(&mut self, threshold: NaiveDateTime) -> Result<Vec<Worker>> {
         let results = workers::table
-            .filter(workers::status.eq(WorkerStatus::Active))
+            .filter(workers::status.eq_any([WorkerStatus::Active, WorkerStatus::Draining]))
             .filter(workers::last_heartbeat_at.lt(threshold))
             .load::<Worker>(self.conn)?;
         Ok(results)
@@ -149,7 +295,55 @@ impl WorkerRepository for PgWorkerRepository<'_> {
     fn find_idle_by_machine_group(&mut self, machine_group: Option<&str>) -> Result<Vec<Worker>> {
         let mut query = workers::table
             .filter(workers::status.eq(WorkerStatus::Active))
-            .filter(workers::current_fragment_id.is_null())
+            .filter(diesel::dsl::not(diesel::dsl::exists(
+                worker_fragments::table.filter(worker_fragments::worker_id.eq(workers::id)),
+            )))
+            .into_boxed();
+
+        if let Some(group) = machine_group {
+            query = query.filter(workers::machine_group.eq(group));
+        }
+
+        let results = query.load::<Worker>(self.conn)?;
+        Ok(results)
+    }
+
+    fn mark_draining(&mut self, worker_id: Uuid) -> Result<Option<Worker>> {
+        let updated = diesel::update(
+            workers::table
+                .find(worker_id)
+                .filter(workers::status.eq_any([WorkerStatus::Active, WorkerStatus::Draining])),
+        )
+        .set(workers::status.eq(WorkerStatus::Draining))
+        .returning(Worker::as_returning())
+        .get_result(self.conn)
+        .optional()?;
+        Ok(updated)
+    }
+
+    fn find_draining(
+        &mut self,
+        tenant_id: Uuid,
+        machine_group: Option<&str>,
+    ) -> Result<Vec<Worker>> {
+        let mut query = workers::table
+            .filter(workers::tenant_id.eq(tenant_id))
+            .filter(workers::status.eq(WorkerStatus::Draining))
+            .into_boxed();
+
+        if let Some(group) = machine_group {
+            query = query.filter(workers::machine_group.eq(group));
+        }
+
+        let results = query.load::<Worker>(self.conn)?;
+        Ok(results)
+    }
+
+    fn find_live(&mut self, tenant_id: Uuid, machine_group: Option<&str>) -> Result<Vec<Worker>> {
+        let mut query = workers::table
+            .filter(workers::tenant_id.eq(tenant_id))
+            .filter(workers::status.eq_any([WorkerStatus::Active, WorkerStatus::Draining]))
+            .order(workers::created_at.asc())
             .into_boxed();
 
         if let Some(group) = machine_group {
@@ -169,27 +363,108 @@ impl WorkerRepository for PgWorkerRepository<'_> {
         Ok(updated)
     }
 
-    fn assign_fragment(&mut self, worker_id: Uuid, fragment_id: Uuid) -> Result<Worker> {
-        let updated = diesel::update(workers::table.find(worker_id))
-            .set(workers::current_fragment_id.eq(Some(fragment_id)))
-            .returning(Worker::as_returning())
-            .get_result(self.conn)?;
-        Ok(updated)
+    fn assign_fragment(&mut self, worker_id: Uuid, fragment_id: Uuid) -> Result<()> {
+        diesel::insert_into(worker_fragments::table)
+            .values((
+                worker_fragments::worker_id.eq(worker_id),
+                worker_fragments::fragment_id.eq(fragment_id),
+            ))
+            .on_conflict_do_nothing()
+            .execute(self.conn)?;
+        Ok(())
     }
 
-    fn clear_assignment(&mut self, worker_id: Uuid) -> Result<Worker> {
-        let updated = diesel::update(workers::table.find(worker_id))
-            .set(workers::current_fragment_id.eq(None::<Uuid>))
-            .returning(Worker::as_returning())
+    fn release_fragment(&mut self, worker_id: Uuid, fragment_id: Uuid) -> Result<bool> {
+        let deleted = diesel::delete(worker_fragments::table.find((worker_id, fragment_id)))
+            .execute(self.conn)?;
+        Ok(deleted > 0)
+    }
+
+    fn clear_assignments(&mut self, worker_id: Uuid) -> Result<usize> {
+        let deleted = diesel::delete(
+            worker_fragments::table.filter(worker_fragments::worker_id.eq(worker_id)),
+        )
+        .execute(self.conn)?;
+        Ok(deleted)
+    }
+
+    fn find_assigned_fragments(&mut self, worker_id: Uuid) -> Result<Vec<Uuid>> {
+        let results = worker_fragments::table
+            .filter(worker_fragments::worker_id.eq(worker_id))
+            .order(worker_fragments::assigned_at.asc())
+            .select(worker_fragments::fragment_id)
+            .load::<Uuid>(self.conn)?;
+        Ok(results)
+    }
+
+    fn count_assigned_fragments(&mut self, worker_id: Uuid) -> Result<i64> {
+        let count = worker_fragments::table
+            .filter(worker_fragments::worker_id.eq(worker_id))
+            .count()
             .get_result(self.conn)?;
-        Ok(updated)
+        Ok(count)
     }
 
-    fn count_active_by_machine_group(&mut self, machine_group: Option<&str>) -> Result<i64> {
+    fn sync_assignments(
+        &mut self,
+        worker_id: Uuid,
+        reported: &[Uuid],
+        assigned_before: NaiveDateTime,
+    ) -> Result<Vec<Uuid>> {
+        self.conn.transaction(|conn| {
+            // Only re-add fragments the orchestrator actually has running on this worker
+            let running: Vec<Uuid> = fragments::table
+                .filter(fragments::id.eq_any(reported))
+                .filter(fragments::assigned_worker_id.eq(worker_id))
+                .filter(fragments::status.eq(FragmentStatus::Running))
+                .select(fragments::id)
+                .load(conn)?;
+
+            let rows: Vec<_> = running
+                .iter()
+                .map(|fragment_id| {
+                    (
+                        worker_fragments::worker_id.eq(worker_id),
+                        worker_fragments::fragment_id.eq(*fragment_id),
+                    )
+                })
+                .collect();
+            if !rows.is_empty() {
+                diesel::insert_into(worker_fragments::table)
+                    .values(&rows)
+                    .on_conflict_do_nothing()
+                    .execute(conn)?;
+            }
+
+            // Recent assignments may not have reached the worker yet
+            let lost = diesel::delete(
+                worker_fragments::table
+                    .filter(worker_fragments::worker_id.eq(worker_id))
+                    .filter(diesel::dsl::not(
+                        worker_fragments::fragment_id.eq_any(reported),
+                    ))
+                    .filter(worker_fragments::assigned_at.lt(assigned_before)),
+            )
+            .returning(worker_fragments::fragment_id)
+            .get_results(conn)?;
+
+            Ok(lost)
+        })
+    }
+
+    fn count_active_by_machine_group(
+        &mut self,
+        tenant_id: Option<Uuid>,
+        machine_group: Option<&str>,
+    ) -> Result<i64> {
         let mut query = workers::table
             .filter(workers::status.eq(WorkerStatus::Active))
             .into_boxed();
 
+        if let Some(t) = tenant_id {
+            query = query.filter(workers::tenant_id.eq(t));
+        }
+
         if let Some(group) = machine_group {
             query = query.filter(workers::machine_group.eq(group));
         }
@@ -198,12 +473,11 @@ impl WorkerRepository for PgWorkerRepository<'_> {
         Ok(count)
     }
 
-    fn is_busy(&mut self, worker_id: Uuid) -> Result<Option<Uuid>> {
-        let worker = workers::table
-            .find(worker_id)
-            .first::<Worker>(self.conn)
-            .optional()?;
-
-        Ok(worker.and_then(|w| w.current_fragment_id))
+    fn is_busy(&mut self, worker_id: Uuid) -> Result<bool> {
+        let busy = diesel::select(diesel::dsl::exists(
+            worker_fragments::table.filter(worker_fragments::worker_id.eq(worker_id)),
+        ))
+        .get_result(self.conn)?;
+        Ok(busy)
     }
 }
@@ -43,6 +43,9 @@ diesel::table! {
         default_machine -> Nullable<Text>,
         started_at -> Nullable<Timestamp>,
         completed_at -> Nullable<Timestamp>,
+        checkout_depth -> Nullable<Int4>,
+        checkout_sparse_paths -> Nullable<Array<Text>>,
+        trace_context -> Nullable<Text>,
     }
 }
 
@@ -72,6 +75,15 @@ diesel::table! {
         completed_at -> Nullable<Timestamp>,
         exit_code -> Nullable<Int4>,
         error_message -> Nullable<Text>,
+        runs_on -> Array<Text>,
+    }
+}
+
+diesel::table! {
+    worker_fragments (worker_id, fragment_id) {
+        worker_id -> Uuid,
+        fragment_id -> Uuid,
+        assigned_at -> Timestamp,
     }
 }
 
@@ -90,10 +102,15 @@ diesel::table! {
         updated_at -> Timestamp,
         last_heartbeat_at -> Nullable<Timestamp>,
         machine_group -> Nullable<Text>,
-        current_fragment_id -> Nullable<Uuid>,
+        max_concurrency -> Int4,
+        labels -> Array<Text>,
+        name -> Nullable<Text>,
+        retired_at -> Nullable<Timestamp>,
     }
 }
 
 diesel::joinable!(fragments -> chains (chain_id));
+diesel::joinable!(worker_fragments -> fragments (fragment_id));
+diesel::joinable!(worker_fragments -> workers (worker_id));
 
-diesel::allow_tables_to_appear_in_same_query!(chains, fragments, workers,);
+diesel::allow_tables_to_appear_in_same_query!(chains, fragments, worker_fragments, workers,);
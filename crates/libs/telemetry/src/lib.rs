@@ -0,0 +1,176 @@
+//! Vulcan Telemetry - OpenTelemetry tracing shared by the Vulcan services.
+//!
+//! [`init`] installs the `tracing` subscriber of a service: formatted logs filtered
+//! by `RUST_LOG`, and an OpenTelemetry layer that gives every span a trace and span
+//! ID. Spans are exported over OTLP/HTTP when `OTEL_EXPORTER_OTLP_ENDPOINT` or
+//! `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` is set, encoded as selected by
+//! `OTEL_EXPORTER_OTLP_PROTOCOL` (`http/protobuf` by default, or `http/json`).
+//!
+//! Trace context crosses service boundaries as a W3C `traceparent` value, in the
+//! header of that name or in a field of a stored record: [`traceparent`] renders the
+//! context of a span, and [`set_parent`] continues a trace in a new span.
+
+use std::collections::HashMap;
+use std::env;
+
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::{TraceContextExt, TracerProvider};
+use opentelemetry_otlp::{ExporterBuildError, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{SdkTracerProvider, Tracer};
+use tracing::Span;
+use tracing::subscriber::Subscriber;
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+
+pub use opentelemetry_otlp::Protocol;
+
+/// Name of the W3C trace context header.
+pub const TRACEPARENT: &str = "traceparent";
+
+/// Installed tracing of a service. Dropping it exports the spans still buffered.
+pub struct Telemetry {
+    provider: SdkTracerProvider,
+}
+
+impl Telemetry {
+    /// The tracer provider spans are exported through.
+    #[must_use]
+    pub const fn provider(&self) -> &SdkTracerProvider {
+        &self.provider
+    }
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        let _ = self.provider.shutdown();
+    }
+}
+
+/// Install the global `tracing` subscriber of a service.
+///
+/// `default_filter` applies when `RUST_LOG` is not set.
+///
+/// # Errors
+///
+/// Returns an error if the OTLP exporter cannot be built.
+///
+/// # Panics
+///
+/// Panics if a global subscriber is already installed.
+pub fn init(
+    service_name: &'static str,
+    default_filter: &str,
+) -> Result<Telemetry, ExporterBuildError> {
+    let provider = tracer_provider(service_name, exporter_from_env()?);
+
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| default_filter.into()))
+        .with(tracing_subscriber::fmt::layer())
+        .with(layer(&provider))
+        .init();
+
+    Ok(Telemetry { provider })
+}
+
+/// Build the OTLP exporter configured by the environment, if an endpoint is set.
+///
+/// # Errors
+///
+/// Returns an error if the exporter cannot be built.
+pub fn exporter_from_env() -> Result<Option<SpanExporter>, ExporterBuildError> {
+    let configured = [
+        "OTEL_EXPORTER_OTLP_ENDPOINT",
+        "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+    ]
+    .iter()
+    .any(|name| env::var(name).is_ok_and(|value| !value.is_empty()));
+    if !configured {
+        return Ok(None);
+    }
+
+    // The exporter reads the endpoint itself, but not the protocol
+    let protocol = match env::var("OTEL_EXPORTER_OTLP_PROTOCOL").as_deref() {
+        Ok("http/json") => Protocol::HttpJson,
+        _ => Protocol::HttpBinary,
+    };
+    SpanExporter::builder()
+        .with_http()
+        .with_protocol(protocol)
+        .build()
+        .map(Some)
+}
+
+/// Build an OTLP exporter sending spans to the collector at `endpoint`, e.g.
+/// `http://localhost:4318`.
+///
+/// # Errors
+///
+/// Returns an error if the exporter cannot be built.
+pub fn exporter(endpoint: &str, protocol: Protocol) -> Result<SpanExporter, ExporterBuildError> {
+    SpanExporter::builder()
+        .with_http()
+        .with_protocol(protocol)
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()
+}
+
+/// Build a tracer provider for a service, exporting spans in batches through
+/// `exporter` if given.
+#[must_use]
+pub fn tracer_provider(
+    service_name: &'static str,
+    exporter: Option<SpanExporter>,
+) -> SdkTracerProvider {
+    let resource = Resource::builder().with_service_name(service_name).build();
+    let builder = SdkTracerProvider::builder().with_resource(resource);
+    match exporter {
+        Some(exporter) => builder.with_batch_exporter(exporter).build(),
+        None => builder.build(),
+    }
+}
+
+/// A `tracing` layer recording spans through `provider`.
+#[must_use]
+pub fn layer<S>(provider: &SdkTracerProvider) -> OpenTelemetryLayer<S, Tracer>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    tracing_opentelemetry::layer().with_tracer(provider.tracer("vulcan"))
+}
+
+/// The `traceparent` value of `span`, or None if it is not traced.
+///
+/// Starts the span if it has not been entered yet, after which its parent can no
+/// longer be set.
+#[must_use]
+pub fn traceparent(span: &Span) -> Option<String> {
+    let context = span.context();
+    if !context.span().span_context().is_valid() {
+        return None;
+    }
+
+    let mut carrier = HashMap::new();
+    TraceContextPropagator::new().inject_context(&context, &mut carrier);
+    carrier.remove(TRACEPARENT)
+}
+
+/// The `traceparent` value of the current span, or None if it is not traced.
+#[must_use]
+pub fn current_traceparent() -> Option<String> {
+    traceparent(&Span::current())
+}
+
+/// Continue the trace of `traceparent` in `span`, which must not have been entered
+/// yet. An invalid value leaves the span in a trace of its own.
+pub fn set_parent(span: &Span, traceparent: &str) {
+    let carrier = HashMap::from([(TRACEPARENT.to_string(), traceparent.to_string())]);
+    let context = TraceContextPropagator::new().extract(&carrier);
+    if context.span().span_context().is_valid() {
+        let _ = span.set_parent(context);
+    }
+}
@@ -4,6 +4,7 @@ use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde::Serialize;
+use vulcan_chain_parser::{Diagnostic, Severity};
 
 /// API error response body.
 #[derive(Debug, Serialize)]
@@ -12,6 +13,53 @@ pub struct ErrorResponse {
     pub error: String,
     /// Error code for programmatic handling.
     pub code: String,
+    /// Every problem found in the workflow, for parse errors.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<DiagnosticResponse>,
+}
+
+/// A problem in a workflow, located in its file.
+#[derive(Debug, Serialize)]
+pub struct DiagnosticResponse {
+    /// `error` or `warning`.
+    pub severity: &'static str,
+    /// Kind of problem, e.g. `vulcan::unknown_node`.
+    pub code: &'static str,
+    /// Description of the problem.
+    pub message: String,
+    /// Path or URL of the file the problem is in.
+    pub file: Option<String>,
+    /// Line of the problem, from 1.
+    pub line: Option<usize>,
+    /// Column of the problem, from 1, in characters.
+    pub column: Option<usize>,
+    /// The source line the problem is on.
+    pub snippet: Option<String>,
+    /// Short description of what is at the location.
+    pub label: Option<String>,
+    /// Suggestion for fixing the problem.
+    pub help: Option<String>,
+}
+
+impl From<&Diagnostic> for DiagnosticResponse {
+    fn from(diagnostic: &Diagnostic) -> Self {
+        let location = diagnostic.location();
+        Self {
+            severity: match diagnostic.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+                Severity::Advice => "advice",
+            },
+            code: diagnostic.code(),
+            message: diagnostic.error.to_string(),
+            file: diagnostic.file().map(String::from),
+            line: location.map(|location| location.line),
+            column: location.map(|location| location.column),
+            snippet: diagnostic.snippet().map(String::from),
+            label: diagnostic.label.clone(),
+            help: diagnostic.help.clone(),
+        }
+    }
 }
 
 /// API errors that can occur during request handling.
@@ -21,6 +69,10 @@ pub enum ApiError {
     #[error("parse error: {0}")]
     ParseError(#[from] vulcan_chain_parser::ParseError),
 
+    /// Workflow checking found problems.
+    #[error("parse error: {}", summarize(.0))]
+    Diagnostics(Vec<Diagnostic>),
+
     /// Database operation failed.
     #[error("database error: {0}")]
     DatabaseError(#[from] vulcan_core::RepositoryError),
@@ -37,17 +89,37 @@ pub enum ApiError {
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let (status, code) = match &self {
-            Self::ParseError(_) => (StatusCode::BAD_REQUEST, "PARSE_ERROR"),
+            Self::ParseError(_) | Self::Diagnostics(_) => (StatusCode::BAD_REQUEST, "PARSE_ERROR"),
             Self::DatabaseError(_) => (StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR"),
             Self::InvalidRequest(_) => (StatusCode::BAD_REQUEST, "INVALID_REQUEST"),
             Self::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR"),
         };
 
+        let diagnostics = match &self {
+            Self::Diagnostics(diagnostics) => {
+                diagnostics.iter().map(DiagnosticResponse::from).collect()
+            }
+            _ => Vec::new(),
+        };
+
         let body = ErrorResponse {
             error: self.to_string(),
             code: code.to_string(),
+            diagnostics,
         };
 
         (status, Json(body)).into_response()
     }
 }
+
+/// Summarize diagnostics as the first error and how many more there are.
+fn summarize(diagnostics: &[Diagnostic]) -> String {
+    let mut errors = diagnostics.iter().filter(|d| d.is_error());
+    let Some(first) = errors.next() else {
+        return "no errors".to_string();
+    };
+    match errors.count() {
+        0 => first.to_string(),
+        more => format!("{first} (and {more} more)"),
+    }
+}
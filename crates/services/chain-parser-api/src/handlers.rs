@@ -1,19 +1,21 @@
 //! HTTP request handlers.
 
 use axum::extract::State;
-use axum::http::StatusCode;
+use axum::http::{HeaderMap, StatusCode};
 use axum::response::IntoResponse;
 use axum::Json;
 use diesel::pg::PgConnection;
+use diesel::Connection;
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
+use tracing::info_span;
 use uuid::Uuid;
 
 use vulcan_chain_parser::{ChainParserService, ImportFetcher, ParseError, Result as ParseResult, WorkflowContext};
-use vulcan_core::models::chain::TriggerType;
-use vulcan_core::repositories::{ChainRepository, FragmentRepository, PgChainRepository, PgFragmentRepository};
+use vulcan_core::models::chain::{is_commit_sha, is_remote_repository_url, TriggerType};
+use vulcan_core::repositories::{ChainRepository, FragmentRepository, PgChainRepository, PgFragmentRepository, RepositoryError};
 
-use crate::error::ApiError;
+use crate::error::{ApiError, DiagnosticResponse};
 
 /// Shared application state.
 pub struct AppState {
@@ -80,6 +82,10 @@ pub struct ParseResponse {
 
     /// Message.
     pub message: String,
+
+    /// Warnings about the workflow, which did not stop it being stored.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<DiagnosticResponse>,
 }
 
 /// Health check endpoint.
@@ -90,9 +96,29 @@ pub async fn health() -> impl IntoResponse {
 /// Parse and store a workflow.
 ///
 /// POST /parse
+///
+/// The chain is stored in a `store_chain` span, continuing the trace of a
+/// `traceparent` header, and keeps the span's context for its executions.
 pub async fn parse_workflow(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(request): Json<ParseRequest>,
+) -> Result<Json<ParseResponse>, ApiError> {
+    let span = info_span!("store_chain", tenant_id = %request.tenant_id);
+    if let Some(parent) = headers
+        .get(vulcan_telemetry::TRACEPARENT)
+        .and_then(|value| value.to_str().ok())
+    {
+        vulcan_telemetry::set_parent(&span, parent);
+    }
+
+    span.in_scope(|| store_workflow(&state, &request))
+}
+
+/// Parse a workflow and store it as a chain.
+fn store_workflow(
+    state: &AppState,
+    request: &ParseRequest,
 ) -> Result<Json<ParseResponse>, ApiError> {
     // Build workflow context
     let mut context = WorkflowContext::new(request.tenant_id);
@@ -100,10 +126,21 @@ pub async fn parse_workflow(
     if let Some(ref path) = request.source_file_path {
         context = context.with_source(path.clone());
     }
+    // Workers pass these to git, so they must not read as options
     if let Some(ref url) = request.repository_url {
+        if !is_remote_repository_url(url) {
+            return Err(ApiError::InvalidRequest(format!(
+                "invalid repository URL: {url} (only https and ssh repositories are supported)"
+            )));
+        }
         context = context.with_repository(url.clone());
     }
     if let Some(ref sha) = request.commit_sha {
+        if !is_commit_sha(sha) {
+            return Err(ApiError::InvalidRequest(format!(
+                "invalid commit SHA: {sha} (expected 7 to 64 lowercase hexadecimal digits)"
+            )));
+        }
         context = context.with_commit(sha.clone());
     }
     if let Some(ref branch) = request.branch {
@@ -114,13 +151,14 @@ pub async fn parse_workflow(
         context = context.with_trigger(trigger_type, request.trigger_ref.clone());
     }
 
-    // Parse the workflow
+    // Parse the workflow, validating the trigger if given
     let service = ChainParserService::new(NoOpFetcher);
-    let parsed = if request.trigger.is_some() {
-        service.parse(&request.content, &context)?
-    } else {
-        service.parse_without_trigger_validation(&request.content, &context)?
+    let checked = service.check(&request.content, &context);
+    let Some(mut parsed) = checked.value else {
+        return Err(ApiError::Diagnostics(checked.diagnostics));
     };
+    parsed.chain.trace_context = vulcan_telemetry::current_traceparent();
+    let warnings = checked.diagnostics.iter().map(DiagnosticResponse::from).collect();
 
     // Store in database
     let (chain_id, fragment_count) = {
@@ -129,19 +167,24 @@ pub async fn parse_workflow(
             .lock()
             .map_err(|e| ApiError::Internal(format!("failed to acquire db lock: {e}")))?;
 
-        let mut chain_repo = PgChainRepository::new(&mut conn);
-        let chain = chain_repo.create(parsed.chain)?;
+        // Store the chain and make its entry fragments ready atomically
+        conn.transaction::<_, RepositoryError, _>(|conn| {
+            let mut chain_repo = PgChainRepository::new(conn);
+            let chain = chain_repo.create(parsed.chain)?;
 
-        let mut fragment_repo = PgFragmentRepository::new(chain_repo.conn());
-        let fragments = fragment_repo.create_many(parsed.fragments)?;
+            let mut fragment_repo = PgFragmentRepository::new(chain_repo.conn());
+            let fragments = fragment_repo.create_many(parsed.fragments)?;
+            fragment_repo.activate_chain(chain.id)?;
 
-        (chain.id, fragments.len())
+            Ok((chain.id, fragments.len()))
+        })?
     };
 
     Ok(Json(ParseResponse {
         chain_id,
         fragment_count,
         message: "Workflow parsed and stored successfully".to_string(),
+        warnings,
     }))
 }
 
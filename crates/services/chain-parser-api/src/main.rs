@@ -5,20 +5,22 @@
 use std::env;
 use std::net::SocketAddr;
 
-use tracing_subscriber::EnvFilter;
-
 use vulcan_chain_parser_api::{build_router, create_app_state};
 
 #[tokio::main]
 async fn main() {
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env().add_directive("info".parse().unwrap()))
-        .init();
-
     // Load environment variables
     dotenvy::dotenv().ok();
 
+    // Initialize tracing
+    let _telemetry = match vulcan_telemetry::init("vulcan-chain-parser-api", "info") {
+        Ok(telemetry) => telemetry,
+        Err(e) => {
+            eprintln!("Failed to set up trace export: {e}");
+            std::process::exit(1);
+        },
+    };
+
     // Verify DATABASE_URL is set
     if env::var("DATABASE_URL").is_err() {
         tracing::error!("DATABASE_URL environment variable must be set");
@@ -5,8 +5,11 @@ use axum::http::{Request, StatusCode};
 use http_body_util::BodyExt;
 use serde_json::{json, Value};
 use tower::ServiceExt;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
 
 use vulcan_chain_parser_api::{build_router, create_app_state};
+use vulcan_core::repositories::{ChainRepository, PgChainRepository};
 
 /// Create a test router with a real database connection.
 ///
@@ -81,6 +84,7 @@ chain {
     assert!(body.get("chain_id").is_some());
     assert_eq!(body["fragment_count"], 2);
     assert_eq!(body["message"], "Workflow parsed and stored successfully");
+    assert!(body.get("warnings").is_none());
 }
 
 #[tokio::test]
@@ -149,6 +153,67 @@ chain {
     assert!(body["error"].as_str().unwrap().contains("machine"));
 }
 
+#[tokio::test]
+async fn test_parse_reports_located_diagnostics() {
+    let app = create_test_app();
+
+    let workflow_content = r#"version "0.1"
+triggers "push"
+
+chain {
+    machine "default-worker"
+    fragmnet { run "npm build" }
+    fragment { machine "gpu" }
+}
+"#;
+
+    let request_body = json!({
+        "content": workflow_content,
+        "tenant_id": "550e8400-e29b-41d4-a716-446655440000",
+        "source_file_path": ".vulcan/ci.kdl"
+    });
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/parse")
+                .header("Content-Type", "application/json")
+                .body(Body::from(request_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = body_to_json(response.into_body()).await;
+    assert_eq!(body["code"], "PARSE_ERROR");
+    assert_eq!(
+        body["error"],
+        "parse error: .vulcan/ci.kdl:6:5: unknown node type: fragmnet (and 1 more)"
+    );
+
+    let diagnostics = body["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(
+        diagnostics[0],
+        json!({
+            "severity": "error",
+            "code": "vulcan::unknown_node",
+            "message": "unknown node type: fragmnet",
+            "file": ".vulcan/ci.kdl",
+            "line": 6,
+            "column": 5,
+            "snippet": "    fragmnet { run \"npm build\" }",
+            "label": "unknown node",
+            "help": "did you mean `fragment`?"
+        })
+    );
+    assert_eq!(diagnostics[1]["code"], "vulcan::no_content");
+    assert_eq!(diagnostics[1]["line"], 7);
+}
+
 #[tokio::test]
 async fn test_parse_trigger_mismatch() {
     let app = create_test_app();
@@ -227,6 +292,52 @@ chain {
     assert_eq!(body["code"], "INVALID_REQUEST");
 }
 
+#[tokio::test]
+async fn test_parse_rejects_invalid_checkout_source() {
+    let workflow_content = r#"
+version "0.1"
+triggers "push"
+
+chain {
+    machine "default-worker"
+    fragment { run "npm build" }
+}
+"#;
+
+    let cases = [
+        ("https://github.com/org/repo", "--upload-pack=touch PWNED"),
+        ("https://github.com/org/repo", "HEAD"),
+        ("--upload-pack=touch PWNED", "abc1234"),
+        ("file:///var/cache/git", "abc1234"),
+        ("/var/cache/git", "abc1234"),
+    ];
+
+    for (repository_url, commit_sha) in cases {
+        let request_body = json!({
+            "content": workflow_content,
+            "tenant_id": "550e8400-e29b-41d4-a716-446655440000",
+            "repository_url": repository_url,
+            "commit_sha": commit_sha
+        });
+
+        let response = create_test_app()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/parse")
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(request_body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{repository_url} {commit_sha}");
+        let body = body_to_json(response.into_body()).await;
+        assert_eq!(body["code"], "INVALID_REQUEST");
+    }
+}
+
 #[tokio::test]
 async fn test_parse_with_parallel_fragments() {
     let app = create_test_app();
@@ -318,6 +429,48 @@ chain {
     assert_eq!(body["fragment_count"], 2);
 }
 
+#[tokio::test]
+async fn test_parse_returns_warnings() {
+    let app = create_test_app();
+
+    let workflow_content = r#"version "0.1"
+triggers "push"
+
+chain {
+    machine "default-worker"
+    fragment { run "npm build"; machine "default-worker" }
+}
+"#;
+
+    let request_body = json!({
+        "content": workflow_content,
+        "tenant_id": "550e8400-e29b-41d4-a716-446655440000"
+    });
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/parse")
+                .header("Content-Type", "application/json")
+                .body(Body::from(request_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // Warnings do not stop the workflow being stored
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = body_to_json(response.into_body()).await;
+    assert_eq!(body["fragment_count"], 1);
+    let warnings = body["warnings"].as_array().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0]["severity"], "warning");
+    assert_eq!(warnings[0]["code"], "vulcan::unused_machine");
+    assert_eq!(warnings[0]["line"], 6);
+}
+
 #[tokio::test]
 async fn test_parse_invalid_json_body() {
     let app = create_test_app();
@@ -343,7 +496,7 @@ async fn test_parse_missing_tenant_id() {
     let app = create_test_app();
 
     let request_body = json!({
-        "content": "version \"0.1\"\ntriggers \"push\"\nchain { machine \"default\" fragment { run \"test\" } }"
+        "content": "version \"0.1\"\ntriggers \"push\"\nchain { machine \"default\"; fragment { run \"test\" } }"
         // Missing tenant_id
     });
 
@@ -362,3 +515,45 @@ async fn test_parse_missing_tenant_id() {
     // Missing required field in JSON should return 422
     assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
 }
+
+#[tokio::test]
+async fn test_parse_stores_trace_context() {
+    let provider = vulcan_telemetry::tracer_provider("chain-parser-api-test", None);
+    let _subscriber = tracing_subscriber::registry()
+        .with(vulcan_telemetry::layer(&provider))
+        .set_default();
+    let app = create_test_app();
+
+    let trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+    let request_body = json!({
+        "content": "version \"0.1\"\ntriggers \"push\"\nchain { machine \"default\"; fragment { run \"test\" } }",
+        "tenant_id": "550e8400-e29b-41d4-a716-446655440000"
+    });
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/parse")
+                .header("Content-Type", "application/json")
+                .header("traceparent", format!("00-{trace_id}-00f067aa0ba902b7-01"))
+                .body(Body::from(request_body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_to_json(response.into_body()).await;
+    let chain_id = body["chain_id"].as_str().unwrap().parse().unwrap();
+
+    // The chain continues the caller's trace in a span of its own
+    let mut conn = vulcan_core::establish_connection();
+    let chain = PgChainRepository::new(&mut conn)
+        .find_by_id(chain_id)
+        .unwrap()
+        .unwrap();
+    let trace_context = chain.trace_context.unwrap();
+    assert!(trace_context.starts_with(&format!("00-{trace_id}-")));
+    assert!(!trace_context.contains("00f067aa0ba902b7"));
+}
@@ -1,12 +1,18 @@
 //! Vulcan Chain Parser CLI.
 //!
-//! A command-line tool for validating and inspecting KDL workflow files.
+//! A command-line tool for validating and inspecting KDL workflow files, and
+//! migrating them to the newest version of the format.
+
+mod render;
 
 use std::env;
 use std::fs;
 use std::path::Path;
 
-use vulcan_chain_parser::{ChainParserService, ImportFetcher, ParseError, Result, WorkflowContext};
+use vulcan_chain_parser::migrate::{migrate_fragments, migrate_workflow};
+use vulcan_chain_parser::{
+    ChainParserService, ImportFetcher, ParseError, Result, Version, WorkflowContext,
+};
 
 /// File-based import fetcher for local workflow validation.
 ///
@@ -45,14 +51,74 @@ fn print_usage() {
     eprintln!();
     eprintln!("USAGE:");
     eprintln!("    vulcan-parse <workflow.kdl> [OPTIONS]");
+    eprintln!("    vulcan-parse migrate <workflow.kdl> [--write] [--from <version>]");
     eprintln!();
     eprintln!("ARGS:");
     eprintln!("    <workflow.kdl>    Path to the KDL workflow file to parse");
     eprintln!();
     eprintln!("OPTIONS:");
     eprintln!("    --base-path <dir>    Base directory for resolving imports (default: file's directory)");
-    eprintln!("    --quiet              Only output errors, no success details");
+    eprintln!("    --quiet              Only output problems, no success details");
+    eprintln!("    --strict             Treat warnings as errors");
     eprintln!("    --help               Print this help message");
+    eprintln!();
+    eprintln!("MIGRATE:");
+    let latest = Version::latest().name;
+    eprintln!("    Rewrite a workflow file to version \"{latest}\", keeping comments and formatting.");
+    eprintln!("    Prints the migrated file unless --write is given.");
+    eprintln!();
+    eprintln!("    --write              Rewrite the file in place");
+    eprintln!("    --from <version>     Migrate an imported fragment file, written for <version>");
+}
+
+/// Migrate a workflow or imported fragment file to the newest version.
+fn migrate(args: &[String]) {
+    let Some(path) = args.first().filter(|arg| !arg.starts_with("--")) else {
+        print_usage();
+        std::process::exit(1);
+    };
+    let write = args.contains(&"--write".to_string());
+    let from = args
+        .iter()
+        .position(|a| a == "--from")
+        .and_then(|i| args.get(i + 1));
+
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error reading file '{path}': {e}");
+            std::process::exit(1);
+        }
+    };
+
+    let checked = from.map_or_else(
+        || migrate_workflow(&content, Some(path)),
+        |version| migrate_fragments(&content, Some(path), version),
+    );
+    for diagnostic in &checked.diagnostics {
+        eprintln!("{}", render::render(diagnostic));
+    }
+
+    let Some(migrated) = checked.value else {
+        let errors = checked.diagnostics.iter().filter(|d| d.is_error()).count();
+        let plural = if errors == 1 { "" } else { "s" };
+        eprintln!("Migration failed: '{path}' has {errors} error{plural}");
+        std::process::exit(1);
+    };
+
+    let latest = Version::latest().name;
+    if migrated == content {
+        eprintln!("'{path}' is already at version \"{latest}\"");
+    }
+    if !write {
+        print!("{migrated}");
+    } else if migrated != content {
+        if let Err(e) = fs::write(path, &migrated) {
+            eprintln!("Error writing file '{path}': {e}");
+            std::process::exit(1);
+        }
+        eprintln!("Migrated '{path}' to version \"{latest}\"");
+    }
 }
 
 fn main() {
@@ -63,8 +129,14 @@ fn main() {
         std::process::exit(if args.contains(&"--help".to_string()) { 0 } else { 1 });
     }
 
+    if args[1] == "migrate" {
+        migrate(&args[2..]);
+        return;
+    }
+
     let workflow_path = &args[1];
     let quiet = args.contains(&"--quiet".to_string());
+    let strict = args.contains(&"--strict".to_string());
 
     // Determine base path for imports
     let base_path = args
@@ -93,57 +165,64 @@ fn main() {
     let service = ChainParserService::new(fetcher);
     let context = WorkflowContext::new(uuid::Uuid::new_v4()).with_source(workflow_path.clone());
 
-    match service.parse_without_trigger_validation(&content, &context) {
-        Ok(result) => {
-            if quiet {
-                std::process::exit(0);
-            }
+    let mut checked = service.check(&content, &context);
+    if strict {
+        checked = checked.promote_warnings();
+    }
+    for diagnostic in &checked.diagnostics {
+        eprintln!("{}", render::render(diagnostic));
+    }
+
+    let Some(result) = checked.value else {
+        let errors = checked.diagnostics.iter().filter(|d| d.is_error()).count();
+        let plural = if errors == 1 { "" } else { "s" };
+        eprintln!("Parse failed: '{workflow_path}' has {errors} error{plural}");
+        std::process::exit(1);
+    };
+
+    if quiet {
+        std::process::exit(0);
+    }
 
-            println!("Parsed workflow successfully!");
-            println!();
-            println!("Chain:");
-            println!("  ID: {}", result.chain.id);
-            println!("  Default Machine: {:?}", result.chain.default_machine);
-            println!();
-            println!("Fragments ({}):", result.fragments.len());
-
-            for (i, frag) in result.fragments.iter().enumerate() {
-                println!("  [{}] ID: {}", i, frag.id);
-                println!("      Type: {:?}", frag.fragment_type);
-                println!("      Sequence: {}", frag.sequence);
-
-                if let Some(ref parent) = frag.parent_fragment_id {
-                    println!("      Parent: {parent}");
-                }
-
-                if let Some(ref script) = frag.run_script {
-                    let preview: String = script.chars().take(60).collect();
-                    let preview = preview.replace('\n', " ");
-                    if script.len() > 60 {
-                        println!("      Script: {preview}...");
-                    } else {
-                        println!("      Script: {preview}");
-                    }
-                }
-
-                if let Some(ref machine) = frag.machine {
-                    println!("      Machine: {machine}");
-                }
-
-                if let Some(ref condition) = frag.condition {
-                    println!("      Condition: {condition}");
-                }
-
-                if let Some(ref url) = frag.source_url {
-                    println!("      Source: {url}");
-                }
-
-                println!();
+    println!("Parsed workflow successfully!");
+    println!();
+    println!("Chain:");
+    println!("  ID: {}", result.chain.id);
+    println!("  Default Machine: {:?}", result.chain.default_machine);
+    println!();
+    println!("Fragments ({}):", result.fragments.len());
+
+    for (i, frag) in result.fragments.iter().enumerate() {
+        println!("  [{}] ID: {}", i, frag.id);
+        println!("      Type: {:?}", frag.fragment_type);
+        println!("      Sequence: {}", frag.sequence);
+
+        if let Some(ref parent) = frag.parent_fragment_id {
+            println!("      Parent: {parent}");
+        }
+
+        if let Some(ref script) = frag.run_script {
+            let preview: String = script.chars().take(60).collect();
+            let preview = preview.replace('\n', " ");
+            if script.len() > 60 {
+                println!("      Script: {preview}...");
+            } else {
+                println!("      Script: {preview}");
             }
         }
-        Err(e) => {
-            eprintln!("Parse error in '{}': {}", workflow_path, e);
-            std::process::exit(1);
+
+        if let Some(ref machine) = frag.machine {
+            println!("      Machine: {machine}");
         }
+
+        if let Some(ref condition) = frag.condition {
+            println!("      Condition: {condition}");
+        }
+
+        if let Some(ref url) = frag.source_url {
+            println!("      Source: {url}");
+        }
+
+        println!();
     }
 }
@@ -0,0 +1,103 @@
+//! Rendering of diagnostics in the style of `miette`'s graphical reports.
+
+use std::fmt::Write;
+
+use vulcan_chain_parser::diagnostic::{Location, SourceFile};
+use vulcan_chain_parser::{Diagnostic, Severity};
+
+/// Render a diagnostic, with the lines of its file around its span.
+///
+/// ```text
+/// vulcan::unknown_node
+///
+///   × unknown node type: fragmnet
+///    ╭─[.vulcan/ci.kdl:7:5]
+///  6 │
+///  7 │     fragmnet { run "npm build" }
+///    ·     ────┬───
+///    ·         ╰── unknown node
+///  8 │ }
+///    ╰────
+///   help: expected `fragment` or `parallel`
+/// ```
+pub fn render(diagnostic: &Diagnostic) -> String {
+    let marker = match diagnostic.severity {
+        Severity::Error => '×',
+        Severity::Warning => '⚠',
+        Severity::Advice => '☞',
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", diagnostic.code());
+    let _ = writeln!(out);
+    let _ = writeln!(out, "  {marker} {}", diagnostic.error);
+
+    match (diagnostic.source(), diagnostic.location()) {
+        (Some(source), Some(location)) => render_snippet(&mut out, diagnostic, source, location),
+        _ => {
+            if let Some(file) = diagnostic.file() {
+                let _ = writeln!(out, "    in {file}");
+            }
+        },
+    }
+
+    if let Some(help) = &diagnostic.help {
+        let _ = writeln!(out, "  help: {help}");
+    }
+    out
+}
+
+/// Render the span of a diagnostic with a line of context on either side.
+fn render_snippet(
+    out: &mut String,
+    diagnostic: &Diagnostic,
+    source: &SourceFile,
+    location: Location,
+) {
+    let lines: Vec<&str> = source.content.lines().collect();
+    let index = location.line - 1;
+    let first = index.saturating_sub(1);
+    let last = (index + 1).min(lines.len().saturating_sub(1));
+    let gutter = (last + 1).to_string().len();
+
+    let file = source.name.as_deref().unwrap_or("<workflow>");
+    let _ = writeln!(
+        out,
+        " {:gutter$} ╭─[{file}:{}:{}]",
+        "", location.line, location.column
+    );
+
+    for (number, line) in lines.iter().enumerate().take(last + 1).skip(first) {
+        let numbered = format!(" {:>gutter$} │ {line}", number + 1);
+        let _ = writeln!(out, "{}", numbered.trim_end());
+        if number != index {
+            continue;
+        }
+
+        // Underline the span on its first line, keeping tabs for alignment
+        let pad: String = line
+            .chars()
+            .take(location.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let width = diagnostic
+            .span
+            .and_then(|span| {
+                source
+                    .content
+                    .get(span.offset()..span.offset() + span.len())
+            })
+            .and_then(|text| text.lines().next())
+            .map_or(1, |text| text.chars().count().max(1));
+        let middle = width / 2;
+        let underline: String = (0..width)
+            .map(|i| if i == middle { '┬' } else { '─' })
+            .collect();
+
+        let _ = writeln!(out, " {:gutter$} · {pad}{underline}", "");
+        let label = diagnostic.label.as_deref().unwrap_or("here");
+        let _ = writeln!(out, " {:gutter$} · {pad}{:middle$}╰── {label}", "", "");
+    }
+
+    let _ = writeln!(out, " {:gutter$} ╰────", "");
+}
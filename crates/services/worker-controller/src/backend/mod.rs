@@ -0,0 +1,96 @@
+//! Scaling backends: where the workers of deployment-mode pools run.
+//!
+//! The controller decides how many workers a pool needs; a [`ScalingBackend`] runs
+//! them, as the replicas of a Kubernetes Deployment
+//! ([`KubernetesBackend`](crate::kubernetes::KubernetesBackend)) or as processes on
+//! the controller's host ([`ProcessBackend`]).
+
+pub mod process;
+
+use std::future::Future;
+
+use crate::client::dto::WorkerBusyResponse;
+use crate::error::Result;
+use crate::pool::WorkerPool;
+
+pub use process::ProcessBackend;
+
+/// What the worker of an instance is doing, which decides the order instances are
+/// removed in on scale-down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkerActivity {
+    /// The worker is executing fragments: remove last.
+    Busy,
+    /// The worker is idle, or has not registered yet.
+    Idle,
+    /// The worker is draining and idle: remove first.
+    Draining,
+}
+
+impl WorkerActivity {
+    /// Cost of removing an instance: instances with the lowest cost go first.
+    #[must_use]
+    pub const fn removal_cost(self) -> i32 {
+        match self {
+            Self::Busy => 1000,
+            Self::Idle => -500,
+            Self::Draining => -1000,
+        }
+    }
+}
+
+impl From<&WorkerBusyResponse> for WorkerActivity {
+    fn from(busy: &WorkerBusyResponse) -> Self {
+        match (busy.busy, busy.draining) {
+            (true, _) => Self::Busy,
+            (false, false) => Self::Idle,
+            (false, true) => Self::Draining,
+        }
+    }
+}
+
+/// Runs the workers of deployment-mode pools.
+///
+/// Instances are what a worker runs in (a pod or a process); their names are the
+/// names their workers register with.
+pub trait ScalingBackend: Send + Sync {
+    /// Check the pool can be scaled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if it cannot, e.g. because its Deployment does not exist.
+    fn verify(&self, pool: &WorkerPool) -> impl Future<Output = Result<()>> + Send;
+
+    /// Get the replica count of the pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pool's workers cannot be read.
+    fn replicas(&self, pool: &WorkerPool) -> impl Future<Output = Result<i32>> + Send;
+
+    /// Scale the pool to `replicas` workers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pool cannot be scaled.
+    fn scale(&self, pool: &WorkerPool, replicas: i32) -> impl Future<Output = Result<()>> + Send;
+
+    /// List the names of the pool's instances that are not shutting down.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the instances cannot be listed.
+    fn instances(&self, pool: &WorkerPool) -> impl Future<Output = Result<Vec<String>>> + Send;
+
+    /// Set the order instances are removed in on the next scale-down, from what
+    /// their workers are doing. Instances that no longer exist are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the order cannot be recorded.
+    fn set_removal_order(
+        &self,
+        pool: &WorkerPool,
+        instances: &[(String, WorkerActivity)],
+    ) -> impl Future<Output = Result<()>> + Send;
+}
@@ -0,0 +1,375 @@
+//! Worker processes on the controller's host.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::{env, fs};
+
+use tokio::process::{Child, Command};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use super::{ScalingBackend, WorkerActivity};
+use crate::error::{ControllerError, Result};
+use crate::pool::WorkerPool;
+
+/// Environment variables of the controller that are not passed on to its workers.
+const CONTROLLER_ONLY_ENV: [&str; 2] = ["CONTROLLER_TOKEN", "METRICS_PORT"];
+
+/// Directories of the worker given to each process as a subdirectory named after it,
+/// with the worker's defaults.
+const PER_WORKER_DIRS: [(&str, &str); 2] = [
+    ("SANDBOX_SCRATCH_DIR", "/scratch"),
+    ("CHECKOUT_CACHE_DIR", "/cache/git"),
+];
+
+/// A worker process of a pool.
+struct WorkerProcess {
+    /// Name the worker registers with.
+    name: String,
+    pid: u32,
+    /// The process, unless it was started by a previous run of the controller.
+    child: Option<Child>,
+    /// Cost of removing the process: the lowest goes first.
+    removal_cost: i32,
+    /// Whether the process was asked to stop and is draining.
+    stopping: bool,
+}
+
+/// The worker processes of a pool.
+#[derive(Default)]
+struct ProcessPool {
+    /// Number of the next process, naming it.
+    next: u64,
+    processes: Vec<WorkerProcess>,
+}
+
+impl ProcessPool {
+    /// Forget processes that exited, with their pid files in `state_dir`.
+    fn reap(&mut self, state_dir: &Path) {
+        self.processes.retain_mut(|process| {
+            let running = match &mut process.child {
+                Some(child) => match child.try_wait() {
+                    Ok(None) => true,
+                    Ok(Some(status)) => {
+                        debug!(worker = %process.name, %status, "Worker process exited");
+                        false
+                    },
+                    Err(e) => {
+                        warn!(worker = %process.name, error = %e, "Failed to check worker process");
+                        true
+                    },
+                },
+                None => is_worker(process.pid, &process.name),
+            };
+            if !running {
+                let _ = fs::remove_file(pid_file(state_dir, &process.name));
+            }
+            running
+        });
+    }
+
+    /// Processes that are not stopping.
+    fn running(&self) -> impl Iterator<Item = &WorkerProcess> {
+        self.processes.iter().filter(|process| !process.stopping)
+    }
+}
+
+/// Scales pools as `vulcan-worker` processes on the controller's host, so a single
+/// machine can autoscale its workers without Kubernetes.
+///
+/// Each process is started with the controller's environment (the orchestrator URL,
+/// registration token and worker settings), plus the pool's `TENANT_ID`,
+/// `WORKER_GROUP` and `WORKER_LABELS`, and a `WORKER_NAME` of the pool's
+/// `deployment`, an identifier of the backend and a number, so new workers never
+/// take the names of those started before a restart of the controller. Scaling
+/// down sends `SIGTERM`, so workers drain before they exit.
+///
+/// Processes keep running when the controller exits. Each has a pid file in the
+/// state directory, from which the next backend adopts the processes that are still
+/// running, so they are counted and scaled down with the pool.
+///
+/// Workers remove the directories they find in their scratch directory when they
+/// start, and lock their checkout cache only within the process, so each process
+/// gets its own `SANDBOX_SCRATCH_DIR` and `CHECKOUT_CACHE_DIR` below the configured
+/// ones, named after the worker.
+pub struct ProcessBackend {
+    command: PathBuf,
+    /// Directory of the pid files of the workers.
+    state_dir: PathBuf,
+    /// Identifier of this backend in the names of its workers.
+    run: String,
+    pools: Mutex<HashMap<String, ProcessPool>>,
+}
+
+impl ProcessBackend {
+    /// Create a new process backend, adopting the workers of previous runs that are
+    /// still running.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The worker executable, a path or a name looked up in `PATH`
+    /// * `state_dir` - Directory of the pid files of the workers, created if needed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the state directory cannot be created or read.
+    pub fn new(command: PathBuf, state_dir: PathBuf) -> Result<Self> {
+        let unreadable = |e: std::io::Error| {
+            ControllerError::Process(format!("Failed to read {}: {e}", state_dir.display()))
+        };
+        fs::create_dir_all(&state_dir).map_err(unreadable)?;
+
+        let mut pools: HashMap<String, ProcessPool> = HashMap::new();
+        for entry in fs::read_dir(&state_dir).map_err(unreadable)? {
+            let path = entry.map_err(unreadable)?.path();
+            let Some(name) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".pid"))
+            else {
+                continue;
+            };
+
+            let recorded = fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| parse_pid_file(&content));
+            match recorded {
+                Some((pid, pool)) if is_worker(pid, name) => {
+                    info!(worker = %name, pid, pool = %pool, "Adopted worker process");
+                    pools
+                        .entry(pool)
+                        .or_default()
+                        .processes
+                        .push(WorkerProcess {
+                            name: name.to_string(),
+                            pid,
+                            child: None,
+                            removal_cost: 0,
+                            stopping: false,
+                        });
+                },
+                _ => {
+                    debug!(worker = %name, "Worker process of a previous run is gone");
+                    let _ = fs::remove_file(&path);
+                },
+            }
+        }
+
+        Ok(Self {
+            command,
+            state_dir,
+            run: Uuid::new_v4().simple().to_string()[..8].to_string(),
+            pools: Mutex::new(pools),
+        })
+    }
+
+    /// Run `f` on the pool's processes, after forgetting those that exited.
+    fn with_pool<T>(&self, pool: &WorkerPool, f: impl FnOnce(&mut ProcessPool) -> T) -> T {
+        let mut pools = self
+            .pools
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let processes = pools.entry(pool.key()).or_default();
+        processes.reap(&self.state_dir);
+        let result = f(processes);
+        drop(pools);
+        result
+    }
+
+    /// Start a worker process of a pool, and write its pid file.
+    fn spawn(&self, pool: &WorkerPool, name: &str) -> Result<WorkerProcess> {
+        let mut command = Command::new(&self.command);
+        for name in CONTROLLER_ONLY_ENV {
+            command.env_remove(name);
+        }
+        command
+            .env("TENANT_ID", pool.spec.tenant_id.to_string())
+            .env("WORKER_GROUP", &pool.spec.machine_group)
+            .env("WORKER_NAME", name)
+            .stdin(Stdio::null());
+        for (var, default) in PER_WORKER_DIRS {
+            let root = env::var_os(var).map_or_else(|| PathBuf::from(default), PathBuf::from);
+            command.env(var, root.join(name));
+        }
+        if let Some(labels) = &pool.spec.labels {
+            command.env("WORKER_LABELS", labels.join(","));
+        }
+
+        let mut child = command.spawn().map_err(|e| {
+            ControllerError::Process(format!("Failed to start {}: {e}", self.command.display()))
+        })?;
+        let Some(pid) = child.id() else {
+            return Err(ControllerError::Process(format!(
+                "Worker process {name} exited right away"
+            )));
+        };
+
+        let path = pid_file(&self.state_dir, name);
+        if let Err(e) = fs::write(&path, format!("{pid}\n{}\n", pool.key())) {
+            // A worker the next run can't adopt would never be stopped
+            let _ = child.start_kill();
+            return Err(ControllerError::Process(format!(
+                "Failed to write {}: {e}",
+                path.display()
+            )));
+        }
+
+        Ok(WorkerProcess {
+            name: name.to_string(),
+            pid,
+            child: Some(child),
+            removal_cost: 0,
+            stopping: false,
+        })
+    }
+}
+
+impl ScalingBackend for ProcessBackend {
+    async fn verify(&self, _pool: &WorkerPool) -> Result<()> {
+        if command_exists(&self.command) {
+            Ok(())
+        } else {
+            Err(ControllerError::Process(format!(
+                "Worker command {} not found",
+                self.command.display()
+            )))
+        }
+    }
+
+    async fn replicas(&self, pool: &WorkerPool) -> Result<i32> {
+        let running = self.with_pool(pool, |processes| processes.running().count());
+        Ok(i32::try_from(running).unwrap_or(i32::MAX))
+    }
+
+    async fn scale(&self, pool: &WorkerPool, replicas: i32) -> Result<()> {
+        let replicas = usize::try_from(replicas).unwrap_or(0);
+
+        let stopping = self.with_pool(pool, |processes| -> Result<Vec<(String, u32)>> {
+            let running = processes.running().count();
+
+            for _ in running..replicas {
+                let name = format!("{}-{}-{}", pool.spec.deployment, self.run, processes.next);
+                processes.next += 1;
+                let process = self.spawn(pool, &name)?;
+                info!(worker = %name, pid = process.pid, "Started worker process");
+                processes.processes.push(process);
+            }
+
+            // Stop the cheapest processes first, the newest among equal costs
+            let mut candidates: Vec<&mut WorkerProcess> = processes
+                .processes
+                .iter_mut()
+                .filter(|process| !process.stopping)
+                .rev()
+                .collect();
+            candidates.sort_by_key(|process| process.removal_cost);
+
+            Ok(candidates
+                .into_iter()
+                .take(running.saturating_sub(replicas))
+                .map(|process| {
+                    process.stopping = true;
+                    (process.name.clone(), process.pid)
+                })
+                .collect())
+        })?;
+
+        for (name, pid) in stopping {
+            terminate(&name, pid).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn instances(&self, pool: &WorkerPool) -> Result<Vec<String>> {
+        Ok(self.with_pool(pool, |processes| {
+            processes
+                .running()
+                .map(|process| process.name.clone())
+                .collect()
+        }))
+    }
+
+    async fn set_removal_order(
+        &self,
+        pool: &WorkerPool,
+        instances: &[(String, WorkerActivity)],
+    ) -> Result<()> {
+        self.with_pool(pool, |processes| {
+            for process in &mut processes.processes {
+                if let Some((_, activity)) =
+                    instances.iter().find(|(name, _)| *name == process.name)
+                {
+                    process.removal_cost = activity.removal_cost();
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+/// Ask a worker process to drain and exit, with `SIGTERM`.
+async fn terminate(name: &str, pid: u32) -> Result<()> {
+    let status = Command::new("kill")
+        .arg("-TERM")
+        .arg(pid.to_string())
+        .status()
+        .await
+        .map_err(|e| ControllerError::Process(format!("Failed to run kill: {e}")))?;
+
+    if status.success() {
+        info!(worker = %name, pid, "Stopping worker process");
+    } else {
+        // The process exited by itself in the meantime
+        debug!(worker = %name, pid, "Worker process already gone");
+    }
+    Ok(())
+}
+
+/// Path of the pid file of a worker.
+fn pid_file(state_dir: &Path, name: &str) -> PathBuf {
+    state_dir.join(format!("{name}.pid"))
+}
+
+/// Read a pid file: the worker's pid and the key of its pool, on separate lines.
+fn parse_pid_file(content: &str) -> Option<(u32, String)> {
+    let mut lines = content.lines();
+    let pid = lines.next()?.parse().ok()?;
+    let pool = lines.next()?.to_string();
+    Some((pid, pool))
+}
+
+/// Whether the process `pid` is still the worker `name`.
+///
+/// Where `/proc` is available, the process must have the worker's `WORKER_NAME`, so
+/// a pid reused by another process is not taken for the worker.
+fn is_worker(pid: u32, name: &str) -> bool {
+    let proc = Path::new("/proc");
+    if proc.is_dir() {
+        let variable = format!("WORKER_NAME={name}");
+        return fs::read(proc.join(pid.to_string()).join("environ")).is_ok_and(|environ| {
+            environ
+                .split(|&byte| byte == 0)
+                .any(|entry| entry == variable.as_bytes())
+        });
+    }
+
+    std::process::Command::new("kill")
+        .arg("-0")
+        .arg(pid.to_string())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Whether `command` names a file, as a path or a name looked up in `PATH`.
+fn command_exists(command: &Path) -> bool {
+    if command.components().count() > 1 {
+        return command.is_file();
+    }
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(command).is_file()))
+}
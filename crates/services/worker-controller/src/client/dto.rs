@@ -14,11 +14,39 @@ pub struct QueueMetricsResponse {
     pub active_workers: i64,
 }
 
-/// Response indicating if a worker is busy executing a fragment.
+/// Response indicating if a worker is busy executing fragments.
 #[derive(Debug, Deserialize)]
 pub struct WorkerBusyResponse {
-    /// Whether the worker is currently executing a fragment.
+    /// Whether the worker is currently executing any fragment.
     pub busy: bool,
-    /// The fragment ID being executed, if any.
+    /// The longest-running fragment ID being executed, if any.
     pub fragment_id: Option<Uuid>,
+    /// All fragment IDs being executed.
+    #[serde(default)]
+    pub fragment_ids: Vec<Uuid>,
+    /// Whether the worker is draining.
+    #[serde(default)]
+    pub draining: bool,
+}
+
+/// A live (active or draining) worker, as listed by the orchestrator.
+#[derive(Debug, Deserialize)]
+pub struct WorkerSummary {
+    /// Worker ID.
+    pub worker_id: Uuid,
+    /// Name the worker registered with, its pod name when running in Kubernetes.
+    pub name: Option<String>,
+    /// Status of the worker: `Active` or `Draining`.
+    pub status: String,
+}
+
+/// A fragment waiting for a worker, as listed by the orchestrator.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PendingFragment {
+    /// Fragment ID.
+    pub fragment_id: Uuid,
+    /// The chain the fragment belongs to.
+    pub chain_id: Uuid,
+    /// Attempt the fragment will be executed as.
+    pub attempt: i32,
 }
@@ -2,23 +2,72 @@
 
 pub mod dto;
 
-use reqwest::Client;
+use reqwest::{Certificate, Client, Identity, RequestBuilder};
+use uuid::Uuid;
 
-use crate::error::Result;
-use dto::QueueMetricsResponse;
+use crate::config::TlsConfig;
+use crate::error::{ControllerError, Result};
+use dto::{PendingFragment, QueueMetricsResponse, WorkerBusyResponse, WorkerSummary};
+
+/// Build the HTTP client for the orchestrator, trusting the configured CA and
+/// presenting the configured client certificate.
+///
+/// # Errors
+///
+/// Returns an error if the TLS files cannot be read or the HTTP client cannot be
+/// built.
+pub fn http_client(tls: &TlsConfig) -> Result<Client> {
+    let mut builder = Client::builder();
+
+    if let Some(ca_file) = &tls.ca_file {
+        builder = builder.add_root_certificate(Certificate::from_pem(&read_tls_file(ca_file)?)?);
+    }
+    if let Some((cert_file, key_file)) = &tls.identity {
+        builder = builder.identity(Identity::from_pkcs8_pem(
+            &read_tls_file(cert_file)?,
+            &read_tls_file(key_file)?,
+        )?);
+    }
+
+    Ok(builder.build()?)
+}
+
+fn read_tls_file(path: &str) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|e| ControllerError::Config(format!("Cannot read {path}: {e}")))
+}
 
 /// Client for communicating with the orchestrator service.
+///
+/// Requests are authenticated with the controller token of the tenant and carry the
+/// trace context of the current span.
 pub struct OrchestratorClient {
     client: Client,
     base_url: String,
+    token: String,
 }
 
 impl OrchestratorClient {
     /// Create a new orchestrator client.
-    pub fn new(base_url: String) -> Self {
+    pub fn new(base_url: String, token: String) -> Self {
+        Self::with_client(Client::new(), base_url, token)
+    }
+
+    /// Create an orchestrator client sharing the connections of `client`.
+    #[must_use]
+    pub const fn with_client(client: Client, base_url: String, token: String) -> Self {
         Self {
-            client: Client::new(),
+            client,
             base_url,
+            token,
+        }
+    }
+
+    /// Start an authenticated GET request.
+    fn get(&self, url: &str) -> RequestBuilder {
+        let request = self.client.get(url).bearer_auth(&self.token);
+        match vulcan_telemetry::current_traceparent() {
+            Some(traceparent) => request.header(vulcan_telemetry::TRACEPARENT, traceparent),
+            None => request,
         }
     }
 
@@ -26,19 +75,20 @@ impl OrchestratorClient {
     ///
     /// # Arguments
     ///
+    /// * `tenant_id` - Tenant whose fragments and workers are counted
     /// * `machine_group` - Optional machine group to filter metrics
     pub async fn get_queue_metrics(
         &self,
+        tenant_id: Uuid,
         machine_group: Option<&str>,
     ) -> Result<QueueMetricsResponse> {
-        let mut url = format!("{}/queue/metrics", self.base_url);
+        let mut url = format!("{}/queue/metrics?tenant_id={}", self.base_url, tenant_id);
 
         if let Some(group) = machine_group {
-            url = format!("{}?machine_group={}", url, group);
+            url = format!("{}&machine_group={}", url, group);
         }
 
         let response = self
-            .client
             .get(&url)
             .send()
             .await?
@@ -48,4 +98,97 @@ impl OrchestratorClient {
 
         Ok(response)
     }
+
+    /// List the live (active or draining) workers of a tenant.
+    ///
+    /// # Arguments
+    ///
+    /// * `tenant_id` - Tenant whose workers are listed
+    /// * `machine_group` - Optional machine group to filter workers
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the orchestrator rejects it.
+    pub async fn get_workers(
+        &self,
+        tenant_id: Uuid,
+        machine_group: Option<&str>,
+    ) -> Result<Vec<WorkerSummary>> {
+        let mut url = format!("{}/workers?tenant_id={tenant_id}", self.base_url);
+
+        if let Some(group) = machine_group {
+            url = format!("{url}&machine_group={group}");
+        }
+
+        let response = self
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Vec<WorkerSummary>>()
+            .await?;
+
+        Ok(response)
+    }
+
+    /// Check whether a worker is executing fragments.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the orchestrator rejects it.
+    pub async fn get_worker_busy(&self, worker_id: Uuid) -> Result<WorkerBusyResponse> {
+        let url = format!("{}/workers/{worker_id}/busy", self.base_url);
+
+        let response = self
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<WorkerBusyResponse>()
+            .await?;
+
+        Ok(response)
+    }
+
+    /// List the fragments of a tenant waiting for a worker, in the order workers
+    /// claim them.
+    ///
+    /// # Arguments
+    ///
+    /// * `tenant_id` - Tenant whose fragments are listed
+    /// * `machine_group` - Machine group the fragments run on
+    /// * `labels` - Labels of the workers that would run them (any if None)
+    /// * `limit` - How many fragments to list at most
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the orchestrator rejects it.
+    pub async fn get_pending_fragments(
+        &self,
+        tenant_id: Uuid,
+        machine_group: &str,
+        labels: Option<&[String]>,
+        limit: usize,
+    ) -> Result<Vec<PendingFragment>> {
+        let url = format!("{}/queue/pending", self.base_url);
+        let mut query = vec![
+            ("tenant_id", tenant_id.to_string()),
+            ("machine_group", machine_group.to_string()),
+            ("limit", limit.to_string()),
+        ];
+        if let Some(labels) = labels {
+            query.push(("labels", labels.join(",")));
+        }
+
+        let response = self
+            .get(&url)
+            .query(&query)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Vec<PendingFragment>>()
+            .await?;
+
+        Ok(response)
+    }
 }
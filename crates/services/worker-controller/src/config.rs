@@ -1,38 +1,157 @@
 //! Configuration for the worker-controller service.
 
 use std::env;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::pool::{PoolMode, WorkerPool, WorkerPoolSpec};
+use crate::scaler::{CronSchedule, Prediction, ScaleUpLimit, ScalingBehavior};
+
 /// Configuration for the worker-controller.
 #[derive(Debug, Clone)]
 pub struct Config {
     /// URL of the orchestrator service.
     pub orchestrator_url: String,
-    /// Tenant ID for this controller (used for metrics filtering).
-    pub tenant_id: Uuid,
-    /// Machine group to manage.
-    pub machine_group: String,
-    /// Kubernetes deployment name.
-    pub deployment_name: String,
-    /// Kubernetes deployment namespace.
-    pub deployment_namespace: String,
-    /// Scaling configuration.
-    pub scaling: ScalingConfig,
-}
-
-/// Scaling configuration for the controller.
+    /// Controller token authenticating requests to the orchestrator for pools without
+    /// a token secret.
+    pub controller_token: Option<String>,
+    /// Where the worker pools to scale are defined.
+    pub pools: PoolSource,
+    /// Where the workers of deployment-mode pools run.
+    pub backend: Backend,
+    /// TLS settings of the connection to the orchestrator.
+    pub tls: TlsConfig,
+    /// Interval in seconds between scaling checks.
+    pub poll_interval_seconds: i64,
+    /// Port to serve Prometheus metrics on (optional).
+    pub metrics_port: Option<u16>,
+    /// File keeping the work history of pools with predictive scaling across
+    /// restarts (optional).
+    pub history_file: Option<PathBuf>,
+}
+
+/// Where the worker pools to scale are defined.
 #[derive(Debug, Clone)]
+pub enum PoolSource {
+    /// A single pool configured by environment variables.
+    Env(Box<WorkerPool>),
+    /// `WorkerPool` manifests in a YAML file, read at startup.
+    File(PathBuf),
+    /// `WorkerPool` resources, listed on every scaling check.
+    Crd {
+        /// Namespace to list pools in, or None for all namespaces.
+        namespace: Option<String>,
+    },
+}
+
+/// Where the workers of deployment-mode pools run.
+#[derive(Debug, Clone, Default)]
+pub enum Backend {
+    /// Replicas of Kubernetes Deployments.
+    #[default]
+    Kubernetes,
+    /// Processes on the controller's host.
+    Process {
+        /// The worker executable, a path or a name looked up in `PATH`.
+        command: PathBuf,
+        /// Directory of the pid files of the workers, to adopt them after a restart.
+        state_dir: PathBuf,
+    },
+}
+
+/// TLS configuration for the connection to the orchestrator.
+#[derive(Debug, Clone, Default)]
+pub struct TlsConfig {
+    /// PEM file with an additional CA certificate to trust for the orchestrator.
+    pub ca_file: Option<String>,
+    /// PEM files with the client certificate and its PKCS#8 private key, presented
+    /// to an orchestrator that verifies client certificates.
+    pub identity: Option<(String, String)>,
+}
+
+impl TlsConfig {
+    fn from_env() -> Self {
+        let identity = match (env::var("TLS_CERT_FILE").ok(), env::var("TLS_KEY_FILE").ok()) {
+            (Some(cert_file), Some(key_file)) => Some((cert_file, key_file)),
+            (None, None) => None,
+            _ => panic!("TLS_CERT_FILE and TLS_KEY_FILE must be set together"),
+        };
+
+        Self {
+            ca_file: env::var("TLS_CA_FILE").ok(),
+            identity,
+        }
+    }
+}
+
+/// Scaling configuration of a worker pool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, rename_all = "camelCase")]
 pub struct ScalingConfig {
     /// Minimum number of replicas.
     pub min_replicas: i32,
     /// Maximum number of replicas.
     pub max_replicas: i32,
-    /// Target pending fragments per worker.
+    /// Target pending and running fragments per worker.
     pub target_pending_per_worker: f64,
     /// Delay in seconds before scaling down.
     pub scale_down_delay_seconds: i64,
-    /// Interval in seconds between scaling checks.
-    pub poll_interval_seconds: i64,
+    /// Window in seconds over which scale-up recommendations are stabilized.
+    pub scale_up_stabilization_seconds: i64,
+    /// Window in seconds over which scale-down recommendations are stabilized.
+    pub scale_down_stabilization_seconds: i64,
+    /// Replicas that may be added per scale-up period (unlimited if unset).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale_up_max_pods: Option<i32>,
+    /// Percentage of replicas that may be added per scale-up period (unlimited if unset).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale_up_max_percent: Option<i32>,
+    /// Period in seconds the scale-up limits apply to.
+    pub scale_up_period_seconds: i64,
+    /// Minimum replica counts applying while their schedules match.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub schedules: Vec<ScheduledMinimum>,
+    /// Scaling ahead to the work of earlier periods (none if unset).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub predictive: Option<PredictiveScaling>,
+}
+
+/// A minimum replica count applying while a cron schedule matches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledMinimum {
+    /// Cron expression of the minutes the minimum applies in, in UTC, e.g.
+    /// `* 8-17 * * 1-5` for 08:00 to 17:59 on weekdays.
+    pub schedule: String,
+    /// Minimum number of replicas while the schedule matches.
+    pub min_replicas: i32,
+}
+
+/// Scaling ahead to the work seen at the same time of earlier periods.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PredictiveScaling {
+    /// Seconds ahead of now to provision for.
+    pub lookahead_seconds: i64,
+    /// Length in seconds of the period the work repeats with.
+    pub period_seconds: i64,
+    /// Number of earlier periods the predicted work is averaged over.
+    pub periods: u32,
+}
+
+impl Default for PredictiveScaling {
+    fn default() -> Self {
+        Self {
+            lookahead_seconds: 600,
+            period_seconds: 86_400,
+            periods: 7,
+        }
+    }
 }
 
 impl Default for ScalingConfig {
@@ -42,9 +161,50 @@ impl Default for ScalingConfig {
             max_replicas: 10,
             target_pending_per_worker: 1.0,
             scale_down_delay_seconds: 300,
-            poll_interval_seconds: 30,
+            scale_up_stabilization_seconds: 0,
+            scale_down_stabilization_seconds: 0,
+            scale_up_max_pods: None,
+            scale_up_max_percent: None,
+            scale_up_period_seconds: 60,
+            schedules: Vec::new(),
+            predictive: None,
+        }
+    }
+}
+
+/// A duration of seconds, zero if negative.
+fn seconds(seconds: i64) -> Duration {
+    Duration::from_secs(seconds.max(0).unsigned_abs())
+}
+
+impl ScalingConfig {
+    /// The scaling behavior policies of this configuration.
+    #[must_use]
+    pub fn behavior(&self) -> ScalingBehavior {
+        let scale_up_limit = (self.scale_up_max_pods.is_some()
+            || self.scale_up_max_percent.is_some())
+        .then(|| ScaleUpLimit {
+            pods: self.scale_up_max_pods,
+            percent: self.scale_up_max_percent,
+            period: seconds(self.scale_up_period_seconds),
+        });
+
+        ScalingBehavior {
+            scale_up_stabilization: seconds(self.scale_up_stabilization_seconds),
+            scale_down_stabilization: seconds(self.scale_down_stabilization_seconds),
+            scale_up_limit,
         }
     }
+
+    /// How work is predicted, if predictive scaling is enabled.
+    #[must_use]
+    pub fn prediction(&self) -> Option<Prediction> {
+        self.predictive.as_ref().map(|predictive| Prediction {
+            lookahead: seconds(predictive.lookahead_seconds),
+            period: seconds(predictive.period_seconds),
+            periods: predictive.periods,
+        })
+    }
 }
 
 impl Config {
@@ -52,17 +212,53 @@ impl Config {
     ///
     /// # Required environment variables
     /// - `ORCHESTRATOR_URL`: URL of the orchestrator service
+    ///
+    /// # Backend
+    /// - `SCALING_BACKEND`: Where workers run: `kubernetes` (default) or `process`
+    /// - `WORKER_COMMAND`: Worker executable (for `process`, default: `vulcan-worker`)
+    /// - `WORKER_STATE_DIR`: Directory of the workers' pid files (for `process`, default:
+    ///   `vulcan-worker-controller` in the temporary directory)
+    ///
+    /// # Pool source
+    /// - `POOL_SOURCE`: Where pools are defined: `env` (default), `file` or `crd`
+    /// - `POOLS_FILE`: YAML file of `WorkerPool` manifests (required for `file`)
+    /// - `POOL_NAMESPACE`: Namespace to list `WorkerPool` resources in (default: all, for `crd`)
+    /// - `CONTROLLER_TOKEN`: Controller token of the tenant (required for `env`, used by
+    ///   other pools without a token secret)
+    ///
+    /// # Pool environment variables (for `env`)
     /// - `TENANT_ID`: UUID of the tenant
     /// - `MACHINE_GROUP`: Machine group to manage
     /// - `DEPLOYMENT_NAME`: Kubernetes deployment name
-    /// - `DEPLOYMENT_NAMESPACE`: Kubernetes deployment namespace
+    /// - `DEPLOYMENT_NAMESPACE`: Kubernetes deployment namespace (optional for `process`)
+    /// - `POOL_MODE`: `deployment` (default) or `jobs`
+    /// - `POOL_LABELS`: Comma-separated labels of the workers (for `jobs`, default: any)
+    /// - `JOB_TTL_SECONDS_AFTER_FINISHED`: How long finished Jobs are kept (for `jobs`,
+    ///   default: 300)
     ///
     /// # Optional environment variables (with defaults)
     /// - `MIN_REPLICAS`: Minimum replicas (default: 0)
     /// - `MAX_REPLICAS`: Maximum replicas (default: 10)
     /// - `TARGET_PENDING_PER_WORKER`: Target pending per worker (default: 1.0)
     /// - `SCALE_DOWN_DELAY_SECONDS`: Scale down delay (default: 300)
+    /// - `SCALE_UP_STABILIZATION_SECONDS`: Scale-up stabilization window (default: 0)
+    /// - `SCALE_DOWN_STABILIZATION_SECONDS`: Scale-down stabilization window (default: 0)
+    /// - `SCALE_UP_MAX_PODS`: Replicas added per scale-up period (default: unlimited)
+    /// - `SCALE_UP_MAX_PERCENT`: Percent of replicas added per scale-up period (default: unlimited)
+    /// - `SCALE_UP_PERIOD_SECONDS`: Scale-up limit period (default: 60)
+    /// - `SCALING_SCHEDULES`: Scheduled minimum replicas, as `;`-separated
+    ///   `<cron>=<replicas>` (default: none)
+    /// - `PREDICTIVE_LOOKAHEAD_SECONDS`: Enables predictive scaling this far ahead
+    ///   (default: disabled)
+    /// - `PREDICTIVE_PERIOD_SECONDS`: Period the work repeats with (default: 86400)
+    /// - `PREDICTIVE_PERIODS`: Earlier periods predictions average over (default: 7)
     /// - `POLL_INTERVAL_SECONDS`: Poll interval (default: 30)
+    /// - `METRICS_PORT`: Port to serve Prometheus metrics on (default: none)
+    /// - `TLS_CA_FILE`: CA certificate to trust for an `https` orchestrator (default: none)
+    /// - `TLS_CERT_FILE`, `TLS_KEY_FILE`: Client certificate and key presented to the
+    ///   orchestrator (default: none)
+    /// - `SCALING_HISTORY_FILE`: File keeping the work history of predictive pools
+    ///   (default: none, kept in memory)
     ///
     /// # Panics
     ///
@@ -71,52 +267,170 @@ impl Config {
         let orchestrator_url = env::var("ORCHESTRATOR_URL")
             .expect("ORCHESTRATOR_URL must be set");
 
-        let tenant_id = env::var("TENANT_ID")
-            .expect("TENANT_ID must be set")
-            .parse::<Uuid>()
-            .expect("TENANT_ID must be a valid UUID");
-
-        let machine_group = env::var("MACHINE_GROUP")
-            .expect("MACHINE_GROUP must be set");
-
-        let deployment_name = env::var("DEPLOYMENT_NAME")
-            .expect("DEPLOYMENT_NAME must be set");
-
-        let deployment_namespace = env::var("DEPLOYMENT_NAMESPACE")
-            .expect("DEPLOYMENT_NAMESPACE must be set");
-
-        let defaults = ScalingConfig::default();
-
-        let scaling = ScalingConfig {
-            min_replicas: env::var("MIN_REPLICAS")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(defaults.min_replicas),
-            max_replicas: env::var("MAX_REPLICAS")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(defaults.max_replicas),
-            target_pending_per_worker: env::var("TARGET_PENDING_PER_WORKER")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(defaults.target_pending_per_worker),
-            scale_down_delay_seconds: env::var("SCALE_DOWN_DELAY_SECONDS")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(defaults.scale_down_delay_seconds),
-            poll_interval_seconds: env::var("POLL_INTERVAL_SECONDS")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(defaults.poll_interval_seconds),
+        let controller_token = env::var("CONTROLLER_TOKEN").ok();
+
+        let backend = match env::var("SCALING_BACKEND").as_deref() {
+            Err(_) | Ok("kubernetes") => Backend::Kubernetes,
+            Ok("process") => Backend::Process {
+                command: env::var("WORKER_COMMAND")
+                    .unwrap_or_else(|_| "vulcan-worker".to_string())
+                    .into(),
+                state_dir: env::var_os("WORKER_STATE_DIR").map_or_else(
+                    || env::temp_dir().join("vulcan-worker-controller"),
+                    PathBuf::from,
+                ),
+            },
+            Ok(other) => panic!("SCALING_BACKEND must be kubernetes or process, not {other}"),
+        };
+
+        let pools = match env::var("POOL_SOURCE").as_deref() {
+            Err(_) | Ok("env") => {
+                assert!(controller_token.is_some(), "CONTROLLER_TOKEN must be set");
+                let namespace_required = matches!(backend, Backend::Kubernetes);
+                PoolSource::Env(Box::new(pool_from_env(namespace_required)))
+            }
+            Ok("file") => PoolSource::File(
+                env::var("POOLS_FILE")
+                    .expect("POOLS_FILE must be set")
+                    .into(),
+            ),
+            Ok("crd") => PoolSource::Crd {
+                namespace: env::var("POOL_NAMESPACE").ok(),
+            },
+            Ok(other) => panic!("POOL_SOURCE must be env, file or crd, not {other}"),
         };
 
         Self {
             orchestrator_url,
+            controller_token,
+            pools,
+            backend,
+            tls: TlsConfig::from_env(),
+            poll_interval_seconds: parse_env("POLL_INTERVAL_SECONDS").unwrap_or(30),
+            metrics_port: parse_env("METRICS_PORT"),
+            history_file: env::var("SCALING_HISTORY_FILE").ok().map(PathBuf::from),
+        }
+    }
+}
+
+/// Build the pool configured by environment variables.
+///
+/// Without a required namespace, the pool is in the default namespace unless
+/// `DEPLOYMENT_NAMESPACE` is set.
+fn pool_from_env(namespace_required: bool) -> WorkerPool {
+    let tenant_id = env::var("TENANT_ID")
+        .expect("TENANT_ID must be set")
+        .parse::<Uuid>()
+        .expect("TENANT_ID must be a valid UUID");
+
+    let machine_group = env::var("MACHINE_GROUP")
+        .expect("MACHINE_GROUP must be set");
+
+    let deployment_name = env::var("DEPLOYMENT_NAME")
+        .expect("DEPLOYMENT_NAME must be set");
+
+    let deployment_namespace = env::var("DEPLOYMENT_NAMESPACE").ok();
+    assert!(
+        deployment_namespace.is_some() || !namespace_required,
+        "DEPLOYMENT_NAMESPACE must be set"
+    );
+
+    let mode = match env::var("POOL_MODE").as_deref() {
+        Err(_) | Ok("deployment") => PoolMode::Deployment,
+        Ok("jobs") => PoolMode::Jobs,
+        Ok(other) => panic!("POOL_MODE must be deployment or jobs, not {other}"),
+    };
+
+    let labels = env::var("POOL_LABELS").ok().map(|labels| {
+        labels
+            .split(',')
+            .map(str::trim)
+            .filter(|label| !label.is_empty())
+            .map(String::from)
+            .collect()
+    });
+
+    let schedules = env::var("SCALING_SCHEDULES")
+        .map(|schedules| parse_schedules(&schedules))
+        .unwrap_or_default();
+
+    let predictive = parse_env("PREDICTIVE_LOOKAHEAD_SECONDS").map(|lookahead_seconds| {
+        let defaults = PredictiveScaling::default();
+        PredictiveScaling {
+            lookahead_seconds,
+            period_seconds: parse_env("PREDICTIVE_PERIOD_SECONDS")
+                .unwrap_or(defaults.period_seconds),
+            periods: parse_env("PREDICTIVE_PERIODS").unwrap_or(defaults.periods),
+        }
+    });
+
+    let defaults = ScalingConfig::default();
+
+    let scaling = ScalingConfig {
+        min_replicas: parse_env("MIN_REPLICAS").unwrap_or(defaults.min_replicas),
+        max_replicas: parse_env("MAX_REPLICAS").unwrap_or(defaults.max_replicas),
+        target_pending_per_worker: parse_env("TARGET_PENDING_PER_WORKER")
+            .unwrap_or(defaults.target_pending_per_worker),
+        scale_down_delay_seconds: parse_env("SCALE_DOWN_DELAY_SECONDS")
+            .unwrap_or(defaults.scale_down_delay_seconds),
+        scale_up_stabilization_seconds: parse_env("SCALE_UP_STABILIZATION_SECONDS")
+            .unwrap_or(defaults.scale_up_stabilization_seconds),
+        scale_down_stabilization_seconds: parse_env("SCALE_DOWN_STABILIZATION_SECONDS")
+            .unwrap_or(defaults.scale_down_stabilization_seconds),
+        scale_up_max_pods: parse_env("SCALE_UP_MAX_PODS"),
+        scale_up_max_percent: parse_env("SCALE_UP_MAX_PERCENT"),
+        scale_up_period_seconds: parse_env("SCALE_UP_PERIOD_SECONDS")
+            .unwrap_or(defaults.scale_up_period_seconds),
+        schedules,
+        predictive,
+    };
+
+    let mut pool = WorkerPool::new(
+        &deployment_name,
+        WorkerPoolSpec {
             tenant_id,
             machine_group,
-            deployment_name,
-            deployment_namespace,
+            deployment: deployment_name.clone(),
+            mode,
+            labels,
+            job_ttl_seconds_after_finished: parse_env("JOB_TTL_SECONDS_AFTER_FINISHED"),
+            controller_token_secret: None,
             scaling,
-        }
-    }
+        },
+    );
+    pool.metadata.namespace = deployment_namespace;
+    pool
+}
+
+/// Parse scheduled minimum replicas from `;`-separated `<cron>=<replicas>` entries.
+///
+/// # Panics
+///
+/// Panics if an entry is invalid.
+fn parse_schedules(schedules: &str) -> Vec<ScheduledMinimum> {
+    schedules
+        .split(';')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (schedule, min_replicas) = entry
+                .rsplit_once('=')
+                .expect("SCALING_SCHEDULES entries must be <cron>=<replicas>");
+            if let Err(e) = schedule.trim().parse::<CronSchedule>() {
+                panic!("SCALING_SCHEDULES: {e}");
+            }
+            ScheduledMinimum {
+                schedule: schedule.trim().to_string(),
+                min_replicas: min_replicas
+                    .trim()
+                    .parse()
+                    .expect("SCALING_SCHEDULES replicas must be a number"),
+            }
+        })
+        .collect()
+}
+
+/// Parse an environment variable, or None if it is unset or invalid.
+fn parse_env<T: FromStr>(name: &str) -> Option<T> {
+    env::var(name).ok().and_then(|v| v.parse().ok())
 }
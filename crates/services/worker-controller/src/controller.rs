@@ -1,44 +1,208 @@
 //! Main controller reconciliation loop.
 
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::core::v1::Secret;
+use kube::api::{Api, ListParams, Patch, PatchParams};
+use kube::{Client, ResourceExt};
+use serde_json::json;
 use tokio::sync::Notify;
-use tracing::{error, info};
+use tracing::{error, info, info_span, warn, Instrument};
 
-use crate::client::OrchestratorClient;
-use crate::config::Config;
-use crate::error::Result;
-use crate::kubernetes::DeploymentScaler;
-use crate::scaler::{calculate_desired_replicas, ScalerState, ScalingConfig};
+use crate::backend::{ScalingBackend, WorkerActivity};
+use crate::client::{OrchestratorClient, http_client};
+use crate::config::{Config, PoolSource};
+use crate::error::{ControllerError, Result};
+use crate::kubernetes::jobs::DEFAULT_JOB_TTL_SECONDS;
+use crate::kubernetes::{JobLauncher, KubernetesBackend};
+use crate::pool::{load_pools, PoolMode, WorkerPool, WorkerPoolStatus};
+use crate::scaler::history::{load_histories, save_histories};
+use crate::scaler::{
+    calculate_desired_replicas, ScalerState, ScalingConfig, ScheduledMinimums, WorkLoad,
+};
+
+/// Where the controller finds its pools.
+enum Pools {
+    /// Pools fixed at startup, from the environment or a pools file.
+    Static(Vec<WorkerPool>),
+    /// `WorkerPool` resources, listed on every reconciliation.
+    Crd(Api<WorkerPool>),
+}
+
+/// Scaling state of one pool, kept across reconciliations.
+#[derive(Default)]
+struct PoolState {
+    scaler: ScalerState,
+    status: WorkerPoolStatus,
+    /// The pool's scheduled minimums, parsed when first seen or changed.
+    schedules: Option<ScheduledMinimums>,
+}
+
+impl PoolState {
+    /// Calculate the replicas a deployment pool's work asks for, raised to the
+    /// minimum of its matching schedules and to the work predicted ahead.
+    fn desired_replicas(
+        &mut self,
+        scaling: &crate::config::ScalingConfig,
+        load: &WorkLoad,
+        now: DateTime<Utc>,
+    ) -> Result<i32> {
+        // Scheduled minimums raise the pool's own, within its maximum
+        if !self
+            .schedules
+            .as_ref()
+            .is_some_and(|schedules| schedules.is_parsed_from(&scaling.schedules))
+        {
+            self.schedules = Some(ScheduledMinimums::parse(&scaling.schedules)?);
+        }
+        let scheduled_min_replicas = self
+            .schedules
+            .as_ref()
+            .and_then(|schedules| schedules.min_replicas(now));
+        let min_replicas = scheduled_min_replicas
+            .map_or(scaling.min_replicas, |scheduled| {
+                scheduled.max(scaling.min_replicas)
+            })
+            .min(scaling.max_replicas);
+
+        // Build scaling config from the pool's configuration
+        let scaling_config = ScalingConfig {
+            min_replicas,
+            max_replicas: scaling.max_replicas,
+            target_pending_per_worker: scaling.target_pending_per_worker,
+        };
+        let mut desired_replicas = calculate_desired_replicas(&scaling_config, load);
+
+        // Provision ahead for the work earlier periods saw by now and the lookahead
+        let predicted_fragments = scaling.prediction().and_then(|prediction| {
+            let work = load.pending_fragments + load.running_fragments;
+            self.scaler.predict_work(work, &prediction, now)
+        });
+        if let Some(predicted) = predicted_fragments {
+            let predicted_load = WorkLoad {
+                pending_fragments: predicted,
+                ..WorkLoad::default()
+            };
+            desired_replicas =
+                desired_replicas.max(calculate_desired_replicas(&scaling_config, &predicted_load));
+        }
+
+        self.status.scheduled_min_replicas = scheduled_min_replicas;
+        self.status.predicted_fragments = predicted_fragments;
+        Ok(desired_replicas)
+    }
+}
 
 /// The main worker controller.
-pub struct Controller {
+///
+/// Deployment-mode pools are scaled through a [`ScalingBackend`], Kubernetes by
+/// default. Without a Kubernetes client, pools come from the environment or a pools
+/// file, and must run in deployment mode and without a token secret.
+pub struct Controller<B = KubernetesBackend> {
     config: Config,
-    client: OrchestratorClient,
-    scaler: DeploymentScaler,
-    state: ScalerState,
+    kube: Option<Client>,
+    backend: B,
+    http: reqwest::Client,
+    pools: Pools,
+    states: HashMap<String, PoolState>,
 }
 
 impl Controller {
-    /// Create a new controller.
+    /// Create a new controller using the default Kubernetes client configuration.
     ///
     /// # Arguments
     ///
     /// * `config` - Controller configuration
     pub async fn new(config: Config) -> Result<Self> {
-        let client = OrchestratorClient::new(config.orchestrator_url.clone());
-        let scaler = DeploymentScaler::new(
-            &config.deployment_namespace,
-            config.deployment_name.clone(),
-        )
-        .await?;
+        let kube = Client::try_default().await?;
+        Self::with_client(config, kube)
+    }
+
+    /// Create a new controller using a Kubernetes client.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Controller configuration
+    /// * `kube` - Kubernetes client
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pools file, the scaling history file or the TLS files
+    /// cannot be loaded.
+    pub fn with_client(config: Config, kube: Client) -> Result<Self> {
+        let backend = KubernetesBackend::new(kube.clone());
+        Self::build(config, Some(kube), backend)
+    }
+}
+
+impl<B: ScalingBackend> Controller<B> {
+    /// Create a new controller scaling pools through a backend, without Kubernetes.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Controller configuration
+    /// * `backend` - Backend running the pools' workers
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pools are `WorkerPool` resources, or the pools file,
+    /// the scaling history file or the TLS files cannot be loaded.
+    pub fn with_backend(config: Config, backend: B) -> Result<Self> {
+        Self::build(config, None, backend)
+    }
+
+    /// The backend running the pools' workers.
+    pub const fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    fn build(config: Config, kube: Option<Client>, backend: B) -> Result<Self> {
+        let pools = match (&config.pools, &kube) {
+            (PoolSource::Env(pool), _) => Pools::Static(vec![pool.as_ref().clone()]),
+            (PoolSource::File(path), _) => Pools::Static(load_pools(path)?),
+            (
+                PoolSource::Crd {
+                    namespace: Some(namespace),
+                },
+                Some(kube),
+            ) => Pools::Crd(Api::namespaced(kube.clone(), namespace)),
+            (PoolSource::Crd { namespace: None }, Some(kube)) => Pools::Crd(Api::all(kube.clone())),
+            (PoolSource::Crd { .. }, None) => {
+                return Err(ControllerError::Config(
+                    "WorkerPool resources need the Kubernetes backend".to_string(),
+                ));
+            }
+        };
+
+        // Continue the work history of pools scaled before a restart
+        let histories = match &config.history_file {
+            Some(path) => load_histories(path)?,
+            None => HashMap::new(),
+        };
+        let states = histories
+            .into_iter()
+            .map(|(key, history)| {
+                let state = PoolState {
+                    scaler: ScalerState::with_history(history),
+                    status: WorkerPoolStatus::default(),
+                    schedules: None,
+                };
+                (key, state)
+            })
+            .collect();
+
+        let http = http_client(&config.tls)?;
 
         Ok(Self {
             config,
-            client,
-            scaler,
-            state: ScalerState::new(),
+            kube,
+            backend,
+            http,
+            pools,
+            states,
         })
     }
 
@@ -49,31 +213,20 @@ impl Controller {
     /// * `shutdown` - Notification for graceful shutdown
     pub async fn run(&mut self, shutdown: Arc<Notify>) -> Result<()> {
         info!(
-            tenant_id = %self.config.tenant_id,
-            machine_group = %self.config.machine_group,
-            deployment = %self.config.deployment_name,
-            min_replicas = self.config.scaling.min_replicas,
-            max_replicas = self.config.scaling.max_replicas,
-            target_pending_per_worker = self.config.scaling.target_pending_per_worker,
-            poll_interval_seconds = self.config.scaling.poll_interval_seconds,
-            scale_down_delay_seconds = self.config.scaling.scale_down_delay_seconds,
+            poll_interval_seconds = self.config.poll_interval_seconds,
             "Starting worker controller"
         );
 
-        // Verify deployment exists
-        if !self.scaler.verify_exists().await? {
-            error!(
-                deployment = %self.config.deployment_name,
-                namespace = %self.config.deployment_namespace,
-                "Deployment not found, exiting"
-            );
-            return Err(crate::error::ControllerError::DeploymentNotFound {
-                name: self.config.deployment_name.clone(),
-                namespace: self.config.deployment_namespace.clone(),
-            });
+        match &self.pools {
+            Pools::Static(pools) => {
+                for pool in pools {
+                    self.verify_pool(pool).await?;
+                }
+            }
+            Pools::Crd(_) => info!("Scaling WorkerPool resources"),
         }
 
-        let poll_interval = self.config.scaling.poll_interval_seconds;
+        let poll_interval = self.config.poll_interval_seconds;
 
         // Main reconciliation loop
         loop {
@@ -95,12 +248,128 @@ impl Controller {
         Ok(())
     }
 
-    /// Run one reconciliation cycle.
-    async fn reconcile(&mut self) -> Result<()> {
+    /// Check the deployment of a pool fixed at startup exists.
+    async fn verify_pool(&self, pool: &WorkerPool) -> Result<()> {
+        let namespace = pool.deployment_namespace();
+        info!(
+            pool = %pool.key(),
+            tenant_id = %pool.spec.tenant_id,
+            machine_group = %pool.spec.machine_group,
+            deployment = %pool.spec.deployment,
+            mode = ?pool.spec.mode,
+            min_replicas = pool.spec.scaling.min_replicas,
+            max_replicas = pool.spec.scaling.max_replicas,
+            target_pending_per_worker = pool.spec.scaling.target_pending_per_worker,
+            scale_down_delay_seconds = pool.spec.scaling.scale_down_delay_seconds,
+            scale_up_stabilization_seconds = pool.spec.scaling.scale_up_stabilization_seconds,
+            scale_down_stabilization_seconds = pool.spec.scaling.scale_down_stabilization_seconds,
+            scale_up_max_pods = ?pool.spec.scaling.scale_up_max_pods,
+            scale_up_max_percent = ?pool.spec.scaling.scale_up_max_percent,
+            "Scaling worker pool"
+        );
+
+        if let Err(e) = self.backend.verify(pool).await {
+            error!(
+                deployment = %pool.spec.deployment,
+                namespace = %namespace,
+                error = %e,
+                "Pool cannot be scaled, exiting"
+            );
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Run one reconciliation cycle of every pool.
+    ///
+    /// A pool that fails to reconcile does not stop the others; its error is logged
+    /// and, for `WorkerPool` resources, reported in its status.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `WorkerPool` resources cannot be listed.
+    pub async fn reconcile(&mut self) -> Result<()> {
+        let pools = match &self.pools {
+            Pools::Static(pools) => pools.clone(),
+            Pools::Crd(api) => api.list(&ListParams::default()).await?.items,
+        };
+
+        // Forget the state of pools that were removed
+        self.states
+            .retain(|key, _| pools.iter().any(|pool| pool.key() == *key));
+
+        for pool in &pools {
+            let span = info_span!(
+                "reconcile",
+                pool = %pool.key(),
+                machine_group = %pool.spec.machine_group
+            );
+            let result = self.reconcile_pool(pool).instrument(span.clone()).await;
+
+            let state = self.states.entry(pool.key()).or_default();
+            state.status.error = result.err().map(|e| {
+                span.in_scope(|| error!(error = %e, "Reconciliation failed"));
+                e.to_string()
+            });
+
+            if matches!(self.pools, Pools::Crd(_)) {
+                let status = state.status.clone();
+                self.report_status(pool, &status).instrument(span).await;
+            }
+        }
+
+        self.save_history();
+
+        Ok(())
+    }
+
+    /// Save the work history of the pools to the scaling history file, if set.
+    ///
+    /// Failures are logged rather than returned: the history only improves
+    /// predictions.
+    fn save_history(&self) {
+        let Some(path) = &self.config.history_file else {
+            return;
+        };
+
+        let histories = self
+            .states
+            .iter()
+            .filter(|(_, state)| !state.scaler.history().is_empty())
+            .map(|(key, state)| (key.as_str(), state.scaler.history()))
+            .collect();
+        if let Err(e) = save_histories(path, &histories) {
+            warn!(error = %e, "Failed to save scaling history");
+        }
+    }
+
+    /// Run one reconciliation cycle of a pool.
+    async fn reconcile_pool(&mut self, pool: &WorkerPool) -> Result<()> {
+        let client = OrchestratorClient::with_client(
+            self.http.clone(),
+            self.config.orchestrator_url.clone(),
+            self.controller_token(pool).await?,
+        );
+
+        match pool.spec.mode {
+            PoolMode::Deployment => self.scale_deployment(pool, &client).await,
+            PoolMode::Jobs => self.launch_jobs(pool, &client).await,
+        }
+    }
+
+    /// Scale the deployment of a pool to its work.
+    async fn scale_deployment(
+        &mut self,
+        pool: &WorkerPool,
+        client: &OrchestratorClient,
+    ) -> Result<()> {
+        let key = pool.key();
+        let spec = &pool.spec;
+
         // Get queue metrics
-        let metrics = self
-            .client
-            .get_queue_metrics(Some(&self.config.machine_group))
+        let metrics = client
+            .get_queue_metrics(spec.tenant_id, Some(&spec.machine_group))
             .await?;
 
         info!(
@@ -111,50 +380,273 @@ impl Controller {
         );
 
         // Get current deployment replicas
-        let current_replicas = self.scaler.get_replicas().await?;
-        self.state.set_current_replicas(current_replicas);
+        let current_replicas = self.backend.replicas(pool).await?;
+        let state = self.states.entry(key.clone()).or_default();
+        state.scaler.set_current_replicas(current_replicas);
 
-        // Build scaling config from local configuration
-        let scaling_config = ScalingConfig {
-            min_replicas: self.config.scaling.min_replicas,
-            max_replicas: self.config.scaling.max_replicas,
-            target_pending_per_worker: self.config.scaling.target_pending_per_worker,
-        };
+        let load = WorkLoad::from(&metrics);
+        let desired_replicas = state.desired_replicas(&spec.scaling, &load, Utc::now())?;
+        crate::metrics::record_replicas(&key, current_replicas, desired_replicas);
+
+        state.status.current_replicas = current_replicas;
+        state.status.desired_replicas = desired_replicas;
+        state.status.pending_fragments = load.pending_fragments;
+        state.status.running_fragments = load.running_fragments;
+        state.status.busy_workers = load.busy_workers;
 
-        let desired_replicas = calculate_desired_replicas(&scaling_config, metrics.pending_fragments);
+        // Stabilize and step-limit the desired count
+        let now = Instant::now();
+        let behavior = spec.scaling.behavior();
+        let target_replicas = state.scaler.apply_behavior(desired_replicas, &behavior, now);
 
         info!(
             current = current_replicas,
             desired = desired_replicas,
+            target = target_replicas,
+            busy_workers = load.busy_workers,
+            scheduled_min_replicas = ?state.status.scheduled_min_replicas,
+            predicted_fragments = ?state.status.predicted_fragments,
             "Calculated replica count"
         );
 
         // Check if scaling is needed
-        let scale_down_delay = self.config.scaling.scale_down_delay_seconds;
-        if let Some(new_replicas) = self.state.should_scale(desired_replicas, scale_down_delay) {
-            // Perform scaling
-            self.scaler.scale(new_replicas).await?;
-
-            // Record scale-down for cooldown tracking
-            if new_replicas < current_replicas {
-                self.state.record_scale_down();
+        let scale_down_delay = spec.scaling.scale_down_delay_seconds;
+        let Some(mut new_replicas) = state.scaler.should_scale(target_replicas, scale_down_delay)
+        else {
+            if desired_replicas != current_replicas {
+                crate::metrics::record_scale_decision(&key, "blocked");
+                info!(
+                    current = current_replicas,
+                    desired = desired_replicas,
+                    target = target_replicas,
+                    "Scaling held back by stabilization, step limits or cooldown"
+                );
+            }
+            return Ok(());
+        };
+
+        // Remove idle instances first, and never the busy ones
+        if new_replicas < current_replicas {
+            let busy = match rank_for_removal(client, &self.backend, pool).await {
+                Ok(busy) => busy,
+                Err(e) => {
+                    crate::metrics::record_scale_decision(&key, "blocked");
+                    warn!(error = %e, "Failed to check which workers are busy, not scaling down");
+                    return Ok(());
+                }
+            };
+            let busy = i32::try_from(busy).unwrap_or(i32::MAX);
+
+            if busy > new_replicas {
+                info!(target = new_replicas, busy, "Not scaling below the busy workers");
+                new_replicas = busy;
             }
+            if new_replicas >= current_replicas {
+                crate::metrics::record_scale_decision(&key, "blocked");
+                return Ok(());
+            }
+        }
 
-            self.state.set_current_replicas(new_replicas);
+        // Perform scaling
+        self.backend.scale(pool, new_replicas).await?;
 
+        // Record scale-down for cooldown tracking
+        let state = self.states.entry(key.clone()).or_default();
+        if new_replicas < current_replicas {
+            state.scaler.record_scale_down();
+            crate::metrics::record_scale_decision(&key, "down");
+        } else {
+            state.scaler.record_scale_up(new_replicas - current_replicas, now);
+            crate::metrics::record_scale_decision(&key, "up");
+        }
+
+        state.scaler.set_current_replicas(new_replicas);
+        state.status.current_replicas = new_replicas;
+        state.status.last_scale_time = Some(Utc::now());
+
+        info!(
+            from = current_replicas,
+            to = new_replicas,
+            "Scaled deployment"
+        );
+
+        Ok(())
+    }
+
+    /// Launch a Job for each pending fragment of a pool without one, up to
+    /// `maxReplicas` running Jobs.
+    async fn launch_jobs(&mut self, pool: &WorkerPool, client: &OrchestratorClient) -> Result<()> {
+        let key = pool.key();
+        let spec = &pool.spec;
+        let launcher = JobLauncher::new(
+            self.kube("Jobs mode")?,
+            &pool.deployment_namespace(),
+            pool.name_any(),
+            spec.deployment.clone(),
+        );
+
+        let jobs = launcher.list_jobs().await?;
+        let running = jobs.iter().filter(|job| job.active).count();
+        let max_jobs = usize::try_from(spec.scaling.max_replicas).unwrap_or(0);
+
+        // Fragments are listed until the worker of their Job claims them
+        let pending = client
+            .get_pending_fragments(
+                spec.tenant_id,
+                &spec.machine_group,
+                spec.labels.as_deref(),
+                jobs.len() + max_jobs,
+            )
+            .await?;
+        let unlaunched: Vec<_> = pending
+            .iter()
+            .filter(|fragment| {
+                let name = JobLauncher::job_name(fragment);
+                !jobs.iter().any(|job| job.name == name)
+            })
+            .collect();
+
+        let free = max_jobs.saturating_sub(running);
+        let desired = running + unlaunched.len().min(free);
+        let as_i32 = |count: usize| i32::try_from(count).unwrap_or(i32::MAX);
+        crate::metrics::record_replicas(&key, as_i32(running), as_i32(desired));
+
+        info!(
+            running,
+            pending = pending.len(),
+            unlaunched = unlaunched.len(),
+            max_jobs,
+            "Got pending fragments"
+        );
+
+        let ttl = spec
+            .job_ttl_seconds_after_finished
+            .unwrap_or(DEFAULT_JOB_TTL_SECONDS);
+        let mut created = 0;
+        for fragment in unlaunched.iter().take(free) {
+            if launcher.launch(fragment, ttl).await? {
+                created += 1;
+            }
+        }
+
+        let state = self.states.entry(key.clone()).or_default();
+        state.status.current_replicas = as_i32(running + created);
+        state.status.desired_replicas = as_i32(desired);
+        state.status.pending_fragments = i64::try_from(pending.len()).unwrap_or(i64::MAX);
+        state.status.running_fragments = i64::try_from(running).unwrap_or(i64::MAX);
+        state.status.busy_workers = state.status.running_fragments;
+
+        if created > 0 {
+            crate::metrics::record_scale_decision(&key, "up");
+            state.status.last_scale_time = Some(Utc::now());
+            info!(created, "Launched jobs");
+        }
+        if unlaunched.len() > free {
+            crate::metrics::record_scale_decision(&key, "blocked");
             info!(
-                from = current_replicas,
-                to = new_replicas,
-                "Scaled deployment"
-            );
-        } else if desired_replicas != current_replicas {
-            info!(
-                current = current_replicas,
-                desired = desired_replicas,
-                "Scale-down blocked by cooldown"
+                waiting = unlaunched.len() - free,
+                max_jobs,
+                "Launching held back by the job limit"
             );
         }
 
         Ok(())
     }
+
+    /// The controller token of a pool's tenant, from the pool's token secret or the
+    /// controller's `CONTROLLER_TOKEN`.
+    async fn controller_token(&self, pool: &WorkerPool) -> Result<String> {
+        let Some(secret_ref) = &pool.spec.controller_token_secret else {
+            return self.config.controller_token.clone().ok_or_else(|| {
+                ControllerError::Config(format!(
+                    "Pool {} has no controllerTokenSecret and CONTROLLER_TOKEN is not set",
+                    pool.key()
+                ))
+            });
+        };
+
+        let secrets: Api<Secret> =
+            Api::namespaced(self.kube("controllerTokenSecret")?, &pool.deployment_namespace());
+        let secret = secrets.get(&secret_ref.name).await?;
+        let token = secret
+            .data
+            .and_then(|mut data| data.remove(&secret_ref.key))
+            .and_then(|value| String::from_utf8(value.0).ok())
+            .ok_or_else(|| {
+                ControllerError::Config(format!(
+                    "Secret {} has no controller token at key {}",
+                    secret_ref.name, secret_ref.key
+                ))
+            })?;
+
+        Ok(token.trim().to_string())
+    }
+
+    /// The Kubernetes client, for a feature that needs one.
+    fn kube(&self, feature: &str) -> Result<Client> {
+        self.kube.clone().ok_or_else(|| {
+            ControllerError::Config(format!("{feature} needs the Kubernetes backend"))
+        })
+    }
+
+    /// Write the status of a `WorkerPool` resource.
+    ///
+    /// Failures are logged rather than returned: the status only reports on scaling.
+    async fn report_status(&self, pool: &WorkerPool, status: &WorkerPoolStatus) {
+        let Some(kube) = self.kube.clone() else {
+            return;
+        };
+        let api: Api<WorkerPool> = Api::namespaced(kube, &pool.deployment_namespace());
+        let patch = json!({ "status": status });
+
+        if let Err(e) = api
+            .patch_status(&pool.name_any(), &PatchParams::default(), &Patch::Merge(&patch))
+            .await
+        {
+            warn!(error = %e, "Failed to report pool status");
+        }
+    }
+}
+
+/// Order the instances of a pool for removal on scale-down, returning how many are
+/// busy.
+///
+/// Each instance's worker is found by its registered name and asked whether it is
+/// busy. Busy instances get a high removal cost and idle ones a low one, lowest for
+/// draining workers. Instances without a registered worker count as idle.
+///
+/// # Errors
+///
+/// Returns an error if the instances or workers cannot be listed, a worker cannot be
+/// checked, or the order cannot be recorded.
+async fn rank_for_removal<B: ScalingBackend>(
+    client: &OrchestratorClient,
+    backend: &B,
+    pool: &WorkerPool,
+) -> Result<usize> {
+    let names = backend.instances(pool).await?;
+    let workers = client
+        .get_workers(pool.spec.tenant_id, Some(&pool.spec.machine_group))
+        .await?;
+
+    let mut instances = Vec::with_capacity(names.len());
+    for name in names {
+        let worker = workers
+            .iter()
+            .find(|worker| worker.name.as_deref() == Some(name.as_str()));
+        let activity = match worker {
+            Some(worker) => WorkerActivity::from(&client.get_worker_busy(worker.worker_id).await?),
+            None => WorkerActivity::Idle,
+        };
+        instances.push((name, activity));
+    }
+
+    let busy = instances
+        .iter()
+        .filter(|(_, activity)| *activity == WorkerActivity::Busy)
+        .count();
+    info!(instances = ?instances, busy, "Ordering instances for removal");
+    backend.set_removal_order(pool, &instances).await?;
+
+    Ok(busy)
 }
@@ -17,6 +17,10 @@ pub enum ControllerError {
     #[error("Configuration error: {0}")]
     Config(String),
 
+    /// Worker process error.
+    #[error("Worker process error: {0}")]
+    Process(String),
+
     /// Deployment not found.
     #[error("Deployment {name} not found in namespace {namespace}")]
     DeploymentNotFound {
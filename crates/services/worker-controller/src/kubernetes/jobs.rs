@@ -0,0 +1,206 @@
+//! Kubernetes Jobs executing one fragment each.
+
+use std::collections::BTreeMap;
+
+use k8s_openapi::api::apps::v1::Deployment;
+use k8s_openapi::api::batch::v1::{Job, JobSpec};
+use k8s_openapi::api::core::v1::EnvVar;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kube::{
+    Client, ResourceExt,
+    api::{Api, ListParams, PostParams},
+};
+use tracing::{debug, info};
+
+use crate::client::dto::PendingFragment;
+use crate::error::{ControllerError, Result};
+
+/// Label naming the pool a Job belongs to.
+const POOL_LABEL: &str = "vulcan.dev/pool";
+
+/// Label naming the fragment a Job executes.
+const FRAGMENT_LABEL: &str = "vulcan.dev/fragment-id";
+
+/// Environment variable binding the worker of a Job to its fragment.
+const FRAGMENT_ID_ENV: &str = "FRAGMENT_ID";
+
+/// Seconds a finished Job is kept by default.
+pub const DEFAULT_JOB_TTL_SECONDS: i32 = 300;
+
+/// A Job of a pool.
+#[derive(Debug, Clone)]
+pub struct FragmentJob {
+    /// Name of the Job.
+    pub name: String,
+    /// Whether the Job is still running (it has neither completed nor failed).
+    pub active: bool,
+}
+
+/// Launches Jobs from the pod template of a pool's Deployment.
+pub struct JobLauncher {
+    jobs: Api<Job>,
+    deployments: Api<Deployment>,
+    pool: String,
+    deployment_name: String,
+    namespace: String,
+}
+
+impl JobLauncher {
+    /// Create a new Job launcher.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - Kubernetes client
+    /// * `namespace` - Kubernetes namespace
+    /// * `pool` - Name of the pool the Jobs are labelled with
+    /// * `deployment_name` - Name of the Deployment whose pod template the Jobs use
+    #[must_use]
+    pub fn new(client: Client, namespace: &str, pool: String, deployment_name: String) -> Self {
+        Self {
+            jobs: Api::namespaced(client.clone(), namespace),
+            deployments: Api::namespaced(client, namespace),
+            pool,
+            deployment_name,
+            namespace: namespace.to_string(),
+        }
+    }
+
+    /// Name of the Job executing an attempt of a fragment.
+    ///
+    /// Each attempt gets its own Job, so a retried fragment is launched again while
+    /// the Job of its failed attempt is kept until its TTL passes.
+    #[must_use]
+    pub fn job_name(fragment: &PendingFragment) -> String {
+        format!("fragment-{}-{}", fragment.fragment_id, fragment.attempt)
+    }
+
+    /// List the Jobs of the pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the Jobs cannot be listed.
+    pub async fn list_jobs(&self) -> Result<Vec<FragmentJob>> {
+        let params = ListParams::default().labels(&format!("{POOL_LABEL}={}", self.pool));
+        let jobs = self.jobs.list(&params).await?;
+
+        Ok(jobs
+            .items
+            .into_iter()
+            .map(|job| FragmentJob {
+                active: !is_finished(&job),
+                name: job.name_any(),
+            })
+            .collect())
+    }
+
+    /// Launch a Job executing a fragment.
+    ///
+    /// Returns false if the fragment's Job exists already.
+    ///
+    /// # Arguments
+    ///
+    /// * `fragment` - The fragment to execute
+    /// * `ttl_seconds` - Seconds the Job is kept after it finished
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the Deployment cannot be read or the Job cannot be created.
+    pub async fn launch(&self, fragment: &PendingFragment, ttl_seconds: i32) -> Result<bool> {
+        let job = self.job(fragment, ttl_seconds).await?;
+        let name = job.name_any();
+
+        match self.jobs.create(&PostParams::default(), &job).await {
+            Ok(_) => {
+                info!(
+                    job = %name,
+                    fragment_id = %fragment.fragment_id,
+                    "Launched job"
+                );
+                Ok(true)
+            },
+            Err(kube::Error::Api(ref api_err)) if api_err.code == 409 => {
+                debug!(job = %name, "Job already exists");
+                Ok(false)
+            },
+            Err(e) => Err(ControllerError::Kube(e)),
+        }
+    }
+
+    /// Build the Job executing a fragment from the Deployment's pod template.
+    ///
+    /// The pod runs once (no restarts or retries: the orchestrator retries the
+    /// fragment), and its first container gets the fragment to execute.
+    async fn job(&self, fragment: &PendingFragment, ttl_seconds: i32) -> Result<Job> {
+        let deployment = self
+            .deployments
+            .get(&self.deployment_name)
+            .await
+            .map_err(|e| {
+                if matches!(e, kube::Error::Api(ref api_err) if api_err.code == 404) {
+                    ControllerError::DeploymentNotFound {
+                        name: self.deployment_name.clone(),
+                        namespace: self.namespace.clone(),
+                    }
+                } else {
+                    ControllerError::Kube(e)
+                }
+            })?;
+        let mut template = deployment
+            .spec
+            .map(|spec| spec.template)
+            .unwrap_or_default();
+
+        let labels = BTreeMap::from([
+            (POOL_LABEL.to_string(), self.pool.clone()),
+            (FRAGMENT_LABEL.to_string(), fragment.fragment_id.to_string()),
+        ]);
+
+        let metadata = template.metadata.get_or_insert_with(ObjectMeta::default);
+        metadata
+            .labels
+            .get_or_insert_with(BTreeMap::new)
+            .extend(labels.clone());
+
+        let pod = template.spec.get_or_insert_with(Default::default);
+        pod.restart_policy = Some("Never".to_string());
+        let container = pod.containers.first_mut().ok_or_else(|| {
+            ControllerError::Config(format!(
+                "Deployment {} has no container to run a job with",
+                self.deployment_name
+            ))
+        })?;
+        container.env.get_or_insert_with(Vec::new).push(EnvVar {
+            name: FRAGMENT_ID_ENV.to_string(),
+            value: Some(fragment.fragment_id.to_string()),
+            value_from: None,
+        });
+
+        Ok(Job {
+            metadata: ObjectMeta {
+                name: Some(Self::job_name(fragment)),
+                labels: Some(labels),
+                ..ObjectMeta::default()
+            },
+            spec: Some(JobSpec {
+                backoff_limit: Some(0),
+                ttl_seconds_after_finished: Some(ttl_seconds),
+                template,
+                ..JobSpec::default()
+            }),
+            status: None,
+        })
+    }
+}
+
+/// Whether a Job has completed or failed.
+fn is_finished(job: &Job) -> bool {
+    job.status
+        .as_ref()
+        .and_then(|status| status.conditions.as_ref())
+        .is_some_and(|conditions| {
+            conditions.iter().any(|condition| {
+                matches!(condition.type_.as_str(), "Complete" | "Failed")
+                    && condition.status == "True"
+            })
+        })
+}
@@ -1,19 +1,32 @@
 //! Kubernetes deployment scaling module.
 
+pub mod jobs;
+
 use k8s_openapi::api::apps::v1::Deployment;
+use k8s_openapi::api::core::v1::Pod;
 use kube::{
-    api::{Api, Patch, PatchParams},
+    api::{Api, ListParams, Patch, PatchParams},
     Client,
 };
 use serde_json::json;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 
+use crate::backend::{ScalingBackend, WorkerActivity};
 use crate::error::{ControllerError, Result};
+use crate::pool::WorkerPool;
+
+pub use jobs::JobLauncher;
+
+/// Annotation the `ReplicaSet` controller uses to pick pods to remove on scale-down,
+/// lowest cost first.
+const POD_DELETION_COST: &str = "controller.kubernetes.io/pod-deletion-cost";
 
 /// Kubernetes deployment scaler.
 pub struct DeploymentScaler {
     api: Api<Deployment>,
+    pods: Api<Pod>,
     deployment_name: String,
+    namespace: String,
 }
 
 impl DeploymentScaler {
@@ -21,16 +34,20 @@ impl DeploymentScaler {
     ///
     /// # Arguments
     ///
+    /// * `client` - Kubernetes client
     /// * `namespace` - Kubernetes namespace
     /// * `deployment_name` - Name of the deployment to scale
-    pub async fn new(namespace: &str, deployment_name: String) -> Result<Self> {
-        let client = Client::try_default().await?;
-        let api: Api<Deployment> = Api::namespaced(client, namespace);
+    #[must_use]
+    pub fn new(client: Client, namespace: &str, deployment_name: String) -> Self {
+        let api: Api<Deployment> = Api::namespaced(client.clone(), namespace);
+        let pods: Api<Pod> = Api::namespaced(client, namespace);
 
-        Ok(Self {
+        Self {
             api,
+            pods,
             deployment_name,
-        })
+            namespace: namespace.to_string(),
+        }
     }
 
     /// Get the current replica count of the deployment.
@@ -39,7 +56,7 @@ impl DeploymentScaler {
             if matches!(e, kube::Error::Api(ref api_err) if api_err.code == 404) {
                 ControllerError::DeploymentNotFound {
                     name: self.deployment_name.clone(),
-                    namespace: "unknown".to_string(),
+                    namespace: self.namespace.clone(),
                 }
             } else {
                 ControllerError::Kube(e)
@@ -81,6 +98,70 @@ impl DeploymentScaler {
         Ok(())
     }
 
+    /// List the names of the deployment's pods that are not terminating.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the deployment or its pods cannot be read.
+    pub async fn pod_names(&self) -> Result<Vec<String>> {
+        let deployment = self.api.get(&self.deployment_name).await?;
+        let selector = deployment
+            .spec
+            .and_then(|spec| spec.selector.match_labels)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let pods = self.pods.list(&ListParams::default().labels(&selector)).await?;
+        let names = pods
+            .items
+            .into_iter()
+            .filter(|pod| pod.metadata.deletion_timestamp.is_none())
+            .filter_map(|pod| pod.metadata.name)
+            .collect();
+
+        Ok(names)
+    }
+
+    /// Set the order pods are removed in on the next scale-down.
+    ///
+    /// Sets the deletion cost of each pod from what its worker is doing, so idle and
+    /// draining pods go before busy ones. Costs set earlier are overwritten, as a
+    /// worker may have become busy since. Pods that no longer exist are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `pods` - Names of the pods, with what their workers are doing
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a pod cannot be patched.
+    pub async fn set_removal_order(&self, pods: &[(String, WorkerActivity)]) -> Result<()> {
+        let params = PatchParams::default();
+
+        for (name, activity) in pods {
+            let patch = json!({
+                "metadata": {
+                    "annotations": {
+                        POD_DELETION_COST: activity.removal_cost().to_string()
+                    }
+                }
+            });
+
+            match self.pods.patch(name, &params, &Patch::Merge(&patch)).await {
+                Ok(_) => debug!(pod = %name, ?activity, "Set pod deletion cost"),
+                Err(kube::Error::Api(ref api_err)) if api_err.code == 404 => {
+                    debug!(pod = %name, "Pod already gone");
+                }
+                Err(e) => return Err(ControllerError::Kube(e)),
+            }
+        }
+
+        Ok(())
+    }
+
     /// Verify the deployment exists.
     pub async fn verify_exists(&self) -> Result<bool> {
         match self.api.get(&self.deployment_name).await {
@@ -96,3 +177,63 @@ impl DeploymentScaler {
         }
     }
 }
+
+/// Scales pools as the replicas of their Deployments.
+#[derive(Clone)]
+pub struct KubernetesBackend {
+    client: Client,
+}
+
+impl KubernetesBackend {
+    /// Create a new Kubernetes backend.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - Kubernetes client
+    #[must_use]
+    pub const fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    /// The scaler of a pool's Deployment.
+    fn scaler(&self, pool: &WorkerPool) -> DeploymentScaler {
+        DeploymentScaler::new(
+            self.client.clone(),
+            &pool.deployment_namespace(),
+            pool.spec.deployment.clone(),
+        )
+    }
+}
+
+impl ScalingBackend for KubernetesBackend {
+    async fn verify(&self, pool: &WorkerPool) -> Result<()> {
+        if self.scaler(pool).verify_exists().await? {
+            Ok(())
+        } else {
+            Err(ControllerError::DeploymentNotFound {
+                name: pool.spec.deployment.clone(),
+                namespace: pool.deployment_namespace(),
+            })
+        }
+    }
+
+    async fn replicas(&self, pool: &WorkerPool) -> Result<i32> {
+        self.scaler(pool).get_replicas().await
+    }
+
+    async fn scale(&self, pool: &WorkerPool, replicas: i32) -> Result<()> {
+        self.scaler(pool).scale(replicas).await
+    }
+
+    async fn instances(&self, pool: &WorkerPool) -> Result<Vec<String>> {
+        self.scaler(pool).pod_names().await
+    }
+
+    async fn set_removal_order(
+        &self,
+        pool: &WorkerPool,
+        instances: &[(String, WorkerActivity)],
+    ) -> Result<()> {
+        self.scaler(pool).set_removal_order(instances).await
+    }
+}
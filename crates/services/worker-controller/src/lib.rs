@@ -5,17 +5,29 @@
 //!
 //! # Architecture
 //!
-//! The worker-controller runs on client Kubernetes infrastructure and:
+//! The worker-controller runs on client Kubernetes infrastructure and, for each
+//! worker pool (a [`pool::WorkerPool`] of one tenant and machine group):
 //! 1. Polls the orchestrator for queue metrics (pending/running fragments)
-//! 2. Calculates desired replica count based on pending work
+//! 2. Calculates desired replica count based on pending and running work
 //! 3. Scales the worker Deployment up or down accordingly
 //!
+//! Workers run through a [`backend::ScalingBackend`]: Kubernetes Deployments, or
+//! worker processes on the controller's host.
+//!
 //! # Configuration
 //!
 //! The controller is configured via environment variables:
 //!
 //! ## Required
 //! - `ORCHESTRATOR_URL`: URL of the orchestrator service
+//!
+//! ## Pool source
+//! - `POOL_SOURCE`: Where pools are defined: `env` (default), `file` or `crd`
+//! - `POOLS_FILE`: YAML file of `WorkerPool` manifests (for `file`)
+//! - `POOL_NAMESPACE`: Namespace of `WorkerPool` resources (for `crd`, default: all)
+//! - `CONTROLLER_TOKEN`: Controller token of the tenant (required for `env`)
+//!
+//! ## Pool (for `env`)
 //! - `TENANT_ID`: UUID of the tenant
 //! - `MACHINE_GROUP`: Machine group to manage
 //! - `DEPLOYMENT_NAME`: Kubernetes deployment name
@@ -27,12 +39,34 @@
 //! - `TARGET_PENDING_PER_WORKER`: Target pending per worker (default: 1.0)
 //! - `SCALE_DOWN_DELAY_SECONDS`: Scale down delay (default: 300)
 //! - `POLL_INTERVAL_SECONDS`: Poll interval (default: 30)
+//! - `SCALE_UP_STABILIZATION_SECONDS`: Scale-up stabilization window (default: 0)
+//! - `SCALE_DOWN_STABILIZATION_SECONDS`: Scale-down stabilization window (default: 0)
+//! - `SCALE_UP_MAX_PODS`: Replicas added per scale-up period (default: unlimited)
+//! - `SCALE_UP_MAX_PERCENT`: Percent of replicas added per scale-up period (default: unlimited)
+//! - `SCALE_UP_PERIOD_SECONDS`: Scale-up limit period (default: 60)
+//! - `SCALING_SCHEDULES`: Scheduled minimum replicas, as `;`-separated `<cron>=<replicas>`
+//! - `PREDICTIVE_LOOKAHEAD_SECONDS`: Enables predictive scaling this far ahead
+//! - `PREDICTIVE_PERIOD_SECONDS`: Period the work repeats with (default: 86400)
+//! - `PREDICTIVE_PERIODS`: Earlier periods predictions average over (default: 7)
+//!
+//! ## Backend
+//! - `SCALING_BACKEND`: What runs the workers: `kubernetes` (default) or `process`
+//! - `WORKER_COMMAND`: Worker executable (for `process`, default: `vulcan-worker`)
+//! - `WORKER_STATE_DIR`: Directory of the workers' pid files (for `process`, default:
+//!   `vulcan-worker-controller` in the temporary directory)
+//!
+//! ## Optional
+//! - `METRICS_PORT`: Port to serve Prometheus metrics on
+//! - `SCALING_HISTORY_FILE`: File keeping the work history of predictive pools
 
+pub mod backend;
 pub mod client;
 pub mod config;
 pub mod controller;
 pub mod error;
 pub mod kubernetes;
+pub mod metrics;
+pub mod pool;
 pub mod scaler;
 
 pub use config::Config;
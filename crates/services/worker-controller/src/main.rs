@@ -4,23 +4,25 @@ use std::sync::Arc;
 
 use tokio::sync::Notify;
 use tracing::{error, info};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use vulcan_worker_controller::{Config, Controller};
+use vulcan_worker_controller::backend::{ProcessBackend, ScalingBackend};
+use vulcan_worker_controller::config::Backend;
+use vulcan_worker_controller::{Config, Controller, Result};
 
 #[tokio::main]
 async fn main() {
-    // Initialize logging
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "info,vulcan_worker_controller=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    // Load environment variables from .env file if present, before the tracing
+    // configuration is read
+    let dotenv_loaded = dotenvy::dotenv().is_ok();
 
-    // Load environment variables from .env file if present
-    if dotenvy::dotenv().is_ok() {
+    // Initialize logging and tracing
+    let _telemetry = vulcan_telemetry::init(
+        "vulcan-worker-controller",
+        "info,vulcan_worker_controller=debug",
+    )
+    .expect("Failed to set up trace export");
+
+    if dotenv_loaded {
         info!("Loaded .env file");
     }
 
@@ -28,13 +30,20 @@ async fn main() {
     let config = Config::from_env();
 
     info!(
-        tenant_id = %config.tenant_id,
-        machine_group = %config.machine_group,
-        deployment = %config.deployment_name,
-        namespace = %config.deployment_namespace,
+        orchestrator_url = %config.orchestrator_url,
+        pools = ?config.pools,
+        backend = ?config.backend,
         "Starting vulcan-worker-controller"
     );
 
+    if let Some(port) = config.metrics_port {
+        if let Err(e) = vulcan_worker_controller::metrics::install(port) {
+            error!(error = %e, "Failed to serve metrics");
+            std::process::exit(1);
+        }
+        info!(port, "Serving metrics");
+    }
+
     // Create shutdown notification
     let shutdown = Arc::new(Notify::new());
     let shutdown_clone = Arc::clone(&shutdown);
@@ -49,7 +58,21 @@ async fn main() {
     });
 
     // Create and run controller
-    let mut controller = match Controller::new(config).await {
+    match config.backend.clone() {
+        Backend::Kubernetes => run(Controller::new(config).await, shutdown).await,
+        Backend::Process { command, state_dir } => {
+            let controller = ProcessBackend::new(command, state_dir)
+                .and_then(|backend| Controller::with_backend(config, backend));
+            run(controller, shutdown).await;
+        }
+    }
+
+    info!("Worker controller stopped");
+}
+
+/// Run a controller until shutdown, exiting if it cannot be created or fails.
+async fn run<B: ScalingBackend>(controller: Result<Controller<B>>, shutdown: Arc<Notify>) {
+    let mut controller = match controller {
         Ok(c) => c,
         Err(e) => {
             error!(error = %e, "Failed to create controller");
@@ -61,6 +84,4 @@ async fn main() {
         error!(error = %e, "Controller error");
         std::process::exit(1);
     }
-
-    info!("Worker controller stopped");
 }
@@ -0,0 +1,42 @@
+//! Prometheus metrics of the controller, served on `METRICS_PORT` when set.
+
+use std::net::{Ipv4Addr, SocketAddr};
+
+use metrics::{counter, describe_counter, describe_gauge, gauge};
+use metrics_exporter_prometheus::{BuildError, PrometheusBuilder};
+
+/// Scale decisions, by `pool` and direction (`up`, `down` or `blocked` by the scaling
+/// behavior or cooldown).
+pub const SCALE_DECISIONS: &str = "vulcan_controller_scale_decisions_total";
+/// Replicas the controller last calculated, by `pool`.
+pub const DESIRED_REPLICAS: &str = "vulcan_controller_desired_replicas";
+/// Replicas of the deployment, by `pool`.
+pub const CURRENT_REPLICAS: &str = "vulcan_controller_current_replicas";
+
+/// Install the Prometheus recorder and serve it on `port` on all interfaces.
+///
+/// Must be called from within a Tokio runtime.
+///
+/// # Errors
+/// Returns an error if a recorder is already installed or the listener cannot be set up.
+pub fn install(port: u16) -> Result<(), BuildError> {
+    PrometheusBuilder::new()
+        .with_http_listener(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)))
+        .install()?;
+
+    describe_counter!(SCALE_DECISIONS, "Scale decisions");
+    describe_gauge!(DESIRED_REPLICAS, "Replicas the controller last calculated");
+    describe_gauge!(CURRENT_REPLICAS, "Replicas of the deployment");
+    Ok(())
+}
+
+/// Record the replica counts of a reconciliation of `pool`.
+pub fn record_replicas(pool: &str, current: i32, desired: i32) {
+    gauge!(CURRENT_REPLICAS, "pool" => pool.to_string()).set(f64::from(current));
+    gauge!(DESIRED_REPLICAS, "pool" => pool.to_string()).set(f64::from(desired));
+}
+
+/// Record a scale decision of `pool`, with `direction` one of `up`, `down` or `blocked`.
+pub fn record_scale_decision(pool: &str, direction: &'static str) {
+    counter!(SCALE_DECISIONS, "pool" => pool.to_string(), "direction" => direction).increment(1);
+}
@@ -0,0 +1,260 @@
+//! Worker pools: the deployments the controller scales, or launches Jobs from.
+//!
+//! A pool is described by a [`WorkerPool`], either a Kubernetes custom resource or an
+//! entry of a pools file holding the same manifests.
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use kube::{CustomResource, ResourceExt};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::config::ScalingConfig;
+use crate::error::{ControllerError, Result};
+
+/// Namespace of pools that do not set one.
+const DEFAULT_NAMESPACE: &str = "default";
+
+/// A worker pool: a deployment of workers of one tenant and machine group, and how
+/// to scale it.
+#[derive(CustomResource, Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[kube(
+    group = "vulcan.dev",
+    version = "v1alpha1",
+    kind = "WorkerPool",
+    namespaced,
+    status = "WorkerPoolStatus",
+    shortname = "wp",
+    printcolumn = r#"{"name":"Group","type":"string","jsonPath":".spec.machineGroup"}"#,
+    printcolumn = r#"{"name":"Deployment","type":"string","jsonPath":".spec.deployment"}"#,
+    printcolumn = r#"{"name":"Current","type":"integer","jsonPath":".status.currentReplicas"}"#,
+    printcolumn = r#"{"name":"Desired","type":"integer","jsonPath":".status.desiredReplicas"}"#
+)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkerPoolSpec {
+    /// Tenant whose work the pool executes.
+    pub tenant_id: Uuid,
+    /// Machine group of the pool's workers.
+    pub machine_group: String,
+    /// Name of the worker Deployment, in the namespace of the pool. In `jobs` mode,
+    /// its pod template is the template of the pool's Jobs.
+    pub deployment: String,
+    /// How the pool runs its workers.
+    #[serde(default)]
+    pub mode: PoolMode,
+    /// Labels of the pool's workers, limiting the fragments a Job is launched for in
+    /// `jobs` mode to those whose `runs-on` requirements they meet (any if unset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<String>>,
+    /// Seconds a finished Job is kept before Kubernetes deletes it, in `jobs` mode
+    /// (default: 300).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job_ttl_seconds_after_finished: Option<i32>,
+    /// Secret holding the controller token of the tenant, in the namespace of the pool.
+    /// Without it, the controller's `CONTROLLER_TOKEN` is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub controller_token_secret: Option<SecretKeyRef>,
+    /// Scaling parameters.
+    #[serde(flatten)]
+    pub scaling: ScalingConfig,
+}
+
+/// How a worker pool runs its workers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum PoolMode {
+    /// Scale the replicas of the pool's Deployment with its work.
+    #[default]
+    Deployment,
+    /// Launch a Job per pending fragment, whose worker executes that fragment and
+    /// exits. `maxReplicas` caps the Jobs running at once.
+    Jobs,
+}
+
+/// A key of a Kubernetes Secret.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SecretKeyRef {
+    /// Name of the Secret.
+    pub name: String,
+    /// Key of the value in the Secret.
+    pub key: String,
+}
+
+/// Observed state of a worker pool, written by the controller.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkerPoolStatus {
+    /// Replicas of the deployment, or running Jobs in `jobs` mode.
+    pub current_replicas: i32,
+    /// Replicas the controller calculated for the pool's work.
+    pub desired_replicas: i32,
+    /// Fragments waiting for a worker of the pool.
+    pub pending_fragments: i64,
+    /// Fragments being executed by the pool.
+    pub running_fragments: i64,
+    /// Workers of the pool executing fragments.
+    pub busy_workers: i64,
+    /// Minimum replicas of the schedules matching now, if any matches.
+    pub scheduled_min_replicas: Option<i32>,
+    /// Fragments predicted until the lookahead, with predictive scaling.
+    pub predicted_fragments: Option<i64>,
+    /// When the controller last scaled the deployment or launched Jobs.
+    pub last_scale_time: Option<DateTime<Utc>>,
+    /// Why the last reconciliation failed, if it did.
+    pub error: Option<String>,
+}
+
+impl WorkerPool {
+    /// Namespace of the pool and its deployment.
+    #[must_use]
+    pub fn deployment_namespace(&self) -> String {
+        self.namespace()
+            .unwrap_or_else(|| DEFAULT_NAMESPACE.to_string())
+    }
+
+    /// Key identifying the pool, as `namespace/name`.
+    #[must_use]
+    pub fn key(&self) -> String {
+        format!("{}/{}", self.deployment_namespace(), self.name_any())
+    }
+}
+
+/// Parse worker pools from YAML documents holding `WorkerPool` manifests.
+///
+/// # Errors
+///
+/// Returns an error if a document is not a valid `WorkerPool`.
+pub fn parse_pools(yaml: &str) -> Result<Vec<WorkerPool>> {
+    serde_yaml::Deserializer::from_str(yaml)
+        .map(|document| {
+            WorkerPool::deserialize(document)
+                .map_err(|e| ControllerError::Config(format!("Invalid worker pool: {e}")))
+        })
+        .collect()
+}
+
+/// Load worker pools from a YAML file of `WorkerPool` manifests.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or holds an invalid pool.
+pub fn load_pools(path: &Path) -> Result<Vec<WorkerPool>> {
+    let yaml = std::fs::read_to_string(path)
+        .map_err(|e| ControllerError::Config(format!("Failed to read {}: {e}", path.display())))?;
+    parse_pools(&yaml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kube::CustomResourceExt;
+
+    use crate::scaler::ScheduledMinimums;
+
+    #[test]
+    fn test_parse_pools() {
+        let yaml = r"
+apiVersion: vulcan.dev/v1alpha1
+kind: WorkerPool
+metadata:
+  name: gpu
+  namespace: ci
+spec:
+  tenantId: 00000000-0000-0000-0000-000000000001
+  machineGroup: gpu
+  deployment: vulcan-worker-gpu
+  controllerTokenSecret:
+    name: tenant-a
+    key: token
+  maxReplicas: 4
+  scaleUpMaxPods: 2
+  schedules:
+    - schedule: '45-59 8 * * mon-fri'
+      minReplicas: 2
+    - schedule: '* 9-17 * * mon-fri'
+      minReplicas: 3
+  predictive:
+    lookaheadSeconds: 900
+---
+apiVersion: vulcan.dev/v1alpha1
+kind: WorkerPool
+metadata:
+  name: default
+spec:
+  tenantId: 00000000-0000-0000-0000-000000000002
+  machineGroup: default
+  deployment: vulcan-worker
+  mode: jobs
+  labels: [docker]
+  jobTtlSecondsAfterFinished: 60
+";
+        let pools = parse_pools(yaml).unwrap();
+        assert_eq!(pools.len(), 2);
+
+        let gpu = &pools[0];
+        assert_eq!(gpu.key(), "ci/gpu");
+        assert_eq!(gpu.spec.machine_group, "gpu");
+        assert_eq!(
+            gpu.spec.controller_token_secret.as_ref().unwrap().key,
+            "token"
+        );
+        assert_eq!(gpu.spec.scaling.max_replicas, 4);
+        assert_eq!(gpu.spec.scaling.scale_up_max_pods, Some(2));
+        let prediction = gpu.spec.scaling.prediction().unwrap();
+        assert_eq!(prediction.lookahead.as_secs(), 900);
+        assert_eq!(prediction.period.as_secs(), 86_400);
+        assert_eq!(prediction.periods, 7);
+
+        // (time, scheduled minimum)
+        let schedules = ScheduledMinimums::parse(&gpu.spec.scaling.schedules).unwrap();
+        let cases = [
+            ("2026-03-02T08:30:00Z", None),
+            ("2026-03-02T08:50:00Z", Some(2)),
+            ("2026-03-02T12:00:00Z", Some(3)),
+            ("2026-03-01T12:00:00Z", None),
+        ];
+        for (time, expected) in cases {
+            let at = time.parse().unwrap();
+            assert_eq!(schedules.min_replicas(at), expected, "{time}");
+        }
+        // Unset parameters take their defaults
+        assert_eq!(gpu.spec.mode, PoolMode::Deployment);
+        assert_eq!(gpu.spec.scaling.min_replicas, 0);
+        assert_eq!(gpu.spec.scaling.scale_down_delay_seconds, 300);
+
+        let default = &pools[1];
+        assert_eq!(default.key(), "default/default");
+        assert!(default.spec.controller_token_secret.is_none());
+        assert_eq!(default.spec.scaling.max_replicas, 10);
+        assert_eq!(default.spec.mode, PoolMode::Jobs);
+        assert_eq!(default.spec.labels, Some(vec!["docker".to_string()]));
+        assert_eq!(default.spec.job_ttl_seconds_after_finished, Some(60));
+        assert!(default.spec.scaling.schedules.is_empty());
+        assert!(default.spec.scaling.prediction().is_none());
+    }
+
+    #[test]
+    fn test_parse_pools_rejects_invalid_pool() {
+        let yaml = "metadata:\n  name: broken\nspec:\n  machineGroup: gpu\n";
+        let error = parse_pools(yaml).unwrap_err();
+        assert!(error.to_string().contains("Invalid worker pool"), "{error}");
+    }
+
+    #[test]
+    fn test_crd() {
+        let crd = WorkerPool::crd();
+        assert_eq!(crd.spec.group, "vulcan.dev");
+        assert_eq!(crd.spec.names.kind, "WorkerPool");
+        assert_eq!(crd.spec.scope, "Namespaced");
+        assert!(
+            crd.spec.versions[0]
+                .subresources
+                .as_ref()
+                .unwrap()
+                .status
+                .is_some()
+        );
+    }
+}
@@ -1,5 +1,7 @@
 //! Scaling algorithm implementation.
 
+use crate::client::dto::QueueMetricsResponse;
+
 /// Configuration for the scaling algorithm.
 #[derive(Debug, Clone)]
 pub struct ScalingConfig {
@@ -7,34 +9,65 @@ pub struct ScalingConfig {
     pub min_replicas: i32,
     /// Maximum number of replicas.
     pub max_replicas: i32,
-    /// Target pending fragments per worker.
+    /// Target pending and running fragments per worker.
     pub target_pending_per_worker: f64,
 }
 
-/// Calculate the desired number of replicas based on pending work.
+/// Work of a machine group, as reported by the orchestrator.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorkLoad {
+    /// Fragments waiting for a worker.
+    pub pending_fragments: i64,
+    /// Fragments being executed.
+    pub running_fragments: i64,
+    /// Workers executing at least one fragment.
+    pub busy_workers: i64,
+}
+
+impl From<&QueueMetricsResponse> for WorkLoad {
+    /// The orchestrator does not report which workers are busy, so every running
+    /// fragment is assumed to occupy its own active worker. This overestimates busy
+    /// workers when they run several fragments at once, which only delays scale-down.
+    fn from(metrics: &QueueMetricsResponse) -> Self {
+        Self {
+            pending_fragments: metrics.pending_fragments,
+            running_fragments: metrics.running_fragments,
+            busy_workers: metrics.running_fragments.min(metrics.active_workers),
+        }
+    }
+}
+
+/// Calculate the desired number of replicas based on pending and running work.
 ///
 /// The formula is:
 /// ```text
-/// desired = ceil(pending_fragments / target_pending_per_worker)
-/// result = clamp(desired, min_replicas, max_replicas)
+/// desired = ceil((pending_fragments + running_fragments) / target_pending_per_worker)
+/// result = max(clamp(desired, min_replicas, max_replicas), busy_workers)
 /// ```
 ///
+/// The result never drops below the number of busy workers, so a deployment is not
+/// scaled down while its workers are still executing fragments.
+///
 /// # Arguments
 ///
 /// * `config` - Scaling configuration
-/// * `pending_fragments` - Number of pending fragments in the queue
+/// * `load` - Pending and running work of the machine group
 ///
 /// # Returns
 ///
 /// The desired number of replicas, clamped to the configured range.
-pub fn calculate_desired_replicas(config: &ScalingConfig, pending_fragments: i64) -> i32 {
+pub fn calculate_desired_replicas(config: &ScalingConfig, load: &WorkLoad) -> i32 {
+    let busy_workers = i32::try_from(load.busy_workers).unwrap_or(i32::MAX);
+
     if config.target_pending_per_worker <= 0.0 {
         // Avoid division by zero
-        return config.min_replicas;
+        return config.min_replicas.max(busy_workers);
     }
 
-    let raw = (pending_fragments as f64 / config.target_pending_per_worker).ceil() as i32;
+    let work = load.pending_fragments + load.running_fragments;
+    let raw = (work as f64 / config.target_pending_per_worker).ceil() as i32;
     raw.clamp(config.min_replicas, config.max_replicas)
+        .max(busy_workers)
 }
 
 #[cfg(test)]
@@ -49,16 +82,23 @@ mod tests {
         }
     }
 
+    fn pending(pending_fragments: i64) -> WorkLoad {
+        WorkLoad {
+            pending_fragments,
+            ..WorkLoad::default()
+        }
+    }
+
     #[test]
     fn test_zero_pending_returns_min() {
         let config = default_config();
-        assert_eq!(calculate_desired_replicas(&config, 0), 0);
+        assert_eq!(calculate_desired_replicas(&config, &pending(0)), 0);
     }
 
     #[test]
     fn test_exact_multiple_of_target() {
         let config = default_config();
-        assert_eq!(calculate_desired_replicas(&config, 5), 5);
+        assert_eq!(calculate_desired_replicas(&config, &pending(5)), 5);
     }
 
     #[test]
@@ -68,14 +108,14 @@ mod tests {
             max_replicas: 10,
             target_pending_per_worker: 2.0,
         };
-        assert_eq!(calculate_desired_replicas(&config, 3), 2); // ceil(3/2) = 2
-        assert_eq!(calculate_desired_replicas(&config, 5), 3); // ceil(5/2) = 3
+        assert_eq!(calculate_desired_replicas(&config, &pending(3)), 2); // ceil(3/2) = 2
+        assert_eq!(calculate_desired_replicas(&config, &pending(5)), 3); // ceil(5/2) = 3
     }
 
     #[test]
     fn test_clamps_to_max() {
         let config = default_config();
-        assert_eq!(calculate_desired_replicas(&config, 100), 10);
+        assert_eq!(calculate_desired_replicas(&config, &pending(100)), 10);
     }
 
     #[test]
@@ -85,8 +125,8 @@ mod tests {
             max_replicas: 10,
             target_pending_per_worker: 1.0,
         };
-        assert_eq!(calculate_desired_replicas(&config, 0), 2);
-        assert_eq!(calculate_desired_replicas(&config, 1), 2);
+        assert_eq!(calculate_desired_replicas(&config, &pending(0)), 2);
+        assert_eq!(calculate_desired_replicas(&config, &pending(1)), 2);
     }
 
     #[test]
@@ -96,6 +136,62 @@ mod tests {
             max_replicas: 10,
             target_pending_per_worker: 0.0,
         };
-        assert_eq!(calculate_desired_replicas(&config, 100), 1);
+        assert_eq!(calculate_desired_replicas(&config, &pending(100)), 1);
+    }
+
+    #[test]
+    fn test_desired_replicas() {
+        // (case, min, max, target, pending, running, busy, expected)
+        let cases = [
+            ("idle", 0, 10, 1.0, 0, 0, 0, 0),
+            ("pending only", 0, 10, 1.0, 4, 0, 0, 4),
+            ("running only", 0, 10, 1.0, 0, 3, 3, 3),
+            ("pending and running", 0, 10, 1.0, 4, 3, 3, 7),
+            ("shared workers", 0, 10, 2.0, 3, 4, 2, 4),
+            ("busy above max", 0, 2, 1.0, 5, 3, 3, 3),
+            ("busy above min", 2, 10, 4.0, 0, 3, 3, 3),
+            ("min above busy", 5, 10, 1.0, 0, 2, 2, 5),
+            ("capped at max", 0, 10, 1.0, 20, 5, 5, 10),
+            ("zero target keeps busy", 1, 10, 0.0, 20, 4, 4, 4),
+        ];
+
+        for (case, min_replicas, max_replicas, target, pending, running, busy, expected) in cases {
+            let config = ScalingConfig {
+                min_replicas,
+                max_replicas,
+                target_pending_per_worker: target,
+            };
+            let load = WorkLoad {
+                pending_fragments: pending,
+                running_fragments: running,
+                busy_workers: busy,
+            };
+            assert_eq!(
+                calculate_desired_replicas(&config, &load),
+                expected,
+                "{case}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_busy_workers_from_metrics() {
+        // (running, active, busy)
+        let cases = [(0, 3, 0), (2, 3, 2), (5, 3, 3), (4, 0, 0)];
+
+        for (running_fragments, active_workers, busy) in cases {
+            let metrics = QueueMetricsResponse {
+                pending_fragments: 1,
+                running_fragments,
+                active_workers,
+            };
+            let load = WorkLoad::from(&metrics);
+            assert_eq!(
+                load.busy_workers, busy,
+                "{running_fragments} on {active_workers}"
+            );
+            assert_eq!(load.pending_fragments, 1);
+            assert_eq!(load.running_fragments, running_fragments);
+        }
     }
 }
@@ -0,0 +1,78 @@
+//! Scaling behavior policies, modelled on the `behavior` of a Kubernetes
+//! `HorizontalPodAutoscaler`.
+
+use std::time::Duration;
+
+/// Limits on how fast the controller follows the desired replica count.
+#[derive(Debug, Clone, Default)]
+pub struct ScalingBehavior {
+    /// Scale up only to the lowest replica count desired within this window.
+    pub scale_up_stabilization: Duration,
+    /// Scale down only to the highest replica count desired within this window.
+    pub scale_down_stabilization: Duration,
+    /// Step limits on scaling up (none if unlimited).
+    pub scale_up_limit: Option<ScaleUpLimit>,
+}
+
+/// Step limits on scaling up within a period.
+///
+/// When both limits are set, the one allowing more replicas applies.
+#[derive(Debug, Clone)]
+pub struct ScaleUpLimit {
+    /// Replicas that may be added per period.
+    pub pods: Option<i32>,
+    /// Percentage of the replicas at the start of the period that may be added.
+    pub percent: Option<i32>,
+    /// Period the limits apply to.
+    pub period: Duration,
+}
+
+impl ScaleUpLimit {
+    /// The most replicas allowed when the period started with `start_replicas`.
+    ///
+    /// A percentage limit allows at least one replica, so scaling up from zero is
+    /// possible without a pod limit.
+    #[must_use]
+    pub fn max_replicas(&self, start_replicas: i32) -> i32 {
+        let by_pods = self.pods.map(|pods| start_replicas.saturating_add(pods));
+        let by_percent = self.percent.map(|percent| {
+            let percentage = i64::from(start_replicas.max(0)) * i64::from(percent.max(0));
+            let added = (percentage + 99) / 100;
+            let added = i32::try_from(added).unwrap_or(i32::MAX).max(1);
+            start_replicas.saturating_add(added)
+        });
+
+        match (by_pods, by_percent) {
+            (Some(pods), Some(percent)) => pods.max(percent),
+            (Some(limit), None) | (None, Some(limit)) => limit,
+            (None, None) => i32::MAX,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_up_limit() {
+        // (case, pods, percent, start, expected)
+        let cases = [
+            ("unlimited", None, None, 3, i32::MAX),
+            ("pods", Some(2), None, 3, 5),
+            ("percent", None, Some(50), 3, 5),
+            ("percent of zero", None, Some(100), 0, 1),
+            ("larger of both", Some(4), Some(100), 2, 6),
+            ("larger of both by percent", Some(1), Some(100), 4, 8),
+        ];
+
+        for (case, pods, percent, start, expected) in cases {
+            let limit = ScaleUpLimit {
+                pods,
+                percent,
+                period: Duration::from_mins(1),
+            };
+            assert_eq!(limit.max_replicas(start), expected, "{case}");
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! Rolling history of a pool's work, predicting the work ahead from earlier periods.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{ControllerError, Result};
+
+/// How work is predicted from the history.
+#[derive(Debug, Clone)]
+pub struct Prediction {
+    /// How far ahead to provision for.
+    pub lookahead: Duration,
+    /// Period the work repeats with, e.g. a day.
+    pub period: Duration,
+    /// Number of earlier periods the prediction averages over.
+    pub periods: u32,
+}
+
+/// Work of a pool per minute, kept for as long as predictions look back.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LoadHistory {
+    /// Minutes since the Unix epoch, with the most work seen in each.
+    samples: VecDeque<(i64, i64)>,
+}
+
+/// Whole minutes of a duration.
+fn minutes(duration: Duration) -> i64 {
+    i64::try_from(duration.as_secs() / 60).unwrap_or(i64::MAX)
+}
+
+impl LoadHistory {
+    /// Whether the history holds no work.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Record the work (pending and running fragments) of a pool at `at`.
+    ///
+    /// Samples older than the earliest period the prediction looks at are dropped.
+    pub fn record(&mut self, at: DateTime<Utc>, work: i64, prediction: &Prediction) {
+        let minute = at.timestamp().div_euclid(60);
+
+        match self.samples.back_mut() {
+            Some((last, most)) if *last == minute => *most = (*most).max(work),
+            _ => self.samples.push_back((minute, work)),
+        }
+
+        let retention = minutes(prediction.period)
+            .saturating_mul(i64::from(prediction.periods))
+            .saturating_add(minutes(prediction.lookahead));
+        while self
+            .samples
+            .front()
+            .is_some_and(|&(first, _)| first < minute.saturating_sub(retention))
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Predict the most work of the pool from `now` until the lookahead.
+    ///
+    /// Takes the most work within the same window of each earlier period, and
+    /// averages it (rounding up) over the periods with samples.
+    ///
+    /// # Returns
+    ///
+    /// The predicted work, or None if no earlier period has samples.
+    #[must_use]
+    pub fn predict(&self, prediction: &Prediction, now: DateTime<Utc>) -> Option<i64> {
+        let period = minutes(prediction.period);
+        if period == 0 {
+            return None;
+        }
+        let minute = now.timestamp().div_euclid(60);
+        let lookahead = minutes(prediction.lookahead);
+
+        let peaks: Vec<i64> = (1..=i64::from(prediction.periods))
+            .filter_map(|periods_ago| {
+                let start = minute.saturating_sub(period.saturating_mul(periods_ago));
+                let window = start..=start.saturating_add(lookahead);
+                self.samples
+                    .iter()
+                    .filter(|(at, _)| window.contains(at))
+                    .map(|&(_, work)| work)
+                    .max()
+            })
+            .collect();
+
+        let count = i64::try_from(peaks.len()).ok().filter(|&count| count > 0)?;
+        let total: i64 = peaks.iter().sum();
+        Some((total + count - 1) / count)
+    }
+}
+
+/// Load the histories of the pools, by pool key, from a JSON file.
+///
+/// A missing file holds no histories.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or parsed.
+pub fn load_histories(path: &Path) -> Result<HashMap<String, LoadHistory>> {
+    let json = match std::fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => {
+            return Err(ControllerError::Config(format!(
+                "Failed to read {}: {e}",
+                path.display()
+            )));
+        },
+    };
+
+    serde_json::from_str(&json).map_err(|e| {
+        ControllerError::Config(format!("Invalid scaling history {}: {e}", path.display()))
+    })
+}
+
+/// Save the histories of the pools, by pool key, to a JSON file.
+///
+/// The file is replaced at once, so a crash does not leave it half written.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be written.
+pub fn save_histories(path: &Path, histories: &BTreeMap<&str, &LoadHistory>) -> Result<()> {
+    let write = || -> std::io::Result<()> {
+        let partial = path.with_extension("tmp");
+        std::fs::write(&partial, serde_json::to_vec(histories)?)?;
+        std::fs::rename(&partial, path)
+    };
+
+    write().map_err(|e| ControllerError::Config(format!("Failed to write {}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    fn daily(periods: u32) -> Prediction {
+        Prediction {
+            lookahead: Duration::from_mins(10),
+            period: Duration::from_hours(24),
+            periods,
+        }
+    }
+
+    /// A history of the work on earlier days, as (days ago, minutes from now, work).
+    fn history(now: DateTime<Utc>, samples: &[(i64, i64, i64)]) -> LoadHistory {
+        let mut history = LoadHistory::default();
+        let mut samples = samples.to_vec();
+        samples.sort_by_key(|&(days, minutes, _)| minutes - days * 24 * 60);
+        for (days, minutes, work) in samples {
+            let at = now - TimeDelta::days(days) + TimeDelta::minutes(minutes);
+            history.record(at, work, &daily(7));
+        }
+        history
+    }
+
+    #[test]
+    fn test_predict() {
+        let now = DateTime::from_timestamp(1_772_442_000, 0).unwrap();
+
+        // (case, periods, samples, expected)
+        let cases = [
+            ("no history", 7, vec![], None),
+            ("spike ahead yesterday", 7, vec![(1, 5, 8)], Some(8)),
+            (
+                "peak of the window",
+                7,
+                vec![(1, 0, 2), (1, 10, 6)],
+                Some(6),
+            ),
+            ("after the lookahead", 7, vec![(1, 11, 8)], None),
+            ("before now", 7, vec![(1, -1, 8)], None),
+            ("averaged over days", 7, vec![(1, 5, 8), (2, 5, 3)], Some(6)),
+            ("beyond the periods", 1, vec![(2, 5, 8)], None),
+        ];
+
+        for (case, periods, samples, expected) in cases {
+            let history = history(now, &samples);
+            assert_eq!(history.predict(&daily(periods), now), expected, "{case}");
+        }
+    }
+
+    #[test]
+    fn test_record_keeps_the_most_work_per_minute() {
+        let now = DateTime::from_timestamp(1_772_442_000, 0).unwrap();
+        let mut history = LoadHistory::default();
+        history.record(now, 3, &daily(1));
+        history.record(now + TimeDelta::seconds(30), 5, &daily(1));
+        history.record(now + TimeDelta::seconds(50), 1, &daily(1));
+
+        assert_eq!(history.samples, VecDeque::from([(now.timestamp() / 60, 5)]));
+    }
+
+    #[test]
+    fn test_record_drops_samples_beyond_the_periods() {
+        let now = DateTime::from_timestamp(1_772_442_000, 0).unwrap();
+        let mut history = LoadHistory::default();
+        history.record(now - TimeDelta::hours(25), 4, &daily(1));
+        history.record(now - TimeDelta::hours(23), 2, &daily(1));
+        history.record(now, 1, &daily(1));
+
+        let works: Vec<i64> = history.samples.iter().map(|&(_, work)| work).collect();
+        assert_eq!(works, vec![2, 1]);
+    }
+}
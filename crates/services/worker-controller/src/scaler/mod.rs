@@ -1,7 +1,13 @@
 //! Scaling logic module.
 
 pub mod algorithm;
+pub mod behavior;
+pub mod history;
+pub mod schedule;
 pub mod state;
 
-pub use algorithm::{calculate_desired_replicas, ScalingConfig};
+pub use algorithm::{calculate_desired_replicas, ScalingConfig, WorkLoad};
+pub use behavior::{ScaleUpLimit, ScalingBehavior};
+pub use history::{LoadHistory, Prediction};
+pub use schedule::{CronSchedule, ScheduledMinimums};
 pub use state::ScalerState;
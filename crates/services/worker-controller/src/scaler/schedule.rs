@@ -0,0 +1,307 @@
+//! Cron schedules of minimum replica overrides.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Timelike, Utc};
+
+use crate::config::ScheduledMinimum;
+use crate::error::{ControllerError, Result};
+
+/// Month names, from January (1).
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// Day of week names, from Sunday (0).
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A five-field cron expression (minute, hour, day of month, month and day of week),
+/// matching the minutes it names.
+///
+/// Fields hold `*`, values, ranges (`1-5`) and steps (`*/15`, `8-18/2`), separated by
+/// commas. Months and days of week may be given by their three-letter names, and both
+/// 0 and 7 are Sunday.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether the day of month field starts with `*`.
+    any_day: bool,
+    /// Whether the day of week field starts with `*`.
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    /// Whether the schedule names the minute of `at`.
+    ///
+    /// As in cron, when both the day of month and the day of week are restricted, a
+    /// day matching either of them matches. A field starting with `*`, such as `*/2`,
+    /// does not count as restricted.
+    #[must_use]
+    pub fn matches(&self, at: DateTime<Utc>) -> bool {
+        let contains = |set: u64, value: u32| set & (1 << value) != 0;
+
+        let day = contains(self.days, at.day());
+        let weekday = contains(self.weekdays, at.weekday().num_days_from_sunday());
+        let day_matches = if self.any_day || self.any_weekday {
+            day && weekday
+        } else {
+            day || weekday
+        };
+
+        day_matches
+            && contains(self.minutes, at.minute())
+            && contains(self.hours, at.hour())
+            && contains(self.months, at.month())
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = ControllerError;
+
+    fn from_str(expression: &str) -> Result<Self> {
+        let invalid = |reason: String| {
+            ControllerError::Config(format!("Invalid cron schedule {expression:?}: {reason}"))
+        };
+
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(invalid(format!("expected 5 fields, got {}", fields.len())));
+        };
+
+        let mut weekday_set = parse_field(weekdays, 0, 7, &WEEKDAYS).map_err(invalid)?;
+        // 7 is Sunday too
+        if weekday_set & (1 << 7) != 0 {
+            weekday_set |= 1;
+        }
+
+        Ok(Self {
+            minutes: parse_field(minutes, 0, 59, &[]).map_err(invalid)?,
+            hours: parse_field(hours, 0, 23, &[]).map_err(invalid)?,
+            days: parse_field(days, 1, 31, &[]).map_err(invalid)?,
+            months: parse_field(months, 1, 12, &MONTHS).map_err(invalid)?,
+            weekdays: weekday_set,
+            any_day: days.starts_with('*'),
+            any_weekday: weekdays.starts_with('*'),
+        })
+    }
+}
+
+/// The scheduled minimum replica counts of a pool, with their schedules parsed.
+///
+/// Kept across reconciliations, so schedules are only parsed again when the pool's
+/// configuration changes.
+#[derive(Debug, Clone)]
+pub struct ScheduledMinimums {
+    /// The configuration the schedules were parsed from.
+    source: Vec<ScheduledMinimum>,
+    schedules: Vec<(CronSchedule, i32)>,
+}
+
+impl ScheduledMinimums {
+    /// Parse the schedules of scheduled minimums.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a schedule is not a valid cron expression.
+    pub fn parse(source: &[ScheduledMinimum]) -> Result<Self> {
+        let schedules = source
+            .iter()
+            .map(|scheduled| Ok((scheduled.schedule.parse()?, scheduled.min_replicas)))
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            source: source.to_vec(),
+            schedules,
+        })
+    }
+
+    /// Whether these were parsed from `source`.
+    #[must_use]
+    pub fn is_parsed_from(&self, source: &[ScheduledMinimum]) -> bool {
+        self.source == source
+    }
+
+    /// The highest minimum replica count of the schedules matching `at`, if any
+    /// matches.
+    #[must_use]
+    pub fn min_replicas(&self, at: DateTime<Utc>) -> Option<i32> {
+        self.schedules
+            .iter()
+            .filter(|(schedule, _)| schedule.matches(at))
+            .map(|&(_, min_replicas)| min_replicas)
+            .max()
+    }
+}
+
+/// Parse a cron field into the set of values it names, as a bit set.
+///
+/// `names` name the values from `min` on.
+fn parse_field(
+    field: &str,
+    min: u32,
+    max: u32,
+    names: &[&str],
+) -> std::result::Result<u64, String> {
+    let value = |text: &str| -> std::result::Result<u32, String> {
+        let value = match names
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(text))
+        {
+            Some(index) => min + u32::try_from(index).unwrap_or(u32::MAX),
+            None => text
+                .parse()
+                .map_err(|_| format!("{text:?} is not a number"))?,
+        };
+        if (min..=max).contains(&value) {
+            Ok(value)
+        } else {
+            Err(format!("{value} is not within {min}-{max}"))
+        }
+    };
+
+    let mut set = 0;
+    for element in field.split(',') {
+        let (range, step) = match element.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("{step:?} is not a step"))?;
+                if step == 0 {
+                    return Err("a step must be positive".to_string());
+                }
+                (range, Some(step))
+            },
+            None => (element, None),
+        };
+
+        let (first, last) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((first, last)) => (value(first)?, value(last)?),
+            // A single value with a step runs to the end of the field
+            None if step.is_some() => (value(range)?, max),
+            None => {
+                let value = value(range)?;
+                (value, value)
+            },
+        };
+        if first > last {
+            return Err(format!("{range:?} is an empty range"));
+        }
+
+        for value in (first..=last).step_by(step.unwrap_or(1) as usize) {
+            set |= 1 << value;
+        }
+    }
+
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_matches() {
+        // Monday 2026-03-02 09:15
+        let monday = at(2026, 3, 2, 9, 15);
+        // Sunday 2026-03-01 09:15
+        let sunday = at(2026, 3, 1, 9, 15);
+
+        // (schedule, matches on Monday, matches on Sunday)
+        let cases = [
+            ("* * * * *", true, true),
+            ("15 9 * * *", true, true),
+            ("0 9 * * *", false, false),
+            ("*/15 8-17 * * 1-5", true, false),
+            ("*/20 8-17 * * 1-5", false, false),
+            ("10-20/5 9 * * mon-fri", true, false),
+            ("* 9 * * 0", false, true),
+            ("* 9 * * 7", false, true),
+            ("* 9 * * sun,sat", false, true),
+            ("* * 1 * *", false, true),
+            // A day matching either restricted day field matches
+            ("* * 1 * 1", true, true),
+            // A day field starting with `*` is not restricted: both must match
+            ("* * */2 * 1", false, false),
+            ("* * 1 * */2", false, true),
+            ("* * * mar *", true, true),
+            ("* * * 4-12 *", false, false),
+            ("5/10 * * * *", true, true),
+        ];
+
+        for (expression, on_monday, on_sunday) in cases {
+            let schedule: CronSchedule = expression.parse().unwrap();
+            assert_eq!(
+                schedule.matches(monday),
+                on_monday,
+                "{expression} on Monday"
+            );
+            assert_eq!(
+                schedule.matches(sunday),
+                on_sunday,
+                "{expression} on Sunday"
+            );
+        }
+    }
+
+    #[test]
+    fn test_scheduled_minimums() {
+        let source = vec![
+            ScheduledMinimum {
+                schedule: "* 9-17 * * *".to_string(),
+                min_replicas: 2,
+            },
+            ScheduledMinimum {
+                schedule: "* 12 * * *".to_string(),
+                min_replicas: 5,
+            },
+        ];
+        let schedules = ScheduledMinimums::parse(&source).unwrap();
+
+        assert_eq!(schedules.min_replicas(at(2026, 3, 2, 8, 0)), None);
+        assert_eq!(schedules.min_replicas(at(2026, 3, 2, 9, 0)), Some(2));
+        assert_eq!(schedules.min_replicas(at(2026, 3, 2, 12, 30)), Some(5));
+
+        assert!(schedules.is_parsed_from(&source));
+        assert!(!schedules.is_parsed_from(&source[..1]));
+
+        let invalid = [ScheduledMinimum {
+            schedule: "* 25 * * *".to_string(),
+            min_replicas: 1,
+        }];
+        assert!(ScheduledMinimums::parse(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_invalid_schedules() {
+        // (schedule, error)
+        let cases = [
+            ("* * * *", "expected 5 fields, got 4"),
+            ("60 * * * *", "60 is not within 0-59"),
+            ("* 24 * * *", "24 is not within 0-23"),
+            ("* * 0 * *", "0 is not within 1-31"),
+            ("* * * 13 *", "13 is not within 1-12"),
+            ("* * * * 8", "8 is not within 0-7"),
+            ("* * * * funday", "\"funday\" is not a number"),
+            ("*/0 * * * *", "a step must be positive"),
+            ("*/x * * * *", "\"x\" is not a step"),
+            ("* 17-9 * * *", "\"17-9\" is an empty range"),
+        ];
+
+        for (expression, error) in cases {
+            let message = expression.parse::<CronSchedule>().unwrap_err().to_string();
+            assert!(message.contains(error), "{expression}: {message}");
+        }
+    }
+}
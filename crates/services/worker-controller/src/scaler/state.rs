@@ -1,7 +1,13 @@
-//! State tracking for scale-down cooldown.
+//! State tracking for scale-down cooldown and scaling behavior.
 
+use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Utc};
+
+use super::behavior::ScalingBehavior;
+use super::history::{LoadHistory, Prediction};
+
 /// Tracks scaling state including cooldown timers.
 #[derive(Debug)]
 pub struct ScalerState {
@@ -9,6 +15,12 @@ pub struct ScalerState {
     last_scale_down: Option<Instant>,
     /// Current replica count.
     current_replicas: i32,
+    /// Desired replica counts within the longest stabilization window.
+    recommendations: VecDeque<(Instant, i32)>,
+    /// Replicas added by scale-ups within the scale-up limit period.
+    scale_ups: VecDeque<(Instant, i32)>,
+    /// Work of the pool over the periods predictions look at.
+    history: LoadHistory,
 }
 
 impl ScalerState {
@@ -17,9 +29,48 @@ impl ScalerState {
         Self {
             last_scale_down: None,
             current_replicas: 0,
+            recommendations: VecDeque::new(),
+            scale_ups: VecDeque::new(),
+            history: LoadHistory::default(),
         }
     }
 
+    /// Create a scaler state continuing a saved work history.
+    #[must_use]
+    pub fn with_history(history: LoadHistory) -> Self {
+        Self {
+            history,
+            ..Self::new()
+        }
+    }
+
+    /// Get the work history.
+    #[must_use]
+    pub fn history(&self) -> &LoadHistory {
+        &self.history
+    }
+
+    /// Record the current work and predict the work ahead.
+    ///
+    /// # Arguments
+    ///
+    /// * `work` - Pending and running fragments of this reconciliation
+    /// * `prediction` - How to predict work from the history
+    /// * `now` - Time of this reconciliation
+    ///
+    /// # Returns
+    ///
+    /// The most work predicted until the lookahead, or None without history.
+    pub fn predict_work(
+        &mut self,
+        work: i64,
+        prediction: &Prediction,
+        now: DateTime<Utc>,
+    ) -> Option<i64> {
+        self.history.record(now, work, prediction);
+        self.history.predict(prediction, now)
+    }
+
     /// Update the current replica count.
     pub fn set_current_replicas(&mut self, replicas: i32) {
         self.current_replicas = replicas;
@@ -54,6 +105,75 @@ impl ScalerState {
         self.last_scale_down = Some(Instant::now());
     }
 
+    /// Record that a scale-up operation added `added` replicas.
+    pub fn record_scale_up(&mut self, added: i32, now: Instant) {
+        self.scale_ups.push_back((now, added));
+    }
+
+    /// Apply the scaling behavior to the desired replica count.
+    ///
+    /// Scales up to the lowest count desired within the scale-up stabilization
+    /// window, limited by the replicas the scale-up limit still allows in its period,
+    /// and down to the highest count desired within the scale-down stabilization
+    /// window.
+    ///
+    /// # Arguments
+    ///
+    /// * `desired_replicas` - Desired replica count of this reconciliation
+    /// * `behavior` - Scaling behavior policies
+    /// * `now` - Time of this reconciliation
+    ///
+    /// # Returns
+    ///
+    /// The replica count to scale to.
+    pub fn apply_behavior(
+        &mut self,
+        desired_replicas: i32,
+        behavior: &ScalingBehavior,
+        now: Instant,
+    ) -> i32 {
+        let within = |at: Instant, window: Duration| now.saturating_duration_since(at) <= window;
+
+        let window = behavior
+            .scale_up_stabilization
+            .max(behavior.scale_down_stabilization);
+        self.recommendations.retain(|&(at, _)| within(at, window));
+        self.recommendations.push_back((now, desired_replicas));
+
+        let scale_up_to = self
+            .recommendations
+            .iter()
+            .filter(|&&(at, _)| within(at, behavior.scale_up_stabilization))
+            .map(|&(_, replicas)| replicas)
+            .min()
+            .unwrap_or(desired_replicas);
+        let scale_down_to = self
+            .recommendations
+            .iter()
+            .filter(|&&(at, _)| within(at, behavior.scale_down_stabilization))
+            .map(|&(_, replicas)| replicas)
+            .max()
+            .unwrap_or(desired_replicas);
+
+        let replicas = self.current_replicas.max(scale_up_to).min(scale_down_to);
+
+        let Some(limit) = &behavior.scale_up_limit else {
+            self.scale_ups.clear();
+            return replicas;
+        };
+        self.scale_ups.retain(|&(at, _)| within(at, limit.period));
+        if replicas <= self.current_replicas {
+            return replicas;
+        }
+
+        let added: i32 = self.scale_ups.iter().map(|&(_, added)| added).sum();
+        let start_replicas = (self.current_replicas - added).max(0);
+        let allowed = limit
+            .max_replicas(start_replicas)
+            .max(self.current_replicas);
+        replicas.min(allowed)
+    }
+
     /// Determine if scaling is needed and what action to take.
     ///
     /// # Arguments
@@ -96,6 +216,7 @@ impl Default for ScalerState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::scaler::behavior::ScaleUpLimit;
 
     #[test]
     fn test_initial_state() {
@@ -130,6 +251,164 @@ mod tests {
         assert_eq!(state.should_scale(2, 300), Some(2));
     }
 
+    /// A history entry: seconds after the start, desired replicas and replicas added
+    /// by a scale-up.
+    type History = Vec<(u64, i32, i32)>;
+
+    fn stabilized(up_seconds: u64, down_seconds: u64) -> ScalingBehavior {
+        ScalingBehavior {
+            scale_up_stabilization: Duration::from_secs(up_seconds),
+            scale_down_stabilization: Duration::from_secs(down_seconds),
+            scale_up_limit: None,
+        }
+    }
+
+    fn limited(pods: i32, period_seconds: u64) -> ScalingBehavior {
+        ScalingBehavior {
+            scale_up_limit: Some(ScaleUpLimit {
+                pods: Some(pods),
+                percent: None,
+                period: Duration::from_secs(period_seconds),
+            }),
+            ..ScalingBehavior::default()
+        }
+    }
+
+    /// Check cases of (case, behavior, current, history, desired, expected), applying
+    /// the behavior a minute after the start of their history.
+    fn check_behavior(cases: Vec<(&str, ScalingBehavior, i32, History, i32, i32)>) {
+        let start = Instant::now();
+        let now = start + Duration::from_mins(1);
+        for (case, behavior, current, history, desired, expected) in cases {
+            let mut state = ScalerState::new();
+            for (seconds, recommended, added) in history {
+                let at = start + Duration::from_secs(seconds);
+                state.apply_behavior(recommended, &behavior, at);
+                if added > 0 {
+                    state.record_scale_up(added, at);
+                }
+            }
+            state.set_current_replicas(current);
+
+            assert_eq!(
+                state.apply_behavior(desired, &behavior, now),
+                expected,
+                "{case}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_stabilization_windows() {
+        check_behavior(vec![
+            (
+                "no behavior up",
+                ScalingBehavior::default(),
+                3,
+                vec![],
+                7,
+                7,
+            ),
+            (
+                "no behavior down",
+                ScalingBehavior::default(),
+                7,
+                vec![(10, 9, 0)],
+                2,
+                2,
+            ),
+            (
+                "down at window max",
+                stabilized(0, 60),
+                8,
+                vec![(20, 6, 0), (50, 4, 0)],
+                2,
+                6,
+            ),
+            (
+                "down after window",
+                stabilized(0, 30),
+                8,
+                vec![(20, 6, 0), (50, 4, 0)],
+                2,
+                4,
+            ),
+            (
+                "down never above current",
+                stabilized(0, 60),
+                5,
+                vec![(30, 9, 0)],
+                2,
+                5,
+            ),
+            (
+                "up at window min",
+                stabilized(60, 0),
+                2,
+                vec![(30, 4, 0), (50, 6, 0)],
+                9,
+                4,
+            ),
+            (
+                "up held by low desire",
+                stabilized(60, 0),
+                3,
+                vec![(30, 1, 0)],
+                9,
+                3,
+            ),
+            (
+                "up after window",
+                stabilized(20, 0),
+                3,
+                vec![(30, 1, 0)],
+                9,
+                9,
+            ),
+            (
+                "down window ignores up",
+                stabilized(0, 60),
+                2,
+                vec![(30, 1, 0)],
+                6,
+                6,
+            ),
+        ]);
+    }
+
+    #[test]
+    fn test_scale_up_limits() {
+        check_behavior(vec![
+            ("step limited", limited(2, 60), 3, vec![], 9, 5),
+            ("within limit", limited(2, 60), 3, vec![], 4, 4),
+            (
+                "limited within period",
+                limited(4, 60),
+                6,
+                vec![(30, 6, 3)],
+                12,
+                7,
+            ),
+            ("limit used up", limited(2, 60), 5, vec![(30, 5, 2)], 12, 5),
+            (
+                "limit after period",
+                limited(2, 20),
+                5,
+                vec![(30, 5, 2)],
+                12,
+                7,
+            ),
+            (
+                "down not limited",
+                limited(1, 60),
+                6,
+                vec![(30, 6, 5)],
+                1,
+                1,
+            ),
+        ]);
+    }
+
     #[test]
     fn test_scale_down_blocked_during_cooldown() {
         let mut state = ScalerState::new();
@@ -0,0 +1,827 @@
+//! Integration tests for reconciling worker pools against a stub Kubernetes API and
+//! a stub orchestrator.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, patch};
+use axum::{Json, Router};
+use kube::CustomResourceExt;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use vulcan_worker_controller::config::{Backend, PoolSource, TlsConfig};
+use vulcan_worker_controller::pool::WorkerPool;
+use vulcan_worker_controller::{Config, Controller};
+
+const TENANT_A: Uuid = Uuid::from_u128(0xa);
+const TENANT_B: Uuid = Uuid::from_u128(0xb);
+const TENANT_C: Uuid = Uuid::from_u128(0xc);
+
+/// State of the stub Kubernetes API.
+#[derive(Default)]
+struct Cluster {
+    /// `WorkerPool` resources, as JSON.
+    pools: Vec<Value>,
+    /// Replicas of each deployment.
+    deployments: HashMap<String, i32>,
+    /// Deployment scale patches, as (deployment, replicas).
+    scaled: Vec<(String, i32)>,
+    /// Status patches, as (pool, status).
+    statuses: Vec<(String, Value)>,
+    /// Jobs, as JSON.
+    jobs: Vec<Value>,
+    /// Jobs created by the controller, as JSON.
+    created_jobs: Vec<Value>,
+    /// Pod names of each deployment.
+    pods: HashMap<String, Vec<String>>,
+    /// Deletion cost annotations set on pods.
+    deletion_costs: HashMap<String, String>,
+}
+
+type SharedCluster = Arc<Mutex<Cluster>>;
+
+fn not_found(name: &str) -> Response {
+    let status = json!({
+        "apiVersion": "v1",
+        "kind": "Status",
+        "status": "Failure",
+        "message": format!("{name} not found"),
+        "reason": "NotFound",
+        "code": 404
+    });
+    (StatusCode::NOT_FOUND, Json(status)).into_response()
+}
+
+fn deployment(name: &str, replicas: i32) -> Value {
+    json!({
+        "apiVersion": "apps/v1",
+        "kind": "Deployment",
+        "metadata": { "name": name, "namespace": "ci" },
+        "spec": {
+            "replicas": replicas,
+            "selector": { "matchLabels": { "app": name } },
+            "template": {
+                "metadata": { "labels": { "app": name } },
+                "spec": { "containers": [{ "name": "worker", "image": "vulcan-worker" }] }
+            }
+        }
+    })
+}
+
+async fn list_pools(State(cluster): State<SharedCluster>) -> Json<Value> {
+    let items = cluster.lock().unwrap().pools.clone();
+    Json(json!({
+        "apiVersion": "vulcan.dev/v1alpha1",
+        "kind": "WorkerPoolList",
+        "metadata": {},
+        "items": items
+    }))
+}
+
+async fn get_secret(Path((_, name)): Path<(String, String)>) -> Response {
+    if name != "tenant-a" {
+        return not_found(&name);
+    }
+    Json(json!({
+        "apiVersion": "v1",
+        "kind": "Secret",
+        "metadata": { "name": name },
+        // base64 of "token-a\n"
+        "data": { "token": "dG9rZW4tYQo=" }
+    }))
+    .into_response()
+}
+
+async fn get_deployment(
+    State(cluster): State<SharedCluster>,
+    Path((_, name)): Path<(String, String)>,
+) -> Response {
+    match cluster.lock().unwrap().deployments.get(&name) {
+        Some(&replicas) => Json(deployment(&name, replicas)).into_response(),
+        None => not_found(&name),
+    }
+}
+
+async fn patch_deployment(
+    State(cluster): State<SharedCluster>,
+    Path((_, name)): Path<(String, String)>,
+    Json(patch): Json<Value>,
+) -> Json<Value> {
+    let replicas = i32::try_from(patch["spec"]["replicas"].as_i64().unwrap()).unwrap();
+    {
+        let mut cluster = cluster.lock().unwrap();
+        cluster.deployments.insert(name.clone(), replicas);
+        cluster.scaled.push((name.clone(), replicas));
+    }
+    Json(deployment(&name, replicas))
+}
+
+async fn patch_pool_status(
+    State(cluster): State<SharedCluster>,
+    Path((_, name)): Path<(String, String)>,
+    Json(patch): Json<Value>,
+) -> Json<Value> {
+    let mut cluster = cluster.lock().unwrap();
+    cluster
+        .statuses
+        .push((name.clone(), patch["status"].clone()));
+    let pool = cluster
+        .pools
+        .iter()
+        .find(|pool| pool["metadata"]["name"] == name)
+        .cloned();
+    drop(cluster);
+    Json(pool.unwrap())
+}
+
+async fn list_jobs(State(cluster): State<SharedCluster>) -> Json<Value> {
+    let items = cluster.lock().unwrap().jobs.clone();
+    Json(json!({
+        "apiVersion": "batch/v1",
+        "kind": "JobList",
+        "metadata": {},
+        "items": items
+    }))
+}
+
+async fn create_job(State(cluster): State<SharedCluster>, Json(job): Json<Value>) -> Json<Value> {
+    let mut cluster = cluster.lock().unwrap();
+    cluster.jobs.push(job.clone());
+    cluster.created_jobs.push(job.clone());
+    drop(cluster);
+    Json(job)
+}
+
+#[derive(Deserialize)]
+struct PodsQuery {
+    #[serde(rename = "labelSelector")]
+    label_selector: String,
+}
+
+fn pod(name: &str) -> Value {
+    json!({
+        "apiVersion": "v1",
+        "kind": "Pod",
+        "metadata": { "name": name, "namespace": "ci" }
+    })
+}
+
+async fn list_pods(
+    State(cluster): State<SharedCluster>,
+    Query(query): Query<PodsQuery>,
+) -> Json<Value> {
+    let deployment = query
+        .label_selector
+        .strip_prefix("app=")
+        .unwrap_or_default();
+    let names = cluster
+        .lock()
+        .unwrap()
+        .pods
+        .get(deployment)
+        .cloned()
+        .unwrap_or_default();
+    Json(json!({
+        "apiVersion": "v1",
+        "kind": "PodList",
+        "metadata": {},
+        "items": names.iter().map(|name| pod(name)).collect::<Vec<_>>()
+    }))
+}
+
+async fn patch_pod(
+    State(cluster): State<SharedCluster>,
+    Path((_, name)): Path<(String, String)>,
+    Json(patch): Json<Value>,
+) -> Json<Value> {
+    let cost = patch["metadata"]["annotations"]["controller.kubernetes.io/pod-deletion-cost"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    cluster
+        .lock()
+        .unwrap()
+        .deletion_costs
+        .insert(name.clone(), cost);
+    Json(pod(&name))
+}
+
+/// Start the stub Kubernetes API, returning a client for it.
+async fn start_cluster(cluster: SharedCluster) -> kube::Client {
+    let app = Router::new()
+        .route("/apis/vulcan.dev/v1alpha1/workerpools", get(list_pools))
+        .route(
+            "/apis/vulcan.dev/v1alpha1/namespaces/{namespace}/workerpools/{name}/status",
+            patch(patch_pool_status),
+        )
+        .route(
+            "/api/v1/namespaces/{namespace}/secrets/{name}",
+            get(get_secret),
+        )
+        .route(
+            "/apis/apps/v1/namespaces/{namespace}/deployments/{name}",
+            get(get_deployment).patch(patch_deployment),
+        )
+        .route("/api/v1/namespaces/{namespace}/pods", get(list_pods))
+        .route(
+            "/api/v1/namespaces/{namespace}/pods/{name}",
+            patch(patch_pod),
+        )
+        .route(
+            "/apis/batch/v1/namespaces/{namespace}/jobs",
+            get(list_jobs).post(create_job),
+        )
+        .with_state(cluster);
+
+    let url = serve(app).await;
+    kube::Client::try_from(kube::Config::new(url.parse().unwrap())).unwrap()
+}
+
+/// Queue metrics of each tenant, with the controller token the tenant expects.
+type Queues = Arc<HashMap<Uuid, (&'static str, Value)>>;
+
+#[derive(Deserialize)]
+struct MetricsQuery {
+    tenant_id: Uuid,
+}
+
+async fn queue_metrics(
+    State(queues): State<Queues>,
+    Query(query): Query<MetricsQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let Some((token, metrics)) = queues.get(&query.tenant_id) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    if headers["authorization"] != format!("Bearer {token}") {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    Json(metrics.clone()).into_response()
+}
+
+/// Pending fragments listed by the stub orchestrator, with the queries it received.
+#[derive(Default)]
+struct PendingQueue {
+    fragments: Vec<Value>,
+    queries: Vec<HashMap<String, String>>,
+}
+
+type SharedPendingQueue = Arc<Mutex<PendingQueue>>;
+
+/// A worker registered with the stub orchestrator.
+#[derive(Clone)]
+struct StubWorker {
+    id: Uuid,
+    name: String,
+    busy: bool,
+    draining: bool,
+}
+
+/// State of the stub orchestrator.
+#[derive(Clone)]
+struct Orchestrator {
+    queues: Queues,
+    pending: SharedPendingQueue,
+    workers: Arc<Vec<StubWorker>>,
+    /// Whether busy checks fail.
+    busy_unavailable: bool,
+}
+
+impl Orchestrator {
+    fn new(queues: Queues) -> Self {
+        Self {
+            queues,
+            pending: SharedPendingQueue::default(),
+            workers: Arc::default(),
+            busy_unavailable: false,
+        }
+    }
+}
+
+async fn pending_fragments(
+    State(orchestrator): State<Orchestrator>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Json<Value> {
+    let mut pending = orchestrator.pending.lock().unwrap();
+    pending.queries.push(query);
+    Json(Value::from(pending.fragments.clone()))
+}
+
+async fn queue_metrics_of(
+    State(orchestrator): State<Orchestrator>,
+    query: Query<MetricsQuery>,
+    headers: HeaderMap,
+) -> Response {
+    queue_metrics(State(orchestrator.queues), query, headers).await
+}
+
+async fn list_workers(State(orchestrator): State<Orchestrator>) -> Json<Value> {
+    let workers = orchestrator
+        .workers
+        .iter()
+        .map(|worker| {
+            let status = if worker.draining {
+                "Draining"
+            } else {
+                "Active"
+            };
+            json!({ "worker_id": worker.id, "name": worker.name, "status": status })
+        })
+        .collect::<Vec<_>>();
+    Json(Value::from(workers))
+}
+
+async fn worker_busy(
+    State(orchestrator): State<Orchestrator>,
+    Path(worker_id): Path<Uuid>,
+) -> Response {
+    if orchestrator.busy_unavailable {
+        return StatusCode::SERVICE_UNAVAILABLE.into_response();
+    }
+    let Some(worker) = orchestrator
+        .workers
+        .iter()
+        .find(|worker| worker.id == worker_id)
+    else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    Json(json!({
+        "busy": worker.busy,
+        "fragment_id": null,
+        "fragment_ids": [],
+        "draining": worker.draining
+    }))
+    .into_response()
+}
+
+/// Start the stub orchestrator, returning its URL.
+async fn start_orchestrator(queues: Queues) -> String {
+    start_orchestrator_with(Orchestrator::new(queues)).await
+}
+
+/// Start the stub orchestrator with its state, returning its URL.
+async fn start_orchestrator_with(orchestrator: Orchestrator) -> String {
+    let app = Router::new()
+        .route("/queue/metrics", get(queue_metrics_of))
+        .route("/queue/pending", get(pending_fragments))
+        .route("/workers", get(list_workers))
+        .route("/workers/{id}/busy", get(worker_busy))
+        .with_state(orchestrator);
+    serve(app).await
+}
+
+async fn serve(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    url
+}
+
+fn metrics(pending: i64, running: i64, active: i64) -> Value {
+    json!({
+        "pending_fragments": pending,
+        "running_fragments": running,
+        "active_workers": active
+    })
+}
+
+fn pool_manifest(name: &str, tenant_id: Uuid, group: &str, deployment: &str) -> Value {
+    json!({
+        "apiVersion": "vulcan.dev/v1alpha1",
+        "kind": "WorkerPool",
+        "metadata": { "name": name, "namespace": "ci" },
+        "spec": {
+            "tenantId": tenant_id,
+            "machineGroup": group,
+            "deployment": deployment,
+            "maxReplicas": 4
+        }
+    })
+}
+
+/// Pools of two tenants, one authenticated by a token secret and one by the
+/// controller's own token, and a third whose deployment does not exist.
+fn pools() -> Vec<Value> {
+    let mut gpu = pool_manifest("gpu", TENANT_A, "gpu", "vulcan-worker-gpu");
+    gpu["spec"]["controllerTokenSecret"] = json!({ "name": "tenant-a", "key": "token" });
+    vec![
+        gpu,
+        pool_manifest("cpu", TENANT_B, "default", "vulcan-worker-cpu"),
+        pool_manifest("missing", TENANT_C, "default", "vulcan-worker-missing"),
+    ]
+}
+
+fn queues(cpu_metrics: Value) -> Queues {
+    Arc::new(HashMap::from([
+        (TENANT_A, ("token-a", metrics(6, 1, 1))),
+        (TENANT_B, ("token-b", cpu_metrics)),
+        (TENANT_C, ("token-b", metrics(1, 0, 0))),
+    ]))
+}
+
+fn config(orchestrator_url: String, pools: PoolSource) -> Config {
+    Config {
+        orchestrator_url,
+        controller_token: Some("token-b".to_string()),
+        pools,
+        backend: Backend::Kubernetes,
+        tls: TlsConfig::default(),
+        poll_interval_seconds: 30,
+        metrics_port: None,
+        history_file: None,
+    }
+}
+
+#[tokio::test]
+async fn test_reconciles_worker_pool_resources_independently() {
+    let cluster = SharedCluster::default();
+    {
+        let mut cluster = cluster.lock().unwrap();
+        cluster.pools = pools();
+        cluster.deployments = HashMap::from([
+            ("vulcan-worker-gpu".to_string(), 1),
+            ("vulcan-worker-cpu".to_string(), 5),
+        ]);
+    }
+    let kube = start_cluster(cluster.clone()).await;
+    let orchestrator_url = start_orchestrator(queues(metrics(0, 2, 3))).await;
+
+    let config = config(orchestrator_url, PoolSource::Crd { namespace: None });
+    let mut controller = Controller::with_client(config, kube).unwrap();
+    controller.reconcile().await.unwrap();
+
+    let Cluster {
+        scaled, statuses, ..
+    } = std::mem::take(&mut *cluster.lock().unwrap());
+    // gpu: 7 fragments capped at 4 replicas; cpu: down to its 2 busy workers
+    assert_eq!(
+        scaled,
+        vec![
+            ("vulcan-worker-gpu".to_string(), 4),
+            ("vulcan-worker-cpu".to_string(), 2)
+        ]
+    );
+
+    let statuses: HashMap<_, _> = statuses.into_iter().collect();
+    assert_eq!(statuses.len(), 3);
+
+    let gpu = &statuses["gpu"];
+    assert_eq!(gpu["currentReplicas"], 4);
+    assert_eq!(gpu["desiredReplicas"], 4);
+    assert_eq!(gpu["pendingFragments"], 6);
+    assert!(gpu["lastScaleTime"].is_string());
+    assert!(gpu["error"].is_null());
+
+    let cpu = &statuses["cpu"];
+    assert_eq!(cpu["currentReplicas"], 2);
+    assert_eq!(cpu["busyWorkers"], 2);
+
+    let missing = &statuses["missing"];
+    let error = missing["error"].as_str().unwrap();
+    assert!(error.contains("vulcan-worker-missing not found"), "{error}");
+}
+
+#[tokio::test]
+async fn test_pools_keep_their_own_cooldown() {
+    let cluster = SharedCluster::default();
+    {
+        let mut cluster = cluster.lock().unwrap();
+        cluster.pools = pools();
+        cluster.deployments = HashMap::from([
+            ("vulcan-worker-gpu".to_string(), 4),
+            ("vulcan-worker-cpu".to_string(), 5),
+        ]);
+    }
+    let kube = start_cluster(cluster.clone()).await;
+    let orchestrator_url = start_orchestrator(queues(metrics(0, 2, 3))).await;
+
+    let config = config(orchestrator_url, PoolSource::Crd { namespace: None });
+    let mut controller = Controller::with_client(config, kube).unwrap();
+    controller.reconcile().await.unwrap();
+
+    // The cpu pool scaled down; a second scale-down waits for its cooldown, while
+    // the gpu pool scales up again
+    cluster.lock().unwrap().deployments = HashMap::from([
+        ("vulcan-worker-gpu".to_string(), 1),
+        ("vulcan-worker-cpu".to_string(), 3),
+    ]);
+    controller.reconcile().await.unwrap();
+
+    let scaled = std::mem::take(&mut cluster.lock().unwrap().scaled);
+    assert_eq!(
+        scaled,
+        vec![
+            ("vulcan-worker-cpu".to_string(), 2),
+            ("vulcan-worker-gpu".to_string(), 4)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_reconciles_pools_from_file() {
+    let cluster = SharedCluster::default();
+    cluster.lock().unwrap().deployments = HashMap::from([
+        ("vulcan-worker-gpu".to_string(), 1),
+        ("vulcan-worker-cpu".to_string(), 5),
+    ]);
+    let kube = start_cluster(cluster.clone()).await;
+    let orchestrator_url = start_orchestrator(queues(metrics(3, 0, 0))).await;
+
+    let path = std::env::temp_dir().join(format!("worker-pools-{}.yaml", Uuid::new_v4()));
+    let yaml = pools()[..2]
+        .iter()
+        .map(|pool| serde_yaml::to_string(pool).unwrap())
+        .collect::<Vec<_>>()
+        .join("---\n");
+    std::fs::write(&path, yaml).unwrap();
+
+    let config = config(orchestrator_url, PoolSource::File(path.clone()));
+    let mut controller = Controller::with_client(config, kube).unwrap();
+    controller.reconcile().await.unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    let Cluster {
+        scaled, statuses, ..
+    } = std::mem::take(&mut *cluster.lock().unwrap());
+    assert_eq!(
+        scaled,
+        vec![
+            ("vulcan-worker-gpu".to_string(), 4),
+            ("vulcan-worker-cpu".to_string(), 3)
+        ]
+    );
+    // Pools from a file have no resource to report status on
+    assert!(statuses.is_empty());
+}
+
+fn job(name: &str, finished: bool) -> Value {
+    let conditions = if finished {
+        json!([{ "type": "Complete", "status": "True" }])
+    } else {
+        json!([])
+    };
+    json!({
+        "apiVersion": "batch/v1",
+        "kind": "Job",
+        "metadata": { "name": name, "namespace": "ci", "labels": { "vulcan.dev/pool": "batch" } },
+        "status": { "conditions": conditions }
+    })
+}
+
+fn pending_fragment(fragment_id: Uuid, attempt: i32) -> Value {
+    json!({ "fragment_id": fragment_id, "chain_id": Uuid::nil(), "attempt": attempt })
+}
+
+#[tokio::test]
+async fn test_launches_a_job_per_pending_fragment_up_to_the_limit() {
+    let fragments: Vec<Uuid> = (1..=4).map(Uuid::from_u128).collect();
+    let cluster = SharedCluster::default();
+    {
+        let mut pool = pool_manifest("batch", TENANT_B, "heavy", "vulcan-worker-heavy");
+        pool["spec"]["mode"] = json!("jobs");
+        pool["spec"]["maxReplicas"] = json!(2);
+        pool["spec"]["labels"] = json!(["docker", "os=linux"]);
+        pool["spec"]["jobTtlSecondsAfterFinished"] = json!(60);
+
+        let mut cluster = cluster.lock().unwrap();
+        cluster.pools = vec![pool];
+        cluster.deployments = HashMap::from([("vulcan-worker-heavy".to_string(), 0)]);
+        cluster.jobs = vec![
+            // Launched, its worker has not claimed the fragment yet
+            job(&format!("fragment-{}-1", fragments[0]), false),
+            // An earlier attempt of a fragment being retried
+            job(&format!("fragment-{}-1", fragments[1]), true),
+        ];
+    }
+    let kube = start_cluster(cluster.clone()).await;
+    let orchestrator = Orchestrator::new(queues(metrics(0, 0, 0)));
+    let pending = orchestrator.pending.clone();
+    pending.lock().unwrap().fragments = vec![
+        pending_fragment(fragments[0], 1),
+        pending_fragment(fragments[1], 2),
+        pending_fragment(fragments[2], 1),
+        pending_fragment(fragments[3], 1),
+    ];
+    let orchestrator_url = start_orchestrator_with(orchestrator).await;
+
+    let config = config(orchestrator_url, PoolSource::Crd { namespace: None });
+    let mut controller = Controller::with_client(config, kube).unwrap();
+    controller.reconcile().await.unwrap();
+
+    let query = pending.lock().unwrap().queries.pop().unwrap();
+    assert_eq!(query["tenant_id"], TENANT_B.to_string());
+    assert_eq!(query["machine_group"], "heavy");
+    assert_eq!(query["labels"], "docker,os=linux");
+    assert_eq!(query["limit"], "4");
+
+    let Cluster {
+        scaled,
+        statuses,
+        created_jobs,
+        ..
+    } = std::mem::take(&mut *cluster.lock().unwrap());
+    assert!(scaled.is_empty());
+
+    // One job is running, so only the retried fragment gets a job within the limit
+    assert_eq!(created_jobs.len(), 1);
+    let created = &created_jobs[0];
+    assert_eq!(
+        created["metadata"]["name"],
+        format!("fragment-{}-2", fragments[1])
+    );
+    assert_eq!(
+        created["metadata"]["labels"]["vulcan.dev/fragment-id"],
+        fragments[1].to_string()
+    );
+    assert_eq!(created["spec"]["backoffLimit"], 0);
+    assert_eq!(created["spec"]["ttlSecondsAfterFinished"], 60);
+
+    let template = &created["spec"]["template"];
+    assert_eq!(template["metadata"]["labels"]["app"], "vulcan-worker-heavy");
+    assert_eq!(template["metadata"]["labels"]["vulcan.dev/pool"], "batch");
+    assert_eq!(template["spec"]["restartPolicy"], "Never");
+    assert_eq!(
+        template["spec"]["containers"][0]["env"],
+        json!([{ "name": "FRAGMENT_ID", "value": fragments[1].to_string() }])
+    );
+
+    let status = &statuses[0].1;
+    assert_eq!(status["currentReplicas"], 2);
+    assert_eq!(status["desiredReplicas"], 2);
+    assert_eq!(status["pendingFragments"], 4);
+    assert!(status["error"].is_null());
+}
+
+/// A cpu pool of five pods: two busy, one idle, one draining and one whose worker
+/// has not registered.
+async fn start_busy_pool(busy_unavailable: bool) -> (SharedCluster, Controller) {
+    let pod_names: Vec<String> = (0..5).map(|i| format!("vulcan-worker-cpu-{i}")).collect();
+    let cluster = SharedCluster::default();
+    {
+        let mut cluster = cluster.lock().unwrap();
+        cluster.pools = vec![pool_manifest(
+            "cpu",
+            TENANT_B,
+            "default",
+            "vulcan-worker-cpu",
+        )];
+        cluster.deployments = HashMap::from([("vulcan-worker-cpu".to_string(), 5)]);
+        cluster.pods = HashMap::from([("vulcan-worker-cpu".to_string(), pod_names.clone())]);
+    }
+    let kube = start_cluster(cluster.clone()).await;
+
+    // (busy, draining)
+    let activity = [(true, false), (true, false), (false, false), (false, true)];
+    let workers = pod_names
+        .iter()
+        .zip(activity)
+        .map(|(name, (busy, draining))| StubWorker {
+            id: Uuid::new_v4(),
+            name: name.clone(),
+            busy,
+            draining,
+        })
+        .collect();
+    let orchestrator = Orchestrator {
+        workers: Arc::new(workers),
+        busy_unavailable,
+        ..Orchestrator::new(queues(metrics(0, 1, 5)))
+    };
+    let orchestrator_url = start_orchestrator_with(orchestrator).await;
+
+    let config = config(orchestrator_url, PoolSource::Crd { namespace: None });
+    (cluster, Controller::with_client(config, kube).unwrap())
+}
+
+#[tokio::test]
+async fn test_scale_down_removes_idle_pods_and_keeps_busy_ones() {
+    let (cluster, mut controller) = start_busy_pool(false).await;
+    controller.reconcile().await.unwrap();
+
+    let Cluster {
+        scaled,
+        deletion_costs,
+        ..
+    } = std::mem::take(&mut *cluster.lock().unwrap());
+    // One running fragment asks for one replica, but two pods are busy
+    assert_eq!(scaled, vec![("vulcan-worker-cpu".to_string(), 2)]);
+
+    let expected = [
+        ("vulcan-worker-cpu-0", "1000"),
+        ("vulcan-worker-cpu-1", "1000"),
+        ("vulcan-worker-cpu-2", "-500"),
+        ("vulcan-worker-cpu-3", "-1000"),
+        ("vulcan-worker-cpu-4", "-500"),
+    ]
+    .into_iter()
+    .map(|(pod, cost)| (pod.to_string(), cost.to_string()))
+    .collect::<HashMap<_, _>>();
+    assert_eq!(deletion_costs, expected);
+}
+
+#[tokio::test]
+async fn test_scale_down_is_held_when_busy_pods_are_unknown() {
+    let (cluster, mut controller) = start_busy_pool(true).await;
+    controller.reconcile().await.unwrap();
+
+    let Cluster {
+        scaled,
+        deletion_costs,
+        ..
+    } = std::mem::take(&mut *cluster.lock().unwrap());
+    assert!(scaled.is_empty());
+    assert!(deletion_costs.is_empty());
+}
+
+/// Reconcile an idle cpu pool of one replica, returning its scale patches and status.
+async fn reconcile_idle_pool(pool: Value, history_file: Option<PathBuf>) -> (Vec<i32>, Value) {
+    let cluster = SharedCluster::default();
+    {
+        let mut cluster = cluster.lock().unwrap();
+        cluster.pools = vec![pool];
+        cluster.deployments = HashMap::from([("vulcan-worker-cpu".to_string(), 1)]);
+    }
+    let kube = start_cluster(cluster.clone()).await;
+    let orchestrator_url = start_orchestrator(queues(metrics(0, 0, 0))).await;
+
+    let config = Config {
+        history_file,
+        ..config(orchestrator_url, PoolSource::Crd { namespace: None })
+    };
+    let mut controller = Controller::with_client(config, kube).unwrap();
+    controller.reconcile().await.unwrap();
+
+    let Cluster {
+        scaled, statuses, ..
+    } = std::mem::take(&mut *cluster.lock().unwrap());
+    let replicas = scaled.into_iter().map(|(_, replicas)| replicas).collect();
+    (replicas, statuses[0].1.clone())
+}
+
+#[tokio::test]
+async fn test_scheduled_minimum_keeps_replicas_warm() {
+    let mut pool = pool_manifest("cpu", TENANT_B, "default", "vulcan-worker-cpu");
+    pool["spec"]["schedules"] = json!([
+        // Always matching, capped at maxReplicas
+        { "schedule": "* * * * *", "minReplicas": 3 },
+        { "schedule": "* * * * *", "minReplicas": 6 },
+        // Never matching (February has no 31st)
+        { "schedule": "* * 31 2 *", "minReplicas": 8 },
+    ]);
+
+    let (scaled, status) = reconcile_idle_pool(pool, None).await;
+    assert_eq!(scaled, vec![4]);
+    assert_eq!(status["scheduledMinReplicas"], 6);
+    assert_eq!(status["desiredReplicas"], 4);
+
+    let mut pool = pool_manifest("cpu", TENANT_B, "default", "vulcan-worker-cpu");
+    pool["spec"]["schedules"] = json!([{ "schedule": "* 25 * * *", "minReplicas": 3 }]);
+    let (scaled, status) = reconcile_idle_pool(pool, None).await;
+    assert!(scaled.is_empty());
+    let error = status["error"].as_str().unwrap();
+    assert!(error.contains("25 is not within 0-23"), "{error}");
+}
+
+#[tokio::test]
+async fn test_predictive_scaling_pre_warms_from_history() {
+    let path = std::env::temp_dir().join(format!("scaling-history-{}.json", Uuid::new_v4()));
+    // Three fragments arrived five minutes from now yesterday
+    let minute = chrono::Utc::now().timestamp() / 60;
+    let history = json!({ "ci/cpu": [[minute - 24 * 60 + 5, 3]] });
+    std::fs::write(&path, history.to_string()).unwrap();
+
+    let mut pool = pool_manifest("cpu", TENANT_B, "default", "vulcan-worker-cpu");
+    pool["spec"]["predictive"] = json!({ "lookaheadSeconds": 600 });
+
+    let (scaled, status) = reconcile_idle_pool(pool, Some(path.clone())).await;
+    assert_eq!(scaled, vec![3]);
+    assert_eq!(status["predictedFragments"], 3);
+
+    // The history is saved with the work of this reconciliation
+    let saved: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(saved["ci/cpu"].as_array().unwrap().len(), 2);
+    assert_eq!(saved["ci/cpu"][1][1], 0);
+}
+
+/// The `WorkerPool` CRD manifest in `k8s/` matches the resource definition.
+///
+/// Regenerate it with `UPDATE_CRD=1 cargo test -p vulcan-worker-controller`.
+#[test]
+fn test_crd_manifest_is_up_to_date() {
+    let path =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../../k8s/05-workerpool-crd.yaml");
+    let crd = serde_yaml::to_string(&WorkerPool::crd()).unwrap();
+
+    if std::env::var_os("UPDATE_CRD").is_some() {
+        std::fs::write(&path, &crd).unwrap();
+    }
+    let manifest = std::fs::read_to_string(&path).unwrap_or_default();
+    assert!(manifest == crd, "{} is out of date", path.display());
+}
@@ -0,0 +1,264 @@
+//! Integration tests for scaling a pool of worker processes against a stub
+//! orchestrator, without Kubernetes.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::{Path as UrlPath, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use vulcan_worker_controller::backend::{ProcessBackend, ScalingBackend};
+use vulcan_worker_controller::config::{Backend, PoolSource, TlsConfig};
+use vulcan_worker_controller::pool::WorkerPool;
+use vulcan_worker_controller::{Config, Controller};
+
+const TENANT: Uuid = Uuid::from_u128(0xa);
+
+/// State of the stub orchestrator: queue metrics, and the busy workers by name.
+#[derive(Default)]
+struct Orchestrator {
+    metrics: Value,
+    busy: Vec<String>,
+    /// Registered workers, by ID.
+    workers: HashMap<Uuid, String>,
+}
+
+type SharedOrchestrator = Arc<Mutex<Orchestrator>>;
+
+async fn queue_metrics(State(orchestrator): State<SharedOrchestrator>) -> Json<Value> {
+    Json(orchestrator.lock().unwrap().metrics.clone())
+}
+
+/// The stub registers every worker the controller asks about by its process name.
+async fn list_workers(State(orchestrator): State<SharedOrchestrator>) -> Json<Value> {
+    let workers: Vec<Value> = orchestrator
+        .lock()
+        .unwrap()
+        .workers
+        .iter()
+        .map(|(id, name)| json!({ "worker_id": id, "name": name, "status": "Active" }))
+        .collect();
+    Json(Value::from(workers))
+}
+
+async fn worker_busy(
+    State(orchestrator): State<SharedOrchestrator>,
+    UrlPath(worker_id): UrlPath<Uuid>,
+) -> Response {
+    let busy = {
+        let orchestrator = orchestrator.lock().unwrap();
+        let Some(name) = orchestrator.workers.get(&worker_id) else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        orchestrator.busy.contains(name)
+    };
+    Json(json!({ "busy": busy, "fragment_id": null, "fragment_ids": [], "draining": false }))
+        .into_response()
+}
+
+async fn start_orchestrator(orchestrator: SharedOrchestrator) -> String {
+    let app = Router::new()
+        .route("/queue/metrics", get(queue_metrics))
+        .route("/workers", get(list_workers))
+        .route("/workers/{id}/busy", get(worker_busy))
+        .with_state(orchestrator);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    url
+}
+
+fn metrics(pending: i64, running: i64, active: i64) -> Value {
+    json!({
+        "pending_fragments": pending,
+        "running_fragments": running,
+        "active_workers": active
+    })
+}
+
+/// Write a worker script recording its environment in `dir` when it starts, and
+/// that it stopped when it gets `SIGTERM`.
+///
+/// The trap is installed before the environment is recorded, since tests stop the
+/// workers once it is, and the record appears at once, complete.
+fn worker_script(dir: &Path) -> PathBuf {
+    let script = dir.join("worker.sh");
+    let dir = dir.display();
+    let body = format!(
+        "#!/bin/sh\n\
+         trap 'touch {dir}/$WORKER_NAME.stopped; exit 0' TERM\n\
+         out={dir}/$WORKER_NAME\n\
+         echo \"$TENANT_ID $WORKER_GROUP $WORKER_LABELS $CONTROLLER_TOKEN\" > $out.tmp\n\
+         echo \"$SANDBOX_SCRATCH_DIR $CHECKOUT_CACHE_DIR\" >> $out.tmp\n\
+         mv $out.tmp $out\n\
+         while :; do sleep 0.1; done\n"
+    );
+    std::fs::write(&script, body).unwrap();
+    std::fs::set_permissions(&script, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+    script
+}
+
+/// Wait until a file exists, for up to 30 seconds, since processes start slowly
+/// on a loaded machine.
+async fn wait_for(path: &Path) {
+    for _ in 0..600 {
+        if path.exists() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("{} was not written", path.display());
+}
+
+fn pool() -> WorkerPool {
+    serde_json::from_value(json!({
+        "apiVersion": "vulcan.dev/v1alpha1",
+        "kind": "WorkerPool",
+        "metadata": { "name": "vm" },
+        "spec": {
+            "tenantId": TENANT,
+            "machineGroup": "default",
+            "deployment": "vulcan-worker",
+            "labels": ["docker"],
+            "maxReplicas": 4
+        }
+    }))
+    .unwrap()
+}
+
+fn config(orchestrator_url: String, pools: PoolSource, command: &Path, dir: &Path) -> Config {
+    Config {
+        orchestrator_url,
+        controller_token: Some("token".to_string()),
+        pools,
+        backend: Backend::Process {
+            command: command.to_path_buf(),
+            state_dir: dir.join("state"),
+        },
+        tls: TlsConfig::default(),
+        poll_interval_seconds: 30,
+        metrics_port: None,
+        history_file: None,
+    }
+}
+
+#[tokio::test]
+async fn test_scales_worker_processes() {
+    let dir = std::env::temp_dir().join(format!("worker-processes-{}", Uuid::new_v4()));
+    std::fs::create_dir(&dir).unwrap();
+    let command = worker_script(&dir);
+
+    let orchestrator = SharedOrchestrator::default();
+    orchestrator.lock().unwrap().metrics = metrics(3, 0, 0);
+    let url = start_orchestrator(orchestrator.clone()).await;
+
+    let pools = PoolSource::Env(Box::new(pool()));
+    let state_dir = dir.join("state");
+    let backend = ProcessBackend::new(command.clone(), state_dir.clone()).unwrap();
+    let config = config(url, pools, &command, &dir);
+    let mut controller = Controller::with_backend(config, backend).unwrap();
+    controller.reconcile().await.unwrap();
+
+    let pool = pool();
+    let names = controller.backend().instances(&pool).await.unwrap();
+    let run = names[0]
+        .strip_prefix("vulcan-worker-")
+        .and_then(|name| name.strip_suffix("-0"))
+        .unwrap();
+    assert_eq!(
+        names,
+        ["0", "1", "2"].map(|n| format!("vulcan-worker-{run}-{n}"))
+    );
+    for name in &names {
+        wait_for(&dir.join(name)).await;
+    }
+    // Workers get the pool's settings and their own directories, but not the
+    // controller's token
+    let env = std::fs::read_to_string(dir.join(&names[0])).unwrap();
+    let lines: Vec<_> = env.lines().map(str::trim).collect();
+    assert_eq!(
+        lines,
+        [
+            format!("{TENANT} default docker"),
+            format!("/scratch/{0} /cache/git/{0}", names[0]),
+        ]
+    );
+
+    // One fragment is left, running on the second worker
+    {
+        let mut orchestrator = orchestrator.lock().unwrap();
+        orchestrator.metrics = metrics(0, 1, 3);
+        orchestrator.busy = vec![names[1].clone()];
+        orchestrator.workers = names
+            .iter()
+            .map(|name| (Uuid::new_v4(), name.clone()))
+            .collect();
+    }
+    controller.reconcile().await.unwrap();
+
+    let backend = controller.backend();
+    assert_eq!(backend.replicas(&pool).await.unwrap(), 1);
+    assert_eq!(backend.instances(&pool).await.unwrap(), [names[1].clone()]);
+    let stopped = |name: &str| dir.join(format!("{name}.stopped"));
+    wait_for(&stopped(&names[0])).await;
+    wait_for(&stopped(&names[2])).await;
+    assert!(!stopped(&names[1]).exists());
+
+    // A restarted controller adopts the worker still running, and doesn't reuse the
+    // names of earlier workers for new ones
+    let restarted = ProcessBackend::new(command.clone(), state_dir.clone()).unwrap();
+    assert_eq!(restarted.replicas(&pool).await.unwrap(), 1);
+    restarted.scale(&pool, 2).await.unwrap();
+    let restarted_names = restarted.instances(&pool).await.unwrap();
+    assert_eq!(restarted_names[0], names[1]);
+    assert!(!names.contains(&restarted_names[1]), "{restarted_names:?}");
+    wait_for(&dir.join(&restarted_names[1])).await;
+
+    restarted.scale(&pool, 0).await.unwrap();
+    wait_for(&stopped(&names[1])).await;
+    wait_for(&stopped(&restarted_names[1])).await;
+
+    // Pid files of workers that exited are removed
+    for _ in 0..600 {
+        restarted.replicas(&pool).await.unwrap();
+        if std::fs::read_dir(&state_dir).unwrap().next().is_none() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert!(std::fs::read_dir(&state_dir).unwrap().next().is_none());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_process_backend_needs_static_pools_and_a_command() {
+    let dir = std::env::temp_dir().join(format!("worker-processes-{}", Uuid::new_v4()));
+    let command = PathBuf::from("/nonexistent/vulcan-worker");
+    let backend = ProcessBackend::new(command.clone(), dir.join("state")).unwrap();
+
+    let pools = PoolSource::Crd { namespace: None };
+    let config = config("http://localhost".to_string(), pools, &command, &dir);
+    let error = Controller::with_backend(config, backend).err().unwrap();
+    assert!(
+        error.to_string().contains("need the Kubernetes backend"),
+        "{error}"
+    );
+
+    let backend = ProcessBackend::new(command, dir.join("state")).unwrap();
+    let error = backend.verify(&pool()).await.unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("/nonexistent/vulcan-worker not found"),
+        "{error}"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,265 @@
+//! Benchmark comparing work-claiming strategies against a local Postgres.
+//!
+//! Seeds a queue of sequential chains for a fresh tenant, then drains it with
+//! concurrent workers that claim and immediately complete fragments, once with the
+//! previous per-candidate strategy (load pending, then query siblings and parent
+//! and try to claim each candidate) and once with the single-query claim.
+//!
+//! ```bash
+//! DATABASE_URL=postgres://... cargo bench -p vulcan-worker-orchestrator --bench claim
+//! ```
+//!
+//! Sizes are configurable with `CLAIM_BENCH_CHAINS`, `CLAIM_BENCH_FRAGMENTS` (per
+//! chain) and `CLAIM_BENCH_WORKERS`.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use diesel::{Connection, PgConnection};
+use uuid::Uuid;
+
+use vulcan_core::models::fragment::Fragment;
+use vulcan_core::{
+    ChainRepository, FragmentRepository, NewChain, NewFragment, NewWorker, PgChainRepository,
+    PgFragmentRepository, PgWorkerRepository, RepositoryError, Worker, WorkerRepository,
+    establish_connection,
+};
+
+/// A way of claiming the next fragment for a worker.
+#[derive(Debug, Clone, Copy)]
+enum Strategy {
+    /// Load all pending candidates, then check dependencies and claim one by one.
+    PerCandidate,
+    /// Evaluate eligibility and claim in one statement.
+    SingleQuery,
+}
+
+impl Strategy {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::PerCandidate => "per-candidate",
+            Self::SingleQuery => "single-query",
+        }
+    }
+
+    fn claim(self, conn: &mut PgConnection, worker: &Worker) -> Option<Fragment> {
+        let mut repo = PgFragmentRepository::new(conn);
+        match self {
+            Self::PerCandidate => claim_per_candidate(&mut repo, worker),
+            Self::SingleQuery => repo
+                .claim_next_ready(
+                    worker.id,
+                    worker.tenant_id,
+                    worker.machine_group.as_deref(),
+                    &worker.labels,
+                )
+                .unwrap(),
+        }
+    }
+}
+
+/// The claim loop the scheduler used before claiming moved into a single query.
+fn claim_per_candidate(repo: &mut PgFragmentRepository<'_>, worker: &Worker) -> Option<Fragment> {
+    let pending = repo
+        .find_pending_matching(
+            worker.tenant_id,
+            worker.machine_group.as_deref(),
+            Some(&worker.labels),
+            i64::MAX,
+        )
+        .unwrap();
+
+    for fragment in pending {
+        let siblings = repo
+            .find_siblings(fragment.chain_id, fragment.parent_fragment_id)
+            .unwrap();
+        let is_parallel = fragment.parent_fragment_id.is_some_and(|parent_id| {
+            repo.find_by_id(parent_id)
+                .unwrap()
+                .is_some_and(|p| p.is_parallel)
+        });
+
+        let ready = is_parallel
+            || siblings
+                .iter()
+                .all(|s| s.sequence >= fragment.sequence || s.status.is_terminal());
+        if !ready {
+            continue;
+        }
+
+        if let Some(claimed) = repo
+            .try_claim(fragment.id, worker.id, worker.tenant_id)
+            .unwrap()
+        {
+            return Some(claimed);
+        }
+    }
+
+    None
+}
+
+/// Benchmark sizes.
+struct Sizes {
+    chains: usize,
+    fragments_per_chain: usize,
+    workers: usize,
+}
+
+impl Sizes {
+    fn from_env() -> Self {
+        let var = |name: &str, default: usize| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        Self {
+            chains: var("CLAIM_BENCH_CHAINS", 200),
+            fragments_per_chain: var("CLAIM_BENCH_FRAGMENTS", 10),
+            workers: var("CLAIM_BENCH_WORKERS", 8),
+        }
+    }
+
+    const fn total(&self) -> usize {
+        self.chains * self.fragments_per_chain
+    }
+}
+
+/// Seed a fresh tenant with sequential chains and workers.
+fn seed(conn: &mut PgConnection, sizes: &Sizes) -> (Vec<Uuid>, Vec<Worker>) {
+    let tenant_id = Uuid::new_v4();
+
+    let chain_ids: Vec<Uuid> = (0..sizes.chains)
+        .map(|_| {
+            let chain = PgChainRepository::new(conn)
+                .create(NewChain::new(tenant_id))
+                .unwrap();
+            let fragments = (0..sizes.fragments_per_chain)
+                .map(|sequence| {
+                    let sequence = i32::try_from(sequence).unwrap();
+                    NewFragment::inline(chain.id, sequence, "true".to_string())
+                })
+                .collect();
+            let mut repo = PgFragmentRepository::new(conn);
+            repo.create_many(fragments).unwrap();
+            repo.activate_chain(chain.id).unwrap();
+            chain.id
+        })
+        .collect();
+
+    let workers = (0..sizes.workers)
+        .map(|_| {
+            PgWorkerRepository::new(conn)
+                .create(NewWorker::new(tenant_id))
+                .unwrap()
+        })
+        .collect();
+
+    (chain_ids, workers)
+}
+
+/// Complete a fragment and release its successors, as reporting a result does.
+fn complete(conn: &mut PgConnection, fragment_id: Uuid) {
+    conn.transaction::<_, RepositoryError, _>(|conn| {
+        let mut repo = PgFragmentRepository::new(conn);
+        repo.complete_execution(fragment_id, 0)?;
+        repo.release_successors(fragment_id)
+    })
+    .unwrap();
+}
+
+/// Drain the queue with one thread per worker, returning the wall time and the
+/// per-claim latencies.
+fn drain(strategy: Strategy, workers: &[Worker], total: usize) -> (Duration, Vec<Duration>) {
+    let completed = AtomicUsize::new(0);
+    let started = Instant::now();
+
+    let latencies = std::thread::scope(|scope| {
+        let handles: Vec<_> = workers
+            .iter()
+            .map(|worker| {
+                let completed = &completed;
+                scope.spawn(move || {
+                    let mut conn = establish_connection();
+                    let mut latencies = Vec::new();
+
+                    while completed.load(Ordering::Relaxed) < total {
+                        let claim_started = Instant::now();
+                        let claimed = strategy.claim(&mut conn, worker);
+                        latencies.push(claim_started.elapsed());
+
+                        match claimed {
+                            Some(fragment) => {
+                                complete(&mut conn, fragment.id);
+                                completed.fetch_add(1, Ordering::Relaxed);
+                            },
+                            // Successors become ready as other workers complete
+                            None => std::thread::sleep(Duration::from_millis(1)),
+                        }
+                    }
+
+                    latencies
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    (started.elapsed(), latencies)
+}
+
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    let index = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[index]
+}
+
+fn main() {
+    dotenvy::dotenv().ok();
+    let sizes = Sizes::from_env();
+    let mut conn = establish_connection();
+
+    println!(
+        "Draining {} chains x {} fragments with {} workers",
+        sizes.chains, sizes.fragments_per_chain, sizes.workers
+    );
+    println!(
+        "{:<14} {:>10} {:>12} {:>10} {:>10} {:>8}",
+        "strategy", "wall (ms)", "claims/sec", "p50 (ms)", "p99 (ms)", "calls"
+    );
+
+    for strategy in [Strategy::PerCandidate, Strategy::SingleQuery] {
+        let (chain_ids, workers) = seed(&mut conn, &sizes);
+        let (wall, mut latencies) = drain(strategy, &workers, sizes.total());
+        latencies.sort();
+
+        #[allow(clippy::cast_precision_loss)]
+        let throughput = sizes.total() as f64 / wall.as_secs_f64();
+        println!(
+            "{:<14} {:>10.1} {:>12.1} {:>10.3} {:>10.3} {:>8}",
+            strategy.name(),
+            wall.as_secs_f64() * 1000.0,
+            throughput,
+            percentile(&latencies, 0.5).as_secs_f64() * 1000.0,
+            percentile(&latencies, 0.99).as_secs_f64() * 1000.0,
+            latencies.len(),
+        );
+
+        for worker in &workers {
+            PgWorkerRepository::new(&mut conn)
+                .delete(worker.id)
+                .unwrap();
+        }
+        for chain_id in chain_ids {
+            PgChainRepository::new(&mut conn).delete(chain_id).unwrap();
+        }
+    }
+}
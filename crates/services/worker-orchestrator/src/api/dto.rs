@@ -1,6 +1,6 @@
 //! Data transfer objects for the API.
 
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -11,10 +11,18 @@ use uuid::Uuid;
 /// Request to register a new worker.
 #[derive(Debug, Deserialize)]
 pub struct RegisterWorkerRequest {
-    /// Tenant ID the worker belongs to.
+    /// Tenant ID the worker belongs to; overridden by its client certificate.
     pub tenant_id: Uuid,
-    /// Machine group this worker belongs to (optional).
+    /// Machine group this worker belongs to (optional); overridden by its client
+    /// certificate if that names one.
     pub machine_group: Option<String>,
+    /// Maximum number of fragments the worker executes at once (default: 1).
+    pub max_concurrency: Option<i32>,
+    /// Capability labels used to route fragments to this worker.
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// Name of the worker's instance, e.g. its pod (optional).
+    pub name: Option<String>,
 }
 
 /// Response after registering a worker.
@@ -24,6 +32,19 @@ pub struct RegisterWorkerResponse {
     pub worker_id: Uuid,
     /// Current status of the worker.
     pub status: String,
+    /// Token authenticating the worker on subsequent requests.
+    pub token: String,
+    /// When the token expires; heartbeats return a renewed one.
+    pub token_expires_at: DateTime<Utc>,
+}
+
+/// A renewed worker token.
+#[derive(Debug, Serialize)]
+pub struct WorkerTokenResponse {
+    /// Token authenticating the worker.
+    pub token: String,
+    /// When the token expires.
+    pub token_expires_at: DateTime<Utc>,
 }
 
 // ============================================================================
@@ -35,6 +56,13 @@ pub struct RegisterWorkerResponse {
 pub struct HeartbeatRequest {
     /// Worker ID sending the heartbeat.
     pub worker_id: Uuid,
+    /// Fragments the worker is currently executing.
+    ///
+    /// When present, the worker's in-flight assignments are reconciled against it.
+    pub fragment_ids: Option<Vec<Uuid>>,
+    /// Whether the worker is draining, e.g. because it was asked to terminate.
+    #[serde(default)]
+    pub draining: bool,
 }
 
 /// Response after a heartbeat.
@@ -44,6 +72,54 @@ pub struct HeartbeatResponse {
     pub status: String,
     /// Server timestamp.
     pub timestamp: NaiveDateTime,
+    /// Renewed worker token.
+    pub token: String,
+    /// When the renewed token expires.
+    pub token_expires_at: DateTime<Utc>,
+    /// Whether the worker should drain: finish its in-flight fragments and exit.
+    pub draining: bool,
+}
+
+// ============================================================================
+// Drain and Deregistration
+// ============================================================================
+
+/// Response after asking a worker to drain.
+#[derive(Debug, Serialize)]
+pub struct DrainWorkerResponse {
+    /// The draining worker.
+    pub worker_id: Uuid,
+    /// Status of the worker.
+    pub status: String,
+}
+
+/// Request to deregister a worker.
+#[derive(Debug, Deserialize)]
+pub struct DeregisterWorkerRequest {
+    /// Worker ID to deregister.
+    pub worker_id: Uuid,
+}
+
+/// A live worker, as listed for a worker controller.
+#[derive(Debug, Serialize)]
+pub struct WorkerSummary {
+    /// The worker's ID.
+    pub worker_id: Uuid,
+    /// Name of the worker's instance, e.g. its pod.
+    pub name: Option<String>,
+    /// Status of the worker: `Active` or `Draining`.
+    pub status: String,
+}
+
+/// A draining worker, as listed for a worker controller.
+#[derive(Debug, Serialize)]
+pub struct DrainingWorker {
+    /// The worker's ID.
+    pub worker_id: Uuid,
+    /// Name of the worker's instance, e.g. its pod.
+    pub name: Option<String>,
+    /// Number of fragments the worker is still executing.
+    pub in_flight: i64,
 }
 
 // ============================================================================
@@ -55,6 +131,11 @@ pub struct HeartbeatResponse {
 pub struct WorkRequest {
     /// Worker ID requesting work.
     pub worker_id: Uuid,
+    /// How long to wait for work to become ready if none is available, in seconds.
+    /// Capped by the orchestrator; no waiting if absent.
+    pub wait_seconds: Option<u64>,
+    /// The only fragment to claim, for a worker launched to execute it (optional).
+    pub fragment_id: Option<Uuid>,
 }
 
 /// Response with assigned work.
@@ -68,6 +149,17 @@ pub struct WorkResponse {
     pub run_script: Option<String>,
     /// Current attempt number.
     pub attempt: i32,
+    /// Repository to check out into the workspace, if any.
+    pub repository_url: Option<String>,
+    /// Commit to check out (set together with `repository_url`).
+    pub commit_sha: Option<String>,
+    /// Clone depth for the checkout (None = full history).
+    pub checkout_depth: Option<i32>,
+    /// Paths for a sparse checkout (empty = whole tree).
+    pub checkout_sparse_paths: Vec<String>,
+    /// W3C `traceparent` of the claim, in the trace of the chain, for the
+    /// execution to continue (None = not traced).
+    pub traceparent: Option<String>,
 }
 
 // ============================================================================
@@ -81,18 +173,24 @@ pub struct WorkResultRequest {
     pub worker_id: Uuid,
     /// Fragment ID that was executed.
     pub fragment_id: Uuid,
+    /// Attempt number the worker was assigned.
+    pub attempt: i32,
     /// Whether execution succeeded.
     pub success: bool,
     /// Exit code from execution.
     pub exit_code: Option<i32>,
     /// Error message if failed.
     pub error_message: Option<String>,
+    /// Whether the worker cancelled the execution without a result, e.g. while
+    /// draining; the fragment is then retried elsewhere.
+    #[serde(default)]
+    pub cancelled: bool,
 }
 
 /// Response after reporting work result.
 #[derive(Debug, Serialize)]
 pub struct WorkResultResponse {
-    /// Acknowledgment status.
+    /// Acknowledgment status: `ok`, or `duplicate` for a result already recorded.
     pub status: String,
     /// Fragment status after update.
     pub fragment_status: String,
@@ -126,15 +224,30 @@ pub struct QueueMetricsResponse {
     pub active_workers: i64,
 }
 
+/// A fragment waiting for a worker, as listed for a worker controller.
+#[derive(Debug, Serialize)]
+pub struct PendingFragment {
+    /// The fragment's ID.
+    pub fragment_id: Uuid,
+    /// The chain the fragment belongs to.
+    pub chain_id: Uuid,
+    /// Attempt the fragment will be executed as.
+    pub attempt: i32,
+}
+
 // ============================================================================
 // Worker Busy Check (for preStop hook)
 // ============================================================================
 
-/// Response indicating if a worker is busy executing a fragment.
+/// Response indicating if a worker is busy executing fragments.
 #[derive(Debug, Serialize)]
 pub struct WorkerBusyResponse {
-    /// Whether the worker is currently executing a fragment.
+    /// Whether the worker is currently executing any fragment.
     pub busy: bool,
-    /// The fragment ID being executed, if any.
+    /// The longest-running fragment ID being executed, if any.
     pub fragment_id: Option<Uuid>,
+    /// All fragment IDs being executed.
+    pub fragment_ids: Vec<Uuid>,
+    /// Whether the worker is draining.
+    pub draining: bool,
 }
@@ -1,28 +1,38 @@
 //! HTTP request handlers for the worker orchestrator API.
 
-use axum::extract::State;
-use axum::http::StatusCode;
+use axum::extract::{Extension, State};
+use axum::http::{header, StatusCode};
 use axum::Json;
-use chrono::Utc;
-use tracing::{info, warn};
+use chrono::{NaiveDateTime, Utc};
+use diesel::{Connection, PgConnection};
+use tracing::{debug, field, info, info_span, warn};
 use uuid::Uuid;
 
 use axum::extract::{Path, Query};
+use tokio::time::{timeout_at, Duration, Instant};
 
-use vulcan_core::models::worker::NewWorker;
+use vulcan_core::models::fragment::{Fragment, FragmentStatus};
+use vulcan_core::models::worker::{NewWorker, Worker, WorkerStatus};
 use vulcan_core::repositories::{
     ChainRepository, FragmentRepository, PgChainRepository, PgFragmentRepository,
-    PgWorkerRepository, WorkerRepository,
+    PgWorkerRepository, RepositoryError, WorkerRepository,
 };
 
 use crate::api::dto::{
-    HeartbeatRequest, HeartbeatResponse, HealthResponse, QueueMetricsResponse,
+    DeregisterWorkerRequest, DrainWorkerResponse, DrainingWorker, HeartbeatRequest,
+    HeartbeatResponse, HealthResponse, PendingFragment, QueueMetricsResponse,
     RegisterWorkerRequest, RegisterWorkerResponse, WorkRequest, WorkResponse, WorkResultRequest,
-    WorkResultResponse, WorkerBusyResponse,
+    WorkResultResponse, WorkerBusyResponse, WorkerSummary, WorkerTokenResponse,
 };
+use crate::auth::{ControllerIdentity, RegistrationIdentity, WorkerIdentity};
+use crate::config::Config;
 use crate::error::{OrchestratorError, Result};
+use crate::metrics;
+use crate::orchestrator::completion::check_chain_completion;
+use crate::orchestrator::health::{recover_fragment, recover_worker, retry_or_fail};
 use crate::orchestrator::scheduler::Scheduler;
 use crate::state::AppState;
+use crate::tls::CertificateIdentity;
 
 /// Health check endpoint.
 pub async fn health() -> Json<HealthResponse> {
@@ -33,37 +43,183 @@ pub async fn health() -> Json<HealthResponse> {
 }
 
 /// Register a new worker.
+///
+/// When the worker connected with a client certificate, the tenant and machine group
+/// named by the certificate replace the ones in the request.
+///
+/// # Errors
+/// Returns `WorkerNameInUse` if the name belongs to a worker that is still alive.
 pub async fn register_worker(
     State(state): State<AppState>,
-    Json(request): Json<RegisterWorkerRequest>,
+    Extension(registration): Extension<RegistrationIdentity>,
+    certificate: Option<Extension<CertificateIdentity>>,
+    Json(mut request): Json<RegisterWorkerRequest>,
 ) -> Result<Json<RegisterWorkerResponse>> {
+    if let Some(Extension(certificate)) = certificate {
+        if certificate.tenant_id != request.tenant_id {
+            debug!(
+                declared = %request.tenant_id,
+                certified = %certificate.tenant_id,
+                "Using the tenant of the client certificate"
+            );
+        }
+        request.tenant_id = certificate.tenant_id;
+        if certificate.machine_group.is_some() {
+            request.machine_group = certificate.machine_group;
+        }
+    }
+
+    registration.authorize(request.tenant_id)?;
+
+    let max_concurrency = request.max_concurrency.unwrap_or(1);
+    if max_concurrency < 1 {
+        return Err(OrchestratorError::InvalidRequest(
+            "max_concurrency must be at least 1".to_string(),
+        ));
+    }
+
     let mut conn = state.get_conn()?;
-    let mut repo = PgWorkerRepository::new(&mut conn);
 
-    let new_worker = NewWorker::new(request.tenant_id)
-        .with_heartbeat(Utc::now().naive_utc());
+    // A named worker that registered before, e.g. a restarted pod, gets its row back.
+    // The row stays locked until it is taken over, so concurrent registrations under
+    // the same name see each other's takeover.
+    let worker = conn.transaction::<_, OrchestratorError, _>(|conn| {
+        let existing = match &request.name {
+            Some(name) => {
+                PgWorkerRepository::new(conn).find_by_name_for_update(request.tenant_id, name)?
+            },
+            None => None,
+        };
+
+        if let Some(worker) = existing {
+            if !is_gone(&state.config, &worker) {
+                return Err(OrchestratorError::WorkerNameInUse(format!(
+                    "{} is registered to live worker {}",
+                    worker.name.as_deref().unwrap_or_default(),
+                    worker.id
+                )));
+            }
+            return reuse_worker(conn, &state, &worker, &request, max_concurrency);
+        }
+
+        let new_worker = NewWorker::new(request.tenant_id)
+            .with_heartbeat(Utc::now().naive_utc())
+            .with_max_concurrency(max_concurrency)
+            .with_labels(request.labels.clone());
 
-    let new_worker = if let Some(group) = request.machine_group {
-        new_worker.with_machine_group(group)
-    } else {
-        new_worker
-    };
+        let new_worker = if let Some(group) = request.machine_group.clone() {
+            new_worker.with_machine_group(group)
+        } else {
+            new_worker
+        };
+
+        let new_worker = if let Some(name) = request.name.clone() {
+            new_worker.with_name(name)
+        } else {
+            new_worker
+        };
+
+        Ok(PgWorkerRepository::new(conn).create(new_worker)?)
+    })?;
 
-    let worker = repo.create(new_worker)?;
+    info!(
+        worker_id = %worker.id,
+        tenant_id = %worker.tenant_id,
+        name = ?worker.name,
+        max_concurrency = worker.max_concurrency,
+        labels = ?worker.labels,
+        "Worker registered"
+    );
 
-    info!(worker_id = %worker.id, tenant_id = %worker.tenant_id, "Worker registered");
+    let (token, token_expires_at) = state.auth.issue_worker_token(worker.id, worker.tenant_id);
 
     Ok(Json(RegisterWorkerResponse {
         worker_id: worker.id,
         status: format!("{:?}", worker.status),
+        token,
+        token_expires_at,
+    }))
+}
+
+/// Whether a worker's row may be taken over by a new registration: the worker is
+/// retired, or has not sent a heartbeat within the heartbeat timeout.
+fn is_gone(config: &Config, worker: &Worker) -> bool {
+    if matches!(
+        worker.status,
+        WorkerStatus::Error | WorkerStatus::Deregistered
+    ) {
+        return true;
+    }
+
+    let timeout =
+        chrono::Duration::seconds(i64::try_from(config.heartbeat_timeout_secs).unwrap_or(i64::MAX));
+    worker
+        .last_heartbeat_at
+        .is_none_or(|heartbeat| heartbeat < Utc::now().naive_utc() - timeout)
+}
+
+/// Bring back the row of a worker registering again under the same name.
+///
+/// Fragments the previous instance had in flight are lost with it and recovered,
+/// and the row is made active with the new registration's settings. Must run in
+/// the transaction that locked the row.
+fn reuse_worker(
+    conn: &mut PgConnection,
+    state: &AppState,
+    worker: &Worker,
+    request: &RegisterWorkerRequest,
+    max_concurrency: i32,
+) -> Result<Worker> {
+    info!(worker_id = %worker.id, previous_status = ?worker.status, "Worker re-registering");
+
+    recover_worker(
+        conn,
+        &state.config,
+        worker.id,
+        "Worker restarted and max retry attempts exceeded",
+    )?;
+
+    Ok(PgWorkerRepository::new(conn).reactivate(
+        worker.id,
+        request.machine_group.as_deref(),
+        max_concurrency,
+        &request.labels,
+    )?)
+}
+
+/// Issue a new token for a registered worker, e.g. after its token expired.
+///
+/// # Errors
+/// Returns an error if the worker does not exist or belongs to another tenant.
+pub async fn renew_worker_token(
+    State(state): State<AppState>,
+    Extension(registration): Extension<RegistrationIdentity>,
+    Path(worker_id): Path<Uuid>,
+) -> Result<Json<WorkerTokenResponse>> {
+    let worker = {
+        let mut conn = state.get_conn()?;
+        let mut repo = PgWorkerRepository::new(&mut conn);
+        repo.find_by_id(worker_id)?
+            .ok_or(OrchestratorError::WorkerNotFound(worker_id))?
+    };
+    registration.authorize(worker.tenant_id)?;
+
+    let (token, token_expires_at) = state.auth.issue_worker_token(worker.id, worker.tenant_id);
+
+    Ok(Json(WorkerTokenResponse {
+        token,
+        token_expires_at,
     }))
 }
 
 /// Handle worker heartbeat.
 pub async fn heartbeat(
     State(state): State<AppState>,
+    Extension(identity): Extension<WorkerIdentity>,
     Json(request): Json<HeartbeatRequest>,
 ) -> Result<Json<HeartbeatResponse>> {
+    identity.authorize(request.worker_id)?;
+
     let mut conn = state.get_conn()?;
     let mut repo = PgWorkerRepository::new(&mut conn);
 
@@ -73,13 +229,51 @@ pub async fn heartbeat(
         .ok_or(OrchestratorError::WorkerNotFound(request.worker_id))?;
 
     // Update heartbeat
-    repo.update_heartbeat(worker.id)?;
+    let mut worker = repo.update_heartbeat(worker.id)?;
+
+    // A worker that reports draining stays draining until it deregisters
+    if request.draining
+        && worker.status != WorkerStatus::Draining
+        && let Some(draining) = repo.mark_draining(worker.id)?
+    {
+        info!(worker_id = %worker.id, "Worker is draining");
+        worker = draining;
+    }
 
     let now = Utc::now().naive_utc();
 
+    // Reconcile in-flight fragments with what the worker reports. Assignments made
+    // within the heartbeat timeout may still be on their way to the worker.
+    if let Some(fragment_ids) = request.fragment_ids {
+        let assigned_before = now
+            - chrono::Duration::seconds(
+                i64::try_from(state.config.heartbeat_timeout_secs).unwrap_or(i64::MAX),
+            );
+        let lost = repo.sync_assignments(worker.id, &fragment_ids, assigned_before)?;
+
+        for fragment_id in lost {
+            warn!(
+                worker_id = %worker.id,
+                fragment_id = %fragment_id,
+                "Worker no longer reports assigned fragment"
+            );
+            recover_fragment(
+                &mut conn,
+                &state.config,
+                fragment_id,
+                "Worker lost fragment and max retry attempts exceeded",
+            )?;
+        }
+    }
+
+    let (token, token_expires_at) = state.auth.issue_worker_token(worker.id, worker.tenant_id);
+
     Ok(Json(HeartbeatResponse {
         status: "ok".to_string(),
         timestamp: now,
+        token,
+        token_expires_at,
+        draining: worker.status == WorkerStatus::Draining,
     }))
 }
 
@@ -87,65 +281,174 @@ pub async fn heartbeat(
 ///
 /// Uses optimistic locking to atomically claim work, preventing race conditions
 /// when thousands of workers request work simultaneously.
+///
+/// With `wait_seconds`, a request finding no work is held open until matching work
+/// becomes ready or the wait elapses. No database connection is held while waiting.
+///
+/// With `fragment_id`, only that fragment is claimed, for a worker launched to
+/// execute it.
 pub async fn request_work(
     State(state): State<AppState>,
+    Extension(identity): Extension<WorkerIdentity>,
     Json(request): Json<WorkRequest>,
 ) -> Result<(StatusCode, Json<Option<WorkResponse>>)> {
-    let mut conn = state.get_conn()?;
+    identity.authorize(request.worker_id)?;
+
+    let wait = request
+        .wait_seconds
+        .unwrap_or(0)
+        .min(state.config.max_work_wait_secs);
+    let deadline = Instant::now() + Duration::from_secs(wait);
 
-    // Get the worker
     let worker = {
-        let mut repo = PgWorkerRepository::new(&mut conn);
-        repo.find_by_id(request.worker_id)?
-            .ok_or(OrchestratorError::WorkerNotFound(request.worker_id))?
+        let mut conn = state.get_conn()?;
+
+        // Get the worker
+        let worker = {
+            let mut repo = PgWorkerRepository::new(&mut conn);
+            repo.find_by_id(request.worker_id)?
+                .ok_or(OrchestratorError::WorkerNotFound(request.worker_id))?
+        };
+
+        // Draining (or failed) workers get no new work
+        if !worker.status.is_available() {
+            debug!(
+                worker_id = %worker.id,
+                status = ?worker.status,
+                "Worker does not accept work"
+            );
+            return Ok((StatusCode::NO_CONTENT, Json(None)));
+        }
+
+        // Only hand out work while the worker has a free slot
+        let in_flight = {
+            let mut repo = PgWorkerRepository::new(&mut conn);
+            repo.count_assigned_fragments(worker.id)?
+        };
+        if in_flight >= i64::from(worker.max_concurrency) {
+            debug!(
+                worker_id = %worker.id,
+                in_flight,
+                max_concurrency = worker.max_concurrency,
+                "Worker has no free slot"
+            );
+            return Ok((StatusCode::NO_CONTENT, Json(None)));
+        }
+
+        worker
     };
 
-    // Use the scheduler to find and atomically claim work
-    // This uses optimistic locking: if another worker claims the fragment first,
-    // the scheduler will try the next eligible fragment
-    let scheduler = Scheduler::new(&mut conn);
-    let fragment = scheduler.find_and_claim_work(&worker)?;
-
-    match fragment {
-        Some(fragment) => {
-            // Fragment is already claimed (status=Running, assigned_worker_id set)
-            // Just need to update worker's current_fragment_id
-            let fragment_id = fragment.id;
-            let chain_id = fragment.chain_id;
-            let run_script = fragment.run_script.clone();
-            let attempt = fragment.attempt;
-            let worker_id = worker.id;
-
-            {
-                let mut worker_repo = PgWorkerRepository::new(&mut conn);
-                worker_repo.assign_fragment(worker_id, fragment_id)?;
-            }
+    // Subscribe before the first attempt so work becoming ready in between is not missed
+    let mut readiness = state.readiness.subscribe(worker.tenant_id);
 
-            info!(
-                worker_id = %worker_id,
-                fragment_id = %fragment_id,
-                "Assigned fragment to worker"
-            );
+    let mut woken = false;
+    loop {
+        if let Some(work) = assign_work(&state, &worker, request.fragment_id)? {
+            return Ok((StatusCode::OK, Json(Some(work))));
+        }
+        if woken {
+            // Another worker claimed what became ready
+            metrics::record_claim_conflict();
+        }
 
-            Ok((
-                StatusCode::OK,
-                Json(Some(WorkResponse {
-                    fragment_id,
-                    chain_id,
-                    run_script,
-                    attempt,
-                })),
-            ))
+        if timeout_at(deadline, readiness.ready()).await.is_err() {
+            return Ok((StatusCode::NO_CONTENT, Json(None)));
         }
-        None => Ok((StatusCode::NO_CONTENT, Json(None))),
+        debug!(worker_id = %worker.id, "Work became ready, retrying claim");
+        woken = true;
     }
 }
 
+/// Claim the next matching fragment (or only `fragment_id`) for a worker and record
+/// the assignment.
+///
+/// The claim is traced in a `claim` span that joins the trace of the fragment's
+/// chain once the fragment is known, and the work carries its context.
+fn assign_work(
+    state: &AppState,
+    worker: &Worker,
+    fragment_id: Option<Uuid>,
+) -> Result<Option<WorkResponse>> {
+    // Opened before the claim so it covers it, but entered only once its parent is known
+    let span = info_span!(
+        "claim",
+        worker_id = %worker.id,
+        fragment_id = field::Empty,
+        chain_id = field::Empty,
+    );
+    let mut conn = state.get_conn()?;
+    let worker_id = worker.id;
+
+    // Claim, record it on the worker and look up the chain together, so a failure
+    // leaves the fragment unclaimed
+    let claimed = conn.transaction::<_, OrchestratorError, _>(|conn| {
+        // Use the scheduler to find and atomically claim work
+        let scheduler = Scheduler::new(conn);
+        let claimed = match fragment_id {
+            Some(fragment_id) => scheduler.claim_bound_work(worker, fragment_id)?,
+            None => scheduler.find_and_claim_work(worker)?,
+        };
+        let Some(fragment) = claimed else {
+            return Ok(None);
+        };
+
+        // Fragment is claimed (status=Running, assigned_worker_id set): record it as
+        // in flight on the worker
+        PgWorkerRepository::new(conn).assign_fragment(worker_id, fragment.id)?;
+
+        // Look up the chain for workspace checkout details
+        let chain = PgChainRepository::new(conn)
+            .find_by_id(fragment.chain_id)?
+            .ok_or(OrchestratorError::ChainNotFound(fragment.chain_id))?;
+
+        Ok(Some((fragment, chain)))
+    })?;
+    let Some((fragment, chain)) = claimed else {
+        return Ok(None);
+    };
+    let fragment_id = fragment.id;
+    let chain_id = fragment.chain_id;
+
+    if let Some(trace_context) = &chain.trace_context {
+        vulcan_telemetry::set_parent(&span, trace_context);
+    }
+    span.record("fragment_id", field::display(fragment_id));
+    span.record("chain_id", field::display(chain_id));
+    let _entered = span.enter();
+
+    info!(
+        worker_id = %worker_id,
+        fragment_id = %fragment_id,
+        "Assigned fragment to worker"
+    );
+
+    Ok(Some(WorkResponse {
+        fragment_id,
+        chain_id,
+        run_script: fragment.run_script,
+        attempt: fragment.attempt,
+        repository_url: chain.repository_url,
+        commit_sha: chain.commit_sha,
+        checkout_depth: chain.checkout_depth,
+        checkout_sparse_paths: chain.checkout_sparse_paths.unwrap_or_default(),
+        traceparent: vulcan_telemetry::traceparent(&span),
+    }))
+}
+
 /// Worker reports execution result.
+///
+/// Recording the result, releasing the fragment's successors and the worker's slot,
+/// and completing the chain happen in one transaction. Only the worker the fragment
+/// is assigned to may report it, for the attempt it was given; late results for a
+/// fragment that was reassigned since are rejected. Repeating a report that was
+/// already recorded is acknowledged without changing anything.
 pub async fn report_result(
     State(state): State<AppState>,
+    Extension(identity): Extension<WorkerIdentity>,
     Json(request): Json<WorkResultRequest>,
 ) -> Result<Json<WorkResultResponse>> {
+    identity.authorize(request.worker_id)?;
+
     let mut conn = state.get_conn()?;
 
     // Verify worker exists
@@ -155,73 +458,294 @@ pub async fn report_result(
             .ok_or(OrchestratorError::WorkerNotFound(request.worker_id))?;
     }
 
-    // Update fragment status
-    let fragment = {
-        let mut repo = PgFragmentRepository::new(&mut conn);
+    let outcome = conn
+        .transaction::<_, RepositoryError, _>(|conn| {
+            // Lock the fragment so concurrent reports and reassignments see a consistent state
+            let Some(fragment) =
+                PgFragmentRepository::new(conn).find_by_id_for_update(request.fragment_id)?
+            else {
+                return Ok(None);
+            };
+
+            let assigned = fragment.assigned_worker_id == Some(request.worker_id);
+            let owned = assigned && fragment.attempt == request.attempt;
+
+            let outcome = if owned && fragment.status == FragmentStatus::Running {
+                let recorded = if request.cancelled {
+                    retry_or_fail(
+                        conn,
+                        &state.config,
+                        &fragment,
+                        "Execution cancelled and max retry attempts exceeded",
+                    )?
+                } else {
+                    record_result(conn, &request)?
+                };
+                ReportOutcome::Recorded(recorded, fragment.started_at)
+            } else if owned && fragment.status.is_terminal() {
+                ReportOutcome::Duplicate(fragment)
+            } else {
+                ReportOutcome::Stale(fragment)
+            };
+
+            // The worker is done with this fragment, unless it holds a newer attempt of it
+            if owned || !assigned {
+                let mut repo = PgWorkerRepository::new(conn);
+                repo.release_fragment(request.worker_id, request.fragment_id)?;
+            }
 
-        if request.success {
-            let exit_code = request.exit_code.unwrap_or(0);
-            repo.complete_execution(request.fragment_id, exit_code)?
-        } else {
-            let error = request
-                .error_message
-                .unwrap_or_else(|| "Unknown error".to_string());
-            repo.fail_execution(request.fragment_id, error)?
+            Ok(Some(outcome))
+        })?
+        .ok_or(OrchestratorError::FragmentNotFound(request.fragment_id))?;
+
+    match outcome {
+        ReportOutcome::Recorded(fragment, started_at) => {
+            if let Some(started_at) = started_at {
+                let outcome = if request.cancelled {
+                    "cancelled"
+                } else if request.success {
+                    "success"
+                } else {
+                    "failure"
+                };
+                let duration = (Utc::now().naive_utc() - started_at).to_std().unwrap_or_default();
+                metrics::record_fragment_duration(outcome, duration);
+            }
+
+            info!(
+                worker_id = %request.worker_id,
+                fragment_id = %request.fragment_id,
+                success = request.success,
+                cancelled = request.cancelled,
+                "Fragment execution completed"
+            );
+
+            Ok(Json(WorkResultResponse {
+                status: "ok".to_string(),
+                fragment_status: format!("{:?}", fragment.status),
+            }))
+        }
+        ReportOutcome::Duplicate(fragment) => {
+            debug!(
+                worker_id = %request.worker_id,
+                fragment_id = %request.fragment_id,
+                "Ignoring duplicate result"
+            );
+
+            Ok(Json(WorkResultResponse {
+                status: "duplicate".to_string(),
+                fragment_status: format!("{:?}", fragment.status),
+            }))
+        }
+        ReportOutcome::Stale(fragment) => {
+            warn!(
+                worker_id = %request.worker_id,
+                fragment_id = %request.fragment_id,
+                attempt = request.attempt,
+                current_attempt = fragment.attempt,
+                assigned_worker_id = ?fragment.assigned_worker_id,
+                "Rejecting stale result"
+            );
+
+            Err(OrchestratorError::StaleResult(format!(
+                "fragment {} attempt {} is no longer assigned to worker {}",
+                request.fragment_id, request.attempt, request.worker_id
+            )))
         }
+    }
+}
+
+/// What a result report did to its fragment.
+enum ReportOutcome {
+    /// The result was recorded; also holds when the execution started.
+    Recorded(Fragment, Option<NaiveDateTime>),
+    /// The same result was recorded before.
+    Duplicate(Fragment),
+    /// The worker no longer owns this attempt of the fragment.
+    Stale(Fragment),
+}
+
+/// Record a fragment's result, make its successors ready and complete its chain if done.
+fn record_result(
+    conn: &mut PgConnection,
+    request: &WorkResultRequest,
+) -> std::result::Result<Fragment, RepositoryError> {
+    let mut repo = PgFragmentRepository::new(conn);
+
+    let fragment = if request.success {
+        let exit_code = request.exit_code.unwrap_or(0);
+        repo.complete_execution(request.fragment_id, exit_code)?
+    } else {
+        let error = request
+            .error_message
+            .clone()
+            .unwrap_or_else(|| "Unknown error".to_string());
+        repo.fail_execution(request.fragment_id, error)?
     };
 
-    // Clear worker assignment
-    {
-        let mut repo = PgWorkerRepository::new(&mut conn);
-        repo.clear_assignment(request.worker_id)?;
+    let ready = repo.release_successors(fragment.id)?;
+    if !ready.is_empty() {
+        debug!(
+            fragment_id = %fragment.id,
+            ready = ?ready,
+            "Successors ready"
+        );
     }
 
-    info!(
-        worker_id = %request.worker_id,
-        fragment_id = %request.fragment_id,
-        success = request.success,
-        "Fragment execution completed"
-    );
+    check_chain_completion(conn, fragment.chain_id)?;
 
-    // Check if chain is complete
-    check_chain_completion(&mut conn, fragment.chain_id)?;
+    Ok(fragment)
+}
 
-    Ok(Json(WorkResultResponse {
-        status: "ok".to_string(),
-        fragment_status: format!("{:?}", fragment.status),
+// ============================================================================
+// Drain and Deregistration
+// ============================================================================
+
+/// Ask a worker to drain: it is assigned no new work, learns about the drain from
+/// its next heartbeat, finishes its in-flight fragments and deregisters.
+///
+/// # Errors
+/// Returns an error if the worker does not exist, belongs to another tenant, or is
+/// neither active nor draining.
+pub async fn drain_worker(
+    State(state): State<AppState>,
+    Extension(controller): Extension<ControllerIdentity>,
+    Path(worker_id): Path<Uuid>,
+) -> Result<Json<DrainWorkerResponse>> {
+    let mut conn = state.get_conn()?;
+    let mut repo = PgWorkerRepository::new(&mut conn);
+
+    let worker = repo
+        .find_by_id(worker_id)?
+        .ok_or(OrchestratorError::WorkerNotFound(worker_id))?;
+    controller.authorize(worker.tenant_id)?;
+
+    let worker = repo.mark_draining(worker_id)?.ok_or_else(|| {
+        OrchestratorError::InvalidRequest(format!(
+            "worker {worker_id} is {:?} and cannot drain",
+            worker.status
+        ))
+    })?;
+
+    info!(worker_id = %worker.id, "Worker asked to drain");
+
+    Ok(Json(DrainWorkerResponse {
+        worker_id: worker.id,
+        status: format!("{:?}", worker.status),
     }))
 }
 
-/// Check if all fragments in a chain are complete and update chain status.
-fn check_chain_completion(
-    conn: &mut diesel::PgConnection,
-    chain_id: Uuid,
-) -> Result<()> {
-    let mut fragment_repo = PgFragmentRepository::new(conn);
-    let fragments = fragment_repo.find_by_chain(chain_id)?;
-
-    let all_complete = fragments
-        .iter()
-        .all(|f| f.status.is_terminal());
-
-    if all_complete {
-        let any_failed = fragments
-            .iter()
-            .any(|f| !f.status.is_success());
-
-        // Need a new connection scope for chain repo
-        let mut chain_repo = PgChainRepository::new(conn);
-
-        if any_failed {
-            chain_repo.mark_failed(chain_id)?;
-            warn!(chain_id = %chain_id, "Chain failed");
-        } else {
-            chain_repo.mark_completed(chain_id)?;
-            info!(chain_id = %chain_id, "Chain completed successfully");
-        }
+/// Deregister a worker that is shutting down.
+///
+/// The worker is kept as deregistered until the retention sweep deletes it, or until
+/// it registers again under the same name. Fragments it still has in flight are
+/// recovered as if it had died.
+///
+/// # Errors
+/// Returns an error if the worker does not exist or cannot be updated.
+pub async fn deregister_worker(
+    State(state): State<AppState>,
+    Extension(identity): Extension<WorkerIdentity>,
+    Json(request): Json<DeregisterWorkerRequest>,
+) -> Result<StatusCode> {
+    identity.authorize(request.worker_id)?;
+
+    let mut conn = state.get_conn()?;
+
+    let in_flight = {
+        let mut repo = PgWorkerRepository::new(&mut conn);
+        repo.retire(request.worker_id, WorkerStatus::Deregistered)?
+            .ok_or(OrchestratorError::WorkerNotFound(request.worker_id))?;
+        repo.count_assigned_fragments(request.worker_id)?
+    };
+
+    if in_flight > 0 {
+        warn!(
+            worker_id = %request.worker_id,
+            in_flight,
+            "Worker deregistered with fragments in flight"
+        );
     }
+    recover_worker(
+        &mut conn,
+        &state.config,
+        request.worker_id,
+        "Worker deregistered and max retry attempts exceeded",
+    )?;
 
-    Ok(())
+    info!(worker_id = %request.worker_id, "Worker deregistered");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Query parameters for the worker listing endpoints.
+#[derive(Debug, serde::Deserialize)]
+pub struct DrainingWorkersQuery {
+    /// Tenant to list (optional, the controller's own tenant if omitted).
+    pub tenant_id: Option<Uuid>,
+    /// Filter by machine group (optional).
+    pub machine_group: Option<String>,
+}
+
+/// List live (active or draining) workers, so a controller can tell which worker
+/// runs on which of its instances.
+///
+/// # Errors
+/// Returns an error if the tenant is not the controller's or the workers cannot be
+/// loaded.
+pub async fn list_workers(
+    State(state): State<AppState>,
+    Extension(controller): Extension<ControllerIdentity>,
+    Query(query): Query<DrainingWorkersQuery>,
+) -> Result<Json<Vec<WorkerSummary>>> {
+    let tenant_id = query.tenant_id.unwrap_or(controller.tenant_id);
+    controller.authorize(tenant_id)?;
+
+    let mut conn = state.get_conn()?;
+    let workers = PgWorkerRepository::new(&mut conn)
+        .find_live(tenant_id, query.machine_group.as_deref())?;
+
+    Ok(Json(
+        workers
+            .into_iter()
+            .map(|worker| WorkerSummary {
+                worker_id: worker.id,
+                name: worker.name,
+                status: format!("{:?}", worker.status),
+            })
+            .collect(),
+    ))
+}
+
+/// List draining workers, so a controller can remove their instances first.
+///
+/// # Errors
+/// Returns an error if the tenant is not the controller's or the workers cannot be
+/// loaded.
+pub async fn draining_workers(
+    State(state): State<AppState>,
+    Extension(controller): Extension<ControllerIdentity>,
+    Query(query): Query<DrainingWorkersQuery>,
+) -> Result<Json<Vec<DrainingWorker>>> {
+    let tenant_id = query.tenant_id.unwrap_or(controller.tenant_id);
+    controller.authorize(tenant_id)?;
+
+    let mut conn = state.get_conn()?;
+    let mut repo = PgWorkerRepository::new(&mut conn);
+
+    let workers = repo.find_draining(tenant_id, query.machine_group.as_deref())?;
+    let draining = workers
+        .into_iter()
+        .map(|worker| {
+            Ok(DrainingWorker {
+                in_flight: repo.count_assigned_fragments(worker.id)?,
+                worker_id: worker.id,
+                name: worker.name,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Json(draining))
 }
 
 // ============================================================================
@@ -231,6 +755,8 @@ fn check_chain_completion(
 /// Query parameters for queue metrics endpoint.
 #[derive(Debug, serde::Deserialize)]
 pub struct QueueMetricsQuery {
+    /// Tenant to report on (optional, the controller's own tenant if omitted).
+    pub tenant_id: Option<Uuid>,
     /// Filter by machine group (optional).
     pub machine_group: Option<String>,
 }
@@ -238,25 +764,30 @@ pub struct QueueMetricsQuery {
 /// Get queue metrics for scaling decisions.
 pub async fn queue_metrics(
     State(state): State<AppState>,
+    Extension(controller): Extension<ControllerIdentity>,
     Query(query): Query<QueueMetricsQuery>,
 ) -> Result<Json<QueueMetricsResponse>> {
+    let tenant_id = query.tenant_id.unwrap_or(controller.tenant_id);
+    controller.authorize(tenant_id)?;
+
     let mut conn = state.get_conn()?;
 
+    let tenant_id = Some(tenant_id);
     let machine_group = query.machine_group.as_deref();
 
     let pending_fragments = {
         let mut repo = PgFragmentRepository::new(&mut conn);
-        repo.count_pending_by_machine(machine_group)?
+        repo.count_pending_by_machine(tenant_id, machine_group)?
     };
 
     let running_fragments = {
         let mut repo = PgFragmentRepository::new(&mut conn);
-        repo.count_running_by_machine(machine_group)?
+        repo.count_running_by_machine(tenant_id, machine_group)?
     };
 
     let active_workers = {
         let mut repo = PgWorkerRepository::new(&mut conn);
-        repo.count_active_by_machine_group(machine_group)?
+        repo.count_active_by_machine_group(tenant_id, machine_group)?
     };
 
     Ok(Json(QueueMetricsResponse {
@@ -266,22 +797,119 @@ pub async fn queue_metrics(
     }))
 }
 
+/// Most fragments listed by the pending fragments endpoint.
+const MAX_PENDING_LIMIT: usize = 1000;
+
+/// Query parameters for the pending fragments endpoint.
+#[derive(Debug, serde::Deserialize)]
+pub struct PendingFragmentsQuery {
+    /// Tenant to list (optional, the controller's own tenant if omitted).
+    pub tenant_id: Option<Uuid>,
+    /// Machine group the fragments must run on (optional, any if omitted).
+    pub machine_group: Option<String>,
+    /// Comma-separated labels of the workers that would run the fragments (optional,
+    /// any `runs-on` requirements if omitted).
+    pub labels: Option<String>,
+    /// How many fragments to list at most (default and cap: 1000).
+    pub limit: Option<usize>,
+}
+
+/// List the fragments waiting for a worker, in the order workers claim them, so a
+/// controller can launch a worker for each.
+///
+/// A fragment is listed when a worker of the machine group and with the labels
+/// could claim it.
+///
+/// # Errors
+/// Returns an error if the tenant is not the controller's or the fragments cannot be
+/// loaded.
+pub async fn pending_fragments(
+    State(state): State<AppState>,
+    Extension(controller): Extension<ControllerIdentity>,
+    Query(query): Query<PendingFragmentsQuery>,
+) -> Result<Json<Vec<PendingFragment>>> {
+    let tenant_id = query.tenant_id.unwrap_or(controller.tenant_id);
+    controller.authorize(tenant_id)?;
+
+    let labels = query.labels.as_deref().map(|labels| {
+        labels
+            .split(',')
+            .map(str::trim)
+            .filter(|label| !label.is_empty())
+            .map(String::from)
+            .collect::<Vec<_>>()
+    });
+    let limit = query.limit.unwrap_or(MAX_PENDING_LIMIT).min(MAX_PENDING_LIMIT);
+
+    let mut conn = state.get_conn()?;
+    let fragments = PgFragmentRepository::new(&mut conn).find_pending_matching(
+        tenant_id,
+        query.machine_group.as_deref(),
+        labels.as_deref(),
+        i64::try_from(limit).unwrap_or(i64::MAX),
+    )?;
+
+    Ok(Json(
+        fragments
+            .into_iter()
+            .map(|fragment| PendingFragment {
+                fragment_id: fragment.id,
+                chain_id: fragment.chain_id,
+                attempt: fragment.attempt,
+            })
+            .collect(),
+    ))
+}
+
 // ============================================================================
 // Worker Busy Check (for preStop hook)
 // ============================================================================
 
-/// Check if a worker is currently busy executing a fragment.
+/// Check if a worker is currently busy executing fragments.
+///
+/// # Errors
+/// Returns an error if the worker does not exist or belongs to another tenant.
 pub async fn worker_busy(
     State(state): State<AppState>,
+    Extension(controller): Extension<ControllerIdentity>,
     Path(worker_id): Path<Uuid>,
 ) -> Result<Json<WorkerBusyResponse>> {
     let mut conn = state.get_conn()?;
     let mut repo = PgWorkerRepository::new(&mut conn);
 
-    let fragment_id = repo.is_busy(worker_id)?;
+    let worker = repo
+        .find_by_id(worker_id)?
+        .ok_or(OrchestratorError::WorkerNotFound(worker_id))?;
+    controller.authorize(worker.tenant_id)?;
+
+    let fragment_ids = repo.find_assigned_fragments(worker_id)?;
+    let draining = worker.status == WorkerStatus::Draining;
 
     Ok(Json(WorkerBusyResponse {
-        busy: fragment_id.is_some(),
-        fragment_id,
+        busy: !fragment_ids.is_empty(),
+        fragment_id: fragment_ids.first().copied(),
+        fragment_ids,
+        draining,
     }))
 }
+
+// ============================================================================
+// Prometheus Metrics
+// ============================================================================
+
+/// Metrics in the Prometheus text format.
+///
+/// # Errors
+/// Returns an error if the queue depth or worker counts cannot be read.
+pub async fn prometheus_metrics(
+    State(state): State<AppState>,
+) -> Result<([(header::HeaderName, &'static str); 1], String)> {
+    let mut conn = state.get_conn()?;
+    let queue = PgFragmentRepository::new(&mut conn).queue_depth()?;
+    let workers = PgWorkerRepository::new(&mut conn).count_by_status()?;
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::render(&queue, &workers),
+    ))
+}
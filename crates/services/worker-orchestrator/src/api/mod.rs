@@ -2,21 +2,57 @@
 
 pub mod dto;
 pub mod handlers;
+pub mod trace;
 
+use axum::middleware::{from_fn, from_fn_with_state};
 use axum::routing::{get, post};
 use axum::Router;
 
+use crate::auth::{require_controller, require_registration, require_worker};
 use crate::state::AppState;
 
 /// Create the API router with all endpoints.
+///
+/// Registration and token renewal require a registration token, worker endpoints a worker token and
+/// the queue and worker management endpoints a controller token. The health check is open. Every
+/// request is handled in a span continuing the trace of its `traceparent` header.
 pub fn create_router(state: AppState) -> Router {
-    Router::new()
-        .route("/health", get(handlers::health))
+    let registration = Router::new()
         .route("/workers/register", post(handlers::register_worker))
+        .route("/workers/{id}/token", post(handlers::renew_worker_token))
+        .route_layer(from_fn_with_state(state.clone(), require_registration));
+
+    let worker = Router::new()
         .route("/workers/heartbeat", post(handlers::heartbeat))
-        .route("/workers/{id}/busy", get(handlers::worker_busy))
         .route("/work/request", post(handlers::request_work))
         .route("/work/result", post(handlers::report_result))
+        .route("/workers/deregister", post(handlers::deregister_worker))
+        .route_layer(from_fn_with_state(state.clone(), require_worker));
+
+    let controller = Router::new()
         .route("/queue/metrics", get(handlers::queue_metrics))
+        .route("/queue/pending", get(handlers::pending_fragments))
+        .route("/workers", get(handlers::list_workers))
+        .route("/workers/draining", get(handlers::draining_workers))
+        .route("/workers/{id}/busy", get(handlers::worker_busy))
+        .route("/workers/{id}/drain", post(handlers::drain_worker))
+        .route_layer(from_fn_with_state(state.clone(), require_controller));
+
+    Router::new()
+        .route("/health", get(handlers::health))
+        .merge(registration)
+        .merge(worker)
+        .merge(controller)
+        .route_layer(from_fn(trace::trace_request))
+        .with_state(state)
+}
+
+/// Create the router serving the Prometheus metrics.
+///
+/// The metrics name tenants, so they are served on a listener of their own, kept
+/// internal, instead of the API.
+pub fn create_metrics_router(state: AppState) -> Router {
+    Router::new()
+        .route("/metrics", get(handlers::prometheus_metrics))
         .with_state(state)
 }
@@ -0,0 +1,30 @@
+//! Trace context of API requests.
+
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::{Instrument, info_span};
+
+/// Handle a request in a span continuing the trace of its `traceparent` header,
+/// e.g. the span of the worker reporting a result.
+pub async fn trace_request(request: Request, next: Next) -> Response {
+    let route = request.extensions().get::<MatchedPath>().map_or_else(
+        || request.uri().path().to_string(),
+        |path| path.as_str().to_string(),
+    );
+    let span = info_span!(
+        "request",
+        otel.name = %format!("{} {route}", request.method()),
+        http.request.method = %request.method(),
+        http.route = %route,
+    );
+    if let Some(parent) = request
+        .headers()
+        .get(vulcan_telemetry::TRACEPARENT)
+        .and_then(|value| value.to_str().ok())
+    {
+        vulcan_telemetry::set_parent(&span, parent);
+    }
+
+    next.run(request).instrument(span).await
+}
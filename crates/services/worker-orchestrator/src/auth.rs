@@ -0,0 +1,339 @@
+//! Authentication of workers and worker controllers.
+//!
+//! - Workers register with a per-tenant registration token and receive a short-lived
+//!   worker token, signed by the orchestrator and bound to their worker ID and tenant.
+//!   The token is renewed on every heartbeat.
+//! - Worker controllers use a per-tenant controller token, which is only accepted on
+//!   the controller endpoints of that tenant.
+//!
+//! All tokens are sent as `Authorization: Bearer <token>`.
+//!
+//! When the listener verifies client certificates, registration and worker requests
+//! must also come with a certificate naming the tenant of their token. Controllers,
+//! health probes and metrics scrapers need none.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use axum::extract::{Request, State};
+use axum::http::header::AUTHORIZATION;
+use axum::middleware::Next;
+use axum::response::Response;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{DateTime, Utc};
+use ring::hmac;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::error::{OrchestratorError, Result};
+use crate::state::AppState;
+use crate::tls::{CertificateIdentity, TlsConfig};
+
+/// A secret that is never printed.
+#[derive(Clone)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Wrap a secret value.
+    #[must_use]
+    pub const fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(***)")
+    }
+}
+
+/// Tokens granted to tenants, several per tenant to allow rotation.
+#[derive(Debug, Clone, Default)]
+pub struct TenantTokens(Vec<(Uuid, Secret)>);
+
+impl TenantTokens {
+    /// Tokens from `(tenant, token)` pairs.
+    #[must_use]
+    pub const fn new(tokens: Vec<(Uuid, Secret)>) -> Self {
+        Self(tokens)
+    }
+
+    /// Parse a comma-separated list of `tenant_id=token` pairs.
+    ///
+    /// # Errors
+    /// Returns a description of the first malformed entry.
+    pub fn parse(spec: &str) -> std::result::Result<Self, String> {
+        spec.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (tenant_id, token) = entry
+                    .split_once('=')
+                    .ok_or_else(|| "expected tenant_id=token".to_string())?;
+                let tenant_id = tenant_id
+                    .trim()
+                    .parse::<Uuid>()
+                    .map_err(|e| format!("invalid tenant ID {tenant_id:?}: {e}"))?;
+                let token = token.trim();
+                if token.is_empty() {
+                    return Err(format!("empty token for tenant {tenant_id}"));
+                }
+                Ok((tenant_id, Secret::new(token.to_string())))
+            })
+            .collect::<std::result::Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// Identity of a caller holding a registration token.
+#[derive(Debug, Clone, Copy)]
+pub struct RegistrationIdentity {
+    /// Tenant the token was issued to.
+    pub tenant_id: Uuid,
+}
+
+impl RegistrationIdentity {
+    /// Check that the caller may register workers for `tenant_id`.
+    ///
+    /// # Errors
+    /// Returns `Forbidden` for any other tenant.
+    pub fn authorize(self, tenant_id: Uuid) -> Result<()> {
+        if self.tenant_id == tenant_id {
+            Ok(())
+        } else {
+            Err(OrchestratorError::Forbidden(format!(
+                "registration token is not valid for tenant {tenant_id}"
+            )))
+        }
+    }
+}
+
+/// Identity of a worker, taken from its worker token.
+#[derive(Debug, Clone, Copy)]
+pub struct WorkerIdentity {
+    /// The worker the token was issued to.
+    pub worker_id: Uuid,
+    /// The worker's tenant.
+    pub tenant_id: Uuid,
+}
+
+impl WorkerIdentity {
+    /// Check that the caller is `worker_id`.
+    ///
+    /// # Errors
+    /// Returns `Forbidden` for any other worker.
+    pub fn authorize(self, worker_id: Uuid) -> Result<()> {
+        if self.worker_id == worker_id {
+            Ok(())
+        } else {
+            Err(OrchestratorError::Forbidden(format!(
+                "worker token is not valid for worker {worker_id}"
+            )))
+        }
+    }
+}
+
+/// Identity of a worker controller, taken from its controller token.
+#[derive(Debug, Clone, Copy)]
+pub struct ControllerIdentity {
+    /// Tenant whose workers the controller manages.
+    pub tenant_id: Uuid,
+}
+
+impl ControllerIdentity {
+    /// Check that the caller may act for `tenant_id`.
+    ///
+    /// # Errors
+    /// Returns `Forbidden` for any other tenant.
+    pub fn authorize(self, tenant_id: Uuid) -> Result<()> {
+        if self.tenant_id == tenant_id {
+            Ok(())
+        } else {
+            Err(OrchestratorError::Forbidden(format!(
+                "controller token is not valid for tenant {tenant_id}"
+            )))
+        }
+    }
+}
+
+/// Issues and verifies tokens.
+///
+/// Configured tokens are only kept as HMACs under the signing key, so looking them
+/// up does not compare secrets directly.
+pub struct Authenticator {
+    key: hmac::Key,
+    registration: HashMap<Vec<u8>, Uuid>,
+    controller: HashMap<Vec<u8>, Uuid>,
+    worker_token_ttl: chrono::Duration,
+}
+
+impl Authenticator {
+    /// Create an authenticator from the service configuration.
+    #[must_use]
+    pub fn new(config: &Config) -> Self {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, config.token_secret.expose().as_bytes());
+        let digests = |tokens: &TenantTokens| {
+            tokens
+                .0
+                .iter()
+                .map(|(tenant_id, token)| (digest(&key, token.expose()), *tenant_id))
+                .collect()
+        };
+
+        Self {
+            registration: digests(&config.registration_tokens),
+            controller: digests(&config.controller_tokens),
+            worker_token_ttl: chrono::Duration::seconds(
+                i64::try_from(config.worker_token_ttl_secs).unwrap_or(i64::MAX),
+            ),
+            key,
+        }
+    }
+
+    /// Issue a worker token, returning it with its expiry time.
+    #[must_use]
+    pub fn issue_worker_token(&self, worker_id: Uuid, tenant_id: Uuid) -> (String, DateTime<Utc>) {
+        let expires_at = Utc::now() + self.worker_token_ttl;
+        let payload = format!("{worker_id}.{tenant_id}.{}", expires_at.timestamp());
+        let signature = URL_SAFE_NO_PAD.encode(hmac::sign(&self.key, payload.as_bytes()));
+        (format!("{payload}.{signature}"), expires_at)
+    }
+
+    /// Verify a worker token, returning the identity it was issued to.
+    #[must_use]
+    pub fn verify_worker_token(&self, token: &str) -> Option<WorkerIdentity> {
+        let (payload, signature) = token.rsplit_once('.')?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+        hmac::verify(&self.key, payload.as_bytes(), &signature).ok()?;
+
+        let mut parts = payload.split('.');
+        let worker_id = parts.next()?.parse().ok()?;
+        let tenant_id = parts.next()?.parse().ok()?;
+        let expires_at = parts.next()?.parse::<i64>().ok()?;
+        if parts.next().is_some() || expires_at <= Utc::now().timestamp() {
+            return None;
+        }
+
+        Some(WorkerIdentity {
+            worker_id,
+            tenant_id,
+        })
+    }
+
+    /// The identity holding a registration token, if it is one.
+    #[must_use]
+    pub fn verify_registration_token(&self, token: &str) -> Option<RegistrationIdentity> {
+        self.registration
+            .get(&digest(&self.key, token))
+            .map(|&tenant_id| RegistrationIdentity { tenant_id })
+    }
+
+    /// The identity holding a controller token, if it is one.
+    #[must_use]
+    pub fn verify_controller_token(&self, token: &str) -> Option<ControllerIdentity> {
+        self.controller
+            .get(&digest(&self.key, token))
+            .map(|&tenant_id| ControllerIdentity { tenant_id })
+    }
+}
+
+fn digest(key: &hmac::Key, token: &str) -> Vec<u8> {
+    hmac::sign(key, token.as_bytes()).as_ref().to_vec()
+}
+
+/// Middleware admitting requests with a registration token.
+///
+/// # Errors
+/// Returns `Unauthorized` if the token is missing or unknown, or a required client
+/// certificate is missing, and `Forbidden` if the certificate is of another tenant.
+pub async fn require_registration(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response> {
+    let identity = state
+        .auth
+        .verify_registration_token(bearer_token(&request)?)
+        .ok_or_else(|| OrchestratorError::Unauthorized("invalid registration token".to_string()))?;
+    check_certificate(&state, &request, identity.tenant_id)?;
+    request.extensions_mut().insert(identity);
+    Ok(next.run(request).await)
+}
+
+/// Middleware admitting requests with a valid worker token.
+///
+/// # Errors
+/// Returns `Unauthorized` if the token is missing, forged or expired, or a required
+/// client certificate is missing, and `Forbidden` if the certificate is of another
+/// tenant.
+pub async fn require_worker(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response> {
+    let identity = state
+        .auth
+        .verify_worker_token(bearer_token(&request)?)
+        .ok_or_else(|| {
+            OrchestratorError::Unauthorized("invalid or expired worker token".to_string())
+        })?;
+    check_certificate(&state, &request, identity.tenant_id)?;
+    request.extensions_mut().insert(identity);
+    Ok(next.run(request).await)
+}
+
+/// Middleware admitting requests with a controller token.
+///
+/// # Errors
+/// Returns `Unauthorized` if the token is missing or unknown.
+pub async fn require_controller(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response> {
+    let identity = state
+        .auth
+        .verify_controller_token(bearer_token(&request)?)
+        .ok_or_else(|| OrchestratorError::Unauthorized("invalid controller token".to_string()))?;
+    request.extensions_mut().insert(identity);
+    Ok(next.run(request).await)
+}
+
+/// Check that a request comes with a client certificate naming `tenant_id`, if the
+/// listener verifies client certificates.
+fn check_certificate(state: &AppState, request: &Request, tenant_id: Uuid) -> Result<()> {
+    if !state
+        .config
+        .tls
+        .as_ref()
+        .is_some_and(TlsConfig::verifies_clients)
+    {
+        return Ok(());
+    }
+
+    match request.extensions().get::<CertificateIdentity>() {
+        Some(certificate) if certificate.tenant_id == tenant_id => Ok(()),
+        Some(certificate) => Err(OrchestratorError::Forbidden(format!(
+            "client certificate of tenant {} is not valid for tenant {tenant_id}",
+            certificate.tenant_id
+        ))),
+        None => Err(OrchestratorError::Unauthorized(
+            "missing client certificate naming a tenant".to_string(),
+        )),
+    }
+}
+
+/// The bearer token of a request.
+fn bearer_token(request: &Request) -> Result<&str> {
+    request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| OrchestratorError::Unauthorized("missing bearer token".to_string()))
+}
@@ -2,6 +2,13 @@
 
 use std::env;
 
+use crate::auth::{Secret, TenantTokens};
+use crate::tls::TlsConfig;
+
+/// Advisory lock key used for leader election unless `LEADER_LOCK_KEY` is set
+/// ("vulcan" in ASCII).
+pub const DEFAULT_LEADER_LOCK_KEY: i64 = 0x7675_6c63_616e;
+
 /// Configuration for the worker orchestrator.
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -11,12 +18,34 @@ pub struct Config {
     pub host: String,
     /// Port to bind the HTTP server to.
     pub port: u16,
+    /// Port to serve Prometheus metrics on, apart from the API (optional).
+    pub metrics_port: Option<u16>,
     /// Heartbeat timeout in seconds (workers not heard from in this time are considered dead).
     pub heartbeat_timeout_secs: u64,
     /// How often to run the health check in seconds.
     pub health_check_interval_secs: u64,
     /// Maximum retry attempts for failed fragments.
     pub max_retry_attempts: i32,
+    /// Longest time in seconds a work request may wait for work to become ready.
+    pub max_work_wait_secs: u64,
+    /// Key signing worker tokens.
+    pub token_secret: Secret,
+    /// Per-tenant tokens workers register with.
+    pub registration_tokens: TenantTokens,
+    /// Per-tenant tokens of worker controllers.
+    pub controller_tokens: TenantTokens,
+    /// Lifetime of worker tokens in seconds; they are renewed on every heartbeat.
+    pub worker_token_ttl_secs: u64,
+    /// How long in seconds errored and deregistered workers are kept before deletion.
+    pub worker_retention_secs: u64,
+    /// Key of the advisory lock replicas elect their leader with.
+    pub leader_lock_key: i64,
+    /// How often in seconds the leader renews its lease.
+    pub leader_renew_interval_secs: u64,
+    /// How long in seconds a lease lasts without renewal.
+    pub leader_lease_secs: u64,
+    /// TLS settings of the listener; plain HTTP is served without them.
+    pub tls: Option<TlsConfig>,
 }
 
 impl Config {
@@ -32,6 +61,9 @@ impl Config {
                 .unwrap_or_else(|_| "3002".to_string())
                 .parse()
                 .expect("PORT must be a valid number"),
+            metrics_port: env::var("METRICS_PORT")
+                .ok()
+                .map(|port| port.parse().expect("METRICS_PORT must be a valid number")),
             heartbeat_timeout_secs: env::var("HEARTBEAT_TIMEOUT_SECS")
                 .unwrap_or_else(|_| "30".to_string())
                 .parse()
@@ -44,6 +76,40 @@ impl Config {
                 .unwrap_or_else(|_| "3".to_string())
                 .parse()
                 .expect("MAX_RETRY_ATTEMPTS must be a valid number"),
+            max_work_wait_secs: env::var("MAX_WORK_WAIT_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .expect("MAX_WORK_WAIT_SECS must be a valid number"),
+            token_secret: Secret::new(env::var("TOKEN_SECRET").expect("TOKEN_SECRET must be set")),
+            registration_tokens: TenantTokens::parse(
+                &env::var("REGISTRATION_TOKENS").unwrap_or_default(),
+            )
+            .unwrap_or_else(|e| panic!("REGISTRATION_TOKENS is invalid: {e}")),
+            controller_tokens: TenantTokens::parse(
+                &env::var("CONTROLLER_TOKENS").unwrap_or_default(),
+            )
+            .unwrap_or_else(|e| panic!("CONTROLLER_TOKENS is invalid: {e}")),
+            worker_token_ttl_secs: env::var("WORKER_TOKEN_TTL_SECS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()
+                .expect("WORKER_TOKEN_TTL_SECS must be a valid number"),
+            worker_retention_secs: env::var("WORKER_RETENTION_SECS")
+                .unwrap_or_else(|_| "86400".to_string())
+                .parse()
+                .expect("WORKER_RETENTION_SECS must be a valid number"),
+            leader_lock_key: env::var("LEADER_LOCK_KEY")
+                .unwrap_or_else(|_| DEFAULT_LEADER_LOCK_KEY.to_string())
+                .parse()
+                .expect("LEADER_LOCK_KEY must be a valid number"),
+            leader_renew_interval_secs: env::var("LEADER_RENEW_INTERVAL_SECS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .expect("LEADER_RENEW_INTERVAL_SECS must be a valid number"),
+            leader_lease_secs: env::var("LEADER_LEASE_SECS")
+                .unwrap_or_else(|_| "15".to_string())
+                .parse()
+                .expect("LEADER_LEASE_SECS must be a valid number"),
+            tls: tls_from_env(),
         }
     }
 
@@ -51,4 +117,33 @@ impl Config {
     pub fn socket_addr(&self) -> String {
         format!("{}:{}", self.host, self.port)
     }
+
+    /// Returns the socket address to serve metrics on, if enabled.
+    #[must_use]
+    pub fn metrics_addr(&self) -> Option<String> {
+        self.metrics_port
+            .map(|port| format!("{}:{port}", self.host))
+    }
+}
+
+/// TLS settings, enabled by setting both `TLS_CERT_FILE` and `TLS_KEY_FILE`.
+fn tls_from_env() -> Option<TlsConfig> {
+    match (
+        env::var("TLS_CERT_FILE").ok(),
+        env::var("TLS_KEY_FILE").ok(),
+    ) {
+        (Some(cert_file), Some(key_file)) => Some(TlsConfig {
+            cert_file,
+            key_file,
+            client_ca_file: env::var("TLS_CLIENT_CA_FILE").ok(),
+        }),
+        (None, None) => {
+            assert!(
+                env::var("TLS_CLIENT_CA_FILE").is_err(),
+                "TLS_CLIENT_CA_FILE requires TLS_CERT_FILE and TLS_KEY_FILE"
+            );
+            None
+        },
+        _ => panic!("TLS_CERT_FILE and TLS_KEY_FILE must be set together"),
+    }
 }
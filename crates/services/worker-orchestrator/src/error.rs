@@ -36,6 +36,28 @@ pub enum OrchestratorError {
     /// Invalid request.
     #[error("Invalid request: {0}")]
     InvalidRequest(String),
+
+    /// Missing or invalid credentials.
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// Valid credentials that do not grant access to the resource.
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    /// Result reported for a fragment the worker no longer owns.
+    #[error("Stale result: {0}")]
+    StaleResult(String),
+
+    /// Registration under the name of a worker that is still alive.
+    #[error("Worker name in use: {0}")]
+    WorkerNameInUse(String),
+}
+
+impl From<diesel::result::Error> for OrchestratorError {
+    fn from(error: diesel::result::Error) -> Self {
+        Self::Database(error.into())
+    }
 }
 
 /// Error response body.
@@ -55,6 +77,11 @@ impl IntoResponse for OrchestratorError {
             }
             Self::NoWorkAvailable => (StatusCode::NO_CONTENT, self.to_string()),
             Self::InvalidRequest(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            Self::Unauthorized(_) => (StatusCode::UNAUTHORIZED, self.to_string()),
+            Self::Forbidden(_) => (StatusCode::FORBIDDEN, self.to_string()),
+            Self::StaleResult(_) | Self::WorkerNameInUse(_) => {
+                (StatusCode::CONFLICT, self.to_string())
+            }
         };
 
         let body = Json(ErrorResponse { error: message });
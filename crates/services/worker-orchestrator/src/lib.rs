@@ -4,10 +4,13 @@
 //! including API handlers, scheduling logic, and health monitoring.
 
 pub mod api;
+pub mod auth;
 pub mod config;
 pub mod error;
+pub mod metrics;
 pub mod orchestrator;
 pub mod state;
+pub mod tls;
 
 pub use config::Config;
 pub use error::{OrchestratorError, Result};
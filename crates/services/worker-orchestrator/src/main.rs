@@ -5,10 +5,12 @@
 use std::net::SocketAddr;
 
 use tracing::info;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use vulcan_worker_orchestrator::api::create_router;
+use vulcan_worker_orchestrator::api::{create_metrics_router, create_router};
 use vulcan_worker_orchestrator::orchestrator::health::start_health_monitor;
+use vulcan_worker_orchestrator::orchestrator::leader::start_leader_election;
+use vulcan_worker_orchestrator::orchestrator::readiness::start_readiness_listener;
+use vulcan_worker_orchestrator::tls::serve_tls;
 use vulcan_worker_orchestrator::{AppState, Config};
 
 #[tokio::main]
@@ -17,13 +19,14 @@ async fn main() {
     dotenvy::dotenv().ok();
 
     // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "vulcan_worker_orchestrator=debug,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    let _telemetry = vulcan_telemetry::init(
+        "vulcan-worker-orchestrator",
+        "vulcan_worker_orchestrator=debug,tower_http=debug",
+    )
+    .expect("Failed to set up trace export");
+
+    // Record metrics from here on
+    vulcan_worker_orchestrator::metrics::install();
 
     // Load configuration
     let config = Config::from_env();
@@ -32,8 +35,34 @@ async fn main() {
     // Create application state
     let state = AppState::new(config);
 
+    // Elect the replica running background tasks
+    let leadership = start_leader_election(&state.config);
+
     // Start the health monitor background task
-    start_health_monitor(state.pool.clone(), state.config.clone());
+    start_health_monitor(state.pool.clone(), state.config.clone(), leadership);
+
+    // Forward fragment readiness notifications to waiting work requests
+    start_readiness_listener(state.config.database_url.clone(), state.readiness.clone());
+
+    let tls = state.config.tls.as_ref().map(|tls| {
+        tls.server_config()
+            .unwrap_or_else(|e| panic!("Invalid TLS configuration: {e}"))
+    });
+
+    // Serve the metrics apart from the API
+    if let Some(metrics_addr) = state.config.metrics_addr() {
+        let metrics_addr: SocketAddr = metrics_addr.parse().expect("Invalid metrics address");
+        let listener = tokio::net::TcpListener::bind(metrics_addr)
+            .await
+            .expect("Failed to bind to metrics address");
+        let metrics = create_metrics_router(state.clone());
+        info!("Serving metrics on {}", metrics_addr);
+        tokio::spawn(async move {
+            axum::serve(listener, metrics)
+                .await
+                .expect("Metrics server error");
+        });
+    }
 
     // Create the router
     let app = create_router(state);
@@ -41,14 +70,16 @@ async fn main() {
     // Parse socket address
     let socket_addr: SocketAddr = addr.parse().expect("Invalid socket address");
 
-    info!("Starting Vulcan Worker Orchestrator on {}", socket_addr);
-
     // Start the server
     let listener = tokio::net::TcpListener::bind(socket_addr)
         .await
         .expect("Failed to bind to address");
 
-    axum::serve(listener, app)
-        .await
-        .expect("Server error");
+    if let Some(tls) = tls {
+        info!("Starting Vulcan Worker Orchestrator on {} (TLS)", socket_addr);
+        serve_tls(listener, app, tls).await;
+    } else {
+        info!("Starting Vulcan Worker Orchestrator on {}", socket_addr);
+        axum::serve(listener, app).await.expect("Server error");
+    }
 }
@@ -0,0 +1,152 @@
+//! Prometheus metrics of the orchestrator, served on `/metrics` of `METRICS_PORT`
+//! when set.
+//!
+//! Counters and histograms are recorded where the events happen. Queue depth and
+//! worker counts are read from the database on each scrape instead, so every
+//! replica reports the same, current values and series of tenants without work
+//! disappear.
+
+use std::fmt::Write;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use metrics::{counter, describe_counter, describe_histogram, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+use vulcan_core::models::fragment::QueueDepth;
+use vulcan_core::models::worker::WorkerStatus;
+
+/// Time to claim work for a worker, by whether a fragment was claimed.
+pub const CLAIM_DURATION: &str = "vulcan_claim_duration_seconds";
+/// Claims that found nothing right after a fragment became ready, i.e. another
+/// worker claimed it first.
+pub const CLAIM_CONFLICTS: &str = "vulcan_claim_conflicts_total";
+/// Time from claim to result of fragments, by outcome.
+pub const FRAGMENT_DURATION: &str = "vulcan_fragment_duration_seconds";
+/// Chains that finished, by status.
+pub const CHAINS_FINISHED: &str = "vulcan_chains_finished_total";
+/// Workers the health monitor found dead.
+pub const DEAD_WORKERS: &str = "vulcan_health_monitor_dead_workers_total";
+/// Fragments the health monitor reset after their worker died.
+pub const FRAGMENT_RESETS: &str = "vulcan_health_monitor_fragment_resets_total";
+/// Retired workers the health monitor deleted.
+pub const WORKERS_DELETED: &str = "vulcan_health_monitor_workers_deleted_total";
+/// Fragments by tenant, machine group and status (pending or running).
+pub const QUEUE_DEPTH: &str = "vulcan_queue_depth";
+/// Workers by status.
+pub const WORKERS: &str = "vulcan_workers";
+
+const CLAIM_BUCKETS: &[f64] = &[0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0];
+const FRAGMENT_BUCKETS: &[f64] = &[
+    1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0,
+];
+
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Install the Prometheus recorder, once per process.
+///
+/// # Panics
+/// Panics if the histogram buckets are invalid.
+pub fn install() -> &'static PrometheusHandle {
+    HANDLE.get_or_init(|| {
+        let recorder = PrometheusBuilder::new()
+            .set_buckets_for_metric(Matcher::Full(CLAIM_DURATION.to_string()), CLAIM_BUCKETS)
+            .and_then(|builder| {
+                builder.set_buckets_for_metric(
+                    Matcher::Full(FRAGMENT_DURATION.to_string()),
+                    FRAGMENT_BUCKETS,
+                )
+            })
+            .expect("Invalid histogram buckets")
+            .build_recorder();
+        let handle = recorder.handle();
+        // Another recorder only takes over the events recorded here
+        let _ = metrics::set_global_recorder(recorder);
+
+        describe_histogram!(CLAIM_DURATION, "Time to claim work for a worker");
+        describe_counter!(
+            CLAIM_CONFLICTS,
+            "Claims that found nothing right after a fragment became ready"
+        );
+        describe_histogram!(FRAGMENT_DURATION, "Time from claim to result of fragments");
+        describe_counter!(CHAINS_FINISHED, "Chains that finished");
+        describe_counter!(DEAD_WORKERS, "Workers found dead");
+        describe_counter!(FRAGMENT_RESETS, "Fragments reset after their worker died");
+        describe_counter!(WORKERS_DELETED, "Retired workers deleted");
+
+        handle
+    })
+}
+
+/// Record a claim attempt.
+pub fn record_claim(duration: Duration, claimed: bool) {
+    let result = if claimed { "claimed" } else { "empty" };
+    histogram!(CLAIM_DURATION, "result" => result).record(duration.as_secs_f64());
+}
+
+/// Record a claim that lost the fragment it was woken up for to another worker.
+pub fn record_claim_conflict() {
+    counter!(CLAIM_CONFLICTS).increment(1);
+}
+
+/// Record a fragment whose result was reported.
+pub fn record_fragment_duration(outcome: &'static str, duration: Duration) {
+    histogram!(FRAGMENT_DURATION, "outcome" => outcome).record(duration.as_secs_f64());
+}
+
+/// Record a chain that finished.
+pub fn record_chain_finished(failed: bool) {
+    let status = if failed { "failed" } else { "completed" };
+    counter!(CHAINS_FINISHED, "status" => status).increment(1);
+}
+
+/// Record a worker the health monitor found dead, with the fragments it had in flight.
+pub fn record_dead_worker(fragments_reset: usize) {
+    counter!(DEAD_WORKERS).increment(1);
+    counter!(FRAGMENT_RESETS).increment(fragments_reset as u64);
+}
+
+/// Record retired workers the health monitor deleted.
+pub fn record_workers_deleted(deleted: usize) {
+    counter!(WORKERS_DELETED).increment(deleted as u64);
+}
+
+/// Render all metrics in the Prometheus text format, with the queue depth and
+/// worker counts read from the database.
+#[must_use]
+pub fn render(queue: &[QueueDepth], workers: &[(WorkerStatus, i64)]) -> String {
+    let mut out = install().render();
+
+    let _ = writeln!(
+        out,
+        "# HELP {QUEUE_DEPTH} Fragments by tenant, machine group and status"
+    );
+    let _ = writeln!(out, "# TYPE {QUEUE_DEPTH} gauge");
+    for depth in queue {
+        let _ = writeln!(
+            out,
+            "{QUEUE_DEPTH}{{tenant_id=\"{}\",machine_group=\"{}\",status=\"{}\"}} {}",
+            depth.tenant_id,
+            escape(depth.machine_group.as_deref().unwrap_or_default()),
+            format!("{:?}", depth.status).to_lowercase(),
+            depth.count
+        );
+    }
+
+    let _ = writeln!(out, "# HELP {WORKERS} Workers by status");
+    let _ = writeln!(out, "# TYPE {WORKERS} gauge");
+    for (status, count) in workers {
+        let status = format!("{status:?}").to_lowercase();
+        let _ = writeln!(out, "{WORKERS}{{status=\"{status}\"}} {count}");
+    }
+
+    out
+}
+
+/// Escape a label value for the Prometheus text format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
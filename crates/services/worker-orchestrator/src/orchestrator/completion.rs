@@ -0,0 +1,45 @@
+//! Chain completion once all of its fragments have finished.
+
+use diesel::PgConnection;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use vulcan_core::repositories::{
+    ChainRepository, FragmentRepository, PgChainRepository, PgFragmentRepository,
+};
+
+/// Mark a chain as completed, or failed if any fragment failed, once all of its
+/// fragments are terminal.
+///
+/// Call this in the transaction that finished a fragment, after releasing its
+/// successors: that locks the chain, so concurrent reports for the same chain are
+/// serialized and the last one sees every other fragment finished.
+///
+/// # Errors
+///
+/// Returns an error if the chain or its fragments cannot be loaded or updated.
+pub fn check_chain_completion(
+    conn: &mut PgConnection,
+    chain_id: Uuid,
+) -> vulcan_core::repositories::Result<()> {
+    let fragments = PgFragmentRepository::new(conn).find_by_chain(chain_id)?;
+
+    if !fragments.iter().all(|f| f.status.is_terminal()) {
+        return Ok(());
+    }
+
+    let any_failed = fragments.iter().any(|f| !f.status.is_success());
+    let mut chain_repo = PgChainRepository::new(conn);
+
+    crate::metrics::record_chain_finished(any_failed);
+
+    if any_failed {
+        chain_repo.mark_failed(chain_id)?;
+        warn!(chain_id = %chain_id, "Chain failed");
+    } else {
+        chain_repo.mark_completed(chain_id)?;
+        info!(chain_id = %chain_id, "Chain completed successfully");
+    }
+
+    Ok(())
+}
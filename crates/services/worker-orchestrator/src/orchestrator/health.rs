@@ -1,9 +1,10 @@
 //! Health monitor for detecting dead workers.
 //!
-//! Background task that runs periodically to:
+//! Background task that runs periodically, in the leader replica only, to:
 //! 1. Find workers whose heartbeat is older than the timeout threshold
 //! 2. Mark dead workers as Error status
-//! 3. Reset their assigned fragments to Pending for retry (if under max attempts)
+//! 3. Reset each of their in-flight fragments to Pending for retry (if under max attempts)
+//! 4. Delete workers that errored or deregistered longer than the retention period ago
 
 use std::sync::Arc;
 use std::time::Duration;
@@ -12,22 +13,38 @@ use chrono::Utc;
 use tokio::time::interval;
 use tracing::{error, info, warn};
 
+use diesel::{Connection, PgConnection};
+use uuid::Uuid;
+
+use vulcan_core::models::fragment::{Fragment, FragmentStatus};
 use vulcan_core::models::worker::WorkerStatus;
 use vulcan_core::repositories::{
-    FragmentRepository, PgFragmentRepository, PgWorkerRepository, WorkerRepository,
+    FragmentRepository, PgFragmentRepository, PgWorkerRepository, RepositoryError,
+    WorkerRepository,
 };
 
 use crate::config::Config;
+use crate::orchestrator::completion::check_chain_completion;
+use crate::orchestrator::leader::Leadership;
+use crate::error::Result;
+use crate::metrics;
 use crate::state::DbPool;
 
 /// Start the health monitor background task.
-pub fn start_health_monitor(pool: DbPool, config: Arc<Config>) {
+///
+/// Every replica runs the task, but it only checks worker health while
+/// `leadership` says this replica leads.
+pub fn start_health_monitor(pool: DbPool, config: Arc<Config>, leadership: Leadership) {
     tokio::spawn(async move {
         let mut ticker = interval(Duration::from_secs(config.health_check_interval_secs));
 
         loop {
             ticker.tick().await;
 
+            if !leadership.is_leader() {
+                continue;
+            }
+
             if let Err(e) = check_worker_health(&pool, &config) {
                 error!(error = %e, "Health check failed");
             }
@@ -36,7 +53,10 @@ pub fn start_health_monitor(pool: DbPool, config: Arc<Config>) {
 }
 
 /// Check for dead workers and handle them.
-fn check_worker_health(pool: &DbPool, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+fn check_worker_health(
+    pool: &DbPool,
+    config: &Config,
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
     let mut conn = pool.get()?;
 
     // Calculate threshold time
@@ -57,48 +77,132 @@ fn check_worker_health(pool: &DbPool, config: &Config) -> Result<(), Box<dyn std
         );
 
         // Mark worker as error
-        {
-            let mut worker_repo = PgWorkerRepository::new(&mut conn);
-            let mut worker_to_update = worker.clone();
-            worker_to_update.status = WorkerStatus::Error;
-            worker_repo.update(&worker_to_update)?;
-        }
+        PgWorkerRepository::new(&mut conn).retire(worker.id, WorkerStatus::Error)?;
+
+        // Reset every fragment the worker had in flight
+        let fragments_reset = recover_worker(
+            &mut conn,
+            config,
+            worker.id,
+            "Worker died and max retry attempts exceeded",
+        )?;
+        metrics::record_dead_worker(fragments_reset);
+    }
 
-        // If worker had an assigned fragment, reset it for retry
-        if let Some(fragment_id) = worker.current_fragment_id {
-            let fragment = {
-                let mut fragment_repo = PgFragmentRepository::new(&mut conn);
-                fragment_repo.find_by_id(fragment_id)?
-            };
-
-            if let Some(fragment) = fragment {
-                let mut fragment_repo = PgFragmentRepository::new(&mut conn);
-                if fragment.attempt < config.max_retry_attempts {
-                    info!(
-                        fragment_id = %fragment_id,
-                        attempt = fragment.attempt,
-                        max_attempts = config.max_retry_attempts,
-                        "Resetting fragment for retry"
-                    );
-                    fragment_repo.reset_for_retry(fragment_id)?;
-                } else {
-                    warn!(
-                        fragment_id = %fragment_id,
-                        attempt = fragment.attempt,
-                        "Fragment exceeded max retry attempts, marking as failed"
-                    );
-                    fragment_repo.fail_execution(
-                        fragment_id,
-                        "Worker died and max retry attempts exceeded".to_string(),
-                    )?;
-                }
-            }
+    let deleted = delete_retired_workers(&mut conn, config)?;
+    if deleted > 0 {
+        info!(deleted, "Deleted retired workers");
+        metrics::record_workers_deleted(deleted);
+    }
 
-            // Clear the worker's assignment
-            let mut worker_repo = PgWorkerRepository::new(&mut conn);
-            worker_repo.clear_assignment(worker.id)?;
-        }
+    Ok(())
+}
+
+/// Delete workers that errored or deregistered more than `WORKER_RETENTION_SECS` ago.
+///
+/// Returns the number of deleted workers.
+///
+/// # Errors
+///
+/// Returns an error if the workers cannot be deleted.
+pub fn delete_retired_workers(conn: &mut PgConnection, config: &Config) -> Result<usize> {
+    let retired_before = Utc::now().naive_utc()
+        - chrono::Duration::seconds(
+            i64::try_from(config.worker_retention_secs).unwrap_or(i64::MAX),
+        );
+    Ok(PgWorkerRepository::new(conn).delete_retired(retired_before)?)
+}
+
+/// Recover every fragment a worker has in flight and clear its assignments, for a
+/// worker that died, deregistered or restarted.
+///
+/// Returns the number of fragments the worker had in flight.
+///
+/// # Errors
+///
+/// Returns an error if the worker's fragments cannot be loaded or recovered.
+pub fn recover_worker(
+    conn: &mut PgConnection,
+    config: &Config,
+    worker_id: Uuid,
+    exhausted_message: &str,
+) -> Result<usize> {
+    let fragment_ids = PgWorkerRepository::new(conn).find_assigned_fragments(worker_id)?;
+
+    for fragment_id in &fragment_ids {
+        recover_fragment(conn, config, *fragment_id, exhausted_message)?;
     }
 
+    PgWorkerRepository::new(conn).clear_assignments(worker_id)?;
+    Ok(fragment_ids.len())
+}
+
+/// Reset a fragment whose worker can no longer complete it.
+///
+/// The fragment is reset to Pending for retry if it is under the maximum number of
+/// attempts, and marked as failed with `exhausted_message` otherwise, which makes its
+/// successors ready and may finish its chain. Fragments that are no longer running,
+/// e.g. because their result was reported meanwhile, are left alone.
+///
+/// # Errors
+///
+/// Returns an error if the fragment cannot be loaded or updated.
+pub fn recover_fragment(
+    conn: &mut PgConnection,
+    config: &Config,
+    fragment_id: Uuid,
+    exhausted_message: &str,
+) -> Result<()> {
+    conn.transaction::<_, RepositoryError, _>(|conn| {
+        let Some(fragment) = PgFragmentRepository::new(conn).find_by_id_for_update(fragment_id)?
+        else {
+            return Ok(());
+        };
+        if fragment.status != FragmentStatus::Running {
+            return Ok(());
+        }
+
+        retry_or_fail(conn, config, &fragment, exhausted_message)?;
+        Ok(())
+    })?;
+
     Ok(())
 }
+
+/// Reset a running fragment to Pending for retry, or fail it with `exhausted_message`
+/// once it has used up its attempts, which makes its successors ready and may finish
+/// its chain.
+///
+/// Call this in the transaction holding the fragment's row lock.
+///
+/// # Errors
+///
+/// Returns an error if the fragment or its chain cannot be updated.
+pub fn retry_or_fail(
+    conn: &mut PgConnection,
+    config: &Config,
+    fragment: &Fragment,
+    exhausted_message: &str,
+) -> vulcan_core::repositories::Result<Fragment> {
+    let mut fragment_repo = PgFragmentRepository::new(conn);
+
+    if fragment.attempt < config.max_retry_attempts {
+        info!(
+            fragment_id = %fragment.id,
+            attempt = fragment.attempt,
+            max_attempts = config.max_retry_attempts,
+            "Resetting fragment for retry"
+        );
+        return fragment_repo.reset_for_retry(fragment.id);
+    }
+
+    warn!(
+        fragment_id = %fragment.id,
+        attempt = fragment.attempt,
+        "Fragment exceeded max retry attempts, marking as failed"
+    );
+    let failed = fragment_repo.fail_execution(fragment.id, exhausted_message.to_string())?;
+    fragment_repo.release_successors(fragment.id)?;
+    check_chain_completion(conn, fragment.chain_id)?;
+    Ok(failed)
+}
@@ -0,0 +1,136 @@
+//! Leader election among orchestrator replicas.
+//!
+//! Request handling is stateless and runs in every replica, but background tasks
+//! such as the health monitor must run in one replica at a time. The replicas
+//! elect a leader with a Postgres session-level advisory lock: an election thread
+//! holds a dedicated connection and tries to take the lock, and whoever holds it
+//! leads until its session ends.
+//!
+//! Leadership is a lease. The leader checks every `LEADER_RENEW_INTERVAL_SECS`
+//! that its session still holds the lock, and each successful check extends the
+//! lease by `LEADER_LEASE_SECS`. When a check fails, e.g. because the connection
+//! was lost and Postgres released the lock, the replica steps down immediately;
+//! when checks hang, leadership lapses with the lease. A replica that lost
+//! leadership waits for one lease before campaigning again, so a follower takes
+//! over. Background tasks check [`Leadership::is_leader`] before each run.
+
+use std::error::Error;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use diesel::sql_types::{BigInt, Bool};
+use diesel::{Connection, PgConnection, QueryableByName, RunQueryDsl};
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use crate::config::Config;
+
+/// Current lease of this replica, if it leads.
+#[derive(Clone)]
+pub struct Leadership {
+    lease: watch::Receiver<Option<Instant>>,
+}
+
+impl Leadership {
+    /// Whether this replica currently leads, i.e. holds an unexpired lease.
+    #[must_use]
+    pub fn is_leader(&self) -> bool {
+        self.lease
+            .borrow()
+            .is_some_and(|expires_at| Instant::now() < expires_at)
+    }
+}
+
+#[derive(QueryableByName)]
+struct Held {
+    #[diesel(sql_type = Bool)]
+    held: bool,
+}
+
+/// Take the lock if it is free; a no-op returning true if this session holds it.
+const TRY_LOCK_SQL: &str = "SELECT pg_try_advisory_lock($1) AS held";
+
+/// Whether this session holds the lock. A bigint advisory lock key is split into
+/// `classid` (high half) and `objid` (low half), with `objsubid` 1.
+const HOLDS_LOCK_SQL: &str = "SELECT EXISTS (
+    SELECT 1 FROM pg_locks
+    WHERE locktype = 'advisory'
+      AND pid = pg_backend_pid()
+      AND granted
+      AND classid = ($1 >> 32)::oid
+      AND objid = ($1 & 4294967295)::oid
+      AND objsubid = 1
+) AS held";
+
+/// Start the background thread campaigning for leadership.
+///
+/// # Panics
+/// Panics if the thread cannot be spawned.
+#[must_use]
+pub fn start_leader_election(config: &Config) -> Leadership {
+    let (sender, lease) = watch::channel(None);
+    let database_url = config.database_url.clone();
+    let key = config.leader_lock_key;
+    let renew_interval = Duration::from_secs(config.leader_renew_interval_secs);
+    let lease_duration = Duration::from_secs(config.leader_lease_secs);
+
+    thread::Builder::new()
+        .name("leader-election".to_string())
+        .spawn(move || {
+            loop {
+                let result = campaign(&database_url, key, renew_interval, lease_duration, &sender);
+                let was_leader = sender.send_replace(None).is_some();
+                if let Err(e) = result {
+                    if was_leader {
+                        warn!(error = %e, "Lost leadership");
+                    } else {
+                        warn!(error = %e, "Leader election failed, retrying");
+                    }
+                }
+                // Give a follower the chance to take over before campaigning again
+                thread::sleep(lease_duration);
+            }
+        })
+        .expect("Failed to spawn leader election thread");
+
+    Leadership { lease }
+}
+
+/// Campaign for the lock on a new connection, and renew the lease while holding it.
+///
+/// Returns when the connection fails or the lock is lost.
+fn campaign(
+    database_url: &str,
+    key: i64,
+    renew_interval: Duration,
+    lease_duration: Duration,
+    sender: &watch::Sender<Option<Instant>>,
+) -> Result<(), Box<dyn Error>> {
+    let mut conn = PgConnection::establish(database_url)?;
+    let mut leading = false;
+
+    loop {
+        let checked_at = Instant::now();
+        let query = if leading {
+            HOLDS_LOCK_SQL
+        } else {
+            TRY_LOCK_SQL
+        };
+        let held = diesel::sql_query(query)
+            .bind::<BigInt, _>(key)
+            .get_result::<Held>(&mut conn)?
+            .held;
+
+        if held {
+            if !leading {
+                info!(key, "Acquired leadership");
+                leading = true;
+            }
+            sender.send_replace(Some(checked_at + lease_duration));
+        } else if leading {
+            return Err("advisory lock no longer held".into());
+        }
+
+        thread::sleep(renew_interval);
+    }
+}
@@ -1,4 +1,7 @@
 //! Orchestrator logic for managing workers and fragments.
 
+pub mod completion;
 pub mod health;
+pub mod leader;
+pub mod readiness;
 pub mod scheduler;
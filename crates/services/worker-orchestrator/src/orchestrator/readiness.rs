@@ -0,0 +1,133 @@
+//! Notifications of fragments becoming ready to claim.
+//!
+//! A database trigger announces every fragment that becomes `Pending` on the
+//! `fragment_ready` channel, with the tenant of its chain as payload. A listener
+//! thread holds a dedicated connection subscribed to that channel and broadcasts
+//! the tenants to long-polling work requests, which then retry their claim.
+
+use std::error::Error;
+use std::future::pending;
+use std::thread;
+use std::time::Duration;
+
+use diesel::{Connection, PgConnection, RunQueryDsl};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// Postgres channel on which ready fragments are announced.
+pub const CHANNEL: &str = "fragment_ready";
+
+/// How often the listener checks its connection for notifications.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Delay before the listener reconnects after losing its connection.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Capacity of the broadcast channel; slower subscribers see a lag instead.
+const CAPACITY: usize = 1024;
+
+/// Broadcasts the tenant of every fragment that becomes ready.
+#[derive(Clone)]
+pub struct ReadinessNotifier {
+    sender: broadcast::Sender<Uuid>,
+}
+
+impl ReadinessNotifier {
+    /// Create a notifier without subscribers.
+    #[must_use]
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        Self { sender }
+    }
+
+    /// Announce that a fragment of the given tenant is ready.
+    pub fn notify(&self, tenant_id: Uuid) {
+        // No subscribers just means nobody is waiting
+        let _ = self.sender.send(tenant_id);
+    }
+
+    /// Subscribe to ready fragments of one tenant.
+    ///
+    /// Only announcements made after subscribing are seen, so subscribe before
+    /// checking for work to avoid missing one in between.
+    #[must_use]
+    pub fn subscribe(&self, tenant_id: Uuid) -> ReadinessSubscription {
+        ReadinessSubscription {
+            receiver: self.sender.subscribe(),
+            tenant_id,
+        }
+    }
+}
+
+impl Default for ReadinessNotifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Subscription to ready fragments of one tenant.
+pub struct ReadinessSubscription {
+    receiver: broadcast::Receiver<Uuid>,
+    tenant_id: Uuid,
+}
+
+impl ReadinessSubscription {
+    /// Wait until a fragment of the tenant may have become ready.
+    ///
+    /// Also returns when announcements were dropped because this subscriber fell
+    /// behind, since one of them may have been for the tenant.
+    pub async fn ready(&mut self) {
+        loop {
+            match self.receiver.recv().await {
+                Ok(tenant_id) if tenant_id == self.tenant_id => return,
+                Ok(_) => {}
+                Err(RecvError::Lagged(_)) => return,
+                Err(RecvError::Closed) => pending().await,
+            }
+        }
+    }
+}
+
+/// Start the background thread forwarding database notifications to the notifier.
+///
+/// The thread reconnects whenever its connection is lost.
+///
+/// # Panics
+/// Panics if the thread cannot be spawned.
+pub fn start_readiness_listener(database_url: String, notifier: ReadinessNotifier) {
+    thread::Builder::new()
+        .name("readiness-listener".to_string())
+        .spawn(move || {
+            loop {
+                if let Err(e) = listen(&database_url, &notifier) {
+                    error!(error = %e, "Readiness listener failed, reconnecting");
+                }
+                thread::sleep(RECONNECT_DELAY);
+            }
+        })
+        .expect("Failed to spawn readiness listener thread");
+}
+
+/// Listen for notifications until the connection fails.
+fn listen(database_url: &str, notifier: &ReadinessNotifier) -> Result<(), Box<dyn Error>> {
+    let mut conn = PgConnection::establish(database_url)?;
+    diesel::sql_query(format!("LISTEN {CHANNEL}")).execute(&mut conn)?;
+
+    info!(channel = CHANNEL, "Listening for ready fragments");
+
+    loop {
+        for notification in conn.notifications_iter() {
+            let notification = notification?;
+            if let Ok(tenant_id) = notification.payload.parse::<Uuid>() {
+                notifier.notify(tenant_id);
+            } else {
+                warn!(
+                    payload = %notification.payload,
+                    "Ignoring readiness notification without a tenant"
+                );
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
@@ -1,23 +1,35 @@
 //! Fragment scheduler for assigning work to workers.
 //!
 //! The scheduler determines which fragment a worker can execute based on:
-//! 1. Machine group matching (or no group = any machine)
-//! 2. Fragment dependencies being satisfied:
-//!    - Sequential siblings: all previous siblings must be completed
-//!    - Parallel siblings: can run immediately once parent is active
+//! 1. Tenant isolation: only fragments of chains owned by the worker's tenant
+//! 2. Machine group matching (or no group = any machine)
+//! 3. Label matching: the fragment's `runs-on` labels must all be among the worker's labels
+//! 4. Readiness: only `Pending` fragments, whose dependencies are satisfied
 //!
-//! Uses optimistic locking to prevent race conditions when multiple workers
-//! request work simultaneously. This allows the system to scale to thousands
-//! of workers without lock contention.
+//! Readiness is maintained when results are reported rather than recomputed here:
+//! fragments start `Blocked` and are promoted to `Pending` once their previous
+//! sibling (or the group they belong to) allows them to run.
+//!
+//! Eligibility is evaluated entirely in SQL and the claim is a single
+//! `UPDATE ... WHERE id = (SELECT ... FOR UPDATE SKIP LOCKED LIMIT 1)` statement,
+//! so a work request costs one round trip regardless of queue depth, and workers
+//! polling concurrently skip each other's candidates instead of contending for them.
+//!
+//! A worker launched for one fragment claims only that fragment, provided it is
+//! eligible for the worker by the same rules.
+
+use std::time::Instant;
 
 use diesel::PgConnection;
-use tracing::{debug, trace};
+use tracing::debug;
+use uuid::Uuid;
 
 use vulcan_core::models::fragment::Fragment;
 use vulcan_core::models::worker::Worker;
 use vulcan_core::repositories::{FragmentRepository, PgFragmentRepository};
 
 use crate::error::Result;
+use crate::metrics;
 
 /// Scheduler for finding and claiming executable fragments.
 pub struct Scheduler<'a> {
@@ -32,95 +44,57 @@ impl<'a> Scheduler<'a> {
 
     /// Find and atomically claim work for a specific worker.
     ///
-    /// Uses optimistic locking to prevent race conditions:
-    /// 1. Find candidate pending fragments matching worker's machine group
-    /// 2. Check dependencies for each candidate
-    /// 3. Atomically try to claim the first eligible fragment
-    /// 4. If claim fails (another worker got it), try the next candidate
+    /// Claims the first ready fragment (by sequence) of the worker's tenant that
+    /// matches its machine group and labels and whose dependencies are satisfied.
     ///
     /// Returns the claimed fragment, or None if no work is available.
     pub fn find_and_claim_work(self, worker: &Worker) -> Result<Option<Fragment>> {
-        let mut repo = PgFragmentRepository::new(self.conn);
-
-        // Get pending fragments matching worker's machine group
-        let pending_fragments = repo.find_pending_by_machine(worker.machine_group.as_deref())?;
-
-        trace!(
-            worker_id = %worker.id,
-            pending_count = pending_fragments.len(),
-            "Found pending fragments"
-        );
+        self.claim(worker, None)
+    }
 
-        // Try to claim each eligible fragment
-        for fragment in pending_fragments {
-            // Check dependencies first (cheap operation)
-            let siblings = repo.find_siblings(fragment.chain_id, fragment.parent_fragment_id)?;
+    /// Atomically claim one specific fragment for a worker launched to execute it.
+    ///
+    /// The fragment must be ready and match the worker just like in
+    /// [`find_and_claim_work`](Self::find_and_claim_work).
+    ///
+    /// Returns the claimed fragment, or None if it is not available (any more).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the claim fails.
+    pub fn claim_bound_work(self, worker: &Worker, fragment_id: Uuid) -> Result<Option<Fragment>> {
+        self.claim(worker, Some(fragment_id))
+    }
 
-            let is_parallel = if let Some(parent_id) = fragment.parent_fragment_id {
-                let parent = repo.find_by_id(parent_id)?;
-                parent.map(|p| p.is_parallel).unwrap_or(false)
-            } else {
-                false
-            };
+    fn claim(self, worker: &Worker, fragment_id: Option<Uuid>) -> Result<Option<Fragment>> {
+        let mut repo = PgFragmentRepository::new(self.conn);
+        let machine = worker.machine_group.as_deref();
 
-            if !can_execute_with_siblings(&fragment, &siblings, is_parallel) {
-                trace!(
-                    fragment_id = %fragment.id,
-                    "Fragment not eligible due to dependencies"
-                );
-                continue;
-            }
+        let started = Instant::now();
+        let claimed = match fragment_id {
+            Some(fragment_id) => repo.claim_ready_fragment(
+                fragment_id,
+                worker.id,
+                worker.tenant_id,
+                machine,
+                &worker.labels,
+            )?,
+            None => repo.claim_next_ready(worker.id, worker.tenant_id, machine, &worker.labels)?,
+        };
+        metrics::record_claim(started.elapsed(), claimed.is_some());
 
-            // Try to atomically claim this fragment
-            // This uses optimistic locking: only succeeds if still pending
-            match repo.try_claim(fragment.id, worker.id)? {
-                Some(claimed) => {
-                    debug!(
-                        fragment_id = %claimed.id,
-                        worker_id = %worker.id,
-                        "Successfully claimed fragment for worker"
-                    );
-                    return Ok(Some(claimed));
-                }
-                None => {
-                    // Fragment was claimed by another worker, try next one
-                    trace!(
-                        fragment_id = %fragment.id,
-                        worker_id = %worker.id,
-                        "Fragment already claimed by another worker"
-                    );
-                    continue;
-                }
-            }
+        match &claimed {
+            Some(fragment) => debug!(
+                fragment_id = %fragment.id,
+                worker_id = %worker.id,
+                "Successfully claimed fragment for worker"
+            ),
+            None => debug!(
+                worker_id = %worker.id,
+                "No claimable work available"
+            ),
         }
 
-        debug!(
-            worker_id = %worker.id,
-            "No claimable work available"
-        );
-        Ok(None)
-    }
-}
-
-/// Check if a fragment can be executed given its siblings.
-fn can_execute_with_siblings(fragment: &Fragment, siblings: &[Fragment], is_parallel: bool) -> bool {
-    if is_parallel {
-        // Parallel: can execute immediately (no dependency on siblings)
-        true
-    } else {
-        // Sequential: all previous siblings must be completed
-        for sibling in siblings {
-            if sibling.sequence < fragment.sequence && !sibling.status.is_terminal() {
-                trace!(
-                    fragment_id = %fragment.id,
-                    sibling_id = %sibling.id,
-                    sibling_sequence = sibling.sequence,
-                    sibling_status = ?sibling.status,
-                    "Fragment blocked by uncompleted sibling"
-                );
-                return false;
-            }
-        }
-        true
+        Ok(claimed)
     }
 }
@@ -5,7 +5,9 @@ use std::sync::Arc;
 use diesel::r2d2::{self, ConnectionManager};
 use diesel::PgConnection;
 
+use crate::auth::Authenticator;
 use crate::config::Config;
+use crate::orchestrator::readiness::ReadinessNotifier;
 
 /// Type alias for the database connection pool.
 pub type DbPool = r2d2::Pool<ConnectionManager<PgConnection>>;
@@ -17,6 +19,10 @@ pub struct AppState {
     pub pool: DbPool,
     /// Service configuration.
     pub config: Arc<Config>,
+    /// Announces fragments becoming ready to long-polling work requests.
+    pub readiness: ReadinessNotifier,
+    /// Issues and verifies worker and controller tokens.
+    pub auth: Arc<Authenticator>,
 }
 
 impl AppState {
@@ -33,7 +39,9 @@ impl AppState {
 
         Self {
             pool,
+            auth: Arc::new(Authenticator::new(&config)),
             config: Arc::new(config),
+            readiness: ReadinessNotifier::new(),
         }
     }
 
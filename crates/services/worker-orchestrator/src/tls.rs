@@ -0,0 +1,284 @@
+//! TLS for the orchestrator's listener, optionally verifying client certificates.
+//!
+//! When a client CA is configured, clients are asked for a certificate issued by it.
+//! The certificate subject names the worker's tenant and machine group:
+//!
+//! - `O` (organization): the tenant ID
+//! - `OU` (organizational unit): the machine group, optional
+//!
+//! These are made available to handlers as a [`CertificateIdentity`] request
+//! extension and take precedence over what a worker declares about itself. Clients
+//! without a certificate, such as health probes and metrics scrapers, may still
+//! connect: only the worker and registration endpoints require one (see
+//! [`crate::auth`]).
+
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::Router;
+use axum::http::Request;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::service::TowerToHyperService;
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+use tower::ServiceExt;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// How long a client may take to complete the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// TLS settings of the listener.
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    /// PEM file with the server certificate chain.
+    pub cert_file: String,
+    /// PEM file with the server's private key.
+    pub key_file: String,
+    /// PEM file with the CA certificates client certificates must be issued by.
+    /// Without it, clients are not asked for a certificate.
+    pub client_ca_file: Option<String>,
+}
+
+impl TlsConfig {
+    /// Whether clients are asked for a certificate, which workers must then present.
+    #[must_use]
+    pub const fn verifies_clients(&self) -> bool {
+        self.client_ca_file.is_some()
+    }
+}
+
+impl TlsConfig {
+    /// Build the rustls server configuration.
+    ///
+    /// # Errors
+    /// Returns a description of the problem if a file cannot be read or holds no
+    /// usable certificate or key.
+    pub fn server_config(&self) -> std::result::Result<Arc<ServerConfig>, String> {
+        let provider = Arc::new(tokio_rustls::rustls::crypto::ring::default_provider());
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| e.to_string())?;
+
+        let builder = if let Some(ca_file) = &self.client_ca_file {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(ca_file)? {
+                roots
+                    .add(cert)
+                    .map_err(|e| format!("invalid CA certificate in {ca_file}: {e}"))?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(roots.into(), provider)
+                .allow_unauthenticated()
+                .build()
+                .map_err(|e| format!("invalid client CA {ca_file}: {e}"))?;
+            builder.with_client_cert_verifier(verifier)
+        } else {
+            builder.with_no_client_auth()
+        };
+
+        let mut config = builder
+            .with_single_cert(load_certs(&self.cert_file)?, load_key(&self.key_file)?)
+            .map_err(|e| format!("invalid server certificate or key: {e}"))?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        Ok(Arc::new(config))
+    }
+}
+
+fn load_certs(path: &str) -> std::result::Result<Vec<CertificateDer<'static>>, String> {
+    let file = File::open(path).map_err(|e| format!("cannot open {path}: {e}"))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| format!("cannot read certificates from {path}: {e}"))?;
+    if certs.is_empty() {
+        return Err(format!("no certificates in {path}"));
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &str) -> std::result::Result<PrivateKeyDer<'static>, String> {
+    let file = File::open(path).map_err(|e| format!("cannot open {path}: {e}"))?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|e| format!("cannot read private key from {path}: {e}"))?
+        .ok_or_else(|| format!("no private key in {path}"))
+}
+
+/// Identity of a client, taken from the subject of its certificate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateIdentity {
+    /// Tenant named by the subject's organization.
+    pub tenant_id: Uuid,
+    /// Machine group named by the subject's organizational unit, if any.
+    pub machine_group: Option<String>,
+}
+
+impl CertificateIdentity {
+    /// Read the identity from a DER-encoded certificate.
+    ///
+    /// Returns `None` if the certificate cannot be parsed or its organization is
+    /// not a tenant ID.
+    #[must_use]
+    pub fn from_der(cert: &[u8]) -> Option<Self> {
+        let mut tenant_id = None;
+        let mut machine_group = None;
+
+        for (oid, value) in subject_attributes(cert)? {
+            match oid {
+                OID_ORGANIZATION => tenant_id = Some(value.parse().ok()?),
+                OID_ORGANIZATIONAL_UNIT => machine_group = Some(value),
+                _ => {},
+            }
+        }
+
+        Some(Self {
+            tenant_id: tenant_id?,
+            machine_group,
+        })
+    }
+}
+
+/// DER encoding of the `id-at-organizationName` attribute type (2.5.4.10).
+const OID_ORGANIZATION: &[u8] = &[0x55, 0x04, 0x0a];
+/// DER encoding of the `id-at-organizationalUnitName` attribute type (2.5.4.11).
+const OID_ORGANIZATIONAL_UNIT: &[u8] = &[0x55, 0x04, 0x0b];
+
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_OID: u8 = 0x06;
+const TAG_EXPLICIT_VERSION: u8 = 0xa0;
+
+/// The string attributes of a certificate's subject, as `(type OID, value)` pairs.
+///
+/// Only walks as much of the structure as needed; the certificate has already been
+/// verified by the TLS layer.
+fn subject_attributes(cert: &[u8]) -> Option<Vec<(&[u8], String)>> {
+    let (certificate, _) = read_tlv(cert, TAG_SEQUENCE)?;
+    let (mut tbs, _) = read_tlv(certificate, TAG_SEQUENCE)?;
+
+    if tbs.first() == Some(&TAG_EXPLICIT_VERSION) {
+        tbs = skip_tlv(tbs)?;
+    }
+    // serialNumber, signature, issuer, validity
+    for _ in 0..4 {
+        tbs = skip_tlv(tbs)?;
+    }
+    let (mut rdns, _) = read_tlv(tbs, TAG_SEQUENCE)?;
+
+    let mut attributes = Vec::new();
+    while !rdns.is_empty() {
+        let (mut rdn, rest) = read_tlv(rdns, TAG_SET)?;
+        rdns = rest;
+        while !rdn.is_empty() {
+            let (attribute, rest) = read_tlv(rdn, TAG_SEQUENCE)?;
+            rdn = rest;
+            let (oid, value) = read_tlv(attribute, TAG_OID)?;
+            let (_, value, _) = read_any_tlv(value)?;
+            if let Ok(value) = std::str::from_utf8(value) {
+                attributes.push((oid, value.to_string()));
+            }
+        }
+    }
+    Some(attributes)
+}
+
+/// Read an element with the expected tag, returning its contents and the rest.
+fn read_tlv(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    let (actual, contents, rest) = read_any_tlv(input)?;
+    (actual == tag).then_some((contents, rest))
+}
+
+fn skip_tlv(input: &[u8]) -> Option<&[u8]> {
+    read_any_tlv(input).map(|(_, _, rest)| rest)
+}
+
+/// Read one element, returning its tag, contents and the rest of the input.
+fn read_any_tlv(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, input) = input.split_first()?;
+    let (&first, mut input) = input.split_first()?;
+
+    let len = if first < 0x80 {
+        usize::from(first)
+    } else {
+        let count = usize::from(first & 0x7f);
+        if count == 0 || count > std::mem::size_of::<usize>() || input.len() < count {
+            return None;
+        }
+        let (bytes, rest) = input.split_at(count);
+        input = rest;
+        bytes
+            .iter()
+            .fold(0usize, |len, &byte| (len << 8) | usize::from(byte))
+    };
+
+    (input.len() >= len).then(|| {
+        let (contents, rest) = input.split_at(len);
+        (tag, contents, rest)
+    })
+}
+
+/// Serve the router over TLS.
+///
+/// The identity in a client's certificate, if it presented one naming a tenant, is
+/// added to each of its requests as a [`CertificateIdentity`] extension.
+pub async fn serve_tls(listener: TcpListener, router: Router, config: Arc<ServerConfig>) {
+    let acceptor = TlsAcceptor::from(config);
+
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!(error = %e, "Failed to accept connection");
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                continue;
+            },
+        };
+
+        let acceptor = acceptor.clone();
+        let router = router.clone();
+        tokio::spawn(async move {
+            let stream =
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(e)) => {
+                        debug!(peer = %peer, error = %e, "TLS handshake failed");
+                        return;
+                    },
+                    Err(_) => {
+                        debug!(peer = %peer, "TLS handshake timed out");
+                        return;
+                    },
+                };
+
+            let identity = match stream.get_ref().1.peer_certificates() {
+                Some([cert, ..]) => {
+                    let identity = CertificateIdentity::from_der(cert);
+                    if identity.is_none() {
+                        debug!(peer = %peer, "Client certificate does not name a tenant");
+                    }
+                    identity
+                },
+                _ => None,
+            };
+
+            let service = router.map_request(move |mut request: Request<_>| {
+                if let Some(identity) = &identity {
+                    request.extensions_mut().insert(identity.clone());
+                }
+                request
+            });
+
+            if let Err(e) = auto::Builder::new(TokioExecutor::new())
+                .serve_connection(TokioIo::new(stream), TowerToHyperService::new(service))
+                .await
+            {
+                debug!(peer = %peer, error = %e, "Connection closed with error");
+            }
+        });
+    }
+}
@@ -0,0 +1,226 @@
+//! Integration tests for worker and controller authentication.
+
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use vulcan_worker_orchestrator::AppState;
+use vulcan_worker_orchestrator::auth::TenantTokens;
+
+use common::{
+    cleanup, controller_token, create_test_state_for, create_worker, registration_token, send,
+    test_config, worker_token,
+};
+
+async fn heartbeat(state: &AppState, worker_id: Uuid, token: Option<&str>) -> (StatusCode, Value) {
+    send(
+        state,
+        "POST",
+        "/workers/heartbeat",
+        token,
+        Some(json!({ "worker_id": worker_id })),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_registration_issues_worker_token() {
+    let tenant_id = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id]);
+
+    let (status, body) = send(
+        &state,
+        "POST",
+        "/workers/register",
+        Some(&registration_token(tenant_id)),
+        Some(json!({ "tenant_id": tenant_id })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let worker_id: Uuid = body["worker_id"].as_str().unwrap().parse().unwrap();
+    let token = body["token"].as_str().unwrap().to_string();
+
+    let (status, body) = heartbeat(&state, worker_id, Some(&token)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["token"].is_string(), "heartbeat renews the token");
+
+    cleanup(&state, &[], &[worker_id]);
+}
+
+#[tokio::test]
+async fn test_registration_requires_token_of_the_tenant() {
+    let tenant_a = Uuid::new_v4();
+    let tenant_b = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_a, tenant_b]);
+    let register = json!({ "tenant_id": tenant_a });
+
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/workers/register",
+        None,
+        Some(register.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/workers/register",
+        Some("not-a-token"),
+        Some(register.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/workers/register",
+        Some(&registration_token(tenant_b)),
+        Some(register),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_worker_endpoints_require_own_worker_token() {
+    let tenant_id = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id]);
+    let worker = create_worker(&state, tenant_id);
+    let other = create_worker(&state, tenant_id);
+
+    let (status, _) = heartbeat(&state, worker, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Tampering with the token invalidates its signature
+    let token = worker_token(&state, worker);
+    let forged = token.replacen(&worker.to_string(), &other.to_string(), 1);
+    let (status, _) = heartbeat(&state, other, Some(&forged)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // A valid token only speaks for its own worker
+    let (status, _) = heartbeat(&state, other, Some(&token)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/work/request",
+        Some(&token),
+        Some(json!({ "worker_id": other })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Controller and registration tokens are not worker tokens
+    for token in [controller_token(tenant_id), registration_token(tenant_id)] {
+        let (status, _) = heartbeat(&state, worker, Some(&token)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    cleanup(&state, &[], &[worker, other]);
+}
+
+#[tokio::test]
+async fn test_token_renewal_requires_registration_token_of_the_tenant() {
+    let tenant_a = Uuid::new_v4();
+    let tenant_b = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_a, tenant_b]);
+    let worker = create_worker(&state, tenant_a);
+    let uri = format!("/workers/{worker}/token");
+
+    let (status, _) = send(
+        &state,
+        "POST",
+        &uri,
+        Some(&registration_token(tenant_b)),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = send(
+        &state,
+        "POST",
+        &uri,
+        Some(&registration_token(tenant_a)),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let token = body["token"].as_str().unwrap();
+    let (status, _) = heartbeat(&state, worker, Some(token)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    cleanup(&state, &[], &[worker]);
+}
+
+#[tokio::test]
+async fn test_expired_worker_token_is_rejected() {
+    let mut config = test_config();
+    config.worker_token_ttl_secs = 0;
+    let state = AppState::new(config);
+    let worker = create_worker(&state, Uuid::new_v4());
+
+    let (status, _) = heartbeat(&state, worker, Some(&worker_token(&state, worker))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    cleanup(&state, &[], &[worker]);
+}
+
+#[tokio::test]
+async fn test_queue_metrics_require_controller_token_of_the_tenant() {
+    let tenant_a = Uuid::new_v4();
+    let tenant_b = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_a, tenant_b]);
+    let worker = create_worker(&state, tenant_a);
+    let uri = format!("/queue/metrics?tenant_id={tenant_a}");
+
+    let (status, _) = send(&state, "GET", &uri, None, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = send(
+        &state,
+        "GET",
+        &uri,
+        Some(&worker_token(&state, worker)),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = send(&state, "GET", &uri, Some(&controller_token(tenant_b)), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = send(&state, "GET", &uri, Some(&controller_token(tenant_a)), None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Without a tenant, the controller's own tenant is reported
+    let (status, metrics) = send(
+        &state,
+        "GET",
+        "/queue/metrics",
+        Some(&controller_token(tenant_a)),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(metrics["active_workers"], 1);
+
+    cleanup(&state, &[], &[worker]);
+}
+
+#[test]
+fn test_tenant_tokens_parse() {
+    let tenant_id = Uuid::new_v4();
+    assert!(TenantTokens::parse("").is_ok());
+    assert!(TenantTokens::parse(&format!("{tenant_id}=a, {tenant_id}=b")).is_ok());
+    assert!(TenantTokens::parse("not-a-uuid=token").is_err());
+    assert!(TenantTokens::parse(&format!("{tenant_id}=")).is_err());
+    assert!(TenantTokens::parse("token-without-tenant").is_err());
+}
@@ -0,0 +1,246 @@
+//! Shared helpers for orchestrator integration tests.
+
+#![allow(dead_code)]
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use vulcan_core::{
+    ChainRepository, FragmentRepository, FragmentStatus, NewChain, NewFragment, NewWorker,
+    PgChainRepository, PgFragmentRepository, PgWorkerRepository, WorkerRepository,
+};
+use vulcan_worker_orchestrator::api::create_router;
+use vulcan_worker_orchestrator::auth::{Secret, TenantTokens};
+use vulcan_worker_orchestrator::config::DEFAULT_LEADER_LOCK_KEY;
+use vulcan_worker_orchestrator::{AppState, Config};
+
+/// Configuration against the test database, without any tenant tokens.
+///
+/// Requires `DATABASE_URL` to be set.
+pub fn test_config() -> Config {
+    dotenvy::dotenv().ok();
+    Config {
+        database_url: std::env::var("DATABASE_URL").expect("DATABASE_URL must be set"),
+        host: "127.0.0.1".to_string(),
+        port: 0,
+        metrics_port: None,
+        heartbeat_timeout_secs: 30,
+        health_check_interval_secs: 10,
+        max_retry_attempts: 3,
+        max_work_wait_secs: 30,
+        token_secret: Secret::new("test-secret".to_string()),
+        registration_tokens: TenantTokens::default(),
+        controller_tokens: TenantTokens::default(),
+        worker_token_ttl_secs: 300,
+        worker_retention_secs: 86400,
+        leader_lock_key: DEFAULT_LEADER_LOCK_KEY,
+        leader_renew_interval_secs: 5,
+        leader_lease_secs: 15,
+        tls: None,
+    }
+}
+
+/// Create the orchestrator state against the test database.
+pub fn create_test_state() -> AppState {
+    AppState::new(test_config())
+}
+
+/// Create the orchestrator state with registration and controller tokens for `tenants`.
+pub fn create_test_state_for(tenants: &[Uuid]) -> AppState {
+    AppState::new(test_config_for(tenants))
+}
+
+/// Configuration with registration and controller tokens for `tenants`.
+pub fn test_config_for(tenants: &[Uuid]) -> Config {
+    let tokens = |token: fn(Uuid) -> String| {
+        TenantTokens::new(
+            tenants
+                .iter()
+                .map(|&tenant_id| (tenant_id, Secret::new(token(tenant_id))))
+                .collect(),
+        )
+    };
+
+    let mut config = test_config();
+    config.registration_tokens = tokens(registration_token);
+    config.controller_tokens = tokens(controller_token);
+    config
+}
+
+/// Registration token of a tenant in states from [`create_test_state_for`].
+pub fn registration_token(tenant_id: Uuid) -> String {
+    format!("register-{tenant_id}")
+}
+
+/// Controller token of a tenant in states from [`create_test_state_for`].
+pub fn controller_token(tenant_id: Uuid) -> String {
+    format!("control-{tenant_id}")
+}
+
+/// Issue a worker token for an existing worker.
+pub fn worker_token(state: &AppState, worker_id: Uuid) -> String {
+    let mut conn = state.get_conn().unwrap();
+    let worker = PgWorkerRepository::new(&mut conn)
+        .find_by_id(worker_id)
+        .unwrap()
+        .unwrap();
+    state.auth.issue_worker_token(worker.id, worker.tenant_id).0
+}
+
+/// Create and activate a chain for `tenant_id` from fragments built for its ID.
+pub fn create_chain(
+    state: &AppState,
+    tenant_id: Uuid,
+    build: impl FnOnce(Uuid) -> Vec<NewFragment>,
+) -> Uuid {
+    let mut conn = state.get_conn().unwrap();
+    let chain = PgChainRepository::new(&mut conn)
+        .create(NewChain::new(tenant_id))
+        .unwrap();
+
+    let mut repo = PgFragmentRepository::new(&mut conn);
+    repo.create_many(build(chain.id)).unwrap();
+    repo.activate_chain(chain.id).unwrap();
+
+    chain.id
+}
+
+/// Inline fragments `0..count` under `parent` (top level if None).
+pub fn inline_fragments(chain_id: Uuid, parent: Option<Uuid>, count: i32) -> Vec<NewFragment> {
+    (0..count)
+        .map(|sequence| {
+            let fragment = NewFragment::inline(chain_id, sequence, "true".to_string());
+            match parent {
+                Some(parent_id) => fragment.with_parent(parent_id),
+                None => fragment,
+            }
+        })
+        .collect()
+}
+
+/// Create a chain for `tenant_id` with `count` fragments that are all ready,
+/// returning the chain ID and the fragment IDs.
+pub fn create_pending_work(state: &AppState, tenant_id: Uuid, count: i32) -> (Uuid, Vec<Uuid>) {
+    let mut ids = Vec::new();
+    let chain_id = create_chain(state, tenant_id, |chain_id| {
+        let group = NewFragment::parallel_group(chain_id, 0);
+        let children = inline_fragments(chain_id, Some(group.id), count);
+        ids = children.iter().map(|f| f.id).collect();
+        std::iter::once(group).chain(children).collect()
+    });
+    (chain_id, ids)
+}
+
+/// Create a chain for `tenant_id` with `count` top-level fragments that run one
+/// after another, returning the chain ID and the fragment IDs.
+pub fn create_sequential_work(state: &AppState, tenant_id: Uuid, count: i32) -> (Uuid, Vec<Uuid>) {
+    let mut ids = Vec::new();
+    let chain_id = create_chain(state, tenant_id, |chain_id| {
+        let fragments = inline_fragments(chain_id, None, count);
+        ids = fragments.iter().map(|f| f.id).collect();
+        fragments
+    });
+    (chain_id, ids)
+}
+
+/// Remove test chains (and their fragments) and workers.
+pub fn cleanup(state: &AppState, chain_ids: &[Uuid], worker_ids: &[Uuid]) {
+    let mut conn = state.get_conn().unwrap();
+    for id in worker_ids {
+        PgWorkerRepository::new(&mut conn).delete(*id).unwrap();
+    }
+    for id in chain_ids {
+        PgChainRepository::new(&mut conn).delete(*id).unwrap();
+    }
+}
+
+/// Current status of a fragment.
+pub fn fragment_status(state: &AppState, fragment_id: Uuid) -> FragmentStatus {
+    let mut conn = state.get_conn().unwrap();
+    PgFragmentRepository::new(&mut conn)
+        .find_by_id(fragment_id)
+        .unwrap()
+        .unwrap()
+        .status
+}
+
+/// Register a worker for `tenant_id`.
+pub fn create_worker(state: &AppState, tenant_id: Uuid) -> Uuid {
+    let mut conn = state.get_conn().unwrap();
+    PgWorkerRepository::new(&mut conn)
+        .create(NewWorker::new(tenant_id))
+        .unwrap()
+        .id
+}
+
+/// Assign a fragment to a worker as if it had claimed it, returning the attempt.
+pub fn assign(state: &AppState, worker_id: Uuid, fragment_id: Uuid) -> i32 {
+    let mut conn = state.get_conn().unwrap();
+    let fragment = PgFragmentRepository::new(&mut conn)
+        .start_execution(fragment_id, worker_id)
+        .unwrap();
+    PgWorkerRepository::new(&mut conn)
+        .assign_fragment(worker_id, fragment_id)
+        .unwrap();
+    fragment.attempt
+}
+
+/// Send a JSON request through the API with an optional bearer token, returning
+/// the status and response body.
+pub async fn send(
+    state: &AppState,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json");
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Bearer {token}"));
+    }
+    let request = request
+        .body(body.map_or_else(Body::empty, |b| Body::from(b.to_string())))
+        .unwrap();
+
+    let response = create_router(state.clone()).oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = http_body_util::BodyExt::collect(response.into_body())
+        .await
+        .unwrap()
+        .to_bytes();
+    (
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    )
+}
+
+/// Post a result through the API, returning the status and response body.
+pub async fn post_result(
+    state: &AppState,
+    worker_id: Uuid,
+    fragment_id: Uuid,
+    attempt: i32,
+    success: bool,
+) -> (StatusCode, Value) {
+    let body = json!({
+        "worker_id": worker_id,
+        "fragment_id": fragment_id,
+        "attempt": attempt,
+        "success": success,
+        "exit_code": i32::from(!success),
+    });
+    send(
+        state,
+        "POST",
+        "/work/result",
+        Some(&worker_token(state, worker_id)),
+        Some(body),
+    )
+    .await
+}
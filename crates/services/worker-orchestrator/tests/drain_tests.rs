@@ -0,0 +1,271 @@
+//! Integration tests for draining and deregistering workers.
+
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use vulcan_core::{
+    FragmentRepository, FragmentStatus, PgFragmentRepository, PgWorkerRepository, WorkerRepository,
+    WorkerStatus,
+};
+use vulcan_worker_orchestrator::AppState;
+
+use common::{
+    assign, cleanup, controller_token, create_pending_work, create_test_state_for, create_worker,
+    fragment_status, registration_token, send, worker_token,
+};
+
+async fn drain(state: &AppState, worker_id: Uuid, token: &str) -> (StatusCode, Value) {
+    let uri = format!("/workers/{worker_id}/drain");
+    send(state, "POST", &uri, Some(token), None).await
+}
+
+async fn heartbeat(state: &AppState, worker_id: Uuid, draining: bool) -> Value {
+    let body = json!({ "worker_id": worker_id, "draining": draining });
+    let token = worker_token(state, worker_id);
+    let (status, body) = send(
+        state,
+        "POST",
+        "/workers/heartbeat",
+        Some(&token),
+        Some(body),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    body
+}
+
+fn worker_status(state: &AppState, worker_id: Uuid) -> Option<WorkerStatus> {
+    let mut conn = state.get_conn().unwrap();
+    PgWorkerRepository::new(&mut conn)
+        .find_by_id(worker_id)
+        .unwrap()
+        .map(|worker| worker.status)
+}
+
+#[tokio::test]
+async fn test_drained_worker_gets_no_work() {
+    let tenant_id = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id]);
+    let (chain, fragments) = create_pending_work(&state, tenant_id, 1);
+    let worker = create_worker(&state, tenant_id);
+
+    let (status, body) = drain(&state, worker, &controller_token(tenant_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "Draining");
+
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/work/request",
+        Some(&worker_token(&state, worker)),
+        Some(json!({ "worker_id": worker })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    // The claim itself refuses a worker that started draining after asking for work
+    {
+        let mut conn = state.get_conn().unwrap();
+        let claimed = PgFragmentRepository::new(&mut conn)
+            .claim_next_ready(worker, tenant_id, None, &[])
+            .unwrap();
+        assert!(claimed.is_none());
+    }
+    assert_eq!(
+        fragment_status(&state, fragments[0]),
+        FragmentStatus::Pending
+    );
+
+    // The worker learns about the drain from its heartbeat
+    assert_eq!(heartbeat(&state, worker, false).await["draining"], true);
+
+    let (_, busy) = send(
+        &state,
+        "GET",
+        &format!("/workers/{worker}/busy"),
+        Some(&controller_token(tenant_id)),
+        None,
+    )
+    .await;
+    assert_eq!(busy["draining"], true);
+
+    cleanup(&state, &[chain], &[worker]);
+}
+
+#[tokio::test]
+async fn test_drain_requires_controller_of_the_tenant() {
+    let tenant_a = Uuid::new_v4();
+    let tenant_b = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_a, tenant_b]);
+    let worker = create_worker(&state, tenant_a);
+
+    let (status, _) = drain(&state, worker, &controller_token(tenant_b)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = drain(&state, worker, &worker_token(&state, worker)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    assert_eq!(worker_status(&state, worker), Some(WorkerStatus::Active));
+
+    // Whether the worker is busy is only told to its own tenant's controllers too
+    let busy = format!("/workers/{worker}/busy");
+    let (status, _) = send(
+        &state,
+        "GET",
+        &busy,
+        Some(&controller_token(tenant_b)),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = send(&state, "GET", &busy, None, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    cleanup(&state, &[], &[worker]);
+}
+
+#[tokio::test]
+async fn test_worker_reports_draining_and_is_listed() {
+    let tenant_id = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id]);
+    let (chain, fragments) = create_pending_work(&state, tenant_id, 1);
+
+    let (status, body) = send(
+        &state,
+        "POST",
+        "/workers/register",
+        Some(&registration_token(tenant_id)),
+        Some(json!({ "tenant_id": tenant_id, "name": "vulcan-worker-abc12" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let worker: Uuid = body["worker_id"].as_str().unwrap().parse().unwrap();
+    assign(&state, worker, fragments[0]);
+
+    assert_eq!(heartbeat(&state, worker, true).await["draining"], true);
+    assert_eq!(worker_status(&state, worker), Some(WorkerStatus::Draining));
+
+    // Reporting no drain later does not make the worker active again
+    assert_eq!(heartbeat(&state, worker, false).await["draining"], true);
+
+    let (status, draining) = send(
+        &state,
+        "GET",
+        "/workers/draining",
+        Some(&controller_token(tenant_id)),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        draining,
+        json!([{ "worker_id": worker, "name": "vulcan-worker-abc12", "in_flight": 1 }])
+    );
+
+    cleanup(&state, &[chain], &[worker]);
+}
+
+#[tokio::test]
+async fn test_live_workers_are_listed() {
+    let tenant_id = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id]);
+    let active = create_worker(&state, tenant_id);
+    let draining = create_worker(&state, tenant_id);
+    let retired = create_worker(&state, tenant_id);
+    {
+        let mut conn = state.get_conn().unwrap();
+        let mut repo = PgWorkerRepository::new(&mut conn);
+        repo.mark_draining(draining).unwrap();
+        repo.retire(retired, WorkerStatus::Deregistered).unwrap();
+    }
+
+    let (status, workers) = send(
+        &state,
+        "GET",
+        "/workers",
+        Some(&controller_token(tenant_id)),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        workers,
+        json!([
+            { "worker_id": active, "name": null, "status": "Active" },
+            { "worker_id": draining, "name": null, "status": "Draining" }
+        ])
+    );
+
+    cleanup(&state, &[], &[active, draining, retired]);
+}
+
+#[tokio::test]
+async fn test_cancelled_result_requeues_fragment() {
+    let tenant_id = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id]);
+    let (chain, fragments) = create_pending_work(&state, tenant_id, 1);
+    let worker = create_worker(&state, tenant_id);
+    let attempt = assign(&state, worker, fragments[0]);
+
+    let (status, body) = send(
+        &state,
+        "POST",
+        "/work/result",
+        Some(&worker_token(&state, worker)),
+        Some(json!({
+            "worker_id": worker,
+            "fragment_id": fragments[0],
+            "attempt": attempt,
+            "success": false,
+            "cancelled": true,
+        })),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["fragment_status"], "Pending");
+    let mut conn = state.get_conn().unwrap();
+    assert!(
+        PgWorkerRepository::new(&mut conn)
+            .find_assigned_fragments(worker)
+            .unwrap()
+            .is_empty()
+    );
+    drop(conn);
+
+    cleanup(&state, &[chain], &[worker]);
+}
+
+#[tokio::test]
+async fn test_deregister_recovers_in_flight_fragments() {
+    let tenant_id = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id]);
+    let (chain, fragments) = create_pending_work(&state, tenant_id, 1);
+    let worker = create_worker(&state, tenant_id);
+    assign(&state, worker, fragments[0]);
+
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/workers/deregister",
+        Some(&worker_token(&state, worker)),
+        Some(json!({ "worker_id": worker })),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(
+        worker_status(&state, worker),
+        Some(WorkerStatus::Deregistered)
+    );
+    assert_eq!(
+        fragment_status(&state, fragments[0]),
+        FragmentStatus::Pending
+    );
+
+    cleanup(&state, &[chain], &[worker]);
+}
@@ -0,0 +1,155 @@
+//! Integration tests for launching a worker per pending fragment.
+
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use vulcan_core::{FragmentRepository, FragmentStatus, NewFragment, PgFragmentRepository};
+use vulcan_worker_orchestrator::AppState;
+
+use common::{
+    assign, cleanup, controller_token, create_chain, create_pending_work, create_test_state_for,
+    create_worker, fragment_status, send, worker_token,
+};
+
+async fn pending(state: &AppState, tenant_id: Uuid, query: &str) -> (StatusCode, Value) {
+    let uri = format!("/queue/pending?{query}");
+    send(state, "GET", &uri, Some(&controller_token(tenant_id)), None).await
+}
+
+fn fragment_ids(body: &Value) -> Vec<String> {
+    body.as_array()
+        .unwrap()
+        .iter()
+        .map(|fragment| fragment["fragment_id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+async fn request_bound_work(
+    state: &AppState,
+    worker_id: Uuid,
+    fragment_id: Uuid,
+) -> (StatusCode, Value) {
+    let body = json!({ "worker_id": worker_id, "fragment_id": fragment_id });
+    let token = worker_token(state, worker_id);
+    send(state, "POST", "/work/request", Some(&token), Some(body)).await
+}
+
+#[tokio::test]
+async fn test_pending_fragments_in_claim_order() {
+    let tenant_id = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id]);
+    let (chain, fragments) = create_pending_work(&state, tenant_id, 3);
+    let worker = create_worker(&state, tenant_id);
+    assign(&state, worker, fragments[0]);
+
+    let (status, body) = pending(&state, tenant_id, "").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        fragment_ids(&body),
+        vec![fragments[1].to_string(), fragments[2].to_string()]
+    );
+    assert_eq!(body[0]["chain_id"], chain.to_string());
+    assert_eq!(body[0]["attempt"], 1);
+
+    let (_, body) = pending(&state, tenant_id, "limit=1").await;
+    assert_eq!(fragment_ids(&body), vec![fragments[1].to_string()]);
+
+    cleanup(&state, &[chain], &[worker]);
+}
+
+#[tokio::test]
+async fn test_pending_fragments_are_limited_by_the_query() {
+    let tenant_id = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id]);
+    let (chain, fragments) = create_pending_work(&state, tenant_id, 5);
+
+    let (_, body) = pending(&state, tenant_id, "limit=2").await;
+    assert_eq!(
+        fragment_ids(&body),
+        vec![fragments[0].to_string(), fragments[1].to_string()]
+    );
+
+    // The database returns no more than the limit
+    let mut conn = state.get_conn().unwrap();
+    let listed = PgFragmentRepository::new(&mut conn)
+        .find_pending_matching(tenant_id, None, None, 3)
+        .unwrap();
+    let listed: Vec<_> = listed.iter().map(|fragment| fragment.id).collect();
+    assert_eq!(listed, fragments[..3]);
+    drop(conn);
+
+    cleanup(&state, &[chain], &[]);
+}
+
+#[tokio::test]
+async fn test_pending_fragments_match_machine_group_and_labels() {
+    let tenant_id = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id]);
+    let mut fragment_id = Uuid::nil();
+    let chain = create_chain(&state, tenant_id, |chain_id| {
+        let fragment = NewFragment::inline(chain_id, 0, "true".to_string())
+            .with_machine("gpu".to_string())
+            .with_runs_on(vec!["docker".to_string()]);
+        fragment_id = fragment.id;
+        vec![fragment]
+    });
+
+    // (query, listed)
+    let cases = [
+        ("", true),
+        ("machine_group=gpu", true),
+        ("machine_group=cpu", false),
+        ("machine_group=gpu&labels=docker,os%3Dlinux", true),
+        ("machine_group=gpu&labels=os%3Dlinux", false),
+        ("machine_group=gpu&labels=", false),
+    ];
+    for (query, listed) in cases {
+        let (status, body) = pending(&state, tenant_id, query).await;
+        assert_eq!(status, StatusCode::OK, "{query}");
+        let expected = if listed {
+            vec![fragment_id.to_string()]
+        } else {
+            vec![]
+        };
+        assert_eq!(fragment_ids(&body), expected, "{query}");
+    }
+
+    cleanup(&state, &[chain], &[]);
+}
+
+#[tokio::test]
+async fn test_pending_fragments_of_another_tenant_are_forbidden() {
+    let tenant_id = Uuid::new_v4();
+    let other_tenant = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id, other_tenant]);
+
+    let query = format!("tenant_id={other_tenant}");
+    let (status, _) = pending(&state, tenant_id, &query).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_bound_worker_claims_only_its_fragment() {
+    let tenant_id = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id]);
+    let (chain, fragments) = create_pending_work(&state, tenant_id, 2);
+    let worker = create_worker(&state, tenant_id);
+    let other = create_worker(&state, tenant_id);
+
+    let (status, body) = request_bound_work(&state, worker, fragments[1]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["fragment_id"], fragments[1].to_string());
+    assert_eq!(
+        fragment_status(&state, fragments[0]),
+        FragmentStatus::Pending
+    );
+
+    // A fragment claimed already is not handed out again
+    let (status, _) = request_bound_work(&state, other, fragments[1]).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    cleanup(&state, &[chain], &[worker, other]);
+}
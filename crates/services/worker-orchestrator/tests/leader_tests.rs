@@ -0,0 +1,140 @@
+//! Integration tests for leader election, simulating the loss of the leader.
+
+mod common;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Integer};
+use uuid::Uuid;
+
+use vulcan_core::schema::workers;
+use vulcan_core::{PgWorkerRepository, WorkerRepository, WorkerStatus};
+use vulcan_worker_orchestrator::orchestrator::health::start_health_monitor;
+use vulcan_worker_orchestrator::orchestrator::leader::{Leadership, start_leader_election};
+use vulcan_worker_orchestrator::{AppState, Config};
+
+use common::{cleanup, create_test_state, create_worker, test_config};
+
+/// Configuration electing on a lock key of its own, with a short lease.
+fn election_config() -> Config {
+    let key = Uuid::new_v4().as_u64_pair().0 >> 1;
+    Config {
+        leader_lock_key: i64::try_from(key).unwrap(),
+        leader_renew_interval_secs: 1,
+        leader_lease_secs: 2,
+        health_check_interval_secs: 1,
+        ..test_config()
+    }
+}
+
+/// Wait up to `timeout` for `condition` to hold.
+async fn eventually(timeout: Duration, condition: impl Fn() -> bool) -> bool {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if condition() {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    condition()
+}
+
+#[derive(QueryableByName)]
+struct Pid {
+    #[diesel(sql_type = Integer)]
+    pid: i32,
+}
+
+/// Kill the database session holding the advisory lock, as if its replica died.
+fn kill_lock_holder(state: &AppState, key: i64) {
+    let mut conn = state.get_conn().unwrap();
+    let holder = diesel::sql_query(
+        "SELECT pid FROM pg_locks
+         WHERE locktype = 'advisory' AND granted
+           AND classid = ($1 >> 32)::oid AND objid = ($1 & 4294967295)::oid",
+    )
+    .bind::<BigInt, _>(key)
+    .get_result::<Pid>(&mut conn)
+    .unwrap();
+
+    diesel::sql_query("SELECT pg_terminate_backend($1)")
+        .bind::<Integer, _>(holder.pid)
+        .execute(&mut conn)
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_follower_takes_over_when_leader_is_lost() {
+    let config = election_config();
+    let state = AppState::new(config.clone());
+    let replicas = [
+        start_leader_election(&config),
+        start_leader_election(&config),
+    ];
+    let leader_count = || replicas.iter().filter(|r| r.is_leader()).count();
+
+    assert!(eventually(Duration::from_secs(5), || leader_count() == 1).await);
+    let leader = replicas.iter().position(Leadership::is_leader).unwrap();
+    let follower = 1 - leader;
+
+    // Leadership stays put while the leader renews its lease
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert!(replicas[leader].is_leader());
+    assert!(!replicas[follower].is_leader());
+
+    kill_lock_holder(&state, config.leader_lock_key);
+
+    assert!(eventually(Duration::from_secs(10), || replicas[follower].is_leader()).await);
+    assert!(!replicas[leader].is_leader());
+    assert_eq!(leader_count(), 1);
+}
+
+#[tokio::test]
+async fn test_health_monitor_runs_only_on_leader() {
+    let config = election_config();
+    let key = config.leader_lock_key;
+    let state = create_test_state();
+    let tenant_id = Uuid::new_v4();
+    let worker = create_worker(&state, tenant_id);
+    {
+        let an_hour_ago = Utc::now().naive_utc() - chrono::Duration::hours(1);
+        let mut conn = state.get_conn().unwrap();
+        diesel::update(workers::table.find(worker))
+            .set(workers::last_heartbeat_at.eq(an_hour_ago))
+            .execute(&mut conn)
+            .unwrap();
+    }
+    let worker_errored = || {
+        let mut conn = state.get_conn().unwrap();
+        let worker = PgWorkerRepository::new(&mut conn)
+            .find_by_id(worker)
+            .unwrap()
+            .unwrap();
+        worker.status == WorkerStatus::Error
+    };
+
+    // Another replica leads: hold the lock on a connection of our own
+    let mut other_leader = PgConnection::establish(&config.database_url).unwrap();
+    diesel::sql_query("SELECT pg_advisory_lock($1)")
+        .bind::<BigInt, _>(key)
+        .execute(&mut other_leader)
+        .unwrap();
+
+    let leadership = start_leader_election(&config);
+    start_health_monitor(state.pool.clone(), Arc::new(config), leadership.clone());
+
+    tokio::time::sleep(Duration::from_millis(2500)).await;
+    assert!(!leadership.is_leader());
+    assert!(!worker_errored());
+
+    // The other replica goes away and this one takes over the sweeps
+    drop(other_leader);
+
+    assert!(eventually(Duration::from_secs(10), || leadership.is_leader()).await);
+    assert!(eventually(Duration::from_secs(5), worker_errored).await);
+
+    cleanup(&state, &[], &[worker]);
+}
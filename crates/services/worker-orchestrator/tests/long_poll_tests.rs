@@ -0,0 +1,137 @@
+//! Integration tests for long-polling work requests.
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use vulcan_worker_orchestrator::AppState;
+use vulcan_worker_orchestrator::api::create_router;
+use vulcan_worker_orchestrator::orchestrator::readiness::start_readiness_listener;
+
+use common::{cleanup, create_pending_work, create_test_state, create_worker, worker_token};
+
+/// Create the test state with the readiness listener running.
+async fn create_listening_state() -> AppState {
+    let state = create_test_state();
+    start_readiness_listener(state.config.database_url.clone(), state.readiness.clone());
+    // Give the listener time to subscribe before work is created
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    state
+}
+
+/// Request work, returning the status and the assigned fragment ID.
+async fn request_work(
+    state: AppState,
+    worker_id: Uuid,
+    wait_seconds: Option<u64>,
+) -> (StatusCode, Option<Uuid>) {
+    let body = json!({ "worker_id": worker_id, "wait_seconds": wait_seconds });
+    let request = Request::builder()
+        .method("POST")
+        .uri("/work/request")
+        .header("Content-Type", "application/json")
+        .header(
+            "Authorization",
+            format!("Bearer {}", worker_token(&state, worker_id)),
+        )
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    let response = create_router(state).oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = http_body_util::BodyExt::collect(response.into_body())
+        .await
+        .unwrap()
+        .to_bytes();
+    let fragment_id = serde_json::from_slice::<Value>(&bytes)
+        .ok()
+        .and_then(|body| body["fragment_id"].as_str().map(str::to_string))
+        .map(|id| id.parse().unwrap());
+
+    (status, fragment_id)
+}
+
+#[tokio::test]
+async fn test_request_without_wait_returns_immediately() {
+    let state = create_test_state();
+    let tenant_id = Uuid::new_v4();
+    let worker_id = create_worker(&state, tenant_id);
+
+    let started = Instant::now();
+    let (status, _) = request_work(state.clone(), worker_id, None).await;
+
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    cleanup(&state, &[], &[worker_id]);
+}
+
+#[tokio::test]
+async fn test_request_waits_until_work_becomes_ready() {
+    let state = create_listening_state().await;
+    let tenant_id = Uuid::new_v4();
+    let worker_id = create_worker(&state, tenant_id);
+
+    let started = Instant::now();
+    let request = tokio::spawn(request_work(state.clone(), worker_id, Some(20)));
+
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let (chain_id, fragment_ids) = create_pending_work(&state, tenant_id, 1);
+
+    let (status, fragment_id) = request.await.unwrap();
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(fragment_id, Some(fragment_ids[0]));
+    assert!(
+        started.elapsed() < Duration::from_secs(10),
+        "request should return once work is ready, not at the end of the wait"
+    );
+
+    cleanup(&state, &[chain_id], &[worker_id]);
+}
+
+#[tokio::test]
+async fn test_request_times_out_when_only_other_tenants_have_work() {
+    let state = create_listening_state().await;
+    let tenant_id = Uuid::new_v4();
+    let worker_id = create_worker(&state, tenant_id);
+
+    let started = Instant::now();
+    let request = tokio::spawn(request_work(state.clone(), worker_id, Some(2)));
+
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let (other_chain, other_fragments) = create_pending_work(&state, Uuid::new_v4(), 1);
+
+    let (status, _) = request.await.unwrap();
+
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert!(started.elapsed() >= Duration::from_secs(2));
+    assert_eq!(
+        common::fragment_status(&state, other_fragments[0]),
+        vulcan_core::FragmentStatus::Pending
+    );
+
+    cleanup(&state, &[other_chain], &[worker_id]);
+}
+
+#[tokio::test]
+async fn test_wait_is_capped_by_config() {
+    let mut config = (*create_test_state().config).clone();
+    config.max_work_wait_secs = 1;
+    let state = AppState::new(config);
+    let worker_id = create_worker(&state, Uuid::new_v4());
+
+    let started = Instant::now();
+    let (status, _) = request_work(state.clone(), worker_id, Some(60)).await;
+
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    cleanup(&state, &[], &[worker_id]);
+}
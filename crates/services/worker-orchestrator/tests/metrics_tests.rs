@@ -0,0 +1,114 @@
+//! Integration tests for the Prometheus metrics endpoint.
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode, header};
+use serde_json::json;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use vulcan_worker_orchestrator::api::{create_metrics_router, create_router};
+use vulcan_worker_orchestrator::{AppState, metrics};
+
+use common::{
+    cleanup, create_pending_work, create_test_state, create_worker, post_result, send, worker_token,
+};
+
+fn metrics_request() -> Request<Body> {
+    Request::builder()
+        .uri("/metrics")
+        .body(Body::empty())
+        .unwrap()
+}
+
+/// Scrape `/metrics` of the metrics listener, returning the body.
+async fn scrape(state: &AppState) -> String {
+    let response = create_metrics_router(state.clone())
+        .oneshot(metrics_request())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain")
+    );
+    let bytes = http_body_util::BodyExt::collect(response.into_body())
+        .await
+        .unwrap()
+        .to_bytes();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_metrics_report_queue_depth_per_tenant() {
+    metrics::install();
+    let state = create_test_state();
+    let tenant_id = Uuid::new_v4();
+    let worker_id = create_worker(&state, tenant_id);
+    let (chain_id, _) = create_pending_work(&state, tenant_id, 3);
+
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/work/request",
+        Some(&worker_token(&state, worker_id)),
+        Some(json!({ "worker_id": worker_id })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let body = scrape(&state).await;
+    assert!(body.contains(&format!(
+        "vulcan_queue_depth{{tenant_id=\"{tenant_id}\",machine_group=\"\",status=\"pending\"}} 2"
+    )));
+    assert!(body.contains(&format!(
+        "vulcan_queue_depth{{tenant_id=\"{tenant_id}\",machine_group=\"\",status=\"running\"}} 1"
+    )));
+    assert!(body.contains("vulcan_workers{status=\"active\"}"));
+
+    cleanup(&state, &[chain_id], &[worker_id]);
+}
+
+#[tokio::test]
+async fn test_metrics_record_claims_and_fragment_durations() {
+    metrics::install();
+    let state = create_test_state();
+    let tenant_id = Uuid::new_v4();
+    let worker_id = create_worker(&state, tenant_id);
+    let (chain_id, _) = create_pending_work(&state, tenant_id, 1);
+
+    let (status, work) = send(
+        &state,
+        "POST",
+        "/work/request",
+        Some(&worker_token(&state, worker_id)),
+        Some(json!({ "worker_id": worker_id })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let fragment_id = work["fragment_id"].as_str().unwrap().parse().unwrap();
+    let attempt = i32::try_from(work["attempt"].as_i64().unwrap()).unwrap();
+    let (status, _) = post_result(&state, worker_id, fragment_id, attempt, true).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let body = scrape(&state).await;
+    assert!(body.contains("vulcan_claim_duration_seconds_count{result=\"claimed\"}"));
+    assert!(body.contains("vulcan_fragment_duration_seconds_count{outcome=\"success\"}"));
+    assert!(body.contains("vulcan_chains_finished_total{status=\"completed\"}"));
+
+    cleanup(&state, &[chain_id], &[worker_id]);
+}
+
+#[tokio::test]
+async fn test_metrics_are_not_served_on_the_api() {
+    let state = create_test_state();
+
+    let response = create_router(state)
+        .oneshot(metrics_request())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
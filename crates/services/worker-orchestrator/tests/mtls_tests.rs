@@ -0,0 +1,380 @@
+//! Integration tests for mutual TLS, using certificates generated for each test.
+
+mod common;
+
+use std::path::PathBuf;
+
+use openssl::asn1::Asn1Time;
+use openssl::bn::BigNum;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Private};
+use openssl::rsa::Rsa;
+use openssl::x509::extension::{BasicConstraints, ExtendedKeyUsage, SubjectAlternativeName};
+use openssl::x509::{X509, X509NameBuilder};
+use reqwest::{Certificate, Client, Identity, StatusCode};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use vulcan_core::{PgWorkerRepository, WorkerRepository};
+use vulcan_worker_orchestrator::AppState;
+use vulcan_worker_orchestrator::api::create_router;
+use vulcan_worker_orchestrator::tls::{CertificateIdentity, TlsConfig, serve_tls};
+
+use common::{cleanup, controller_token, registration_token, test_config_for};
+
+/// A certificate with its private key.
+struct KeyPair {
+    cert: X509,
+    key: PKey<Private>,
+}
+
+impl KeyPair {
+    fn cert_pem(&self) -> Vec<u8> {
+        self.cert.to_pem().unwrap()
+    }
+
+    fn key_pem(&self) -> Vec<u8> {
+        self.key.private_key_to_pem_pkcs8().unwrap()
+    }
+
+    fn identity(&self) -> Identity {
+        Identity::from_pkcs8_pem(&self.cert_pem(), &self.key_pem()).unwrap()
+    }
+}
+
+/// Issue a certificate with the given subject, self-signed when there is no issuer.
+fn issue(subject: &[(&str, &str)], issuer: Option<&KeyPair>, server: bool) -> KeyPair {
+    let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+
+    let mut name = X509NameBuilder::new().unwrap();
+    for (field, value) in subject {
+        name.append_entry_by_text(field, value).unwrap();
+    }
+    let name = name.build();
+
+    let mut builder = X509::builder().unwrap();
+    builder.set_version(2).unwrap();
+    let serial = BigNum::from_u32(Uuid::new_v4().as_fields().0)
+        .and_then(|serial| serial.to_asn1_integer())
+        .unwrap();
+    builder.set_serial_number(&serial).unwrap();
+    builder.set_subject_name(&name).unwrap();
+    builder
+        .set_issuer_name(issuer.map_or(&name, |ca| ca.cert.subject_name()))
+        .unwrap();
+    builder.set_pubkey(&key).unwrap();
+    builder
+        .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    builder
+        .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+        .unwrap();
+
+    if issuer.is_none() {
+        let constraints = BasicConstraints::new().critical().ca().build().unwrap();
+        builder.append_extension(constraints).unwrap();
+    } else if server {
+        let context = builder.x509v3_context(issuer.map(|ca| &*ca.cert), None);
+        let san = SubjectAlternativeName::new()
+            .dns("localhost")
+            .build(&context)
+            .unwrap();
+        builder.append_extension(san).unwrap();
+        let usage = ExtendedKeyUsage::new().server_auth().build().unwrap();
+        builder.append_extension(usage).unwrap();
+    } else {
+        let usage = ExtendedKeyUsage::new().client_auth().build().unwrap();
+        builder.append_extension(usage).unwrap();
+    }
+
+    let signing_key = issuer.map_or(&key, |ca| &ca.key);
+    builder.sign(signing_key, MessageDigest::sha256()).unwrap();
+
+    KeyPair {
+        cert: builder.build(),
+        key,
+    }
+}
+
+/// Certificates of a test deployment, written to a scratch directory.
+struct Pki {
+    dir: PathBuf,
+    ca: KeyPair,
+    config: TlsConfig,
+}
+
+impl Pki {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("vulcan-mtls-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let ca = issue(&[("CN", "Vulcan Test CA")], None, false);
+        let server = issue(&[("CN", "localhost")], Some(&ca), true);
+
+        let write = |name: &str, contents: Vec<u8>| {
+            let path = dir.join(name);
+            std::fs::write(&path, contents).unwrap();
+            path.to_string_lossy().into_owned()
+        };
+        let config = TlsConfig {
+            cert_file: write("server.pem", server.cert_pem()),
+            key_file: write("server.key", server.key_pem()),
+            client_ca_file: Some(write("ca.pem", ca.cert_pem())),
+        };
+
+        Self { dir, ca, config }
+    }
+
+    /// A client certificate issued by the test CA.
+    fn client(&self, tenant_id: Uuid, machine_group: Option<&str>) -> KeyPair {
+        let tenant_id = tenant_id.to_string();
+        let mut subject = vec![("CN", "worker"), ("O", tenant_id.as_str())];
+        subject.extend(machine_group.map(|group| ("OU", group)));
+        issue(&subject, Some(&self.ca), false)
+    }
+
+    /// Orchestrator state for `tenants` that verifies client certificates.
+    fn state(&self, tenants: &[Uuid]) -> AppState {
+        let mut config = test_config_for(tenants);
+        config.tls = Some(self.config.clone());
+        AppState::new(config)
+    }
+
+    /// Serve the orchestrator over TLS, returning its base URL.
+    async fn serve(&self, state: &AppState) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let tls = self.config.server_config().unwrap();
+        tokio::spawn(serve_tls(listener, create_router(state.clone()), tls));
+        format!("https://localhost:{port}")
+    }
+
+    /// An HTTP client trusting the test CA and presenting `identity`.
+    fn http_client(&self, identity: Option<Identity>) -> Client {
+        let mut builder = Client::builder()
+            .add_root_certificate(Certificate::from_pem(&self.ca.cert_pem()).unwrap());
+        if let Some(identity) = identity {
+            builder = builder.identity(identity);
+        }
+        builder.build().unwrap()
+    }
+}
+
+impl Drop for Pki {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+async fn register(client: &Client, base_url: &str, token: &str, body: Value) -> reqwest::Response {
+    client
+        .post(format!("{base_url}/workers/register"))
+        .bearer_auth(token)
+        .json(&body)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_certificate_decides_tenant_and_machine_group() {
+    let tenant_id = Uuid::new_v4();
+    let pki = Pki::new();
+    let state = pki.state(&[tenant_id]);
+    let base_url = pki.serve(&state).await;
+    let client = pki.http_client(Some(pki.client(tenant_id, Some("gpu")).identity()));
+
+    // The worker claims another tenant and group than its certificate names
+    let response = register(
+        &client,
+        &base_url,
+        &registration_token(tenant_id),
+        json!({ "tenant_id": Uuid::new_v4(), "machine_group": "cpu" }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    let worker_id: Uuid = body["worker_id"].as_str().unwrap().parse().unwrap();
+
+    let worker = {
+        let mut conn = state.get_conn().unwrap();
+        PgWorkerRepository::new(&mut conn)
+            .find_by_id(worker_id)
+            .unwrap()
+            .unwrap()
+    };
+    assert_eq!(worker.tenant_id, tenant_id);
+    assert_eq!(worker.machine_group.as_deref(), Some("gpu"));
+
+    cleanup(&state, &[], &[worker_id]);
+}
+
+#[tokio::test]
+async fn test_certificate_tenant_must_match_registration_token() {
+    let tenant_a = Uuid::new_v4();
+    let tenant_b = Uuid::new_v4();
+    let pki = Pki::new();
+    let state = pki.state(&[tenant_a, tenant_b]);
+    let base_url = pki.serve(&state).await;
+    let client = pki.http_client(Some(pki.client(tenant_a, None).identity()));
+
+    let response = register(
+        &client,
+        &base_url,
+        &registration_token(tenant_b),
+        json!({ "tenant_id": tenant_b }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_client_without_certificate_may_use_health_and_controller_endpoints() {
+    let tenant_id = Uuid::new_v4();
+    let pki = Pki::new();
+    let state = pki.state(&[tenant_id]);
+    let base_url = pki.serve(&state).await;
+    let client = pki.http_client(None);
+
+    // Health probes have no certificate
+    let response = client
+        .get(format!("{base_url}/health"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Controllers are authenticated by their token
+    let response = client
+        .get(format!("{base_url}/queue/metrics"))
+        .bearer_auth(controller_token(tenant_id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Workers must present a certificate
+    let response = register(
+        &client,
+        &base_url,
+        &registration_token(tenant_id),
+        json!({ "tenant_id": tenant_id }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_certificate_from_other_ca_is_rejected() {
+    let tenant_id = Uuid::new_v4();
+    let pki = Pki::new();
+    let state = pki.state(&[tenant_id]);
+    let other = Pki::new();
+    let base_url = pki.serve(&state).await;
+
+    let result = pki
+        .http_client(Some(other.client(tenant_id, None).identity()))
+        .get(format!("{base_url}/health"))
+        .send()
+        .await;
+    assert!(
+        result.is_err(),
+        "handshake should fail with an unknown issuer"
+    );
+}
+
+#[tokio::test]
+async fn test_certificate_without_tenant_cannot_register() {
+    let tenant_id = Uuid::new_v4();
+    let pki = Pki::new();
+    let state = pki.state(&[tenant_id]);
+    let base_url = pki.serve(&state).await;
+    let cert = issue(
+        &[("CN", "worker"), ("O", "not-a-tenant")],
+        Some(&pki.ca),
+        false,
+    );
+    let client = pki.http_client(Some(cert.identity()));
+
+    // The connection is kept for the endpoints that need no certificate
+    let response = client
+        .get(format!("{base_url}/health"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = register(
+        &client,
+        &base_url,
+        &registration_token(tenant_id),
+        json!({ "tenant_id": tenant_id }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_worker_requests_need_certificate_of_its_tenant() {
+    let tenant_a = Uuid::new_v4();
+    let tenant_b = Uuid::new_v4();
+    let pki = Pki::new();
+    let state = pki.state(&[tenant_a, tenant_b]);
+    let base_url = pki.serve(&state).await;
+    let client = pki.http_client(Some(pki.client(tenant_a, None).identity()));
+
+    let response = register(
+        &client,
+        &base_url,
+        &registration_token(tenant_a),
+        json!({ "tenant_id": tenant_a }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    let worker_id: Uuid = body["worker_id"].as_str().unwrap().parse().unwrap();
+    let token = body["token"].as_str().unwrap().to_string();
+
+    let heartbeat = |client: Client| {
+        let request = client
+            .post(format!("{base_url}/workers/heartbeat"))
+            .bearer_auth(&token)
+            .json(&json!({ "worker_id": worker_id }));
+        async move { request.send().await.unwrap().status() }
+    };
+
+    assert_eq!(heartbeat(client.clone()).await, StatusCode::OK);
+    assert_eq!(
+        heartbeat(pki.http_client(None)).await,
+        StatusCode::UNAUTHORIZED
+    );
+    let other = pki.http_client(Some(pki.client(tenant_b, None).identity()));
+    assert_eq!(heartbeat(other).await, StatusCode::FORBIDDEN);
+
+    cleanup(&state, &[], &[worker_id]);
+}
+
+#[test]
+fn test_certificate_identity_from_der() {
+    let pki = Pki::new();
+    let tenant_id = Uuid::new_v4();
+
+    let cert = pki.client(tenant_id, Some("gpu")).cert.to_der().unwrap();
+    assert_eq!(
+        CertificateIdentity::from_der(&cert),
+        Some(CertificateIdentity {
+            tenant_id,
+            machine_group: Some("gpu".to_string()),
+        })
+    );
+
+    let cert = pki.client(tenant_id, None).cert.to_der().unwrap();
+    assert_eq!(
+        CertificateIdentity::from_der(&cert).unwrap().machine_group,
+        None
+    );
+
+    let cert = pki.ca.cert.to_der().unwrap();
+    assert_eq!(CertificateIdentity::from_der(&cert), None);
+    assert_eq!(CertificateIdentity::from_der(&cert[..cert.len() / 2]), None);
+}
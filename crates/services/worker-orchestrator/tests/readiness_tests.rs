@@ -0,0 +1,205 @@
+//! Integration tests for event-driven fragment readiness.
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use vulcan_core::{ChainRepository, ChainStatus, FragmentStatus, NewFragment, PgChainRepository};
+use vulcan_worker_orchestrator::AppState;
+use vulcan_worker_orchestrator::api::create_router;
+
+use common::{
+    assign, cleanup, controller_token, create_chain, create_sequential_work, create_test_state,
+    create_test_state_for, create_worker, fragment_status, inline_fragments, post_result,
+};
+
+use FragmentStatus::{Blocked, Completed, Failed, Pending, Running};
+
+/// Claim a fragment for the worker and report its result through the API.
+async fn report(state: &AppState, worker_id: Uuid, fragment_id: Uuid, success: bool) {
+    let attempt = assign(state, worker_id, fragment_id);
+    let (status, _) = post_result(state, worker_id, fragment_id, attempt, success).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+fn statuses(state: &AppState, fragment_ids: &[Uuid]) -> Vec<FragmentStatus> {
+    fragment_ids
+        .iter()
+        .map(|id| fragment_status(state, *id))
+        .collect()
+}
+
+fn chain_status(state: &AppState, chain_id: Uuid) -> ChainStatus {
+    let mut conn = state.get_conn().unwrap();
+    PgChainRepository::new(&mut conn)
+        .find_by_id(chain_id)
+        .unwrap()
+        .unwrap()
+        .status
+}
+
+/// A chain of: `first`, a parallel `group` of two children, then `last`.
+struct GroupChain {
+    chain: Uuid,
+    first: Uuid,
+    group: Uuid,
+    children: Vec<Uuid>,
+    last: Uuid,
+}
+
+fn create_group_chain(state: &AppState, tenant_id: Uuid, parallel: bool) -> GroupChain {
+    let mut ids = Vec::new();
+    let chain = create_chain(state, tenant_id, |chain_id| {
+        let first = NewFragment::inline(chain_id, 0, "true".to_string());
+        let mut group = NewFragment::parallel_group(chain_id, 1);
+        group.is_parallel = parallel;
+        let children = inline_fragments(chain_id, Some(group.id), 2);
+        let last = NewFragment::inline(chain_id, 2, "true".to_string());
+
+        ids = vec![first.id, group.id, children[0].id, children[1].id, last.id];
+        let mut fragments = vec![first, group];
+        fragments.extend(children);
+        fragments.push(last);
+        fragments
+    });
+
+    GroupChain {
+        chain,
+        first: ids[0],
+        group: ids[1],
+        children: ids[2..4].to_vec(),
+        last: ids[4],
+    }
+}
+
+#[tokio::test]
+async fn test_new_chain_only_readies_first_fragment() {
+    let state = create_test_state();
+    let (chain, fragments) = create_sequential_work(&state, Uuid::new_v4(), 3);
+
+    assert_eq!(
+        statuses(&state, &fragments),
+        vec![Pending, Blocked, Blocked]
+    );
+
+    cleanup(&state, &[chain], &[]);
+}
+
+#[tokio::test]
+async fn test_result_releases_next_sequential_sibling() {
+    let state = create_test_state();
+    let tenant_id = Uuid::new_v4();
+    let (chain, fragments) = create_sequential_work(&state, tenant_id, 3);
+    let worker = create_worker(&state, tenant_id);
+
+    report(&state, worker, fragments[0], true).await;
+    assert_eq!(
+        statuses(&state, &fragments),
+        vec![Completed, Pending, Blocked]
+    );
+
+    // A failed fragment releases its successor too
+    report(&state, worker, fragments[1], false).await;
+    assert_eq!(
+        statuses(&state, &fragments),
+        vec![Completed, Failed, Pending]
+    );
+
+    report(&state, worker, fragments[2], true).await;
+    assert_eq!(chain_status(&state, chain), ChainStatus::Failed);
+
+    cleanup(&state, &[chain], &[worker]);
+}
+
+#[tokio::test]
+async fn test_parallel_group_readies_children_and_completes() {
+    let state = create_test_state();
+    let tenant_id = Uuid::new_v4();
+    let c = create_group_chain(&state, tenant_id, true);
+    let worker = create_worker(&state, tenant_id);
+
+    assert_eq!(
+        statuses(&state, &[c.group, c.children[0], c.children[1]]),
+        vec![Blocked, Blocked, Blocked]
+    );
+
+    report(&state, worker, c.first, true).await;
+    assert_eq!(
+        statuses(&state, &[c.group, c.children[0], c.children[1], c.last]),
+        vec![Running, Pending, Pending, Blocked]
+    );
+
+    report(&state, worker, c.children[1], true).await;
+    assert_eq!(fragment_status(&state, c.last), Blocked);
+
+    report(&state, worker, c.children[0], true).await;
+    assert_eq!(
+        statuses(&state, &[c.group, c.last]),
+        vec![Completed, Pending]
+    );
+
+    report(&state, worker, c.last, true).await;
+    assert_eq!(chain_status(&state, c.chain), ChainStatus::Completed);
+
+    cleanup(&state, &[c.chain], &[worker]);
+}
+
+#[tokio::test]
+async fn test_sequential_group_runs_children_in_order() {
+    let state = create_test_state();
+    let tenant_id = Uuid::new_v4();
+    let c = create_group_chain(&state, tenant_id, false);
+    let worker = create_worker(&state, tenant_id);
+
+    report(&state, worker, c.first, true).await;
+    assert_eq!(
+        statuses(&state, &[c.group, c.children[0], c.children[1]]),
+        vec![Running, Pending, Blocked]
+    );
+
+    report(&state, worker, c.children[0], false).await;
+    assert_eq!(
+        statuses(&state, &[c.children[1], c.last]),
+        vec![Pending, Blocked]
+    );
+
+    report(&state, worker, c.children[1], true).await;
+    assert_eq!(statuses(&state, &[c.group, c.last]), vec![Failed, Pending]);
+
+    cleanup(&state, &[c.chain], &[worker]);
+}
+
+#[tokio::test]
+async fn test_queue_metrics_count_only_ready_work() {
+    let tenant_id = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id]);
+    let c = create_group_chain(&state, tenant_id, true);
+    let worker = create_worker(&state, tenant_id);
+
+    let pending = || async {
+        let request = Request::builder()
+            .uri(format!("/queue/metrics?tenant_id={tenant_id}"))
+            .header(
+                "Authorization",
+                format!("Bearer {}", controller_token(tenant_id)),
+            )
+            .body(Body::empty())
+            .unwrap();
+        let response = create_router(state.clone()).oneshot(request).await.unwrap();
+        let bytes = http_body_util::BodyExt::collect(response.into_body())
+            .await
+            .unwrap()
+            .to_bytes();
+        let metrics: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        metrics["pending_fragments"].as_i64().unwrap()
+    };
+
+    assert_eq!(pending().await, 1);
+    report(&state, worker, c.first, true).await;
+    assert_eq!(pending().await, 2);
+
+    cleanup(&state, &[c.chain], &[worker]);
+}
@@ -0,0 +1,162 @@
+//! Integration tests for result reporting: ownership, stale and duplicate reports.
+
+mod common;
+
+use axum::http::StatusCode;
+use uuid::Uuid;
+
+use vulcan_core::{
+    ChainRepository, ChainStatus, FragmentStatus, PgChainRepository, PgWorkerRepository,
+    WorkerRepository,
+};
+use vulcan_worker_orchestrator::AppState;
+use vulcan_worker_orchestrator::orchestrator::health::recover_fragment;
+
+use common::{
+    assign, cleanup, create_pending_work, create_sequential_work, create_test_state, create_worker,
+    fragment_status, post_result,
+};
+
+fn chain_status(state: &AppState, chain_id: Uuid) -> ChainStatus {
+    let mut conn = state.get_conn().unwrap();
+    PgChainRepository::new(&mut conn)
+        .find_by_id(chain_id)
+        .unwrap()
+        .unwrap()
+        .status
+}
+
+fn assigned_fragments(state: &AppState, worker_id: Uuid) -> Vec<Uuid> {
+    let mut conn = state.get_conn().unwrap();
+    PgWorkerRepository::new(&mut conn)
+        .find_assigned_fragments(worker_id)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_result_completes_chain_and_releases_worker() {
+    let state = create_test_state();
+    let tenant_id = Uuid::new_v4();
+    let (chain, fragments) = create_sequential_work(&state, tenant_id, 1);
+    let worker = create_worker(&state, tenant_id);
+
+    let attempt = assign(&state, worker, fragments[0]);
+    let (status, body) = post_result(&state, worker, fragments[0], attempt, true).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ok");
+    assert_eq!(chain_status(&state, chain), ChainStatus::Completed);
+    assert!(assigned_fragments(&state, worker).is_empty());
+
+    cleanup(&state, &[chain], &[worker]);
+}
+
+#[tokio::test]
+async fn test_duplicate_result_is_acknowledged_without_changes() {
+    let state = create_test_state();
+    let tenant_id = Uuid::new_v4();
+    let (chain, fragments) = create_sequential_work(&state, tenant_id, 2);
+    let worker = create_worker(&state, tenant_id);
+
+    let attempt = assign(&state, worker, fragments[0]);
+    let (status, _) = post_result(&state, worker, fragments[0], attempt, true).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // A retried report, even with a different outcome, changes nothing
+    let (status, body) = post_result(&state, worker, fragments[0], attempt, false).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "duplicate");
+    assert_eq!(
+        fragment_status(&state, fragments[0]),
+        FragmentStatus::Completed
+    );
+    assert_eq!(
+        fragment_status(&state, fragments[1]),
+        FragmentStatus::Pending
+    );
+
+    cleanup(&state, &[chain], &[worker]);
+}
+
+#[tokio::test]
+async fn test_result_from_other_worker_is_rejected() {
+    let state = create_test_state();
+    let tenant_id = Uuid::new_v4();
+    let (chain, fragments) = create_pending_work(&state, tenant_id, 1);
+    let owner = create_worker(&state, tenant_id);
+    let other = create_worker(&state, tenant_id);
+
+    let attempt = assign(&state, owner, fragments[0]);
+    let (status, _) = post_result(&state, other, fragments[0], attempt, true).await;
+
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(
+        fragment_status(&state, fragments[0]),
+        FragmentStatus::Running
+    );
+    assert_eq!(assigned_fragments(&state, owner), vec![fragments[0]]);
+
+    cleanup(&state, &[chain], &[owner, other]);
+}
+
+#[tokio::test]
+async fn test_late_result_after_reassignment_is_rejected() {
+    let state = create_test_state();
+    let tenant_id = Uuid::new_v4();
+    let (chain, fragments) = create_pending_work(&state, tenant_id, 1);
+    let first = create_worker(&state, tenant_id);
+    let second = create_worker(&state, tenant_id);
+
+    // The first worker is presumed dead and the fragment goes to the second one
+    let stale_attempt = assign(&state, first, fragments[0]);
+    {
+        let mut conn = state.get_conn().unwrap();
+        recover_fragment(&mut conn, &state.config, fragments[0], "exhausted").unwrap();
+        PgWorkerRepository::new(&mut conn)
+            .clear_assignments(first)
+            .unwrap();
+    }
+    let attempt = assign(&state, second, fragments[0]);
+    assert_eq!(attempt, stale_attempt + 1);
+
+    let (status, _) = post_result(&state, first, fragments[0], stale_attempt, false).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(
+        fragment_status(&state, fragments[0]),
+        FragmentStatus::Running
+    );
+
+    let (status, _) = post_result(&state, second, fragments[0], attempt, true).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        fragment_status(&state, fragments[0]),
+        FragmentStatus::Completed
+    );
+
+    cleanup(&state, &[chain], &[first, second]);
+}
+
+#[tokio::test]
+async fn test_recovery_leaves_reported_fragment_alone() {
+    let state = create_test_state();
+    let tenant_id = Uuid::new_v4();
+    let (chain, fragments) = create_sequential_work(&state, tenant_id, 1);
+    let worker = create_worker(&state, tenant_id);
+
+    let attempt = assign(&state, worker, fragments[0]);
+    let (status, _) = post_result(&state, worker, fragments[0], attempt, true).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let mut conn = state.get_conn().unwrap();
+    recover_fragment(&mut conn, &state.config, fragments[0], "exhausted").unwrap();
+    drop(conn);
+
+    assert_eq!(
+        fragment_status(&state, fragments[0]),
+        FragmentStatus::Completed
+    );
+    assert_eq!(chain_status(&state, chain), ChainStatus::Completed);
+
+    cleanup(&state, &[chain], &[worker]);
+}
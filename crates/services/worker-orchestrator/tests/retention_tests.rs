@@ -0,0 +1,260 @@
+//! Integration tests for re-registering workers and deleting retired ones.
+
+mod common;
+
+use axum::http::StatusCode;
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use vulcan_core::schema::workers;
+use vulcan_core::{FragmentStatus, PgWorkerRepository, Worker, WorkerRepository, WorkerStatus};
+use vulcan_worker_orchestrator::AppState;
+use vulcan_worker_orchestrator::orchestrator::health::delete_retired_workers;
+
+use common::{
+    assign, cleanup, create_pending_work, create_test_state_for, create_worker, fragment_status,
+    registration_token, send, worker_token,
+};
+
+async fn register(state: &AppState, tenant_id: Uuid, body: Value) -> Uuid {
+    let (status, body) = send(
+        state,
+        "POST",
+        "/workers/register",
+        Some(&registration_token(tenant_id)),
+        Some(body),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    body["worker_id"].as_str().unwrap().parse().unwrap()
+}
+
+fn find_worker(state: &AppState, worker_id: Uuid) -> Option<Worker> {
+    let mut conn = state.get_conn().unwrap();
+    PgWorkerRepository::new(&mut conn)
+        .find_by_id(worker_id)
+        .unwrap()
+}
+
+/// Retire a worker as if it happened `age` ago.
+fn retire(state: &AppState, worker_id: Uuid, status: WorkerStatus, age: Duration) {
+    let mut conn = state.get_conn().unwrap();
+    PgWorkerRepository::new(&mut conn)
+        .retire(worker_id, status)
+        .unwrap();
+    diesel::update(workers::table.find(worker_id))
+        .set(workers::retired_at.eq(Utc::now().naive_utc() - age))
+        .execute(&mut conn)
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_reregistering_by_name_reuses_the_worker() {
+    let tenant_id = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id]);
+    let (chain, fragments) = create_pending_work(&state, tenant_id, 1);
+
+    let name = "vulcan-worker-7d9f8b6c5-x2kqp";
+    let worker = register(
+        &state,
+        tenant_id,
+        json!({ "tenant_id": tenant_id, "name": name }),
+    )
+    .await;
+    assign(&state, worker, fragments[0]);
+    retire(&state, worker, WorkerStatus::Error, Duration::minutes(1));
+
+    // The pod restarts and registers again with new settings
+    let body = json!({
+        "tenant_id": tenant_id,
+        "name": name,
+        "machine_group": "gpu",
+        "max_concurrency": 4,
+        "labels": ["gpu"],
+    });
+    assert_eq!(register(&state, tenant_id, body).await, worker);
+
+    let reused = find_worker(&state, worker).unwrap();
+    assert_eq!(reused.status, WorkerStatus::Active);
+    assert_eq!(reused.retired_at, None);
+    assert_eq!(reused.machine_group.as_deref(), Some("gpu"));
+    assert_eq!(reused.max_concurrency, 4);
+    assert_eq!(reused.labels, vec!["gpu".to_string()]);
+
+    // What the previous instance had in flight went with it
+    assert_eq!(
+        fragment_status(&state, fragments[0]),
+        FragmentStatus::Pending
+    );
+
+    // The same name in another tenant is another worker
+    let other_tenant = Uuid::new_v4();
+    let other_state = create_test_state_for(&[other_tenant]);
+    let other = register(
+        &other_state,
+        other_tenant,
+        json!({ "tenant_id": other_tenant, "name": name }),
+    )
+    .await;
+    assert_ne!(other, worker);
+
+    cleanup(&state, &[chain], &[worker, other]);
+}
+
+#[tokio::test]
+async fn test_name_of_live_worker_is_not_taken_over() {
+    let tenant_id = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id]);
+    let (chain, fragments) = create_pending_work(&state, tenant_id, 1);
+
+    let body = json!({ "tenant_id": tenant_id, "name": "worker-a" });
+    let worker = register(&state, tenant_id, body.clone()).await;
+    assign(&state, worker, fragments[0]);
+
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/workers/register",
+        Some(&registration_token(tenant_id)),
+        Some(body.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(
+        fragment_status(&state, fragments[0]),
+        FragmentStatus::Running
+    );
+
+    // Once its heartbeat times out, the worker is gone and its name free again
+    let timeout = Duration::seconds(i64::try_from(state.config.heartbeat_timeout_secs).unwrap());
+    {
+        let mut conn = state.get_conn().unwrap();
+        diesel::update(workers::table.find(worker))
+            .set(workers::last_heartbeat_at.eq(Utc::now().naive_utc() - timeout * 2))
+            .execute(&mut conn)
+            .unwrap();
+    }
+    assert_eq!(register(&state, tenant_id, body).await, worker);
+    assert_eq!(
+        fragment_status(&state, fragments[0]),
+        FragmentStatus::Pending
+    );
+
+    cleanup(&state, &[chain], &[worker]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_registrations_take_over_a_name_once() {
+    let tenant_id = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id]);
+
+    let body = json!({ "tenant_id": tenant_id, "name": "worker-a" });
+    let worker = register(&state, tenant_id, body.clone()).await;
+    let timeout = Duration::seconds(i64::try_from(state.config.heartbeat_timeout_secs).unwrap());
+    {
+        let mut conn = state.get_conn().unwrap();
+        diesel::update(workers::table.find(worker))
+            .set(workers::last_heartbeat_at.eq(Utc::now().naive_utc() - timeout * 2))
+            .execute(&mut conn)
+            .unwrap();
+    }
+
+    // Only one of the restarted instances gets the gone worker's row
+    let registrations: Vec<_> = (0..4)
+        .map(|_| {
+            let state = state.clone();
+            let body = body.clone();
+            tokio::spawn(async move {
+                send(
+                    &state,
+                    "POST",
+                    "/workers/register",
+                    Some(&registration_token(tenant_id)),
+                    Some(body),
+                )
+                .await
+                .0
+            })
+        })
+        .collect();
+    let mut statuses = Vec::new();
+    for registration in registrations {
+        statuses.push(registration.await.unwrap());
+    }
+    statuses.sort();
+    assert_eq!(
+        statuses,
+        [
+            StatusCode::OK,
+            StatusCode::CONFLICT,
+            StatusCode::CONFLICT,
+            StatusCode::CONFLICT
+        ]
+    );
+
+    cleanup(&state, &[], &[worker]);
+}
+
+#[tokio::test]
+async fn test_deregistered_worker_can_register_again() {
+    let tenant_id = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id]);
+
+    let body = json!({ "tenant_id": tenant_id, "name": "worker-a" });
+    let worker = register(&state, tenant_id, body.clone()).await;
+
+    let (status, _) = send(
+        &state,
+        "POST",
+        "/workers/deregister",
+        Some(&worker_token(&state, worker)),
+        Some(json!({ "worker_id": worker })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let deregistered = find_worker(&state, worker).unwrap();
+    assert_eq!(deregistered.status, WorkerStatus::Deregistered);
+    assert!(deregistered.retired_at.is_some());
+
+    assert_eq!(register(&state, tenant_id, body).await, worker);
+    assert_eq!(
+        find_worker(&state, worker).unwrap().status,
+        WorkerStatus::Active
+    );
+
+    cleanup(&state, &[], &[worker]);
+}
+
+#[tokio::test]
+async fn test_retired_workers_are_deleted_after_retention() {
+    let tenant_id = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id]);
+    let retention = Duration::seconds(i64::try_from(state.config.worker_retention_secs).unwrap());
+
+    let expired_error = create_worker(&state, tenant_id);
+    retire(&state, expired_error, WorkerStatus::Error, retention * 2);
+    let expired_deregistered = create_worker(&state, tenant_id);
+    retire(
+        &state,
+        expired_deregistered,
+        WorkerStatus::Deregistered,
+        retention * 2,
+    );
+    let recent = create_worker(&state, tenant_id);
+    retire(&state, recent, WorkerStatus::Error, Duration::minutes(1));
+    let active = create_worker(&state, tenant_id);
+
+    let mut conn = state.get_conn().unwrap();
+    let deleted = delete_retired_workers(&mut conn, &state.config).unwrap();
+    drop(conn);
+
+    assert!(deleted >= 2);
+    assert!(find_worker(&state, expired_error).is_none());
+    assert!(find_worker(&state, expired_deregistered).is_none());
+    assert!(find_worker(&state, recent).is_some());
+    assert!(find_worker(&state, active).is_some());
+
+    cleanup(&state, &[], &[recent, active]);
+}
@@ -0,0 +1,154 @@
+//! Integration tests for single-query work claiming.
+
+mod common;
+
+use std::collections::HashSet;
+
+use diesel::Connection;
+use uuid::Uuid;
+
+use vulcan_core::{
+    FragmentRepository, FragmentStatus, NewFragment, NewWorker, PgFragmentRepository,
+    PgWorkerRepository, RepositoryError, Worker, WorkerRepository,
+};
+use vulcan_worker_orchestrator::AppState;
+use vulcan_worker_orchestrator::orchestrator::scheduler::Scheduler;
+
+use common::{
+    cleanup, create_chain, create_pending_work, create_sequential_work, create_test_state,
+    fragment_status,
+};
+
+fn create_worker(state: &AppState, worker: NewWorker) -> Worker {
+    let mut conn = state.get_conn().unwrap();
+    PgWorkerRepository::new(&mut conn).create(worker).unwrap()
+}
+
+fn claim(state: &AppState, worker: &Worker) -> Option<Uuid> {
+    let mut conn = state.get_conn().unwrap();
+    Scheduler::new(&mut conn)
+        .find_and_claim_work(worker)
+        .unwrap()
+        .map(|f| f.id)
+}
+
+/// Complete a fragment the way reporting a result does.
+fn complete(state: &AppState, fragment_id: Uuid) {
+    let mut conn = state.get_conn().unwrap();
+    conn.transaction::<_, RepositoryError, _>(|conn| {
+        let mut repo = PgFragmentRepository::new(conn);
+        repo.complete_execution(fragment_id, 0)?;
+        repo.release_successors(fragment_id)
+    })
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_sequential_fragments_wait_for_earlier_siblings() {
+    let state = create_test_state();
+    let tenant_id = Uuid::new_v4();
+    let (chain, fragments) = create_sequential_work(&state, tenant_id, 2);
+    let worker = create_worker(&state, NewWorker::new(tenant_id));
+
+    assert_eq!(claim(&state, &worker), Some(fragments[0]));
+    assert_eq!(claim(&state, &worker), None);
+
+    complete(&state, fragments[0]);
+    assert_eq!(claim(&state, &worker), Some(fragments[1]));
+
+    cleanup(&state, &[chain], &[worker.id]);
+}
+
+#[tokio::test]
+async fn test_parallel_children_are_claimable_together() {
+    let state = create_test_state();
+    let tenant_id = Uuid::new_v4();
+    let (chain, fragments) = create_pending_work(&state, tenant_id, 3);
+    let worker = create_worker(&state, NewWorker::new(tenant_id));
+
+    let claimed: HashSet<Uuid> = (0..3).filter_map(|_| claim(&state, &worker)).collect();
+    assert_eq!(claimed, fragments.iter().copied().collect());
+    assert_eq!(claim(&state, &worker), None);
+
+    cleanup(&state, &[chain], &[worker.id]);
+}
+
+#[tokio::test]
+async fn test_claim_matches_machine_group_and_labels() {
+    let state = create_test_state();
+    let tenant_id = Uuid::new_v4();
+
+    let mut fragment_id = Uuid::nil();
+    let chain = create_chain(&state, tenant_id, |chain_id| {
+        let fragment = NewFragment::inline(chain_id, 0, "true".to_string())
+            .with_machine("gpu".to_string())
+            .with_runs_on(vec!["docker".to_string()]);
+        fragment_id = fragment.id;
+        vec![fragment]
+    });
+
+    let wrong_group = create_worker(
+        &state,
+        NewWorker::new(tenant_id)
+            .with_machine_group("cpu".to_string())
+            .with_labels(vec!["docker".to_string()]),
+    );
+    let missing_label = create_worker(
+        &state,
+        NewWorker::new(tenant_id).with_machine_group("gpu".to_string()),
+    );
+    let matching = create_worker(
+        &state,
+        NewWorker::new(tenant_id)
+            .with_machine_group("gpu".to_string())
+            .with_labels(vec!["docker".to_string(), "os=linux".to_string()]),
+    );
+
+    assert_eq!(claim(&state, &wrong_group), None);
+    assert_eq!(claim(&state, &missing_label), None);
+    assert_eq!(claim(&state, &matching), Some(fragment_id));
+
+    cleanup(
+        &state,
+        &[chain],
+        &[wrong_group.id, missing_label.id, matching.id],
+    );
+}
+
+#[tokio::test]
+async fn test_concurrent_claims_never_hand_out_a_fragment_twice() {
+    let state = create_test_state();
+    let tenant_id = Uuid::new_v4();
+    let (chain, fragments) = create_pending_work(&state, tenant_id, 20);
+    let workers: Vec<Worker> = (0..8)
+        .map(|_| create_worker(&state, NewWorker::new(tenant_id)))
+        .collect();
+
+    let claims: Vec<Uuid> = std::thread::scope(|scope| {
+        let mut handles = Vec::new();
+        for worker in &workers {
+            let state = &state;
+            handles
+                .push(scope.spawn(move || {
+                    std::iter::from_fn(|| claim(state, worker)).collect::<Vec<_>>()
+                }));
+        }
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect()
+    });
+
+    let unique: HashSet<Uuid> = claims.iter().copied().collect();
+    assert_eq!(claims.len(), fragments.len());
+    assert_eq!(unique, fragments.iter().copied().collect());
+    for fragment_id in fragments {
+        assert_eq!(
+            fragment_status(&state, fragment_id),
+            FragmentStatus::Running
+        );
+    }
+
+    let worker_ids: Vec<Uuid> = workers.iter().map(|w| w.id).collect();
+    cleanup(&state, &[chain], &worker_ids);
+}
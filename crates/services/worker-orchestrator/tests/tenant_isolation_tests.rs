@@ -0,0 +1,179 @@
+//! Integration tests for tenant isolation in scheduling and queue metrics.
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use http_body_util::BodyExt;
+use serde_json::{Value, json};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use vulcan_core::{
+    FragmentRepository, FragmentStatus, NewWorker, PgFragmentRepository, PgWorkerRepository,
+    WorkerRepository,
+};
+use vulcan_worker_orchestrator::AppState;
+use vulcan_worker_orchestrator::api::create_router;
+
+use common::{
+    cleanup, controller_token, create_pending_work, create_test_state, create_test_state_for,
+    fragment_status, registration_token, worker_token,
+};
+
+/// Send a JSON request and return the status and parsed body (Null if empty).
+async fn send(
+    state: &AppState,
+    method: &str,
+    uri: &str,
+    token: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .header("Authorization", format!("Bearer {token}"))
+        .body(body.map_or_else(Body::empty, |b| Body::from(b.to_string())))
+        .unwrap();
+
+    let response = create_router(state.clone()).oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let value = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    (status, value)
+}
+
+/// Register a worker for `tenant_id` through the API, returning its ID.
+async fn register_worker(state: &AppState, tenant_id: Uuid, max_concurrency: i32) -> Uuid {
+    let (status, body) = send(
+        state,
+        "POST",
+        "/workers/register",
+        &registration_token(tenant_id),
+        Some(json!({ "tenant_id": tenant_id, "max_concurrency": max_concurrency })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    body["worker_id"].as_str().unwrap().parse().unwrap()
+}
+
+async fn request_work(state: &AppState, worker_id: Uuid) -> (StatusCode, Value) {
+    send(
+        state,
+        "POST",
+        "/work/request",
+        &worker_token(state, worker_id),
+        Some(json!({ "worker_id": worker_id })),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_worker_only_claims_own_tenant_work() {
+    let tenant_a = Uuid::new_v4();
+    let tenant_b = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_a, tenant_b]);
+    let (chain_a, fragments_a) = create_pending_work(&state, tenant_a, 1);
+    let (chain_b, fragments_b) = create_pending_work(&state, tenant_b, 1);
+    let worker = register_worker(&state, tenant_a, 2).await;
+
+    let (status, body) = request_work(&state, worker).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["fragment_id"], json!(fragments_a[0]));
+
+    // A free slot remains, but the only pending work belongs to tenant B
+    let (status, _) = request_work(&state, worker).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(
+        fragment_status(&state, fragments_b[0]),
+        FragmentStatus::Pending
+    );
+
+    cleanup(&state, &[chain_a, chain_b], &[worker]);
+}
+
+#[tokio::test]
+async fn test_worker_without_own_work_gets_nothing() {
+    let tenant_id = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_id]);
+    let (chain, fragments) = create_pending_work(&state, Uuid::new_v4(), 3);
+    let worker = register_worker(&state, tenant_id, 1).await;
+
+    let (status, _) = request_work(&state, worker).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    for fragment_id in fragments {
+        assert_eq!(
+            fragment_status(&state, fragment_id),
+            FragmentStatus::Pending
+        );
+    }
+
+    cleanup(&state, &[chain], &[worker]);
+}
+
+#[tokio::test]
+async fn test_try_claim_rejects_other_tenant() {
+    let state = create_test_state();
+    let tenant_a = Uuid::new_v4();
+    let (chain, fragments) = create_pending_work(&state, Uuid::new_v4(), 1);
+
+    let mut conn = state.get_conn().unwrap();
+    let worker = PgWorkerRepository::new(&mut conn)
+        .create(NewWorker::new(tenant_a))
+        .unwrap();
+
+    let claimed = PgFragmentRepository::new(&mut conn)
+        .try_claim(fragments[0], worker.id, tenant_a)
+        .unwrap();
+    assert!(claimed.is_none());
+    drop(conn);
+    assert_eq!(
+        fragment_status(&state, fragments[0]),
+        FragmentStatus::Pending
+    );
+
+    cleanup(&state, &[chain], &[worker.id]);
+}
+
+#[tokio::test]
+async fn test_queue_metrics_scoped_to_tenant() {
+    let tenant_a = Uuid::new_v4();
+    let tenant_b = Uuid::new_v4();
+    let state = create_test_state_for(&[tenant_a, tenant_b]);
+    let (chain_a, _) = create_pending_work(&state, tenant_a, 2);
+    let (chain_b, _) = create_pending_work(&state, tenant_b, 5);
+    let worker_a = register_worker(&state, tenant_a, 1).await;
+    let worker_b = register_worker(&state, tenant_b, 1).await;
+
+    // Tenant A's worker claims one of its two fragments
+    let (status, _) = request_work(&state, worker_a).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, metrics) = send(
+        &state,
+        "GET",
+        &format!("/queue/metrics?tenant_id={tenant_a}"),
+        &controller_token(tenant_a),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(metrics["pending_fragments"], 1);
+    assert_eq!(metrics["running_fragments"], 1);
+    assert_eq!(metrics["active_workers"], 1);
+
+    let (_, metrics) = send(
+        &state,
+        "GET",
+        &format!("/queue/metrics?tenant_id={tenant_b}"),
+        &controller_token(tenant_b),
+        None,
+    )
+    .await;
+    assert_eq!(metrics["pending_fragments"], 5);
+    assert_eq!(metrics["running_fragments"], 0);
+    assert_eq!(metrics["active_workers"], 1);
+
+    cleanup(&state, &[chain_a, chain_b], &[worker_a, worker_b]);
+}
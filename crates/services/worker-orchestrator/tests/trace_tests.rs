@@ -0,0 +1,158 @@
+//! Integration tests for trace propagation from a stored chain to the claim and
+//! result report of its fragments, exported to an in-process OTLP collector stub.
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Request, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+use tracing::info_span;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use uuid::Uuid;
+
+use vulcan_core::{
+    ChainRepository, FragmentRepository, NewChain, PgChainRepository, PgFragmentRepository,
+};
+use vulcan_telemetry::Protocol;
+use vulcan_worker_orchestrator::AppState;
+use vulcan_worker_orchestrator::api::create_router;
+
+use common::{cleanup, create_test_state, create_worker, inline_fragments, send, worker_token};
+
+/// Spans received by the collector stub, as OTLP JSON.
+type Received = Arc<Mutex<Vec<Value>>>;
+
+/// Start an OTLP/HTTP collector accepting JSON traces, returning its URL and the
+/// spans it receives.
+async fn start_collector() -> (String, Received) {
+    async fn export(State(received): State<Received>, Json(body): Json<Value>) -> Json<Value> {
+        let spans = body["resourceSpans"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .flat_map(|resource| resource["scopeSpans"].as_array().into_iter().flatten())
+            .flat_map(|scope| scope["spans"].as_array().into_iter().flatten())
+            .cloned();
+        received.lock().unwrap().extend(spans);
+        Json(json!({}))
+    }
+
+    let received = Received::default();
+    let app = Router::new()
+        .route("/v1/traces", post(export))
+        .with_state(received.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    (url, received)
+}
+
+/// Trace ID and span ID of a `traceparent` value.
+fn ids(traceparent: &str) -> (String, String) {
+    let parts: Vec<&str> = traceparent.split('-').collect();
+    (parts[1].to_string(), parts[2].to_string())
+}
+
+/// Create an active chain of one fragment that continues `trace_context`.
+fn create_traced_chain(state: &AppState, tenant_id: Uuid, trace_context: String) -> Uuid {
+    let mut conn = state.get_conn().unwrap();
+    let chain = PgChainRepository::new(&mut conn)
+        .create(NewChain::new(tenant_id).with_trace_context(trace_context))
+        .unwrap();
+
+    let mut repo = PgFragmentRepository::new(&mut conn);
+    repo.create_many(inline_fragments(chain.id, None, 1))
+        .unwrap();
+    repo.activate_chain(chain.id).unwrap();
+    chain.id
+}
+
+/// The received span named `name` in trace `trace_id`.
+fn find_span(received: &Received, trace_id: &str, name: &str) -> Value {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|span| span["traceId"] == trace_id && span["name"] == name)
+        .cloned()
+        .unwrap_or_else(|| panic!("no {name} span in trace {trace_id}"))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_claim_and_result_join_the_trace_of_the_chain() {
+    let (collector, received) = start_collector().await;
+    let exporter = vulcan_telemetry::exporter(&collector, Protocol::HttpJson).unwrap();
+    let provider = vulcan_telemetry::tracer_provider("trace-test", Some(exporter));
+    let _subscriber = tracing_subscriber::registry()
+        .with(vulcan_telemetry::layer(&provider))
+        .set_default();
+
+    let state = create_test_state();
+    let tenant_id = Uuid::new_v4();
+    let worker_id = create_worker(&state, tenant_id);
+
+    // The chain as stored by the chain parser API
+    let chain_trace = vulcan_telemetry::traceparent(&info_span!("store_chain")).unwrap();
+    let (trace_id, store_span_id) = ids(&chain_trace);
+    let chain_id = create_traced_chain(&state, tenant_id, chain_trace);
+
+    let (status, work) = send(
+        &state,
+        "POST",
+        "/work/request",
+        Some(&worker_token(&state, worker_id)),
+        Some(json!({ "worker_id": worker_id })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (work_trace_id, claim_span_id) = ids(work["traceparent"].as_str().unwrap());
+    assert_eq!(work_trace_id, trace_id);
+
+    // The worker reports the result in a span continuing the claim
+    let report_span = info_span!("report_result");
+    vulcan_telemetry::set_parent(&report_span, work["traceparent"].as_str().unwrap());
+    let report_trace = vulcan_telemetry::traceparent(&report_span).unwrap();
+    let body = json!({
+        "worker_id": worker_id,
+        "fragment_id": work["fragment_id"],
+        "attempt": work["attempt"],
+        "success": true,
+        "exit_code": 0,
+    });
+    let request = Request::builder()
+        .method("POST")
+        .uri("/work/result")
+        .header("Content-Type", "application/json")
+        .header(
+            "Authorization",
+            format!("Bearer {}", worker_token(&state, worker_id)),
+        )
+        .header("traceparent", &report_trace)
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = create_router(state.clone()).oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    drop(report_span);
+
+    tokio::task::spawn_blocking(move || provider.force_flush())
+        .await
+        .unwrap()
+        .unwrap();
+
+    let claim = find_span(&received, &trace_id, "claim");
+    assert_eq!(claim["spanId"], claim_span_id);
+    assert_eq!(claim["parentSpanId"], store_span_id);
+
+    let result = find_span(&received, &trace_id, "POST /work/result");
+    assert_eq!(result["parentSpanId"], ids(&report_trace).1);
+
+    cleanup(&state, &[chain_id], &[worker_id]);
+}
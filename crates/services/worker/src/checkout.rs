@@ -0,0 +1,323 @@
+//! Workspace checkout of the triggering commit.
+//!
+//! Before a fragment runs, the worker materializes the chain's repository at
+//! `commit_sha` into the workspace directory. To avoid cloning the repository
+//! for every fragment, each worker keeps a bare mirror per repository in its
+//! cache directory and only fetches from the remote when the commit is missing.
+//!
+//! Credentials are read from an optional git-credentials file (the format used
+//! by `git credential-store`), matched per repository path.
+//!
+//! Repository URLs and commits come from workflow submissions, so they are checked
+//! before git sees them: commits must be hexadecimal object names, and repositories
+//! `https://` or SSH URLs. Local repositories would let a chain read the mirrors of
+//! other tenants in the shared cache directory.
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+
+use tokio::process::Command;
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+use vulcan_core::models::chain::{is_commit_sha, is_remote_repository_url};
+
+use crate::config::CheckoutConfig;
+use crate::error::{Result, WorkerError};
+
+/// What to check out into a workspace.
+#[derive(Debug, Clone)]
+pub struct CheckoutRequest<'a> {
+    /// Repository URL to check out.
+    pub repository_url: &'a str,
+    /// Commit to check out.
+    pub commit_sha: &'a str,
+    /// Number of commits of history to fetch (None = full history).
+    pub depth: Option<i32>,
+    /// Paths to materialize (empty = whole tree).
+    pub sparse_paths: &'a [String],
+}
+
+/// Materializes repositories into workspaces via a local mirror cache.
+#[derive(Debug, Clone)]
+pub struct Checkout {
+    /// Checkout configuration.
+    config: CheckoutConfig,
+    /// Serializes mirror updates so concurrent checkouts don't race on the cache.
+    mirror_lock: Arc<Mutex<()>>,
+}
+
+impl Checkout {
+    /// Create a new checkout helper with the given configuration.
+    #[must_use]
+    pub fn new(config: CheckoutConfig) -> Self {
+        Self {
+            config,
+            mirror_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Check out the requested commit into `workspace`.
+    ///
+    /// The workspace is created if needed. An existing checkout in the workspace is
+    /// reused: the commit is force-checked-out and untracked files are removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the repository cannot be fetched or the commit checked out.
+    pub async fn materialize(&self, request: &CheckoutRequest<'_>, workspace: &Path) -> Result<()> {
+        validate_commit_sha(request.commit_sha)?;
+        self.validate_repository_url(request.repository_url)?;
+
+        info!(
+            repository_url = %request.repository_url,
+            commit_sha = %request.commit_sha,
+            workspace = %workspace.display(),
+            "Checking out workspace"
+        );
+
+        let mirror = self
+            .update_mirror(request.repository_url, request.commit_sha)
+            .await?;
+
+        self.checkout_from_mirror(&mirror, request, workspace).await
+    }
+
+    /// Ensure the mirror for `repository_url` exists and contains `commit_sha`.
+    async fn update_mirror(&self, repository_url: &str, commit_sha: &str) -> Result<PathBuf> {
+        let _guard = self.mirror_lock.lock().await;
+
+        let cache_dir = Path::new(&self.config.cache_dir);
+        let mirror = cache_dir.join(mirror_dir_name(repository_url));
+
+        if !mirror.join("HEAD").exists() {
+            debug!(mirror = %mirror.display(), "Creating repository mirror");
+            tokio::fs::create_dir_all(cache_dir).await?;
+
+            let mirror_str = mirror.to_string_lossy();
+            if let Err(e) = self
+                .git(
+                    None,
+                    &[
+                        "clone",
+                        "--mirror",
+                        "--quiet",
+                        "--end-of-options",
+                        repository_url,
+                        &mirror_str,
+                    ],
+                )
+                .await
+            {
+                // Don't leave a half-cloned mirror behind
+                let _ = tokio::fs::remove_dir_all(&mirror).await;
+                return Err(e);
+            }
+
+            // Allow workspaces to fetch exact commits (needed for shallow fetches)
+            self.git(
+                Some(&mirror),
+                &["config", "uploadpack.allowAnySHA1InWant", "true"],
+            )
+            .await?;
+        }
+
+        if !self.has_commit(&mirror, commit_sha).await {
+            debug!(mirror = %mirror.display(), "Fetching repository mirror");
+            self.git(Some(&mirror), &["fetch", "--prune", "--quiet", "origin"])
+                .await?;
+        }
+
+        if !self.has_commit(&mirror, commit_sha).await {
+            // The commit may not be reachable from any branch (e.g. a pull request head)
+            self.git(
+                Some(&mirror),
+                &["fetch", "--quiet", "--end-of-options", "origin", commit_sha],
+            )
+            .await?;
+        }
+
+        if !self.has_commit(&mirror, commit_sha).await {
+            return Err(WorkerError::Checkout(format!(
+                "commit {commit_sha} not found in {repository_url}"
+            )));
+        }
+
+        Ok(mirror)
+    }
+
+    /// Check out `commit_sha` from the mirror into the workspace.
+    async fn checkout_from_mirror(
+        &self,
+        mirror: &Path,
+        request: &CheckoutRequest<'_>,
+        workspace: &Path,
+    ) -> Result<()> {
+        tokio::fs::create_dir_all(workspace).await?;
+
+        if !workspace.join(".git").exists() {
+            self.git(Some(workspace), &["init", "--quiet"]).await?;
+        }
+
+        // Scripts should see the real remote, not the local mirror
+        self.git(
+            Some(workspace),
+            &[
+                "config",
+                "--end-of-options",
+                "remote.origin.url",
+                request.repository_url,
+            ],
+        )
+        .await?;
+
+        // git percent-decodes file:// URLs, so escape the mirror name's encoding
+        let mirror_url = format!(
+            "file://{}",
+            mirror.display().to_string().replace('%', "%25")
+        );
+        let depth_arg = request.depth.map(|d| format!("--depth={d}"));
+        let mut fetch_args = vec!["fetch", "--quiet"];
+        if let Some(ref depth) = depth_arg {
+            fetch_args.push(depth);
+        }
+        fetch_args.push("--end-of-options");
+        fetch_args.push(&mirror_url);
+        fetch_args.push(request.commit_sha);
+        self.git(Some(workspace), &fetch_args).await?;
+
+        if request.sparse_paths.is_empty() {
+            // Restore the full tree if a previous checkout in this workspace was sparse
+            if workspace.join(".git/info/sparse-checkout").exists() {
+                self.git(Some(workspace), &["sparse-checkout", "disable"])
+                    .await?;
+            }
+        } else {
+            // Anchor patterns at the repository root
+            let patterns: Vec<String> = request
+                .sparse_paths
+                .iter()
+                .map(|p| format!("/{}", p.trim_start_matches('/')))
+                .collect();
+            let mut sparse_args = vec!["sparse-checkout", "set", "--no-cone", "--"];
+            sparse_args.extend(patterns.iter().map(String::as_str));
+            self.git(Some(workspace), &sparse_args).await?;
+        }
+
+        // `checkout --detach` rejects `--end-of-options`: the commit is validated, and
+        // the trailing `--` keeps it from being read as a path
+        self.git(
+            Some(workspace),
+            &[
+                "checkout",
+                "--quiet",
+                "--force",
+                "--detach",
+                request.commit_sha,
+                "--",
+            ],
+        )
+        .await?;
+        self.git(Some(workspace), &["clean", "-ffdxq"]).await?;
+
+        info!(
+            commit_sha = %request.commit_sha,
+            workspace = %workspace.display(),
+            "Workspace checked out"
+        );
+
+        Ok(())
+    }
+
+    /// Check whether the repository at `dir` contains the given commit.
+    async fn has_commit(&self, dir: &Path, commit_sha: &str) -> bool {
+        let object = format!("{commit_sha}^{{commit}}");
+        self.git(Some(dir), &["cat-file", "-e", "--end-of-options", &object])
+            .await
+            .is_ok()
+    }
+
+    /// Check that a repository URL is one the worker may fetch from: a remote
+    /// repository, or a local one if the configuration allows them.
+    fn validate_repository_url(&self, repository_url: &str) -> Result<()> {
+        let local = self.config.allow_local_repositories
+            && (repository_url.starts_with("file://") || repository_url.starts_with('/'));
+        if local || is_remote_repository_url(repository_url) {
+            Ok(())
+        } else {
+            Err(WorkerError::Checkout(format!(
+                "invalid repository URL {repository_url:?}: only https and ssh repositories are \
+                 supported"
+            )))
+        }
+    }
+
+    /// Run a git command, returning an error with git's stderr on failure.
+    async fn git(&self, dir: Option<&Path>, args: &[&str]) -> Result<()> {
+        let mut cmd = Command::new("git");
+
+        if let Some(dir) = dir {
+            cmd.arg("-C").arg(dir);
+        }
+
+        // Only use the configured credentials, never a system-wide helper
+        cmd.arg("-c").arg("credential.helper=");
+        if let Some(ref file) = self.config.credentials_file {
+            cmd.arg("-c")
+                .arg(format!("credential.helper=store --file={file}"))
+                .arg("-c")
+                .arg("credential.useHttpPath=true");
+        }
+
+        let output = cmd
+            .args(args)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .output()
+            .await?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(WorkerError::Checkout(format!(
+                "git {} failed: {}",
+                args.first().unwrap_or(&""),
+                stderr.trim()
+            )))
+        }
+    }
+}
+
+/// Check that a commit is a hexadecimal object name, abbreviated or full.
+fn validate_commit_sha(commit_sha: &str) -> Result<()> {
+    if is_commit_sha(commit_sha) {
+        Ok(())
+    } else {
+        Err(WorkerError::Checkout(format!(
+            "invalid commit {commit_sha:?}: expected a hexadecimal commit SHA"
+        )))
+    }
+}
+
+/// Directory name for a repository's mirror inside the cache.
+///
+/// Every character outside `[A-Za-z0-9_-]` is percent-encoded, so distinct URLs
+/// always map to distinct directories.
+fn mirror_dir_name(repository_url: &str) -> String {
+    let mut name = String::with_capacity(repository_url.len() + 4);
+    for byte in repository_url.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            name.push(char::from(byte));
+        } else {
+            let _ = write!(name, "%{byte:02X}");
+        }
+    }
+    name.push_str(".git");
+    name
+}
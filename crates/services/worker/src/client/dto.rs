@@ -15,6 +15,12 @@ pub struct RegisterWorkerRequest {
     pub tenant_id: Uuid,
     /// Machine group this worker belongs to (optional).
     pub machine_group: Option<String>,
+    /// Maximum number of fragments the worker executes at once.
+    pub max_concurrency: i32,
+    /// Capability labels used to route fragments to this worker.
+    pub labels: Vec<String>,
+    /// Name of this worker's instance, e.g. its pod (optional).
+    pub name: Option<String>,
 }
 
 /// Response from worker registration.
@@ -24,6 +30,15 @@ pub struct RegisterWorkerResponse {
     pub worker_id: Uuid,
     /// Current status of the worker.
     pub status: String,
+    /// Token authenticating the worker on subsequent requests.
+    pub token: String,
+}
+
+/// A renewed worker token.
+#[derive(Debug, Deserialize)]
+pub struct WorkerTokenResponse {
+    /// Token authenticating the worker.
+    pub token: String,
 }
 
 // ============================================================================
@@ -35,6 +50,10 @@ pub struct RegisterWorkerResponse {
 pub struct HeartbeatRequest {
     /// Worker ID sending the heartbeat.
     pub worker_id: Uuid,
+    /// Fragments the worker is currently executing.
+    pub fragment_ids: Vec<Uuid>,
+    /// Whether the worker is draining.
+    pub draining: bool,
 }
 
 /// Response from heartbeat.
@@ -44,6 +63,22 @@ pub struct HeartbeatResponse {
     pub status: String,
     /// Server timestamp.
     pub timestamp: NaiveDateTime,
+    /// Renewed worker token.
+    pub token: String,
+    /// Whether the worker was asked to drain.
+    #[serde(default)]
+    pub draining: bool,
+}
+
+// ============================================================================
+// Deregistration
+// ============================================================================
+
+/// Request to deregister a worker.
+#[derive(Debug, Serialize)]
+pub struct DeregisterWorkerRequest {
+    /// Worker ID to deregister.
+    pub worker_id: Uuid,
 }
 
 // ============================================================================
@@ -55,6 +90,11 @@ pub struct HeartbeatResponse {
 pub struct WorkRequest {
     /// Worker ID requesting work.
     pub worker_id: Uuid,
+    /// How long the orchestrator may hold the request open waiting for work, in seconds.
+    pub wait_seconds: u64,
+    /// The only fragment to claim, for a worker launched to execute it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fragment_id: Option<Uuid>,
 }
 
 /// Response with assigned work.
@@ -68,6 +108,21 @@ pub struct WorkResponse {
     pub run_script: Option<String>,
     /// Current attempt number.
     pub attempt: i32,
+    /// Repository to check out into the workspace, if any.
+    #[serde(default)]
+    pub repository_url: Option<String>,
+    /// Commit to check out (set together with `repository_url`).
+    #[serde(default)]
+    pub commit_sha: Option<String>,
+    /// Clone depth for the checkout (None = full history).
+    #[serde(default)]
+    pub checkout_depth: Option<i32>,
+    /// Paths for a sparse checkout (empty = whole tree).
+    #[serde(default)]
+    pub checkout_sparse_paths: Vec<String>,
+    /// W3C `traceparent` of the claim, continued by the execution.
+    #[serde(default)]
+    pub traceparent: Option<String>,
 }
 
 // ============================================================================
@@ -81,12 +136,16 @@ pub struct WorkResultRequest {
     pub worker_id: Uuid,
     /// Fragment ID that was executed.
     pub fragment_id: Uuid,
+    /// Attempt number the fragment was assigned with.
+    pub attempt: i32,
     /// Whether execution succeeded.
     pub success: bool,
     /// Exit code from execution.
     pub exit_code: Option<i32>,
     /// Error message if failed.
     pub error_message: Option<String>,
+    /// Whether execution was cancelled before producing a result.
+    pub cancelled: bool,
 }
 
 /// Response after reporting work result.
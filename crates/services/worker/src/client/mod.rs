@@ -2,7 +2,10 @@
 
 pub mod dto;
 
-use reqwest::{Client, StatusCode};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use reqwest::{Certificate, Client, Identity, RequestBuilder, StatusCode};
 use tracing::debug;
 use uuid::Uuid;
 
@@ -10,15 +13,24 @@ use crate::config::Config;
 use crate::error::{Result, WorkerError};
 
 pub use dto::{
-    HeartbeatRequest, HeartbeatResponse, RegisterWorkerRequest, RegisterWorkerResponse,
-    WorkRequest, WorkResponse, WorkResultRequest, WorkResultResponse,
+    DeregisterWorkerRequest, HeartbeatRequest, HeartbeatResponse, RegisterWorkerRequest,
+    RegisterWorkerResponse,
+    WorkRequest, WorkResponse, WorkResultRequest, WorkResultResponse, WorkerTokenResponse,
 };
 
 /// Client for communicating with the worker orchestrator API.
+///
+/// Registers with the tenant's registration token and authenticates every later
+/// request with the worker token it receives, which heartbeats keep renewed. Every
+/// request carries the trace context of the current span in a `traceparent` header.
 #[derive(Clone)]
 pub struct OrchestratorClient {
     client: Client,
     base_url: String,
+    request_timeout: Duration,
+    registration_token: String,
+    /// Current worker token, shared between clones.
+    worker_token: Arc<RwLock<String>>,
 }
 
 impl OrchestratorClient {
@@ -26,18 +38,49 @@ impl OrchestratorClient {
     ///
     /// # Errors
     ///
-    /// Returns an error if the HTTP client cannot be built.
+    /// Returns an error if the TLS files cannot be read or the HTTP client cannot be
+    /// built.
     pub fn new(config: &Config) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(config.request_timeout)
-            .build()?;
+        let mut builder = Client::builder().timeout(config.request_timeout);
+
+        if let Some(ca_file) = &config.tls.ca_file {
+            let ca = Certificate::from_pem(&read_tls_file(ca_file)?)?;
+            builder = builder.add_root_certificate(ca);
+        }
+        if let Some((cert_file, key_file)) = &config.tls.identity {
+            builder = builder.identity(Identity::from_pkcs8_pem(
+                &read_tls_file(cert_file)?,
+                &read_tls_file(key_file)?,
+            )?);
+        }
+
+        let client = builder.build()?;
 
         Ok(Self {
             client,
             base_url: config.orchestrator_url.clone(),
+            request_timeout: config.request_timeout,
+            registration_token: config.registration_token.clone(),
+            worker_token: Arc::default(),
         })
     }
 
+    /// Attach the current worker token to a request.
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        let token = self
+            .worker_token
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        traced(request).bearer_auth(token.as_str())
+    }
+
+    fn set_worker_token(&self, token: String) {
+        *self
+            .worker_token
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = token;
+    }
+
     /// Register this worker with the orchestrator.
     ///
     /// # Errors
@@ -47,19 +90,30 @@ impl OrchestratorClient {
         &self,
         tenant_id: Uuid,
         machine_group: Option<String>,
+        max_concurrency: usize,
+        labels: Vec<String>,
+        name: Option<String>,
     ) -> Result<RegisterWorkerResponse> {
         let url = format!("{}/workers/register", self.base_url);
         let request = RegisterWorkerRequest {
             tenant_id,
             machine_group,
+            max_concurrency: i32::try_from(max_concurrency).unwrap_or(i32::MAX),
+            labels,
+            name,
         };
 
         debug!(%url, "Registering worker");
 
-        let response = self.client.post(&url).json(&request).send().await?;
+        let response = traced(self.client.post(&url))
+            .bearer_auth(&self.registration_token)
+            .json(&request)
+            .send()
+            .await?;
 
         if response.status().is_success() {
             let body = response.json::<RegisterWorkerResponse>().await?;
+            self.set_worker_token(body.token.clone());
             Ok(body)
         } else {
             let status = response.status();
@@ -70,21 +124,76 @@ impl OrchestratorClient {
         }
     }
 
-    /// Send a heartbeat to the orchestrator.
+    /// Request a new worker token with the registration token.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the renewal request fails.
+    pub async fn renew_token(&self, worker_id: Uuid) -> Result<()> {
+        let url = format!("{}/workers/{worker_id}/token", self.base_url);
+
+        debug!(%url, %worker_id, "Renewing worker token");
+
+        let response = traced(self.client.post(&url))
+            .bearer_auth(&self.registration_token)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            let body = response.json::<WorkerTokenResponse>().await?;
+            self.set_worker_token(body.token);
+            Ok(())
+        } else {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            Err(WorkerError::Orchestrator(format!(
+                "Token renewal failed: {status} - {body}"
+            )))
+        }
+    }
+
+    /// Send a heartbeat to the orchestrator, reporting the fragments in flight and
+    /// whether the worker is draining.
+    ///
+    /// The renewed worker token in the response replaces the current one. If the
+    /// current token was rejected, e.g. because it expired while the orchestrator
+    /// was unreachable, a new one is requested with the registration token.
     ///
     /// # Errors
     ///
     /// Returns an error if the heartbeat request fails.
-    pub async fn heartbeat(&self, worker_id: Uuid) -> Result<HeartbeatResponse> {
+    pub async fn heartbeat(
+        &self,
+        worker_id: Uuid,
+        fragment_ids: Vec<Uuid>,
+        draining: bool,
+    ) -> Result<HeartbeatResponse> {
         let url = format!("{}/workers/heartbeat", self.base_url);
-        let request = HeartbeatRequest { worker_id };
+        let in_flight = fragment_ids.len();
+        let request = HeartbeatRequest {
+            worker_id,
+            fragment_ids,
+            draining,
+        };
 
-        debug!(%url, %worker_id, "Sending heartbeat");
+        debug!(%url, %worker_id, in_flight, draining, "Sending heartbeat");
 
-        let response = self.client.post(&url).json(&request).send().await?;
+        let response = self
+            .authorized(self.client.post(&url))
+            .json(&request)
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::UNAUTHORIZED {
+            self.renew_token(worker_id).await?;
+            return Err(WorkerError::Orchestrator(
+                "Heartbeat rejected, worker token renewed".to_string(),
+            ));
+        }
 
         if response.status().is_success() {
             let body = response.json::<HeartbeatResponse>().await?;
+            self.set_worker_token(body.token.clone());
             Ok(body)
         } else {
             let status = response.status();
@@ -95,20 +204,65 @@ impl OrchestratorClient {
         }
     }
 
+    /// Deregister this worker before it exits.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the deregistration request fails.
+    pub async fn deregister(&self, worker_id: Uuid) -> Result<()> {
+        let url = format!("{}/workers/deregister", self.base_url);
+        let request = DeregisterWorkerRequest { worker_id };
+
+        debug!(%url, %worker_id, "Deregistering worker");
+
+        let response = self
+            .authorized(self.client.post(&url))
+            .json(&request)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            Err(WorkerError::Orchestrator(format!(
+                "Deregistration failed: {status} - {body}"
+            )))
+        }
+    }
+
     /// Request work from the orchestrator.
     ///
-    /// Returns `None` if no work is available (204 No Content).
+    /// The orchestrator holds the request open for up to `wait` until matching work
+    /// becomes ready. Returns `None` if no work is available by then (204 No Content).
+    /// With `fragment_id`, only that fragment is claimed.
     ///
     /// # Errors
     ///
     /// Returns an error if the request fails.
-    pub async fn request_work(&self, worker_id: Uuid) -> Result<Option<WorkResponse>> {
+    pub async fn request_work(
+        &self,
+        worker_id: Uuid,
+        wait: Duration,
+        fragment_id: Option<Uuid>,
+    ) -> Result<Option<WorkResponse>> {
         let url = format!("{}/work/request", self.base_url);
-        let request = WorkRequest { worker_id };
+        let request = WorkRequest {
+            worker_id,
+            wait_seconds: wait.as_secs(),
+            fragment_id,
+        };
 
-        debug!(%url, %worker_id, "Requesting work");
+        debug!(%url, %worker_id, wait_secs = wait.as_secs(), "Requesting work");
 
-        let response = self.client.post(&url).json(&request).send().await?;
+        // The request may be held open for the whole wait, on top of the usual timeout
+        let response = self
+            .authorized(self.client.post(&url))
+            .timeout(self.request_timeout + wait)
+            .json(&request)
+            .send()
+            .await?;
 
         match response.status() {
             StatusCode::OK => {
@@ -127,29 +281,76 @@ impl OrchestratorClient {
 
     /// Report work execution result to the orchestrator.
     ///
+    /// `attempt` is the attempt the work was assigned with; reporting the same result
+    /// twice is harmless.
+    ///
     /// # Errors
     ///
-    /// Returns an error if the request fails.
+    /// Returns an error if the request fails, or if the fragment has since been
+    /// reassigned (409 Conflict).
     pub async fn report_result(
         &self,
         worker_id: Uuid,
         fragment_id: Uuid,
+        attempt: i32,
         success: bool,
         exit_code: Option<i32>,
         error_message: Option<String>,
     ) -> Result<WorkResultResponse> {
-        let url = format!("{}/work/result", self.base_url);
-        let request = WorkResultRequest {
+        self.send_result(WorkResultRequest {
             worker_id,
             fragment_id,
+            attempt,
             success,
             exit_code,
             error_message,
-        };
+            cancelled: false,
+        })
+        .await
+    }
 
-        debug!(%url, %worker_id, %fragment_id, %success, "Reporting result");
+    /// Report that the execution of a fragment was cancelled without a result, so
+    /// the orchestrator retries it elsewhere.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails, or if the fragment has since been
+    /// reassigned (409 Conflict).
+    pub async fn report_cancelled(
+        &self,
+        worker_id: Uuid,
+        fragment_id: Uuid,
+        attempt: i32,
+    ) -> Result<WorkResultResponse> {
+        self.send_result(WorkResultRequest {
+            worker_id,
+            fragment_id,
+            attempt,
+            success: false,
+            exit_code: None,
+            error_message: Some("Execution cancelled".to_string()),
+            cancelled: true,
+        })
+        .await
+    }
+
+    async fn send_result(&self, request: WorkResultRequest) -> Result<WorkResultResponse> {
+        let url = format!("{}/work/result", self.base_url);
 
-        let response = self.client.post(&url).json(&request).send().await?;
+        debug!(
+            %url,
+            worker_id = %request.worker_id,
+            fragment_id = %request.fragment_id,
+            success = request.success,
+            cancelled = request.cancelled,
+            "Reporting result"
+        );
+
+        let response = self
+            .authorized(self.client.post(&url))
+            .json(&request)
+            .send()
+            .await?;
 
         if response.status().is_success() {
             let body = response.json::<WorkResultResponse>().await?;
@@ -163,3 +364,16 @@ impl OrchestratorClient {
         }
     }
 }
+
+/// Attach the trace context of the current span to a request.
+fn traced(request: RequestBuilder) -> RequestBuilder {
+    match vulcan_telemetry::current_traceparent() {
+        Some(traceparent) => request.header(vulcan_telemetry::TRACEPARENT, traceparent),
+        None => request,
+    }
+}
+
+fn read_tls_file(path: &str) -> Result<Vec<u8>> {
+    std::fs::read(path)
+        .map_err(|e| WorkerError::InvalidConfig(format!("Cannot read {path}: {e}")))
+}
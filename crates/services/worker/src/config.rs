@@ -6,6 +6,7 @@ use std::time::Duration;
 use uuid::Uuid;
 
 use crate::error::{Result, WorkerError};
+use crate::labels;
 
 /// Sandbox configuration for script execution.
 #[derive(Debug, Clone)]
@@ -16,8 +17,12 @@ pub struct SandboxConfig {
     pub memory_limit: String,
     /// Whether to allow network access in sandbox.
     pub network: bool,
-    /// Scratch directory for script execution.
+    /// Root directory under which per-execution scratch directories are created.
     pub scratch_dir: String,
+    /// Whether to keep the scratch directory of a failed execution for debugging.
+    pub preserve_on_failure: bool,
+    /// How long a preserved scratch directory is kept.
+    pub preserve_ttl: Duration,
 }
 
 impl Default for SandboxConfig {
@@ -27,10 +32,92 @@ impl Default for SandboxConfig {
             memory_limit: "512M".to_string(),
             network: false,
             scratch_dir: "/scratch".to_string(),
+            preserve_on_failure: false,
+            preserve_ttl: Duration::from_hours(1),
         }
     }
 }
 
+impl SandboxConfig {
+    fn from_env() -> Self {
+        Self {
+            enabled: env::var("SANDBOX_ENABLED")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(true),
+            memory_limit: env::var("SANDBOX_MEMORY_LIMIT")
+                .unwrap_or_else(|_| "512M".to_string()),
+            network: env::var("SANDBOX_NETWORK")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(false),
+            scratch_dir: env::var("SANDBOX_SCRATCH_DIR")
+                .unwrap_or_else(|_| "/scratch".to_string()),
+            preserve_on_failure: env::var("SANDBOX_PRESERVE_ON_FAILURE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(false),
+            preserve_ttl: Duration::from_secs(
+                env::var("SANDBOX_PRESERVE_TTL_SECS")
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(3600),
+            ),
+        }
+    }
+}
+
+/// Workspace checkout configuration.
+#[derive(Debug, Clone)]
+pub struct CheckoutConfig {
+    /// Directory holding the per-worker bare mirror cache.
+    pub cache_dir: String,
+    /// Optional git-credentials file with per-repository credentials.
+    pub credentials_file: Option<String>,
+    /// Whether `file://` and local path repositories may be checked out. Off in
+    /// workers, as the cache directory holds the mirrors of every tenant.
+    pub allow_local_repositories: bool,
+}
+
+impl Default for CheckoutConfig {
+    fn default() -> Self {
+        Self {
+            cache_dir: "/cache/git".to_string(),
+            credentials_file: None,
+            allow_local_repositories: false,
+        }
+    }
+}
+
+/// TLS configuration for the connection to the orchestrator.
+#[derive(Debug, Clone, Default)]
+pub struct TlsConfig {
+    /// PEM file with an additional CA certificate to trust for the orchestrator.
+    pub ca_file: Option<String>,
+    /// PEM files with the client certificate and its PKCS#8 private key, presented
+    /// to an orchestrator that requires client certificates.
+    pub identity: Option<(String, String)>,
+}
+
+impl TlsConfig {
+    fn from_env() -> Result<Self> {
+        let identity = match (env::var("TLS_CERT_FILE").ok(), env::var("TLS_KEY_FILE").ok()) {
+            (Some(cert_file), Some(key_file)) => Some((cert_file, key_file)),
+            (None, None) => None,
+            _ => {
+                return Err(WorkerError::InvalidConfig(
+                    "TLS_CERT_FILE and TLS_KEY_FILE must be set together".to_string(),
+                ));
+            }
+        };
+
+        Ok(Self {
+            ca_file: env::var("TLS_CA_FILE").ok(),
+            identity,
+        })
+    }
+}
+
 /// Worker configuration loaded from environment variables.
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -38,18 +125,37 @@ pub struct Config {
     pub orchestrator_url: String,
     /// Tenant ID this worker belongs to.
     pub tenant_id: Uuid,
+    /// Token the worker registers with, issued for its tenant.
+    pub registration_token: String,
     /// Machine group for this worker (optional).
     pub worker_group: Option<String>,
+    /// Name of this worker's instance, e.g. its pod (optional).
+    pub worker_name: Option<String>,
+    /// Maximum number of fragments executed at once.
+    pub concurrency: usize,
+    /// Capability labels advertised to the orchestrator (detected plus configured).
+    pub labels: Vec<String>,
     /// Heartbeat interval.
     pub heartbeat_interval: Duration,
-    /// Work polling interval.
-    pub poll_interval: Duration,
+    /// How long a work request waits for work to become ready before returning empty.
+    pub work_wait: Duration,
     /// HTTP request timeout.
     pub request_timeout: Duration,
     /// Script execution timeout.
     pub script_timeout: Duration,
+    /// How long a draining worker waits for in-flight fragments before cancelling them.
+    pub drain_timeout: Duration,
     /// Sandbox configuration.
     pub sandbox: SandboxConfig,
+    /// Workspace checkout configuration.
+    pub checkout: CheckoutConfig,
+    /// TLS configuration.
+    pub tls: TlsConfig,
+    /// Port to serve Prometheus metrics on (optional).
+    pub metrics_port: Option<u16>,
+    /// Fragment the worker was launched to execute (optional). A worker bound to a
+    /// fragment executes only that one and then exits.
+    pub fragment_id: Option<Uuid>,
 }
 
 impl Config {
@@ -67,8 +173,34 @@ impl Config {
         let tenant_id = Uuid::parse_str(&tenant_id_str)
             .map_err(|e| WorkerError::InvalidConfig(format!("Invalid TENANT_ID: {e}")))?;
 
+        let registration_token = env::var("REGISTRATION_TOKEN")
+            .map_err(|_| WorkerError::MissingEnvVar("REGISTRATION_TOKEN".to_string()))?;
+
         let worker_group = env::var("WORKER_GROUP").ok();
 
+        // In Kubernetes, the hostname is the pod name. Elsewhere it is shared by every
+        // worker on the host, and would make them take over each other's registration.
+        let worker_name = env::var("WORKER_NAME").ok().or_else(|| {
+            env::var_os("KUBERNETES_SERVICE_HOST")
+                .and_then(|_| env::var("HOSTNAME").ok())
+        });
+
+        let concurrency = match env::var("WORKER_CONCURRENCY") {
+            Ok(s) => s
+                .parse::<usize>()
+                .ok()
+                .filter(|&n| n > 0 && i32::try_from(n).is_ok())
+                .ok_or_else(|| {
+                    WorkerError::InvalidConfig(format!(
+                        "Invalid WORKER_CONCURRENCY: {s} (must be a positive integer)"
+                    ))
+                })?,
+            Err(_) => 1,
+        };
+
+        let configured_labels = labels::parse(&env::var("WORKER_LABELS").unwrap_or_default())?;
+        let labels = labels::merge(labels::detect(), configured_labels);
+
         let heartbeat_interval = Duration::from_secs(
             env::var("HEARTBEAT_INTERVAL_SECS")
                 .ok()
@@ -76,11 +208,11 @@ impl Config {
                 .unwrap_or(10),
         );
 
-        let poll_interval = Duration::from_secs(
-            env::var("POLL_INTERVAL_SECS")
+        let work_wait = Duration::from_secs(
+            env::var("WORK_WAIT_SECS")
                 .ok()
                 .and_then(|s| s.parse().ok())
-                .unwrap_or(5),
+                .unwrap_or(20),
         );
 
         let request_timeout = Duration::from_secs(
@@ -97,30 +229,52 @@ impl Config {
                 .unwrap_or(300),
         );
 
-        let sandbox = SandboxConfig {
-            enabled: env::var("SANDBOX_ENABLED")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(true),
-            memory_limit: env::var("SANDBOX_MEMORY_LIMIT")
-                .unwrap_or_else(|_| "512M".to_string()),
-            network: env::var("SANDBOX_NETWORK")
+        let drain_timeout = Duration::from_secs(
+            env::var("DRAIN_TIMEOUT_SECS")
                 .ok()
                 .and_then(|s| s.parse().ok())
-                .unwrap_or(false),
-            scratch_dir: env::var("SANDBOX_SCRATCH_DIR")
-                .unwrap_or_else(|_| "/scratch".to_string()),
+                .unwrap_or(300),
+        );
+
+        let sandbox = SandboxConfig::from_env();
+
+        let checkout = CheckoutConfig {
+            cache_dir: env::var("CHECKOUT_CACHE_DIR")
+                .unwrap_or_else(|_| "/cache/git".to_string()),
+            credentials_file: env::var("GIT_CREDENTIALS_FILE").ok(),
+            allow_local_repositories: false,
         };
 
+        let tls = TlsConfig::from_env()?;
+
+        let metrics_port = env::var("METRICS_PORT").ok().and_then(|s| s.parse().ok());
+
+        let fragment_id = env::var("FRAGMENT_ID")
+            .ok()
+            .map(|s| {
+                Uuid::parse_str(&s)
+                    .map_err(|e| WorkerError::InvalidConfig(format!("Invalid FRAGMENT_ID: {e}")))
+            })
+            .transpose()?;
+
         Ok(Self {
             orchestrator_url,
             tenant_id,
+            registration_token,
             worker_group,
+            worker_name,
+            concurrency,
+            labels,
             heartbeat_interval,
-            poll_interval,
+            work_wait,
             request_timeout,
             script_timeout,
+            drain_timeout,
             sandbox,
+            checkout,
+            tls,
+            metrics_port,
+            fragment_id,
         })
     }
 }
@@ -32,6 +32,14 @@ pub enum WorkerError {
     #[error("Script execution timed out after {0} seconds")]
     ScriptTimeout(u64),
 
+    /// Workspace checkout error.
+    #[error("Checkout error: {0}")]
+    Checkout(String),
+
+    /// Scratch directory error.
+    #[error("Scratch directory error: {0}")]
+    Scratch(String),
+
     /// I/O error.
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
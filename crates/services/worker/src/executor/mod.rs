@@ -2,6 +2,7 @@
 
 pub mod output;
 
+use std::path::Path;
 use std::process::Stdio;
 use std::time::Duration;
 
@@ -23,7 +24,7 @@ use crate::error::Result;
 /// - New mount namespace (isolated filesystem view)
 /// - New network namespace (no network if disabled)
 /// - Read-only root filesystem
-/// - Writable scratch directory for execution
+/// - Writable per-execution scratch directory
 #[derive(Debug, Clone)]
 pub struct Executor {
     /// Timeout for script execution.
@@ -41,16 +42,21 @@ impl Executor {
 
     /// Execute a script and return the output.
     ///
-    /// If sandboxing is enabled, the script runs inside bubblewrap.
-    /// Otherwise, it runs directly via `/bin/sh -c`.
-    pub async fn execute(&self, fragment_id: Uuid, script: &str) -> Result<ExecutionOutput> {
+    /// The script runs in `workdir`, which is bind-mounted as `/work` when
+    /// sandboxing is enabled. Otherwise, it runs directly via `/bin/sh -c`.
+    pub async fn execute(
+        &self,
+        fragment_id: Uuid,
+        script: &str,
+        workdir: &Path,
+    ) -> Result<ExecutionOutput> {
         info!(%fragment_id, sandbox_enabled = self.sandbox.enabled, "Executing script");
         debug!(%fragment_id, script = %script, "Script content");
 
         let mut child = if self.sandbox.enabled {
-            self.spawn_sandboxed(script)?
+            self.spawn_sandboxed(script, workdir)?
         } else {
-            self.spawn_direct(script)?
+            self.spawn_direct(script, workdir)?
         };
 
         // Take stdout and stderr handles before waiting
@@ -107,8 +113,14 @@ impl Executor {
     }
 
     /// Spawn script directly without sandboxing.
-    fn spawn_direct(&self, script: &str) -> std::io::Result<tokio::process::Child> {
-        Command::new("/bin/sh")
+    fn spawn_direct(
+        &self,
+        script: &str,
+        workdir: &Path,
+    ) -> std::io::Result<tokio::process::Child> {
+        let mut cmd = Command::new("/bin/sh");
+
+        cmd.current_dir(workdir)
             .arg("-c")
             .arg(script)
             .stdout(Stdio::piped())
@@ -127,11 +139,15 @@ impl Executor {
     /// - `--die-with-parent`: Kill sandbox if parent dies
     /// - `--new-session`: New session to prevent terminal access
     /// - `--ro-bind`: Read-only filesystem binds
-    /// - `--bind`: Writable bind for scratch directory
+    /// - `--bind`: Writable bind for the execution's scratch directory
     /// - `--dev /dev`: Minimal /dev
     /// - `--proc /proc`: Process filesystem
     /// - `--tmpfs /tmp`: Temporary filesystem
-    fn spawn_sandboxed(&self, script: &str) -> std::io::Result<tokio::process::Child> {
+    fn spawn_sandboxed(
+        &self,
+        script: &str,
+        workdir: &Path,
+    ) -> std::io::Result<tokio::process::Child> {
         let mut cmd = Command::new("bwrap");
 
         // Namespace isolation
@@ -170,9 +186,9 @@ impl Executor {
             .arg("--tmpfs").arg("/run");
 
         // Writable scratch directory
-        // The scratch dir on host is bind-mounted as /work inside sandbox
+        // The execution's own scratch dir on host is bind-mounted as /work inside sandbox
         cmd.arg("--bind")
-            .arg(&self.sandbox.scratch_dir)
+            .arg(workdir)
             .arg("/work");
 
         // Set working directory to scratch
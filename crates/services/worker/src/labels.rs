@@ -0,0 +1,93 @@
+//! Worker capability labels.
+//!
+//! Labels describe what a worker can run and are matched against the `runs-on`
+//! requirements of fragments. A label is either a bare tag (`docker`) or a
+//! `key=value` pair (`arch=x86_64`). Some labels are detected from the host; the
+//! rest come from the `WORKER_LABELS` setting.
+
+use crate::error::{Result, WorkerError};
+
+/// Detect labels describing the host: `os`, `arch`, `cpus` and `memory-gib`.
+#[must_use]
+pub fn detect() -> Vec<String> {
+    let mut labels = vec![
+        format!("os={}", std::env::consts::OS),
+        format!("arch={}", std::env::consts::ARCH),
+    ];
+
+    if let Ok(cpus) = std::thread::available_parallelism() {
+        labels.push(format!("cpus={cpus}"));
+    }
+
+    if let Some(memory_gib) = total_memory_gib() {
+        labels.push(format!("memory-gib={memory_gib}"));
+    }
+
+    labels
+}
+
+/// Parse a comma-separated list of labels, e.g. `gpu=none,docker`.
+///
+/// Surrounding whitespace and empty entries are ignored.
+///
+/// # Errors
+///
+/// Returns an error if a label is malformed.
+pub fn parse(spec: &str) -> Result<Vec<String>> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|label| !label.is_empty())
+        .map(|label| {
+            if label.starts_with('=') || label.chars().any(char::is_whitespace) {
+                Err(WorkerError::InvalidConfig(format!(
+                    "Invalid label in WORKER_LABELS: {label:?}"
+                )))
+            } else {
+                Ok(label.to_string())
+            }
+        })
+        .collect()
+}
+
+/// Combine detected and configured labels.
+///
+/// A configured `key=value` label replaces any detected label with the same key,
+/// so detection can be overridden (e.g. `cpus=8` on a shared host). The result is
+/// sorted and free of duplicates.
+#[must_use]
+pub fn merge(detected: Vec<String>, configured: Vec<String>) -> Vec<String> {
+    let overridden: Vec<String> = configured
+        .iter()
+        .filter_map(|label| label_key(label))
+        .map(str::to_string)
+        .collect();
+
+    let mut labels: Vec<String> = detected
+        .into_iter()
+        .filter(|label| label_key(label).is_none_or(|key| !overridden.iter().any(|k| k == key)))
+        .chain(configured)
+        .collect();
+
+    labels.sort();
+    labels.dedup();
+    labels
+}
+
+/// The key of a `key=value` label, or `None` for a bare tag.
+fn label_key(label: &str) -> Option<&str> {
+    label.split_once('=').map(|(key, _)| key)
+}
+
+/// Total memory of the host in whole GiB, if it can be determined.
+fn total_memory_gib() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let kib: u64 = meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kib / (1024 * 1024))
+}
@@ -3,8 +3,12 @@
 //! This crate provides the worker service that connects to the orchestrator,
 //! requests work, executes scripts, and reports results.
 
+pub mod checkout;
 pub mod client;
 pub mod config;
 pub mod error;
 pub mod executor;
+pub mod labels;
+pub mod metrics;
+pub mod scratch;
 pub mod worker;
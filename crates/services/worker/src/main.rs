@@ -3,8 +3,8 @@
 //! Executes individual chain fragments and reports results.
 
 use tokio::signal;
+use tokio::signal::unix::{signal as unix_signal, SignalKind};
 use tracing::{error, info};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use vulcan_worker::config::Config;
 use vulcan_worker::worker::Worker;
@@ -15,13 +15,13 @@ async fn main() {
     let _ = dotenvy::dotenv();
 
     // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "vulcan_worker=info".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    let _telemetry = match vulcan_telemetry::init("vulcan-worker", "vulcan_worker=info") {
+        Ok(telemetry) => telemetry,
+        Err(e) => {
+            eprintln!("Failed to set up trace export: {e}");
+            std::process::exit(1);
+        }
+    };
 
     info!("Starting Vulcan Worker");
 
@@ -38,12 +38,22 @@ async fn main() {
         orchestrator_url = %config.orchestrator_url,
         tenant_id = %config.tenant_id,
         worker_group = ?config.worker_group,
+        concurrency = config.concurrency,
+        labels = ?config.labels,
         heartbeat_interval_secs = config.heartbeat_interval.as_secs(),
-        poll_interval_secs = config.poll_interval.as_secs(),
+        work_wait_secs = config.work_wait.as_secs(),
         script_timeout_secs = config.script_timeout.as_secs(),
         "Configuration loaded"
     );
 
+    if let Some(port) = config.metrics_port {
+        if let Err(e) = vulcan_worker::metrics::install(port) {
+            error!(error = %e, "Failed to serve metrics");
+            std::process::exit(1);
+        }
+        info!(port, "Serving metrics");
+    }
+
     // Create worker
     let mut worker = match Worker::new(config) {
         Ok(w) => w,
@@ -53,17 +63,17 @@ async fn main() {
         }
     };
 
-    // Get shutdown handle for Ctrl+C
-    let shutdown = worker.shutdown_handle();
-
-    // Spawn Ctrl+C handler
+    // Drain on SIGTERM (e.g. from Kubernetes) or Ctrl+C
+    let drain = worker.drain_handle();
     tokio::spawn(async move {
-        if let Err(e) = signal::ctrl_c().await {
-            error!(error = %e, "Failed to listen for Ctrl+C");
-            return;
+        match shutdown_signal().await {
+            Ok(signal) => info!(signal, "Received shutdown signal, draining"),
+            Err(e) => {
+                error!(error = %e, "Failed to listen for shutdown signals");
+                return;
+            }
         }
-        info!("Received Ctrl+C, initiating graceful shutdown");
-        shutdown.notify_waiters();
+        drain.drain();
     });
 
     // Run worker
@@ -74,3 +84,12 @@ async fn main() {
 
     info!("Vulcan Worker shutdown complete");
 }
+
+/// Wait for SIGTERM or Ctrl+C, returning the name of the signal received.
+async fn shutdown_signal() -> std::io::Result<&'static str> {
+    let mut terminate = unix_signal(SignalKind::terminate())?;
+    tokio::select! {
+        result = signal::ctrl_c() => result.map(|()| "SIGINT"),
+        _ = terminate.recv() => Ok("SIGTERM"),
+    }
+}
@@ -0,0 +1,55 @@
+//! Prometheus metrics of the worker, served on `METRICS_PORT` when set.
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use metrics::{counter, describe_counter, describe_histogram, histogram};
+use metrics_exporter_prometheus::{BuildError, Matcher, PrometheusBuilder};
+
+/// Time to check out and run fragments, by outcome.
+pub const EXECUTION_DURATION: &str = "vulcan_worker_execution_duration_seconds";
+/// Fragments whose script ran into the execution timeout.
+pub const EXECUTION_TIMEOUTS: &str = "vulcan_worker_execution_timeouts_total";
+/// Heartbeats that failed to reach the orchestrator.
+pub const HEARTBEAT_FAILURES: &str = "vulcan_worker_heartbeat_failures_total";
+
+const EXECUTION_BUCKETS: &[f64] = &[
+    1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0,
+];
+
+/// Install the Prometheus recorder and serve it on `port` on all interfaces.
+///
+/// Must be called from within a Tokio runtime.
+///
+/// # Errors
+/// Returns an error if a recorder is already installed or the listener cannot be set up.
+pub fn install(port: u16) -> Result<(), BuildError> {
+    PrometheusBuilder::new()
+        .with_http_listener(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)))
+        .set_buckets_for_metric(
+            Matcher::Full(EXECUTION_DURATION.to_string()),
+            EXECUTION_BUCKETS,
+        )?
+        .install()?;
+
+    describe_histogram!(EXECUTION_DURATION, "Time to check out and run fragments");
+    describe_counter!(
+        EXECUTION_TIMEOUTS,
+        "Fragments that ran into the execution timeout"
+    );
+    describe_counter!(HEARTBEAT_FAILURES, "Heartbeats that failed");
+    Ok(())
+}
+
+/// Record an execution, with `outcome` one of `success`, `failure` or `timeout`.
+pub fn record_execution(outcome: &'static str, duration: Duration) {
+    histogram!(EXECUTION_DURATION, "outcome" => outcome).record(duration.as_secs_f64());
+    if outcome == "timeout" {
+        counter!(EXECUTION_TIMEOUTS).increment(1);
+    }
+}
+
+/// Record a failed heartbeat.
+pub fn record_heartbeat_failure() {
+    counter!(HEARTBEAT_FAILURES).increment(1);
+}
@@ -0,0 +1,262 @@
+//! Per-execution scratch directories.
+//!
+//! Every fragment attempt gets its own freshly created directory under the
+//! configured scratch root, so nothing written by one execution is visible to the
+//! next. Directory names encode their state:
+//!
+//! - `run-<fragment>-<attempt>-<nonce>`: in use by an execution
+//! - `failed-<expires>-<fragment>-<attempt>-<nonce>`: preserved after a failure
+//!   for debugging, until the unix timestamp `<expires>`
+//!
+//! `run-` directories found at startup belong to a previous (crashed) worker
+//! process and are always removed.
+
+use std::fs;
+use std::io;
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::config::SandboxConfig;
+use crate::error::{Result, WorkerError};
+
+/// Name prefix of directories in use by an execution.
+const RUN_PREFIX: &str = "run-";
+
+/// Name prefix of directories preserved after a failed execution.
+const FAILED_PREFIX: &str = "failed-";
+
+/// Outcome of an execution, deciding what happens to its scratch directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScratchOutcome {
+    /// The execution succeeded; the directory is removed.
+    Succeeded,
+    /// The execution failed; the directory may be preserved for debugging.
+    Failed,
+    /// The execution timed out; the directory is always removed.
+    TimedOut,
+}
+
+/// A scratch directory owned by a single execution.
+#[derive(Debug)]
+pub struct ScratchDir {
+    /// Absolute path of the directory.
+    path: PathBuf,
+    /// Directory name, without the state prefix.
+    name: String,
+}
+
+impl ScratchDir {
+    /// Path of the directory.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Creates, releases and garbage-collects scratch directories.
+#[derive(Debug, Clone)]
+pub struct ScratchManager {
+    /// Root directory holding all scratch directories.
+    root: PathBuf,
+    /// Whether to keep the directory of a failed execution.
+    preserve_on_failure: bool,
+    /// How long a preserved directory is kept.
+    preserve_ttl: Duration,
+}
+
+impl ScratchManager {
+    /// Create a new scratch manager from the sandbox configuration.
+    #[must_use]
+    pub fn new(config: &SandboxConfig) -> Self {
+        Self {
+            root: PathBuf::from(&config.scratch_dir),
+            preserve_on_failure: config.preserve_on_failure,
+            preserve_ttl: config.preserve_ttl,
+        }
+    }
+
+    /// Create a fresh, private directory for one fragment attempt.
+    ///
+    /// The directory is created with mode `0700` and never reuses an existing path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be created.
+    pub async fn create(&self, fragment_id: Uuid, attempt: i32) -> Result<ScratchDir> {
+        let root = self.root.clone();
+        let name = format!("{fragment_id}-{attempt}-{}", Uuid::new_v4().simple());
+
+        let dir = tokio::task::spawn_blocking(move || -> Result<ScratchDir> {
+            fs::create_dir_all(&root)?;
+
+            let path = root.join(format!("{RUN_PREFIX}{name}"));
+            // Non-recursive create fails if the path exists, so a pre-planted
+            // directory or symlink is never adopted
+            fs::DirBuilder::new().mode(0o700).create(&path)?;
+
+            // The mode passed to mkdir is subject to the umask, so set it explicitly
+            let metadata = fs::symlink_metadata(&path)?;
+            if !metadata.is_dir() {
+                return Err(WorkerError::Scratch(format!(
+                    "{} is not a directory",
+                    path.display()
+                )));
+            }
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o700))?;
+
+            Ok(ScratchDir { path, name })
+        })
+        .await
+        .map_err(|e| WorkerError::Scratch(e.to_string()))??;
+
+        debug!(path = %dir.path.display(), "Created scratch directory");
+        Ok(dir)
+    }
+
+    /// Release a directory once its execution has finished.
+    ///
+    /// The directory is removed, unless the execution failed and preservation is
+    /// enabled, in which case it is kept until the preservation TTL expires.
+    /// Errors are logged rather than returned, so cleanup never fails an execution.
+    pub async fn release(&self, dir: ScratchDir, outcome: ScratchOutcome) {
+        if outcome == ScratchOutcome::Failed && self.preserve_on_failure {
+            let expires = unix_now() + self.preserve_ttl.as_secs();
+            let preserved = self
+                .root
+                .join(format!("{FAILED_PREFIX}{expires}-{}", dir.name));
+
+            match tokio::fs::rename(&dir.path, &preserved).await {
+                Ok(()) => {
+                    info!(
+                        path = %preserved.display(),
+                        ttl_secs = self.preserve_ttl.as_secs(),
+                        "Preserved scratch directory of failed execution"
+                    );
+                    return;
+                },
+                Err(e) => {
+                    warn!(
+                        path = %dir.path.display(),
+                        error = %e,
+                        "Failed to preserve scratch directory, removing it"
+                    );
+                },
+            }
+        }
+
+        let path = dir.path;
+        if let Err(e) = remove(path.clone()).await {
+            warn!(path = %path.display(), error = %e, "Failed to remove scratch directory");
+        } else {
+            debug!(path = %path.display(), "Removed scratch directory");
+        }
+    }
+
+    /// Remove leftovers of a previous worker process and expired preserved directories.
+    ///
+    /// Must only be called while no execution is running, since every in-use
+    /// directory is treated as abandoned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the scratch root cannot be read.
+    pub async fn recover(&self) -> Result<usize> {
+        self.sweep(true).await
+    }
+
+    /// Remove preserved directories whose TTL has expired.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the scratch root cannot be read.
+    pub async fn sweep_expired(&self) -> Result<usize> {
+        self.sweep(false).await
+    }
+
+    /// Remove expired preserved directories, and in-use ones if `abandoned` is set.
+    async fn sweep(&self, abandoned: bool) -> Result<usize> {
+        let mut entries = match tokio::fs::read_dir(&self.root).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let now = unix_now();
+        let mut removed = 0;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+
+            let stale = name.strip_prefix(FAILED_PREFIX).map_or_else(
+                || abandoned && name.starts_with(RUN_PREFIX),
+                |rest| is_expired(rest, now),
+            );
+
+            if !stale {
+                continue;
+            }
+
+            let path = entry.path();
+            match remove(path.clone()).await {
+                Ok(()) => {
+                    debug!(path = %path.display(), "Removed stale scratch directory");
+                    removed += 1;
+                },
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "Failed to remove stale scratch directory");
+                },
+            }
+        }
+
+        Ok(removed)
+    }
+}
+
+/// Check whether a preserved directory name (without prefix) is past its expiry.
+///
+/// Names without a parseable expiry are treated as expired.
+fn is_expired(name: &str, now: u64) -> bool {
+    name.split('-')
+        .next()
+        .and_then(|expires| expires.parse::<u64>().ok())
+        .is_none_or(|expires| expires <= now)
+}
+
+/// Current unix time in seconds.
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Remove a directory tree, including parts a script made read-only.
+async fn remove(path: PathBuf) -> io::Result<()> {
+    tokio::task::spawn_blocking(move || match fs::remove_dir_all(&path) {
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            make_writable(&path)?;
+            fs::remove_dir_all(&path)
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    })
+    .await?
+}
+
+/// Recursively give the owner full access to every directory under `path`.
+fn make_writable(path: &Path) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(());
+    }
+
+    fs::set_permissions(path, fs::Permissions::from_mode(0o700))?;
+    for entry in fs::read_dir(path)? {
+        make_writable(&entry?.path())?;
+    }
+    Ok(())
+}
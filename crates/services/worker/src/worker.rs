@@ -1,17 +1,23 @@
 //! Worker state machine and main loop.
 
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use tokio::sync::Notify;
-use tokio::time::sleep;
-use tracing::{debug, error, info, warn};
+use tokio::sync::{watch, Semaphore};
+use tokio::task::JoinSet;
+use tokio::time::{sleep, timeout, Instant};
+use tracing::{debug, error, info, info_span, warn, Instrument, Span};
 use uuid::Uuid;
 
-use crate::client::OrchestratorClient;
+use crate::checkout::{Checkout, CheckoutRequest};
+use crate::client::{OrchestratorClient, WorkResponse};
 use crate::config::Config;
 use crate::error::{Result, WorkerError};
-use crate::executor::Executor;
+use crate::executor::{ExecutionOutput, Executor};
+use crate::metrics;
+use crate::scratch::{ScratchManager, ScratchOutcome};
 
 /// Maximum backoff duration for retries.
 const MAX_BACKOFF_SECS: u64 = 60;
@@ -19,13 +25,52 @@ const MAX_BACKOFF_SECS: u64 = 60;
 /// Initial backoff duration for retries.
 const INITIAL_BACKOFF_SECS: u64 = 1;
 
+/// Delay after a work request returned empty before its wait elapsed, so an
+/// orchestrator that does not hold requests open is not polled in a tight loop.
+const EARLY_EMPTY_DELAY: Duration = Duration::from_secs(1);
+
 /// Worker that connects to the orchestrator and executes work.
+///
+/// Up to `WORKER_CONCURRENCY` fragments run at once, each in its own task.
 pub struct Worker {
     config: Config,
     client: OrchestratorClient,
-    executor: Executor,
+    runner: FragmentRunner,
+    scratch: ScratchManager,
+    in_flight: InFlight,
     worker_id: Option<Uuid>,
-    shutdown: Arc<Notify>,
+    drain: DrainHandle,
+    /// Set once the drain deadline passes, cancelling in-flight executions.
+    cancel: watch::Sender<bool>,
+}
+
+/// Handle to drain a worker.
+///
+/// A draining worker requests no new work and tells the orchestrator so in its
+/// heartbeats. It waits up to `DRAIN_TIMEOUT_SECS` for its in-flight fragments,
+/// cancels the ones still running after that, deregisters and exits. Draining is
+/// started by a signal or by the orchestrator, and cannot be undone.
+#[derive(Clone)]
+pub struct DrainHandle(Arc<watch::Sender<bool>>);
+
+/// Everything needed to execute a single fragment, cloned into each execution task.
+#[derive(Clone)]
+struct FragmentRunner {
+    client: OrchestratorClient,
+    executor: Executor,
+    checkout: Checkout,
+    scratch: ScratchManager,
+    cancel: watch::Receiver<bool>,
+}
+
+/// Set of fragments currently executing, shared with the heartbeat task.
+#[derive(Clone, Default)]
+struct InFlight(Arc<Mutex<HashSet<Uuid>>>);
+
+/// Removes a fragment from the in-flight set when its execution task ends.
+struct InFlightGuard {
+    in_flight: InFlight,
+    fragment_id: Uuid,
 }
 
 impl Worker {
@@ -37,33 +82,54 @@ impl Worker {
     pub fn new(config: Config) -> Result<Self> {
         let client = OrchestratorClient::new(&config)?;
         let executor = Executor::new(config.script_timeout, config.sandbox.clone());
+        let checkout = Checkout::new(config.checkout.clone());
+        let scratch = ScratchManager::new(&config.sandbox);
+        let (cancel, cancelled) = watch::channel(false);
+        let runner = FragmentRunner {
+            client: client.clone(),
+            executor,
+            checkout,
+            scratch: scratch.clone(),
+            cancel: cancelled,
+        };
 
         Ok(Self {
             config,
             client,
-            executor,
+            runner,
+            scratch,
+            in_flight: InFlight::default(),
             worker_id: None,
-            shutdown: Arc::new(Notify::new()),
+            drain: DrainHandle::default(),
+            cancel,
         })
     }
 
-    /// Get the shutdown notifier for graceful shutdown.
+    /// Get the handle to drain the worker, e.g. on SIGTERM.
     #[must_use]
-    pub fn shutdown_handle(&self) -> Arc<Notify> {
-        Arc::clone(&self.shutdown)
+    pub fn drain_handle(&self) -> DrainHandle {
+        self.drain.clone()
     }
 
     /// Run the worker main loop.
     ///
     /// This will:
-    /// 1. Register with the orchestrator (with retry)
-    /// 2. Start the heartbeat task
-    /// 3. Start the work loop
+    /// 1. Remove scratch directories left behind by a previous worker process
+    /// 2. Register with the orchestrator (with retry)
+    /// 3. Start the heartbeat task
+    /// 4. Start the work loop, until the worker is drained
+    /// 5. Deregister from the orchestrator
     ///
     /// # Errors
     ///
     /// Returns an error if a fatal error occurs.
     pub async fn run(&mut self) -> Result<()> {
+        // Nothing is executing yet, so any scratch directory still in use is abandoned
+        let removed = self.scratch.recover().await?;
+        if removed > 0 {
+            info!(removed, "Removed stale scratch directories");
+        }
+
         // Register with retry
         self.register_with_retry().await?;
 
@@ -73,11 +139,17 @@ impl Worker {
         // Spawn heartbeat task
         let heartbeat_handle = self.spawn_heartbeat_task(worker_id);
 
-        // Run work loop
+        // Run work loop (returns once drained and in-flight fragments have finished)
         let work_result = self.work_loop(worker_id).await;
 
         // Cancel heartbeat task
         heartbeat_handle.abort();
+        info!(%worker_id, "Heartbeat task stopped");
+
+        match self.client.deregister(worker_id).await {
+            Ok(()) => info!(%worker_id, "Worker deregistered"),
+            Err(e) => warn!(%worker_id, error = %e, "Failed to deregister"),
+        }
 
         work_result
     }
@@ -89,7 +161,13 @@ impl Worker {
         loop {
             match self
                 .client
-                .register(self.config.tenant_id, self.config.worker_group.clone())
+                .register(
+                    self.config.tenant_id,
+                    self.config.worker_group.clone(),
+                    self.config.concurrency,
+                    self.config.labels.clone(),
+                    self.config.worker_name.clone(),
+                )
                 .await
             {
                 Ok(response) => {
@@ -111,7 +189,7 @@ impl Worker {
                     // Check for shutdown
                     tokio::select! {
                         () = sleep(backoff) => {}
-                        () = self.shutdown.notified() => {
+                        () = self.drain.drained() => {
                             info!("Shutdown requested during registration");
                             return Err(WorkerError::Orchestrator("Shutdown requested".to_string()));
                         }
@@ -124,118 +202,254 @@ impl Worker {
     }
 
     /// Spawn the heartbeat background task.
+    ///
+    /// Each heartbeat reports the fragments currently in flight and whether the
+    /// worker is draining; the first one after draining started is sent right away.
+    /// A heartbeat response asking the worker to drain starts draining. The task
+    /// keeps running until aborted, so heartbeats continue while in-flight work
+    /// finishes during a drain.
     fn spawn_heartbeat_task(&self, worker_id: Uuid) -> tokio::task::JoinHandle<()> {
         let client = self.client.clone();
         let interval = self.config.heartbeat_interval;
-        let shutdown = Arc::clone(&self.shutdown);
+        let in_flight = self.in_flight.clone();
+        let drain = self.drain.clone();
 
         tokio::spawn(async move {
             let mut backoff = Duration::from_secs(INITIAL_BACKOFF_SECS);
 
             loop {
+                let draining = drain.is_draining();
                 tokio::select! {
-                    () = sleep(interval) => {
-                        match client.heartbeat(worker_id).await {
-                            Ok(_) => {
-                                debug!(%worker_id, "Heartbeat sent");
-                                backoff = Duration::from_secs(INITIAL_BACKOFF_SECS);
-                            }
-                            Err(e) => {
-                                warn!(
-                                    %worker_id,
-                                    error = %e,
-                                    "Heartbeat failed"
-                                );
-                                // Use exponential backoff for failed heartbeats
-                                sleep(backoff).await;
-                                backoff = std::cmp::min(backoff * 2, Duration::from_secs(MAX_BACKOFF_SECS));
-                            }
+                    () = sleep(interval) => {}
+                    () = drain.drained(), if !draining => {}
+                }
+
+                let fragment_ids = in_flight.snapshot();
+                match client
+                    .heartbeat(worker_id, fragment_ids, drain.is_draining())
+                    .await
+                {
+                    Ok(response) => {
+                        debug!(%worker_id, "Heartbeat sent");
+                        if response.draining && !drain.is_draining() {
+                            info!(%worker_id, "Orchestrator asked worker to drain");
+                            drain.drain();
                         }
+                        backoff = Duration::from_secs(INITIAL_BACKOFF_SECS);
                     }
-                    () = shutdown.notified() => {
-                        info!(%worker_id, "Heartbeat task shutting down");
-                        return;
+                    Err(e) => {
+                        warn!(
+                            %worker_id,
+                            error = %e,
+                            "Heartbeat failed"
+                        );
+                        metrics::record_heartbeat_failure();
+                        // Use exponential backoff for failed heartbeats
+                        sleep(backoff).await;
+                        backoff = std::cmp::min(backoff * 2, Duration::from_secs(MAX_BACKOFF_SECS));
                     }
                 }
             }
         })
     }
 
-    /// Main work loop: request work, execute, report results.
+    /// Main work loop: request work whenever a slot is free and execute it in the background.
+    ///
+    /// A worker bound to a fragment requests only that fragment, once, and stops
+    /// requesting work as if draining whether or not it got it.
+    ///
+    /// Once draining, stops requesting work and waits for in-flight fragments to
+    /// finish, cancelling those still running after the drain timeout.
     async fn work_loop(&self, worker_id: Uuid) -> Result<()> {
+        let slots = Arc::new(Semaphore::new(self.config.concurrency));
+        let mut tasks = JoinSet::new();
         let mut backoff = Duration::from_secs(INITIAL_BACKOFF_SECS);
 
+        let shutdown = self.drain.drained();
+        tokio::pin!(shutdown);
+
         loop {
-            tokio::select! {
-                () = self.shutdown.notified() => {
-                    info!(%worker_id, "Work loop shutting down");
-                    return Ok(());
+            // Reap finished executions
+            while let Some(result) = tasks.try_join_next() {
+                log_task_result(worker_id, result);
+            }
+
+            // Wait for a free slot
+            let permit = tokio::select! {
+                () = &mut shutdown => break,
+                permit = Arc::clone(&slots).acquire_owned() => {
+                    permit.expect("work slots semaphore is never closed")
                 }
-                result = self.work_cycle(worker_id) => {
-                    match result {
-                        Ok(had_work) => {
-                            backoff = Duration::from_secs(INITIAL_BACKOFF_SECS);
-                            if !had_work {
-                                // No work available, wait before polling again
-                                sleep(self.config.poll_interval).await;
-                            }
-                        }
-                        Err(e) => {
-                            error!(%worker_id, error = %e, "Work cycle error");
-                            sleep(backoff).await;
-                            backoff = std::cmp::min(backoff * 2, Duration::from_secs(MAX_BACKOFF_SECS));
-                        }
+            };
+
+            let requested_at = Instant::now();
+            let result = tokio::select! {
+                () = &mut shutdown => break,
+                result = self.client.request_work(
+                    worker_id,
+                    self.config.work_wait,
+                    self.config.fragment_id,
+                ) => result,
+            };
+
+            let delay = match result {
+                Ok(Some(work)) => {
+                    backoff = Duration::from_secs(INITIAL_BACKOFF_SECS);
+
+                    info!(
+                        %worker_id,
+                        fragment_id = %work.fragment_id,
+                        chain_id = %work.chain_id,
+                        attempt = work.attempt,
+                        "Received work"
+                    );
+
+                    let guard = self.in_flight.insert(work.fragment_id);
+                    let runner = self.runner.clone();
+                    tasks.spawn(async move {
+                        runner.run(worker_id, work).await;
+                        drop(guard);
+                        drop(permit);
+                    });
+                    if self.config.fragment_id.is_some() {
+                        break;
                     }
+                    continue;
+                }
+                Ok(None) if self.config.fragment_id.is_some() => {
+                    warn!(%worker_id, "Bound fragment is not available");
+                    break;
                 }
+                Ok(None) => {
+                    debug!(%worker_id, "No work available");
+                    backoff = Duration::from_secs(INITIAL_BACKOFF_SECS);
+                    if requested_at.elapsed() < self.config.work_wait {
+                        EARLY_EMPTY_DELAY
+                    } else {
+                        Duration::ZERO
+                    }
+                }
+                Err(e) => {
+                    error!(%worker_id, error = %e, "Work request error");
+                    let delay = backoff;
+                    backoff = std::cmp::min(backoff * 2, Duration::from_secs(MAX_BACKOFF_SECS));
+                    delay
+                }
+            };
+
+            // Free the slot while waiting before requesting again
+            drop(permit);
+            tokio::select! {
+                () = &mut shutdown => break,
+                () = sleep(delay) => {}
             }
         }
-    }
-
-    /// Single work cycle: request work, execute if available, report result.
-    ///
-    /// Returns `true` if work was executed, `false` if no work was available.
-    async fn work_cycle(&self, worker_id: Uuid) -> Result<bool> {
-        // Request work
-        let work = self.client.request_work(worker_id).await?;
-
-        let Some(work) = work else {
-            debug!(%worker_id, "No work available");
-            return Ok(false);
-        };
 
         info!(
             %worker_id,
-            fragment_id = %work.fragment_id,
-            chain_id = %work.chain_id,
-            attempt = work.attempt,
-            "Received work"
+            in_flight = tasks.len(),
+            drain_timeout_secs = self.config.drain_timeout.as_secs(),
+            "Draining, waiting for in-flight fragments"
         );
+        let finished = timeout(self.config.drain_timeout, async {
+            while let Some(result) = tasks.join_next().await {
+                log_task_result(worker_id, result);
+            }
+        })
+        .await;
 
-        // Execute the script
-        let output = if let Some(script) = &work.run_script {
-            self.executor.execute(work.fragment_id, script).await?
-        } else {
+        if finished.is_err() {
             warn!(
                 %worker_id,
-                fragment_id = %work.fragment_id,
-                "Fragment has no run_script"
+                in_flight = tasks.len(),
+                "Drain timeout passed, cancelling in-flight fragments"
             );
-            crate::executor::ExecutionOutput::new(
-                String::new(),
-                "No script to execute".to_string(),
-                1,
-            )
+            self.cancel.send_replace(true);
+            while let Some(result) = tasks.join_next().await {
+                log_task_result(worker_id, result);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FragmentRunner {
+    /// Execute a fragment in a fresh scratch directory and report the result.
+    ///
+    /// Errors are logged rather than returned. A result that could not be reported
+    /// is recovered by the orchestrator once the fragment drops out of heartbeats.
+    ///
+    /// If the execution is cancelled, its script is killed and the cancellation is
+    /// reported instead, so the orchestrator retries the fragment elsewhere. Its
+    /// scratch directory is removed on the next start.
+    async fn run(self, worker_id: Uuid, work: WorkResponse) {
+        let fragment_id = work.fragment_id;
+        let mut cancel = self.cancel.clone();
+
+        tokio::select! {
+            result = self.execute_and_report(worker_id, &work) => {
+                if let Err(e) = result {
+                    error!(%worker_id, %fragment_id, error = %e, "Fragment execution error");
+                }
+            }
+            () = cancelled(&mut cancel) => {
+                warn!(%worker_id, %fragment_id, "Fragment execution cancelled");
+                let span = work_span(
+                    &work,
+                    info_span!("report_result", %fragment_id, cancelled = true),
+                );
+                if let Err(e) = self
+                    .client
+                    .report_cancelled(worker_id, fragment_id, work.attempt)
+                    .instrument(span)
+                    .await
+                {
+                    error!(%worker_id, %fragment_id, error = %e, "Failed to report cancellation");
+                }
+            }
+        }
+    }
+
+    /// Execute a fragment and report its result to the orchestrator.
+    async fn execute_and_report(&self, worker_id: Uuid, work: &WorkResponse) -> Result<()> {
+        // Run in a fresh scratch directory owned by this attempt only
+        let scratch_dir = self.scratch.create(work.fragment_id, work.attempt).await?;
+        let started = Instant::now();
+        let span = work_span(
+            work,
+            info_span!("execute", fragment_id = %work.fragment_id, attempt = work.attempt),
+        );
+        let output = self.execute_in(work, scratch_dir.path()).instrument(span).await;
+
+        let (outcome, metric_outcome) = match &output {
+            Ok(output) if output.success => (ScratchOutcome::Succeeded, "success"),
+            Ok(output) if output.timed_out => (ScratchOutcome::TimedOut, "timeout"),
+            _ => (ScratchOutcome::Failed, "failure"),
         };
+        metrics::record_execution(metric_outcome, started.elapsed());
+        self.scratch.release(scratch_dir, outcome).await;
+        if let Err(e) = self.scratch.sweep_expired().await {
+            warn!(error = %e, "Failed to sweep preserved scratch directories");
+        }
+
+        let output = output?;
 
         // Report result
+        let span = work_span(
+            work,
+            info_span!("report_result", fragment_id = %work.fragment_id, success = output.success),
+        );
         self.client
             .report_result(
                 worker_id,
                 work.fragment_id,
+                work.attempt,
                 output.success,
                 Some(output.exit_code),
                 output.error_message(),
             )
+            .instrument(span)
             .await?;
 
         info!(
@@ -246,6 +460,134 @@ impl Worker {
             "Work completed and reported"
         );
 
-        Ok(true)
+        Ok(())
+    }
+
+    /// Check out the work's commit into `workspace` and run its script there.
+    ///
+    /// A failed checkout is turned into a failed execution without running the script.
+    async fn execute_in(&self, work: &WorkResponse, workspace: &Path) -> Result<ExecutionOutput> {
+        if let Err(e) = self.prepare_workspace(work, workspace).await {
+            warn!(
+                fragment_id = %work.fragment_id,
+                error = %e,
+                "Workspace checkout failed"
+            );
+            return Ok(ExecutionOutput::new(
+                String::new(),
+                format!("Workspace checkout failed: {e}"),
+                1,
+            ));
+        }
+
+        if let Some(script) = &work.run_script {
+            self.executor
+                .execute(work.fragment_id, script, workspace)
+                .await
+        } else {
+            warn!(
+                fragment_id = %work.fragment_id,
+                "Fragment has no run_script"
+            );
+            Ok(ExecutionOutput::new(
+                String::new(),
+                "No script to execute".to_string(),
+                1,
+            ))
+        }
+    }
+
+    /// Check out the chain's repository into the workspace, if the work has one.
+    async fn prepare_workspace(&self, work: &WorkResponse, workspace: &Path) -> Result<()> {
+        let (Some(repository_url), Some(commit_sha)) = (&work.repository_url, &work.commit_sha)
+        else {
+            return Ok(());
+        };
+
+        let request = CheckoutRequest {
+            repository_url,
+            commit_sha,
+            depth: work.checkout_depth,
+            sparse_paths: &work.checkout_sparse_paths,
+        };
+
+        self.checkout.materialize(&request, workspace).await
+    }
+}
+
+/// Continue the trace of the claim of `work` in `span`, so the execution and its
+/// report join the trace of the chain.
+fn work_span(work: &WorkResponse, span: Span) -> Span {
+    if let Some(traceparent) = &work.traceparent {
+        vulcan_telemetry::set_parent(&span, traceparent);
+    }
+    span
+}
+
+/// Wait until `cancel` is set. Never completes if its sender is gone.
+async fn cancelled(cancel: &mut watch::Receiver<bool>) {
+    if cancel.wait_for(|cancelled| *cancelled).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+impl DrainHandle {
+    /// Start draining the worker.
+    pub fn drain(&self) {
+        self.0.send_replace(true);
+    }
+
+    /// Whether the worker is draining.
+    #[must_use]
+    pub fn is_draining(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Wait until the worker is draining.
+    pub async fn drained(&self) {
+        let mut draining = self.0.subscribe();
+        // The sender lives as long as this handle, so waiting cannot fail
+        let _ = draining.wait_for(|draining| *draining).await;
+    }
+}
+
+impl Default for DrainHandle {
+    fn default() -> Self {
+        Self(Arc::new(watch::channel(false).0))
+    }
+}
+
+impl InFlight {
+    /// Mark a fragment as in flight until the returned guard is dropped.
+    fn insert(&self, fragment_id: Uuid) -> InFlightGuard {
+        self.lock().insert(fragment_id);
+        InFlightGuard {
+            in_flight: self.clone(),
+            fragment_id,
+        }
+    }
+
+    /// Fragments currently in flight.
+    fn snapshot(&self) -> Vec<Uuid> {
+        self.lock().iter().copied().collect()
+    }
+
+    /// Lock the set.
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashSet<Uuid>> {
+        // The set is always left consistent, so a poisoned lock is still usable
+        self.0.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.in_flight.lock().remove(&self.fragment_id);
+    }
+}
+
+/// Log the outcome of a finished execution task.
+fn log_task_result(worker_id: Uuid, result: std::result::Result<(), tokio::task::JoinError>) {
+    if let Err(e) = result {
+        error!(%worker_id, error = %e, "Fragment execution task panicked");
     }
 }
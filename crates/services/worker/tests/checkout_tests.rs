@@ -0,0 +1,292 @@
+//! Integration tests for workspace checkout against local `file://` repositories.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use uuid::Uuid;
+
+use vulcan_worker::checkout::{Checkout, CheckoutRequest};
+use vulcan_worker::config::CheckoutConfig;
+
+/// Scratch area for one test, removed on drop.
+struct TestDir {
+    root: PathBuf,
+}
+
+impl TestDir {
+    fn new() -> Self {
+        let root = std::env::temp_dir().join(format!("vulcan-checkout-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        Self { root }
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+
+    fn checkout(&self) -> Checkout {
+        Checkout::new(CheckoutConfig {
+            cache_dir: self.path("cache").to_string_lossy().to_string(),
+            credentials_file: None,
+            allow_local_repositories: true,
+        })
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Run git in `dir` and return its trimmed stdout.
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args([
+            "-c",
+            "user.name=Vulcan Test",
+            "-c",
+            "user.email=test@vulcan.invalid",
+        ])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Write files into the source repository and commit them, returning the commit SHA.
+fn commit(repo: &Path, files: &[(&str, &str)], message: &str) -> String {
+    for (name, content) in files {
+        let path = repo.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    git(repo, &["add", "--all"]);
+    git(repo, &["commit", "--quiet", "-m", message]);
+    git(repo, &["rev-parse", "HEAD"])
+}
+
+fn init_source(dir: &Path) -> String {
+    std::fs::create_dir_all(dir).unwrap();
+    git(dir, &["init", "--quiet"]);
+    format!("file://{}", dir.display())
+}
+
+#[tokio::test]
+async fn test_materializes_commit_into_workspace() {
+    let test = TestDir::new();
+    let source = test.path("source");
+    let url = init_source(&source);
+    let sha = commit(&source, &[("README.md", "hello\n")], "initial");
+
+    let workspace = test.path("workspace");
+    let request = CheckoutRequest {
+        repository_url: &url,
+        commit_sha: &sha,
+        depth: None,
+        sparse_paths: &[],
+    };
+    test.checkout()
+        .materialize(&request, &workspace)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(workspace.join("README.md")).unwrap(),
+        "hello\n"
+    );
+    assert_eq!(git(&workspace, &["rev-parse", "HEAD"]), sha);
+    assert_eq!(git(&workspace, &["config", "remote.origin.url"]), url);
+}
+
+#[tokio::test]
+async fn test_checks_out_older_commit_with_depth() {
+    let test = TestDir::new();
+    let source = test.path("source");
+    let url = init_source(&source);
+    commit(&source, &[("a.txt", "1\n")], "first");
+    let second = commit(&source, &[("a.txt", "2\n")], "second");
+    commit(&source, &[("a.txt", "3\n")], "third");
+
+    let workspace = test.path("workspace");
+    let request = CheckoutRequest {
+        repository_url: &url,
+        commit_sha: &second,
+        depth: Some(1),
+        sparse_paths: &[],
+    };
+    test.checkout()
+        .materialize(&request, &workspace)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(workspace.join("a.txt")).unwrap(),
+        "2\n"
+    );
+    assert_eq!(git(&workspace, &["rev-list", "--count", "HEAD"]), "1");
+}
+
+#[tokio::test]
+async fn test_sparse_checkout_only_materializes_listed_paths() {
+    let test = TestDir::new();
+    let source = test.path("source");
+    let url = init_source(&source);
+    let sha = commit(
+        &source,
+        &[
+            ("src/main.rs", "fn main() {}\n"),
+            ("docs/guide.md", "# Guide\n"),
+            ("Cargo.toml", ""),
+        ],
+        "initial",
+    );
+
+    let workspace = test.path("workspace");
+    let sparse = vec!["src".to_string(), "Cargo.toml".to_string()];
+    let request = CheckoutRequest {
+        repository_url: &url,
+        commit_sha: &sha,
+        depth: None,
+        sparse_paths: &sparse,
+    };
+    test.checkout()
+        .materialize(&request, &workspace)
+        .await
+        .unwrap();
+
+    assert!(workspace.join("src/main.rs").exists());
+    assert!(workspace.join("Cargo.toml").exists());
+    assert!(!workspace.join("docs").exists());
+}
+
+#[tokio::test]
+async fn test_reuses_mirror_and_workspace_for_new_commits() {
+    let test = TestDir::new();
+    let source = test.path("source");
+    let url = init_source(&source);
+    let first = commit(&source, &[("a.txt", "1\n")], "first");
+
+    let checkout = test.checkout();
+    let workspace = test.path("workspace");
+    let request = CheckoutRequest {
+        repository_url: &url,
+        commit_sha: &first,
+        depth: None,
+        sparse_paths: &[],
+    };
+    checkout.materialize(&request, &workspace).await.unwrap();
+
+    // Leave junk behind that the next checkout must clean up
+    std::fs::write(workspace.join("leftover.txt"), "junk").unwrap();
+
+    let second = commit(&source, &[("a.txt", "2\n")], "second");
+    let request = CheckoutRequest {
+        commit_sha: &second,
+        ..request
+    };
+    checkout.materialize(&request, &workspace).await.unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(workspace.join("a.txt")).unwrap(),
+        "2\n"
+    );
+    assert!(!workspace.join("leftover.txt").exists());
+    assert_eq!(std::fs::read_dir(test.path("cache")).unwrap().count(), 1);
+}
+
+#[tokio::test]
+async fn test_unknown_commit_fails() {
+    let test = TestDir::new();
+    let source = test.path("source");
+    let url = init_source(&source);
+    commit(&source, &[("a.txt", "1\n")], "first");
+
+    let missing = "0123456789abcdef0123456789abcdef01234567";
+    let request = CheckoutRequest {
+        repository_url: &url,
+        commit_sha: missing,
+        depth: None,
+        sparse_paths: &[],
+    };
+    let result = test
+        .checkout()
+        .materialize(&request, &test.path("workspace"))
+        .await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_rejects_option_like_commits_and_repositories() {
+    let test = TestDir::new();
+    let source = test.path("source");
+    let url = init_source(&source);
+    commit(&source, &[("a.txt", "1\n")], "first");
+
+    let marker = test.path("PWNED");
+    let upload_pack = format!("--upload-pack=touch {};git-upload-pack", marker.display());
+    let dash_url = format!("-{url}");
+    let cases = [
+        (url.as_str(), upload_pack.as_str()),
+        (url.as_str(), "HEAD"),
+        (url.as_str(), "0123456789ABCDEF"),
+        (dash_url.as_str(), "0123456789abcdef"),
+    ];
+
+    for (repository_url, commit_sha) in cases {
+        let request = CheckoutRequest {
+            repository_url,
+            commit_sha,
+            depth: None,
+            sparse_paths: &[],
+        };
+        let result = test
+            .checkout()
+            .materialize(&request, &test.path("workspace"))
+            .await;
+        assert!(result.is_err(), "{repository_url} {commit_sha}");
+    }
+    assert!(!marker.exists());
+}
+
+#[tokio::test]
+async fn test_rejects_local_and_unsupported_repositories_by_default() {
+    let test = TestDir::new();
+    let source = test.path("source");
+    let url = init_source(&source);
+    let sha = commit(&source, &[("a.txt", "1\n")], "first");
+
+    let checkout = Checkout::new(CheckoutConfig {
+        cache_dir: test.path("cache").to_string_lossy().to_string(),
+        credentials_file: None,
+        allow_local_repositories: false,
+    });
+    let local_path = source.to_string_lossy().to_string();
+    for repository_url in [
+        url.as_str(),
+        local_path.as_str(),
+        "git://example.com/repo.git",
+        "ext::sh -c touch% /tmp/pwned",
+        "ssh://-oProxyCommand=touch%20pwned/repo.git",
+    ] {
+        let request = CheckoutRequest {
+            repository_url,
+            commit_sha: &sha,
+            depth: None,
+            sparse_paths: &[],
+        };
+        let result = checkout
+            .materialize(&request, &test.path("workspace"))
+            .await;
+        assert!(result.is_err(), "{repository_url}");
+    }
+    assert!(!test.path("cache").exists());
+}
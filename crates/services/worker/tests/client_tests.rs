@@ -0,0 +1,90 @@
+//! Integration tests for the orchestrator client against a stub orchestrator.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::Router;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use tracing::{Instrument, info_span};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use uuid::Uuid;
+
+use vulcan_worker::client::OrchestratorClient;
+use vulcan_worker::config::{CheckoutConfig, Config, SandboxConfig, TlsConfig};
+
+/// `traceparent` headers received by the stub orchestrator.
+type Received = Arc<Mutex<Vec<Option<String>>>>;
+
+/// Start an orchestrator stub accepting deregistrations, returning its URL and the
+/// `traceparent` headers of the requests it receives.
+async fn start_orchestrator() -> (String, Received) {
+    async fn deregister(State(received): State<Received>, headers: HeaderMap) -> StatusCode {
+        let traceparent = headers
+            .get("traceparent")
+            .map(|value| value.to_str().unwrap().to_string());
+        received.lock().unwrap().push(traceparent);
+        StatusCode::OK
+    }
+
+    let received = Received::default();
+    let app = Router::new()
+        .route("/workers/deregister", post(deregister))
+        .with_state(received.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    (url, received)
+}
+
+fn config(orchestrator_url: String) -> Config {
+    Config {
+        orchestrator_url,
+        tenant_id: Uuid::new_v4(),
+        registration_token: "registration-token".to_string(),
+        worker_group: None,
+        worker_name: None,
+        concurrency: 1,
+        labels: Vec::new(),
+        heartbeat_interval: Duration::from_secs(10),
+        work_wait: Duration::from_secs(20),
+        request_timeout: Duration::from_secs(5),
+        script_timeout: Duration::from_mins(5),
+        drain_timeout: Duration::from_mins(5),
+        sandbox: SandboxConfig::default(),
+        checkout: CheckoutConfig::default(),
+        tls: TlsConfig::default(),
+        metrics_port: None,
+        fragment_id: None,
+    }
+}
+
+#[tokio::test]
+async fn test_requests_carry_the_trace_context_of_the_current_span() {
+    let provider = vulcan_telemetry::tracer_provider("client-test", None);
+    let _subscriber = tracing_subscriber::registry()
+        .with(vulcan_telemetry::layer(&provider))
+        .set_default();
+    let (url, received) = start_orchestrator().await;
+    let client = OrchestratorClient::new(&config(url)).unwrap();
+
+    // Continuing the trace of a claim, as the worker does for the work it executes
+    let claim = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+    let span = info_span!("report_result");
+    vulcan_telemetry::set_parent(&span, claim);
+    let expected = vulcan_telemetry::traceparent(&span).unwrap();
+
+    client
+        .deregister(Uuid::new_v4())
+        .instrument(span)
+        .await
+        .unwrap();
+    // Outside any span, no trace context is sent
+    client.deregister(Uuid::new_v4()).await.unwrap();
+
+    assert!(expected.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-"));
+    assert_eq!(*received.lock().unwrap(), vec![Some(expected), None]);
+}
@@ -0,0 +1,54 @@
+//! Tests for worker capability labels.
+
+use vulcan_worker::labels;
+
+#[test]
+fn test_detect_includes_os_and_arch() {
+    let detected = labels::detect();
+
+    assert!(detected.contains(&format!("os={}", std::env::consts::OS)));
+    assert!(detected.contains(&format!("arch={}", std::env::consts::ARCH)));
+    assert!(detected.iter().any(|l| l.starts_with("cpus=")));
+}
+
+#[test]
+fn test_parse_comma_separated_labels() {
+    let parsed = labels::parse(" gpu=none, docker,,region=eu-west ").unwrap();
+
+    assert_eq!(parsed, vec!["gpu=none", "docker", "region=eu-west"]);
+}
+
+#[test]
+fn test_parse_empty_spec() {
+    assert!(labels::parse("").unwrap().is_empty());
+}
+
+#[test]
+fn test_parse_rejects_malformed_labels() {
+    assert!(labels::parse("=value").is_err());
+    assert!(labels::parse("gpu=two words").is_err());
+}
+
+#[test]
+fn test_merge_configured_overrides_detected_key() {
+    let detected = vec![
+        "os=linux".to_string(),
+        "cpus=32".to_string(),
+        "arch=x86_64".to_string(),
+    ];
+    let configured = vec!["cpus=8".to_string(), "docker".to_string()];
+
+    let merged = labels::merge(detected, configured);
+
+    assert_eq!(merged, vec!["arch=x86_64", "cpus=8", "docker", "os=linux"]);
+}
+
+#[test]
+fn test_merge_removes_duplicates() {
+    let merged = labels::merge(
+        vec!["docker".to_string(), "os=linux".to_string()],
+        vec!["docker".to_string()],
+    );
+
+    assert_eq!(merged, vec!["docker", "os=linux"]);
+}
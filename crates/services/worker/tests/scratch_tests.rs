@@ -0,0 +1,172 @@
+//! Tests for per-execution scratch directory management.
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use vulcan_worker::config::SandboxConfig;
+use vulcan_worker::scratch::{ScratchManager, ScratchOutcome};
+
+/// Scratch root for one test, removed on drop.
+struct TestRoot {
+    root: PathBuf,
+}
+
+impl TestRoot {
+    fn new() -> Self {
+        let root = std::env::temp_dir().join(format!("vulcan-scratch-{}", Uuid::new_v4()));
+        Self { root }
+    }
+
+    fn manager(&self, preserve_on_failure: bool, preserve_ttl: Duration) -> ScratchManager {
+        ScratchManager::new(&SandboxConfig {
+            scratch_dir: self.root.to_string_lossy().to_string(),
+            preserve_on_failure,
+            preserve_ttl,
+            ..SandboxConfig::default()
+        })
+    }
+
+    fn entries(&self) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(&self.root)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+impl Drop for TestRoot {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+#[tokio::test]
+async fn test_create_makes_unique_private_directories() {
+    let test = TestRoot::new();
+    let manager = test.manager(false, Duration::from_mins(1));
+    let fragment_id = Uuid::new_v4();
+
+    let first = manager.create(fragment_id, 1).await.unwrap();
+    let second = manager.create(fragment_id, 1).await.unwrap();
+
+    assert_ne!(first.path(), second.path());
+    assert!(first.path().starts_with(&test.root));
+    let mode = std::fs::metadata(first.path())
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o700);
+}
+
+#[tokio::test]
+async fn test_release_removes_directory_on_success() {
+    let test = TestRoot::new();
+    let manager = test.manager(true, Duration::from_mins(1));
+
+    let dir = manager.create(Uuid::new_v4(), 1).await.unwrap();
+    std::fs::write(dir.path().join("output.txt"), "data").unwrap();
+    manager.release(dir, ScratchOutcome::Succeeded).await;
+
+    assert!(test.entries().is_empty());
+}
+
+#[tokio::test]
+async fn test_release_preserves_failed_directory_when_enabled() {
+    let test = TestRoot::new();
+    let manager = test.manager(true, Duration::from_mins(1));
+
+    let dir = manager.create(Uuid::new_v4(), 2).await.unwrap();
+    std::fs::write(dir.path().join("output.txt"), "data").unwrap();
+    manager.release(dir, ScratchOutcome::Failed).await;
+
+    let entries = test.entries();
+    assert_eq!(entries.len(), 1);
+    assert!(entries[0].starts_with("failed-"));
+    assert!(test.root.join(&entries[0]).join("output.txt").exists());
+}
+
+#[tokio::test]
+async fn test_release_removes_failed_directory_when_disabled() {
+    let test = TestRoot::new();
+    let manager = test.manager(false, Duration::from_mins(1));
+
+    let dir = manager.create(Uuid::new_v4(), 1).await.unwrap();
+    manager.release(dir, ScratchOutcome::Failed).await;
+
+    assert!(test.entries().is_empty());
+}
+
+#[tokio::test]
+async fn test_release_always_removes_timed_out_directory() {
+    let test = TestRoot::new();
+    let manager = test.manager(true, Duration::from_mins(1));
+
+    let dir = manager.create(Uuid::new_v4(), 1).await.unwrap();
+    manager.release(dir, ScratchOutcome::TimedOut).await;
+
+    assert!(test.entries().is_empty());
+}
+
+#[tokio::test]
+async fn test_release_removes_read_only_contents() {
+    let test = TestRoot::new();
+    let manager = test.manager(false, Duration::from_mins(1));
+
+    let dir = manager.create(Uuid::new_v4(), 1).await.unwrap();
+    let locked = dir.path().join("locked");
+    std::fs::create_dir(&locked).unwrap();
+    std::fs::write(locked.join("file.txt"), "data").unwrap();
+    std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o500)).unwrap();
+    manager.release(dir, ScratchOutcome::Succeeded).await;
+
+    assert!(test.entries().is_empty());
+}
+
+#[tokio::test]
+async fn test_recover_removes_abandoned_and_expired_directories() {
+    let test = TestRoot::new();
+
+    // Left behind by a crashed worker, and preserved with an already expired TTL
+    let crashed = test.manager(true, Duration::ZERO);
+    let _abandoned = crashed.create(Uuid::new_v4(), 1).await.unwrap();
+    let expired = crashed.create(Uuid::new_v4(), 1).await.unwrap();
+    crashed.release(expired, ScratchOutcome::Failed).await;
+
+    // Preserved and still within its TTL
+    let manager = test.manager(true, Duration::from_hours(1));
+    let kept = manager.create(Uuid::new_v4(), 1).await.unwrap();
+    manager.release(kept, ScratchOutcome::Failed).await;
+
+    assert_eq!(test.entries().len(), 3);
+    assert_eq!(manager.recover().await.unwrap(), 2);
+
+    let entries = test.entries();
+    assert_eq!(entries.len(), 1);
+    assert!(entries[0].starts_with("failed-"));
+}
+
+#[tokio::test]
+async fn test_sweep_expired_keeps_directories_in_use() {
+    let test = TestRoot::new();
+    let manager = test.manager(true, Duration::ZERO);
+
+    let in_use = manager.create(Uuid::new_v4(), 1).await.unwrap();
+    let expired = manager.create(Uuid::new_v4(), 1).await.unwrap();
+    manager.release(expired, ScratchOutcome::Failed).await;
+
+    assert_eq!(manager.sweep_expired().await.unwrap(), 1);
+    assert!(in_use.path().exists());
+}
+
+#[tokio::test]
+async fn test_recover_without_root_is_noop() {
+    let test = TestRoot::new();
+    let manager = test.manager(false, Duration::from_mins(1));
+
+    assert_eq!(manager.recover().await.unwrap(), 0);
+}
@@ -0,0 +1,4 @@
+-- Remove workspace checkout options
+ALTER TABLE chains
+    DROP COLUMN IF EXISTS checkout_sparse_paths,
+    DROP COLUMN IF EXISTS checkout_depth;
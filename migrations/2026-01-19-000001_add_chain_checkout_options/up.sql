@@ -0,0 +1,4 @@
+-- Add workspace checkout options to chains table
+ALTER TABLE chains
+    ADD COLUMN checkout_depth INTEGER,
+    ADD COLUMN checkout_sparse_paths TEXT[];
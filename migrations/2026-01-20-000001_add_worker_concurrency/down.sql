@@ -0,0 +1,14 @@
+-- Revert to a single current fragment per worker
+ALTER TABLE workers
+    DROP COLUMN IF EXISTS max_concurrency,
+    ADD COLUMN current_fragment_id UUID REFERENCES fragments(id) ON DELETE SET NULL;
+
+UPDATE workers w
+SET current_fragment_id = (
+    SELECT fragment_id FROM worker_fragments wf
+    WHERE wf.worker_id = w.id
+    ORDER BY assigned_at
+    LIMIT 1
+);
+
+DROP TABLE IF EXISTS worker_fragments;
@@ -0,0 +1,16 @@
+-- Track in-flight fragments per worker, so a worker can run several at once
+CREATE TABLE worker_fragments (
+    worker_id UUID NOT NULL REFERENCES workers(id) ON DELETE CASCADE,
+    fragment_id UUID NOT NULL REFERENCES fragments(id) ON DELETE CASCADE,
+    assigned_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (worker_id, fragment_id)
+);
+
+CREATE INDEX idx_worker_fragments_fragment ON worker_fragments(fragment_id);
+
+INSERT INTO worker_fragments (worker_id, fragment_id)
+SELECT id, current_fragment_id FROM workers WHERE current_fragment_id IS NOT NULL;
+
+ALTER TABLE workers
+    DROP COLUMN current_fragment_id,
+    ADD COLUMN max_concurrency INTEGER NOT NULL DEFAULT 1 CHECK (max_concurrency > 0);
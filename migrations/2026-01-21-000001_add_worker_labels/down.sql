@@ -0,0 +1,6 @@
+-- Revert label-based routing
+ALTER TABLE fragments
+    DROP COLUMN IF EXISTS runs_on;
+
+ALTER TABLE workers
+    DROP COLUMN IF EXISTS labels;
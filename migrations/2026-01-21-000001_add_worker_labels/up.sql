@@ -0,0 +1,6 @@
+-- Label-based routing: workers advertise labels, fragments require a subset of them
+ALTER TABLE workers
+    ADD COLUMN labels TEXT[] NOT NULL DEFAULT '{}';
+
+ALTER TABLE fragments
+    ADD COLUMN runs_on TEXT[] NOT NULL DEFAULT '{}';
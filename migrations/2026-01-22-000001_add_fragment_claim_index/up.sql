@@ -0,0 +1,2 @@
+-- Claim candidates are pending fragments ordered by sequence
+CREATE INDEX idx_fragments_pending ON fragments(sequence) WHERE status = 'pending';
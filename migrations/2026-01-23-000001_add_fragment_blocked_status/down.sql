@@ -0,0 +1,6 @@
+-- Blocked fragments revert to the previous initial status
+UPDATE fragments SET status = 'active' WHERE status = 'blocked';
+
+-- Note: PostgreSQL does not support removing enum values directly.
+-- The enum value 'blocked' will remain.
+-- To fully remove it, you would need to recreate the enum type.
@@ -0,0 +1,2 @@
+-- Fragments wait in 'blocked' until their dependencies complete, then become 'pending'
+ALTER TYPE fragment_status ADD VALUE 'blocked' BEFORE 'pending';
@@ -0,0 +1,2 @@
+-- Blocked fragments are made pending again by reverting the 'blocked' status
+SELECT 1;
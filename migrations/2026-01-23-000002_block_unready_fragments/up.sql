@@ -0,0 +1,73 @@
+-- Fragments created before readiness was tracked are all 'pending', and were only
+-- held back by the claim query checking their earlier siblings. Move them to the
+-- states readiness tracking expects, so the claim query does not run them out of
+-- order. Runs apart from the migration adding 'blocked', which cannot be used in
+-- the transaction that adds it.
+DO $$
+BEGIN
+    -- Groups are no longer executed: finish those whose children have all finished,
+    -- repeating for the groups those complete
+    LOOP
+        UPDATE fragments g
+        SET status = CASE
+                WHEN EXISTS (
+                    SELECT 1 FROM fragments c
+                    WHERE c.parent_fragment_id = g.id AND c.status = 'failed'
+                ) THEN 'failed'::fragment_status
+                ELSE 'completed'::fragment_status
+            END,
+            completed_at = NOW()
+        WHERE g.type = 'group'
+          AND g.status IN ('pending', 'running')
+          AND NOT EXISTS (
+              SELECT 1 FROM fragments c
+              WHERE c.parent_fragment_id = g.id
+                AND c.status NOT IN ('completed', 'failed')
+          );
+        EXIT WHEN NOT FOUND;
+    END LOOP;
+END $$;
+
+-- Fragments whose dependencies are satisfied: top-level fragments and children of
+-- reached sequential groups after their earlier siblings finished, and every child
+-- of a reached parallel group
+CREATE TEMPORARY TABLE reached_fragments AS
+WITH RECURSIVE reached AS (
+    SELECT f.id, f.is_parallel
+    FROM fragments f
+    WHERE f.parent_fragment_id IS NULL
+      AND NOT EXISTS (
+          SELECT 1 FROM fragments s
+          WHERE s.chain_id = f.chain_id
+            AND s.parent_fragment_id IS NULL
+            AND s.sequence < f.sequence
+            AND s.status NOT IN ('completed', 'failed')
+      )
+    UNION ALL
+    SELECT f.id, f.is_parallel
+    FROM fragments f
+    JOIN reached r ON r.id = f.parent_fragment_id
+    WHERE r.is_parallel
+       OR NOT EXISTS (
+          SELECT 1 FROM fragments s
+          WHERE s.parent_fragment_id = f.parent_fragment_id
+            AND s.sequence < f.sequence
+            AND s.status NOT IN ('completed', 'failed')
+      )
+)
+SELECT id FROM reached;
+
+-- Fragments still waiting on their dependencies
+UPDATE fragments
+SET status = 'blocked'
+WHERE status = 'pending'
+  AND id NOT IN (SELECT id FROM reached_fragments);
+
+-- Reached groups run while their children do
+UPDATE fragments
+SET status = 'running', started_at = COALESCE(started_at, NOW())
+WHERE type = 'group'
+  AND status = 'pending'
+  AND id IN (SELECT id FROM reached_fragments);
+
+DROP TABLE reached_fragments;
@@ -0,0 +1,2 @@
+DROP TRIGGER IF EXISTS fragment_ready ON fragments;
+DROP FUNCTION IF EXISTS notify_fragment_ready();
@@ -0,0 +1,18 @@
+-- Wake up long-polling work requests when a fragment becomes claimable.
+-- The payload is the tenant of the fragment's chain, so only that tenant's
+-- waiting workers retry. Notifications are delivered on commit.
+CREATE OR REPLACE FUNCTION notify_fragment_ready() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify(
+        'fragment_ready',
+        (SELECT tenant_id::text FROM chains WHERE id = NEW.chain_id)
+    );
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER fragment_ready
+    AFTER INSERT OR UPDATE OF status ON fragments
+    FOR EACH ROW
+    WHEN (NEW.status = 'pending')
+    EXECUTE PROCEDURE notify_fragment_ready();
@@ -0,0 +1,7 @@
+ALTER TABLE workers DROP COLUMN name;
+
+-- Draining workers revert to active
+UPDATE workers SET status = 'active' WHERE status = 'draining';
+
+-- Note: PostgreSQL does not support removing enum values directly.
+-- The enum value 'draining' will remain.
@@ -0,0 +1,5 @@
+-- Draining workers finish their in-flight fragments but are assigned no new ones
+ALTER TYPE worker_status ADD VALUE 'draining';
+
+-- Name of the worker's instance (e.g. its pod), so a controller can find it
+ALTER TABLE workers ADD COLUMN name TEXT;
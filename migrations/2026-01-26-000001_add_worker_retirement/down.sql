@@ -0,0 +1,9 @@
+DROP INDEX IF EXISTS idx_workers_tenant_name;
+DROP INDEX IF EXISTS idx_workers_retired_at;
+ALTER TABLE workers DROP COLUMN retired_at;
+
+-- Deregistered workers revert to error
+UPDATE workers SET status = 'error' WHERE status = 'deregistered';
+
+-- Note: PostgreSQL does not support removing enum values directly.
+-- The enum value 'deregistered' will remain.
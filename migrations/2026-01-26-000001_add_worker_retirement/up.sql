@@ -0,0 +1,22 @@
+-- Workers that shut down cleanly are kept as deregistered until the retention sweep
+ALTER TYPE worker_status ADD VALUE 'deregistered';
+
+-- When a worker stopped being live (errored or deregistered), for the retention sweep
+ALTER TABLE workers ADD COLUMN retired_at TIMESTAMP;
+
+UPDATE workers SET retired_at = COALESCE(last_heartbeat_at, updated_at) WHERE status = 'error';
+
+CREATE INDEX idx_workers_retired_at ON workers(retired_at) WHERE retired_at IS NOT NULL;
+
+-- A restarted worker re-registers under its name (e.g. its pod) and reuses its row.
+-- Of existing duplicates, only the most recent keeps the name.
+UPDATE workers w SET name = NULL
+WHERE name IS NOT NULL
+  AND EXISTS (
+      SELECT 1 FROM workers o
+      WHERE o.tenant_id = w.tenant_id
+        AND o.name = w.name
+        AND (o.created_at, o.id) > (w.created_at, w.id)
+  );
+
+CREATE UNIQUE INDEX idx_workers_tenant_name ON workers(tenant_id, name) WHERE name IS NOT NULL;
@@ -0,0 +1,3 @@
+-- Remove the chain trace context
+ALTER TABLE chains
+    DROP COLUMN IF EXISTS trace_context;
@@ -0,0 +1,3 @@
+-- W3C traceparent of the span that stored the chain, continued by its executions
+ALTER TABLE chains
+    ADD COLUMN trace_context TEXT;